@@ -0,0 +1,128 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::persistence::{data_dir, write_json};
+use crate::stats::Outcome;
+
+/// Elo limits move by this many points per result
+pub const ELO_STEP: u32 = 50;
+
+/// Adjustments kept for the statistics screen
+const HISTORY_LEN: usize = 50;
+
+/// The engine strength setting adaptive mode steers.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Strength {
+    Skill(u8),
+    Elo(u32),
+}
+
+impl Strength {
+    /// A simple staircase: one step up after a win, one step down after a
+    /// loss, unchanged after a draw.  It settles where the player scores
+    /// about half the points.
+    pub fn adjusted(self, outcome: Outcome, elo_range: Option<(u32, u32)>) -> Strength {
+        let direction: i64 = match outcome {
+            Outcome::Win => 1,
+            Outcome::Draw => 0,
+            Outcome::Loss => -1,
+        };
+        match self {
+            Strength::Skill(level) => Strength::Skill((level as i64 + direction).clamp(1, 20) as u8),
+            Strength::Elo(elo) => {
+                let (min, max) = elo_range.unwrap_or((elo, elo));
+                let elo = (elo as i64 + direction * ELO_STEP as i64).clamp(min as i64, max as i64);
+                Strength::Elo(elo as u32)
+            }
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Strength::Skill(level) => format!("skill level {}", level),
+            Strength::Elo(elo) => format!("Elo {}", elo),
+        }
+    }
+}
+
+/// One automatic change of strength after a game.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Adjustment {
+    /// Local time the game finished, "YYYY-MM-DD HH:MM"
+    pub played_at: String,
+    pub outcome: Outcome,
+    pub from: Strength,
+    pub to: Strength,
+}
+
+/// Whether adaptive strength is on, and the adjustments it has made,
+/// oldest first.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct AdaptiveStrength {
+    pub enabled: bool,
+    pub history: Vec<Adjustment>,
+}
+
+impl AdaptiveStrength {
+    /// Adjust `current` for the result of a game and remember the change.
+    pub fn record(
+        &mut self,
+        played_at: String,
+        outcome: Outcome,
+        current: Strength,
+        elo_range: Option<(u32, u32)>,
+    ) -> Strength {
+        let next = current.adjusted(outcome, elo_range);
+        self.history.push(Adjustment {
+            played_at,
+            outcome,
+            from: current,
+            to: next,
+        });
+        if self.history.len() > HISTORY_LEN {
+            self.history.remove(0);
+        }
+        next
+    }
+}
+
+fn adaptive_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("adaptive.json"))
+}
+
+pub fn load_adaptive() -> AdaptiveStrength {
+    adaptive_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_adaptive(adaptive: &AdaptiveStrength) -> Result<(), AppError> {
+    let path = adaptive_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory available"))?;
+    write_json(&path, adaptive)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staircase_adjustment() {
+        let mut adaptive = AdaptiveStrength::default();
+        let played_at = || "2024-01-01 12:00".to_string();
+
+        let next = adaptive.record(played_at(), Outcome::Win, Strength::Skill(20), None);
+        assert_eq!(next, Strength::Skill(20));
+        let next = adaptive.record(played_at(), Outcome::Loss, next, None);
+        assert_eq!(next, Strength::Skill(19));
+
+        let range = Some((1320, 3190));
+        assert_eq!(Strength::Elo(1500).adjusted(Outcome::Win, range), Strength::Elo(1550));
+        assert_eq!(Strength::Elo(1340).adjusted(Outcome::Loss, range), Strength::Elo(1320));
+        assert_eq!(Strength::Elo(1500).adjusted(Outcome::Draw, range), Strength::Elo(1500));
+        assert_eq!(adaptive.history.len(), 2);
+    }
+}
@@ -0,0 +1,537 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chess::{Board, BoardStatus, ChessMove, Color};
+
+use crate::chess960;
+use crate::engine::{self, ChessEngine, Resources, Score, SearchLimit, MATE_SCORE};
+use crate::error::AppError;
+use crate::game::{move_to_san, parse_uci_move};
+use crate::i18n::Strings;
+use crate::pgn;
+
+/// Search depth used for each position in a game review
+pub const REVIEW_DEPTH: u32 = 12;
+
+/// Search depth of the coach's quick check before a move is played
+pub const COACH_DEPTH: u32 = 10;
+
+/// Evaluations are clamped to this many centipawns before comparing, so a
+/// missed mate in an already won position doesn't count as a catastrophe
+const EVAL_CLAMP: i32 = 1000;
+
+/// How good a move was compared with the engine's choice.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum MoveClass {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl MoveClass {
+    /// Classify by the drop in winning chances (on the -1..1 scale), using
+    /// the same thresholds as lichess.
+    fn from_drop(chance_drop: f64, is_best: bool) -> MoveClass {
+        if is_best {
+            MoveClass::Best
+        } else if chance_drop >= 0.3 {
+            MoveClass::Blunder
+        } else if chance_drop >= 0.2 {
+            MoveClass::Mistake
+        } else if chance_drop >= 0.1 {
+            MoveClass::Inaccuracy
+        } else {
+            MoveClass::Good
+        }
+    }
+
+    pub fn label(self, t: &Strings) -> &'static str {
+        match self {
+            MoveClass::Best => t.class_best,
+            MoveClass::Good => t.class_good,
+            MoveClass::Inaccuracy => t.class_inaccuracy,
+            MoveClass::Mistake => t.class_mistake,
+            MoveClass::Blunder => t.class_blunder,
+        }
+    }
+
+    /// Annotation symbol shown as a badge in the move list
+    pub fn symbol(self) -> &'static str {
+        match self {
+            MoveClass::Best => "!",
+            MoveClass::Good => "",
+            MoveClass::Inaccuracy => "?!",
+            MoveClass::Mistake => "?",
+            MoveClass::Blunder => "??",
+        }
+    }
+}
+
+/// Engine verdict on one position.
+#[derive(Clone, Debug)]
+pub struct PositionEval {
+    /// Evaluation in centipawns from White's point of view (mates mapped
+    /// to large values)
+    pub white_cp: i32,
+    /// Engine's preferred move (UCI), None in finished positions
+    pub best_move: Option<String>,
+}
+
+/// Review result for a single played move.
+#[derive(Clone, Debug)]
+pub struct MoveEvaluation {
+    pub class: MoveClass,
+    /// Centipawns lost relative to the position before the move (clamped)
+    pub cp_loss: i32,
+    /// The engine's choice in the position before the move (UCI)
+    pub best_move: String,
+    /// Evaluations before and after the move, White's point of view
+    pub eval_before: i32,
+    pub eval_after: i32,
+}
+
+impl MoveEvaluation {
+    /// Accuracy percentage of this move alone, for the side that played it.
+    pub fn accuracy(&self, mover: Color) -> f64 {
+        let sign = if mover == Color::White { 1 } else { -1 };
+        move_accuracy(win_percent(sign * self.eval_before), win_percent(sign * self.eval_after))
+    }
+}
+
+/// Winning chances in -1..1 for a centipawn score, using the logistic
+/// curve lichess fits to game outcomes.
+pub fn winning_chances(cp: i32) -> f64 {
+    let cp = cp.clamp(-EVAL_CLAMP, EVAL_CLAMP) as f64;
+    2.0 / (1.0 + (-0.00368208 * cp).exp()) - 1.0
+}
+
+/// Accuracy percentages for both sides of a reviewed game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameAccuracy {
+    pub white: Option<f64>,
+    pub black: Option<f64>,
+}
+
+impl GameAccuracy {
+    /// Lichess-style accuracy from per-move win-probability swings.  Each
+    /// side's score blends a volatility-weighted mean with a harmonic mean,
+    /// so calm positions and single big blunders both count.  `first_mover`
+    /// is the side that played `evaluations[0]`.
+    pub fn compute(evaluations: &[MoveEvaluation], first_mover: Color) -> Option<GameAccuracy> {
+        let first = evaluations.first()?;
+
+        // Win percentage (White's view) of every position in the game
+        let wins: Vec<f64> = std::iter::once(first.eval_before)
+            .chain(evaluations.iter().map(|e| e.eval_after))
+            .map(win_percent)
+            .collect();
+
+        // Positions in sharp stretches of the game weigh more
+        let window_size = (evaluations.len() / 10).clamp(2, 8);
+        let weights: Vec<f64> = std::iter::repeat_n(&wins[..window_size], window_size - 2)
+            .chain(wins.windows(window_size))
+            .map(|window| std_dev(window).clamp(0.5, 12.0))
+            .collect();
+
+        let mut per_side: [Vec<(f64, f64)>; 2] = [Vec::new(), Vec::new()];
+        for (ply, weight) in weights.iter().enumerate().take(evaluations.len()) {
+            let mover = if ply % 2 == 0 { first_mover } else { !first_mover };
+            let (before, after) = match mover {
+                Color::White => (wins[ply], wins[ply + 1]),
+                Color::Black => (100.0 - wins[ply], 100.0 - wins[ply + 1]),
+            };
+            per_side[mover.to_index()].push((move_accuracy(before, after), *weight));
+        }
+
+        let side_accuracy = |moves: &[(f64, f64)]| -> Option<f64> {
+            if moves.is_empty() {
+                return None;
+            }
+            let total_weight: f64 = moves.iter().map(|(_, w)| w).sum();
+            let weighted = moves.iter().map(|(a, w)| a * w).sum::<f64>() / total_weight;
+            let harmonic =
+                moves.len() as f64 / moves.iter().map(|(a, _)| 1.0 / a.max(1.0)).sum::<f64>();
+            Some((weighted + harmonic) / 2.0)
+        };
+
+        Some(GameAccuracy {
+            white: side_accuracy(&per_side[Color::White.to_index()]),
+            black: side_accuracy(&per_side[Color::Black.to_index()]),
+        })
+    }
+}
+
+/// An evaluation in PGN `[%eval]` form: pawns with two decimals, or
+/// "#N" / "#-N" for a forced mate in N.
+pub fn format_eval(white_cp: i32) -> String {
+    if white_cp.abs() >= MATE_SCORE / 2 {
+        let moves = MATE_SCORE - white_cp.abs();
+        if white_cp > 0 || moves == 0 {
+            format!("#{}", moves)
+        } else {
+            format!("#-{}", moves)
+        }
+    } else {
+        format!("{:.2}", white_cp as f64 / 100.0)
+    }
+}
+
+/// The first `max_plies` moves of an engine line (UCI) in SAN, stopping
+/// early at a move that isn't legal.
+pub fn line_to_san(board: &Board, pv: &[String], max_plies: usize) -> String {
+    let mut board = *board;
+    let mut moves = Vec::new();
+    for uci_move in pv.iter().take(max_plies) {
+        let Some(chess_move) = parse_uci_move(&board, uci_move) else {
+            break;
+        };
+        moves.push(move_to_san(&board, chess_move));
+        board = board.make_move_new(chess_move);
+    }
+    moves.join(" ")
+}
+
+fn win_percent(white_cp: i32) -> f64 {
+    50.0 + 50.0 * winning_chances(white_cp)
+}
+
+/// Accuracy of a single move from the mover's win percentage before and
+/// after it (lichess' fitted curve).
+fn move_accuracy(win_before: f64, win_after: f64) -> f64 {
+    let loss = (win_before - win_after).max(0.0);
+    (103.1668 * (-0.04354 * loss).exp() - 3.1669).clamp(0.0, 100.0)
+}
+
+fn std_dev(values: &[f64]) -> f64 {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    variance.sqrt()
+}
+
+/// Evaluate each position with the engine.  Checkmates and stalemates are
+/// scored directly since the engine has no move to report.
+pub fn evaluate_positions(
+    engine: &mut ChessEngine,
+    positions: &[Board],
+    limit: SearchLimit,
+) -> Result<Vec<PositionEval>, AppError> {
+    positions
+        .iter()
+        .map(|board| evaluate_position(engine, board, limit))
+        .collect()
+}
+
+pub fn evaluate_position(
+    engine: &mut ChessEngine,
+    board: &Board,
+    limit: SearchLimit,
+) -> Result<PositionEval, AppError> {
+    if let Some(eval) = final_eval(board) {
+        return Ok(eval);
+    }
+    let analysis = engine.analyse(&board.to_string(), limit)?;
+    let score = analysis.score().unwrap_or(Score::Cp(0));
+    Ok(position_eval(board, score, Some(analysis.best_move)))
+}
+
+/// The verdict on a checkmate or stalemate, which needs no engine.
+fn final_eval(board: &Board) -> Option<PositionEval> {
+    match board.status() {
+        BoardStatus::Checkmate => Some(position_eval(board, Score::Mate(0), None)),
+        BoardStatus::Stalemate => Some(position_eval(board, Score::Cp(0), None)),
+        BoardStatus::Ongoing => None,
+    }
+}
+
+fn position_eval(board: &Board, score: Score, best_move: Option<String>) -> PositionEval {
+    let side_cp = score.to_cp();
+    let white_cp = if board.side_to_move() == Color::White { side_cp } else { -side_cp };
+    PositionEval { white_cp, best_move }
+}
+
+/// Classify each move given evaluations of every position in the game
+/// (`evals[i]` is the position before `moves[i]`).
+pub fn classify_moves(
+    positions: &[Board],
+    moves: &[ChessMove],
+    evals: &[PositionEval],
+) -> Vec<MoveEvaluation> {
+    moves
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i + 1 < evals.len() && *i < positions.len())
+        .map(|(i, played)| {
+            let mover = positions[i].side_to_move();
+            let sign = if mover == Color::White { 1 } else { -1 };
+            let before = evals[i].white_cp;
+            let after = evals[i + 1].white_cp;
+
+            let chance_drop =
+                winning_chances(sign * before) - winning_chances(sign * after);
+            let cp_loss = (sign * before).clamp(-EVAL_CLAMP, EVAL_CLAMP)
+                - (sign * after).clamp(-EVAL_CLAMP, EVAL_CLAMP);
+
+            let best_move = evals[i].best_move.clone().unwrap_or_default();
+            let is_best = best_move == played.to_string();
+
+            MoveEvaluation {
+                class: MoveClass::from_drop(chance_drop, is_best),
+                cp_loss: cp_loss.max(0),
+                best_move,
+                eval_before: before,
+                eval_after: after,
+            }
+        })
+        .collect()
+}
+
+/// Evaluate every position of a game to `depth` and classify each move.
+pub fn analyse_game(
+    engine: &mut ChessEngine,
+    positions: &[Board],
+    moves: &[ChessMove],
+    depth: u32,
+) -> Result<Vec<MoveEvaluation>, AppError> {
+    let evals = evaluate_positions(engine, positions, SearchLimit::Depth(depth))?;
+    Ok(classify_moves(positions, moves, &evals))
+}
+
+/// Run a full-strength engine over every position of a game and classify
+/// each move.  Positions in `known` (by hash) were already evaluated to
+/// review depth and aren't searched again; the engine is only started if
+/// any unfinished ones are left.  Blocks for the duration; run it off the UI thread.
+pub fn review_game(
+    engine_path: &Path,
+    resources: Resources,
+    uci_options: &[(String, String)],
+    positions: &[Board],
+    moves: &[ChessMove],
+    known: &HashMap<u64, PositionEval>,
+) -> Result<Vec<MoveEvaluation>, AppError> {
+    let mut engine: Option<ChessEngine> = None;
+    let mut evals = Vec::with_capacity(positions.len());
+    for board in positions {
+        let eval = match known.get(&board.get_hash()).cloned().or_else(|| final_eval(board)) {
+            Some(eval) => eval,
+            None => {
+                let engine = match engine {
+                    Some(ref mut engine) => engine,
+                    None => {
+                        let mut started = ChessEngine::new();
+                        started.start(engine_path, 20, 0, resources, uci_options)?;
+                        engine.insert(started)
+                    }
+                };
+                evaluate_position(engine, board, SearchLimit::Depth(REVIEW_DEPTH))?
+            }
+        };
+        evals.push(eval);
+    }
+    Ok(classify_moves(positions, moves, &evals))
+}
+
+/// Analyse every game in the PGN file at `input` (main lines only) and
+/// write them to `output` annotated with evaluations and blunder marks.
+/// Prints a line per game as it goes; returns the number of games.
+pub fn annotate_pgn_file(
+    engine_path: &Path,
+    resources: Resources,
+    uci_options: &[(String, String)],
+    input: &Path,
+    output: &Path,
+    depth: u32,
+) -> Result<usize, AppError> {
+    let games = pgn::parse_pgn(&fs::read_to_string(input)?)?;
+    let mut engine = ChessEngine::new();
+    engine.start(engine_path, 20, 0, resources, uci_options)?;
+    let annotator = engine::engine_name(engine_path);
+
+    let mut annotated = String::new();
+    for (n, game) in games.iter().enumerate() {
+        let moves = game.lines.first().map(Vec::as_slice).unwrap_or_default();
+        let positions: Vec<Board> = std::iter::once(game.start)
+            .chain(moves.iter().scan(game.start, |board, &m| {
+                *board = board.make_move_new(m);
+                Some(*board)
+            }))
+            .collect();
+        let evaluations = analyse_game(&mut engine, &positions, moves, depth)?;
+
+        let tag = |name: &str| {
+            game.tags
+                .iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let count = |class| evaluations.iter().filter(|e| e.class == class).count();
+        println!(
+            "Game {}: {} - {}, {} moves: {} inaccuracies, {} mistakes, {} blunders",
+            n + 1,
+            tag("White").unwrap_or("?"),
+            tag("Black").unwrap_or("?"),
+            moves.len(),
+            count(MoveClass::Inaccuracy),
+            count(MoveClass::Mistake),
+            count(MoveClass::Blunder)
+        );
+
+        let mut tags: Vec<(String, String)> = game
+            .tags
+            .iter()
+            .filter(|(name, _)| name != "Annotator")
+            .cloned()
+            .collect();
+        tags.push(("Annotator".to_string(), format!("{} (depth {})", annotator, depth)));
+        let result = tag("Result").unwrap_or("*");
+        annotated.push_str(&pgn::export_annotated(&tags, game.start, moves, &evaluations, result));
+        annotated.push('\n');
+    }
+
+    fs::write(output, annotated)?;
+    Ok(games.len())
+}
+
+/// Quick shallow check of a move the player is about to make.  Returns a
+/// warning, in `t`'s language, if it throws away a mistake's worth of
+/// winning chances or more.
+pub fn check_move(
+    engine: &mut ChessEngine,
+    board: &Board,
+    chess_move: ChessMove,
+    t: &Strings,
+) -> Result<Option<String>, AppError> {
+    let limit = SearchLimit::Depth(COACH_DEPTH);
+    let before = evaluate_position(engine, board, limit)?;
+    if before.best_move.as_deref() == Some(chess_move.to_string().as_str()) {
+        return Ok(None);
+    }
+    let after = evaluate_position(engine, &chess960::make_move(board, chess_move), limit)?;
+
+    let sign = if board.side_to_move() == Color::White { 1 } else { -1 };
+    Ok(coach_warning(sign * before.white_cp, sign * after.white_cp, t))
+}
+
+/// Describe how bad a move is from the mover's evaluation before and after
+/// it; None for moves that aren't at least a mistake.
+fn coach_warning(before: i32, after: i32, t: &Strings) -> Option<String> {
+    let chance_drop = winning_chances(before) - winning_chances(after);
+    if MoveClass::from_drop(chance_drop, false) < MoveClass::Mistake {
+        return None;
+    }
+
+    let loss = before.clamp(-EVAL_CLAMP, EVAL_CLAMP) - after.clamp(-EVAL_CLAMP, EVAL_CLAMP);
+    let warning = if after <= -MATE_SCORE / 2 {
+        t.allows_mate
+    } else if loss >= 250 {
+        t.loses_piece
+    } else if loss >= 80 {
+        t.loses_pawn
+    } else {
+        t.spoils_position
+    };
+    Some(warning.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::parse_uci_move;
+    use crate::i18n::{ENGLISH, GERMAN};
+
+    #[test]
+    fn test_classify_moves_by_eval_swing() {
+        let start = Board::default();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        let after_e4 = start.make_move_new(e4);
+        let f6 = parse_uci_move(&after_e4, "f7f6").unwrap();
+        let after_f6 = after_e4.make_move_new(f6);
+
+        let evals = vec![
+            PositionEval { white_cp: 30, best_move: Some("e2e4".to_string()) },
+            PositionEval { white_cp: 35, best_move: Some("e7e5".to_string()) },
+            PositionEval { white_cp: 420, best_move: None },
+        ];
+
+        let review = classify_moves(&[start, after_e4, after_f6], &[e4, f6], &evals);
+
+        assert_eq!(review.len(), 2);
+        assert_eq!(review[0].class, MoveClass::Best);
+        assert_eq!(review[0].cp_loss, 0);
+        // Black's f6 hands White a winning position
+        assert_eq!(review[1].class, MoveClass::Blunder);
+        assert_eq!(review[1].cp_loss, 385);
+        assert_eq!(review[1].best_move, "e7e5");
+        assert!(review[0].accuracy(Color::White) > 99.0);
+        assert!(review[1].accuracy(Color::Black) < 50.0);
+    }
+
+    #[test]
+    fn test_review_uses_known_evaluations() {
+        let start = Board::default();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        let after_e4 = start.make_move_new(e4);
+        let known = HashMap::from([
+            (start.get_hash(), PositionEval { white_cp: 30, best_move: Some("e2e4".to_string()) }),
+            (after_e4.get_hash(), PositionEval { white_cp: 35, best_move: Some("e7e5".to_string()) }),
+        ]);
+
+        // Nothing is left to search, so no engine is needed
+        let engine_path = Path::new("/nonexistent/engine");
+        let resources = Resources { threads: 1, hash_mb: 16 };
+        let positions = [start, after_e4];
+        let review = review_game(engine_path, resources, &[], &positions, &[e4], &known).unwrap();
+        assert_eq!(review.len(), 1);
+        assert_eq!(review[0].class, MoveClass::Best);
+
+        // A position without an evaluation needs the engine
+        let partial = HashMap::from([(start.get_hash(), known[&start.get_hash()].clone())]);
+        assert!(review_game(engine_path, resources, &[], &positions, &[e4], &partial).is_err());
+    }
+
+    #[test]
+    fn test_game_accuracy_penalises_blunders() {
+        let evaluation = |before, after| MoveEvaluation {
+            class: MoveClass::Good,
+            cp_loss: 0,
+            best_move: String::new(),
+            eval_before: before,
+            eval_after: after,
+        };
+        // White keeps the balance; Black throws away a rook on move two
+        let evaluations = vec![
+            evaluation(20, 25),
+            evaluation(25, 30),
+            evaluation(30, 35),
+            evaluation(35, 540),
+        ];
+
+        let accuracy = GameAccuracy::compute(&evaluations, Color::White).unwrap();
+        let white = accuracy.white.unwrap();
+        let black = accuracy.black.unwrap();
+        assert!(white > 95.0, "white accuracy {}", white);
+        assert!(black < 60.0, "black accuracy {}", black);
+        assert_eq!(GameAccuracy::compute(&[], Color::White), None);
+    }
+
+    #[test]
+    fn test_coach_warning_thresholds() {
+        assert_eq!(coach_warning(30, 10, &ENGLISH), None);
+        assert_eq!(coach_warning(30, -300, &ENGLISH).as_deref(), Some("This loses a piece"));
+        assert_eq!(
+            coach_warning(0, -MATE_SCORE + 3, &ENGLISH).as_deref(),
+            Some("This allows a forced mate")
+        );
+        assert_eq!(coach_warning(30, -120, &GERMAN).as_deref(), Some("Das verliert einen Bauern"));
+        // Already lost positions have few winning chances left to drop
+        assert_eq!(coach_warning(-900, -1200, &ENGLISH), None);
+    }
+
+    #[test]
+    fn test_line_to_san() {
+        let pv: Vec<String> = ["e2e4", "e7e5", "g1f3", "b8c6"].iter().map(|m| m.to_string()).collect();
+        assert_eq!(line_to_san(&Board::default(), &pv, 3), "e4 e5 Nf3");
+        let broken = ["e2e4".to_string(), "e2e4".to_string()];
+        assert_eq!(line_to_san(&Board::default(), &broken, 8), "e4");
+    }
+}
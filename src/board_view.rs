@@ -0,0 +1,414 @@
+use std::time::{Duration, Instant};
+
+use chess::{ChessMove, Color, Square};
+use iced::advanced::layout::{self, Layout};
+use iced::advanced::renderer::{self, Quad};
+use iced::advanced::widget::{tree, Tree, Widget};
+use iced::advanced::{mouse, svg, Clipboard, Shell};
+use iced::{event, touch, Color as IcedColor, Element, Event, Length, Point, Rectangle, Size};
+
+use crate::Message;
+
+/// Share of a square the piece image covers, centred in the square.
+const PIECE_SCALE: f32 = 0.8;
+
+const CURSOR_COLOR: IcedColor = IcedColor::from_rgb(0.1, 0.45, 0.95);
+
+/// Marks on the squares the piece under the mouse could move to
+const HOVER_COLOR: IcedColor = IcedColor::from_rgba(0.0, 0.0, 0.0, 0.18);
+
+/// How long a touch must be held to count as a long press
+const LONG_PRESS: Duration = Duration::from_millis(500);
+
+/// Smooth scrolling distance that steps one move through the game
+const PIXELS_PER_STEP: f32 = 40.0;
+
+/// A piece partway along the move it just made.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slide {
+    pub from: Square,
+    pub to: Square,
+    /// 0.0 at the start square, 1.0 on arrival
+    pub progress: f32,
+}
+
+/// A faint arrow drawn over the board from one square to another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Arrow {
+    pub from: Square,
+    pub to: Square,
+    pub color: IcedColor,
+    /// 1.0 for a full arrow; lighter ones are thinner and fainter
+    pub weight: f32,
+}
+
+/// The board drawn as one widget, so a moving piece can be placed between
+/// squares while it slides. Clicks on a square send `SquareClicked`; in
+/// touch mode the release does, or `SquareLongPressed` after a long hold.
+pub struct BoardView {
+    size: f32,
+    orientation: Color,
+    /// Background of each square, indexed by `Square::to_index`
+    square_colors: [IcedColor; 64],
+    pieces: Vec<(Square, svg::Handle)>,
+    slide: Option<Slide>,
+    arrows: Vec<Arrow>,
+    /// Square outlined for keyboard play
+    cursor: Option<Square>,
+    /// Moves previewed when the mouse rests on their piece
+    hover_moves: Vec<ChessMove>,
+    /// Squares are chosen when the finger (or button) lifts, so a long
+    /// press can be told from a tap
+    touch: bool,
+}
+
+/// Input the board remembers between events.
+#[derive(Default)]
+struct State {
+    /// The square pressed in touch mode, and when
+    pressed: Option<(Square, Instant)>,
+    /// Smooth scrolling not yet amounting to a whole move
+    scrolled: f32,
+    /// The square under the mouse
+    hovered: Option<Square>,
+}
+
+impl BoardView {
+    pub fn new(
+        size: f32,
+        orientation: Color,
+        square_colors: [IcedColor; 64],
+        pieces: Vec<(Square, svg::Handle)>,
+    ) -> Self {
+        BoardView {
+            size,
+            orientation,
+            square_colors,
+            pieces,
+            slide: None,
+            arrows: Vec::new(),
+            cursor: None,
+            hover_moves: Vec::new(),
+            touch: false,
+        }
+    }
+
+    pub fn touch(mut self, touch: bool) -> Self {
+        self.touch = touch;
+        self
+    }
+
+    pub fn arrows(mut self, arrows: impl IntoIterator<Item = Arrow>) -> Self {
+        self.arrows.extend(arrows.into_iter().filter(|arrow| arrow.from != arrow.to));
+        self
+    }
+
+    pub fn cursor(mut self, cursor: Option<Square>) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    /// Faintly mark where a piece can go while the mouse is over it.
+    pub fn hover_moves(mut self, moves: Vec<ChessMove>) -> Self {
+        self.hover_moves = moves;
+        self
+    }
+
+    /// Draw the piece standing on `slide.to` on its way there instead.
+    pub fn slide(mut self, slide: Option<Slide>) -> Self {
+        self.slide = slide.filter(|s| s.progress < 1.0);
+        self
+    }
+
+    /// Top-left corner of a square relative to the board, as fractions of
+    /// the square size.
+    fn grid_position(&self, square: Square) -> (f32, f32) {
+        let file = square.get_file().to_index() as f32;
+        let rank = square.get_rank().to_index() as f32;
+        match self.orientation {
+            Color::White => (file, 7.0 - rank),
+            Color::Black => (7.0 - file, rank),
+        }
+    }
+
+    /// The arrows as an SVG the size of the board, one unit per square.
+    fn arrows_svg(&self) -> String {
+        let centre = |square| {
+            let (x, y) = self.grid_position(square);
+            (x + 0.5, y + 0.5)
+        };
+        let mut drawing = String::from("<svg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 8 8'>");
+        for arrow in &self.arrows {
+            let ((x1, y1), (x2, y2)) = (centre(arrow.from), centre(arrow.to));
+            let length = ((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt();
+            let (ux, uy) = ((x2 - x1) / length, (y2 - y1) / length);
+            // The shaft stops where the head begins
+            let weight = arrow.weight.clamp(0.0, 1.0);
+            let head = 0.4 * weight.max(0.5);
+            let (bx, by) = (x2 - ux * head, y2 - uy * head);
+            let (px, py) = (-uy * 0.22 * weight.max(0.5), ux * 0.22 * weight.max(0.5));
+            let [r, g, b, _] = arrow.color.into_rgba8();
+            let color = format!("rgb({},{},{})", r, g, b);
+            drawing.push_str(&format!(
+                "<g opacity='{opacity}' fill='{color}' stroke='{color}'>\
+                 <line x1='{x1}' y1='{y1}' x2='{bx}' y2='{by}' stroke-width='{width}'/>\
+                 <polygon points='{x2},{y2} {},{} {},{}' stroke='none'/></g>",
+                bx + px,
+                by + py,
+                bx - px,
+                by - py,
+                color = color,
+                opacity = 0.5 * weight,
+                width = 0.16 * weight,
+            ));
+        }
+        drawing.push_str("</svg>");
+        drawing
+    }
+
+    fn square_at(&self, point: Point) -> Option<Square> {
+        let square_size = self.size / 8.0;
+        let column = (point.x / square_size).floor();
+        let row = (point.y / square_size).floor();
+        if !(0.0..8.0).contains(&column) || !(0.0..8.0).contains(&row) {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        let (file, rank) = match self.orientation {
+            Color::White => (column, 7 - row),
+            Color::Black => (7 - column, row),
+        };
+        Some(Square::make_square(
+            chess::Rank::from_index(rank),
+            chess::File::from_index(file),
+        ))
+    }
+}
+
+impl<Renderer> Widget<Message, Renderer> for BoardView
+where
+    Renderer: renderer::Renderer + svg::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn width(&self) -> Length {
+        Length::Fixed(self.size)
+    }
+
+    fn height(&self) -> Length {
+        Length::Fixed(self.size)
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let size = limits.resolve(Size::new(self.size, self.size));
+        layout::Node::new(size)
+    }
+
+    fn draw(
+        &self,
+        state: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let origin = layout.bounds().position();
+        let square_size = self.size / 8.0;
+        let at = |(column, row): (f32, f32), scale: f32| {
+            let inset = square_size * (1.0 - scale) / 2.0;
+            Rectangle {
+                x: origin.x + column * square_size + inset,
+                y: origin.y + row * square_size + inset,
+                width: square_size * scale,
+                height: square_size * scale,
+            }
+        };
+
+        for square in chess::ALL_SQUARES {
+            renderer.fill_quad(
+                Quad {
+                    bounds: at(self.grid_position(square), 1.0),
+                    border_radius: 0.0.into(),
+                    border_width: 0.0,
+                    border_color: IcedColor::TRANSPARENT,
+                },
+                self.square_colors[square.to_index()],
+            );
+        }
+
+        // A dot on an empty square, a ring round a piece that can be taken
+        let hovered = state.state.downcast_ref::<State>().hovered;
+        let targets = self.hover_moves.iter().filter(|m| Some(m.get_source()) == hovered);
+        for target in targets.map(ChessMove::get_dest) {
+            let occupied = self.pieces.iter().any(|(square, _)| *square == target);
+            let (scale, border_width, fill) = match occupied {
+                true => (0.95, square_size / 14.0, IcedColor::TRANSPARENT),
+                false => (0.3, 0.0, HOVER_COLOR),
+            };
+            renderer.fill_quad(
+                Quad {
+                    bounds: at(self.grid_position(target), scale),
+                    border_radius: (square_size * scale / 2.0).into(),
+                    border_width,
+                    border_color: HOVER_COLOR,
+                },
+                fill,
+            );
+        }
+
+        let sliding = self.slide.map(|s| s.to);
+        for (square, handle) in &self.pieces {
+            if Some(*square) != sliding {
+                renderer.draw(handle.clone(), None, at(self.grid_position(*square), PIECE_SCALE));
+            }
+        }
+
+        // The moving piece goes last so it passes over the others
+        if let Some(slide) = self.slide {
+            if let Some((_, handle)) = self.pieces.iter().find(|(square, _)| *square == slide.to) {
+                let (from_x, from_y) = self.grid_position(slide.from);
+                let (to_x, to_y) = self.grid_position(slide.to);
+                let t = slide.progress.clamp(0.0, 1.0);
+                let position = (from_x + (to_x - from_x) * t, from_y + (to_y - from_y) * t);
+                renderer.draw(handle.clone(), None, at(position, PIECE_SCALE));
+            }
+        }
+
+        if let Some(square) = self.cursor {
+            renderer.fill_quad(
+                Quad {
+                    bounds: at(self.grid_position(square), 1.0),
+                    border_radius: 0.0.into(),
+                    border_width: (square_size / 12.0).max(2.0),
+                    border_color: CURSOR_COLOR,
+                },
+                IcedColor::TRANSPARENT,
+            );
+        }
+
+        if !self.arrows.is_empty() {
+            let drawing = self.arrows_svg();
+            let bounds = Rectangle::new(origin, Size::new(self.size, self.size));
+            renderer.draw(svg::Handle::from_memory(drawing.into_bytes()), None, bounds);
+        }
+    }
+
+    fn on_event(
+        &mut self,
+        state: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let bounds = layout.bounds();
+        let square_under = |position: Point| {
+            bounds
+                .contains(position)
+                .then(|| self.square_at(Point::new(position.x - bounds.x, position.y - bounds.y)))
+                .flatten()
+        };
+        let (pressed, lifted) = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                (cursor.position().and_then(square_under), None)
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                (None, Some(cursor.position().and_then(square_under)))
+            }
+            Event::Touch(touch::Event::FingerPressed { position, .. }) => (square_under(position), None),
+            Event::Touch(touch::Event::FingerLifted { position, .. }) => {
+                (None, Some(square_under(position)))
+            }
+            Event::Touch(touch::Event::FingerLost { .. }) => (None, Some(None)),
+            _ => (None, None),
+        };
+
+        let state = state.state.downcast_mut::<State>();
+
+        if let Event::Mouse(mouse::Event::CursorMoved { position }) = event {
+            state.hovered = square_under(position);
+            return event::Status::Ignored;
+        }
+        if let Event::Mouse(mouse::Event::CursorLeft) = event {
+            state.hovered = None;
+            return event::Status::Ignored;
+        }
+
+        // The wheel steps through the game, back when scrolled up
+        if let Event::Mouse(mouse::Event::WheelScrolled { delta }) = event {
+            if !cursor.is_over(bounds) {
+                return event::Status::Ignored;
+            }
+            let steps = match delta {
+                mouse::ScrollDelta::Lines { y, .. } => y.round(),
+                mouse::ScrollDelta::Pixels { y, .. } => {
+                    state.scrolled += y;
+                    let steps = (state.scrolled / PIXELS_PER_STEP).trunc();
+                    state.scrolled -= steps * PIXELS_PER_STEP;
+                    steps
+                }
+            };
+            if steps != 0.0 {
+                shell.publish(Message::StepMove(-steps as isize));
+            }
+            return event::Status::Captured;
+        }
+
+        if let Some(square) = pressed {
+            if self.touch {
+                state.pressed = Some((square, Instant::now()));
+            } else {
+                shell.publish(Message::SquareClicked(square));
+            }
+            return event::Status::Captured;
+        }
+        if let Some(released_on) = lifted {
+            // Sliding off the square before lifting cancels the tap
+            let tapped = state.pressed.take().filter(|(square, _)| Some(*square) == released_on);
+            if let Some((square, since)) = tapped {
+                shell.publish(if since.elapsed() >= LONG_PRESS {
+                    Message::SquareLongPressed(square)
+                } else {
+                    Message::SquareClicked(square)
+                });
+                return event::Status::Captured;
+            }
+        }
+        event::Status::Ignored
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::Idle
+        }
+    }
+}
+
+impl<'a, Renderer> From<BoardView> for Element<'a, Message, Renderer>
+where
+    Renderer: renderer::Renderer + svg::Renderer + 'a,
+{
+    fn from(board: BoardView) -> Self {
+        Element::new(board)
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chess::{Board, ChessMove};
+use rand::distributions::{Distribution, WeightedIndex};
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::persistence::write_json;
+use crate::pgn;
+use crate::stats::Outcome;
+
+/// Opening moves the engine keeps to while the game is in them, read from
+/// the games and variations of a PGN repertoire.  Out of book it searches
+/// as usual.
+#[derive(Debug, Clone, Default)]
+pub struct Book {
+    // Moves played in each position, by hash, with how many games play them
+    moves: HashMap<u64, Vec<(ChessMove, u32)>>,
+    learning: BookLearning,
+}
+
+/// How the engine has done after each of its book moves, kept in a file
+/// beside the book.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct BookLearning {
+    /// Results by position FEN without the move counters, so transposed
+    /// move orders share them, then by the move played there (UCI)
+    pub positions: HashMap<String, HashMap<String, BookResults>>,
+}
+
+impl BookLearning {
+    /// Merge entries saved under full FENs, counters and all, into the
+    /// positions they belong to.
+    fn rekey(self) -> BookLearning {
+        let mut positions: HashMap<String, HashMap<String, BookResults>> = HashMap::new();
+        for (fen, moves) in self.positions {
+            let position = positions.entry(position_key(&fen)).or_default();
+            for (chess_move, results) in moves {
+                let merged = position.entry(chess_move).or_default();
+                merged.wins += results.wins;
+                merged.draws += results.draws;
+                merged.losses += results.losses;
+            }
+        }
+        BookLearning { positions }
+    }
+}
+
+/// The first four FEN fields: placement, side to move, castling rights and
+/// en passant square.
+fn position_key(fen: &str) -> String {
+    fen.split_whitespace().take(4).collect::<Vec<_>>().join(" ")
+}
+
+/// Games the engine won, drew and lost after playing a book move.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct BookResults {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl BookResults {
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+
+    /// What the move's weight is multiplied by: 1 before any games, up to
+    /// 2 for a move that keeps winning and down towards 0 for one that
+    /// keeps losing.  One win and one loss are assumed, so a single game
+    /// doesn't rule a move out.
+    pub fn factor(&self) -> f64 {
+        let games = f64::from(self.wins + self.draws + self.losses);
+        let points = f64::from(self.wins) + f64::from(self.draws) / 2.0;
+        2.0 * (points + 1.0) / (games + 2.0)
+    }
+}
+
+fn learning_path(book: &Path) -> PathBuf {
+    book.with_extension("learn.json")
+}
+
+impl Book {
+    pub fn from_pgn(text: &str) -> Result<Book, AppError> {
+        let mut book = Book::default();
+        for game in pgn::parse_pgn(text)? {
+            // Variations repeat the moves before they branch off, which
+            // count once per game
+            let mut seen = HashSet::new();
+            for line in &game.lines {
+                let mut board = game.start;
+                for &chess_move in line {
+                    if seen.insert((board.get_hash(), chess_move)) {
+                        book.add(&board, chess_move);
+                    }
+                    board = board.make_move_new(chess_move);
+                }
+            }
+        }
+        if book.moves.is_empty() {
+            return Err(AppError::Pgn("The book has no moves".to_string()));
+        }
+        Ok(book)
+    }
+
+    /// Read the PGN book at `path` with what it has learnt.  Polyglot
+    /// `.bin` books, or anything else that isn't PGN text, are refused.
+    pub fn load(path: &Path) -> Result<Book, AppError> {
+        if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("bin")) {
+            return Err(AppError::Pgn(format!(
+                "Book {}: Polyglot books aren't supported; use a PGN repertoire",
+                path.display()
+            )));
+        }
+        let text = fs::read_to_string(path).map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData => {
+                AppError::Pgn(format!("Book {}: not a PGN file", path.display()))
+            }
+            _ => AppError::Pgn(format!("Book {}: {}", path.display(), e)),
+        })?;
+        let mut book = Book::from_pgn(&text)?;
+        book.learning = fs::read_to_string(learning_path(path))
+            .ok()
+            .and_then(|contents| serde_json::from_str::<BookLearning>(&contents).ok())
+            .map(BookLearning::rekey)
+            .unwrap_or_default();
+        Ok(book)
+    }
+
+    /// Write what the book has learnt beside the book at `path`.
+    pub fn save_learning(&self, path: &Path) -> Result<(), AppError> {
+        write_json(&learning_path(path), &self.learning)
+    }
+
+    fn add(&mut self, board: &Board, chess_move: ChessMove) {
+        let known = self.moves.entry(board.get_hash()).or_default();
+        match known.iter_mut().find(|(m, _)| *m == chess_move) {
+            Some((_, count)) => *count += 1,
+            None => known.push((chess_move, 1)),
+        }
+    }
+
+    /// The book's moves in this position and how often it plays each.
+    pub fn moves(&self, board: &Board) -> &[(ChessMove, u32)] {
+        self.moves.get(&board.get_hash()).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// How the engine has done after playing `chess_move` in `board`.
+    pub fn results(&self, board: &Board, chess_move: ChessMove) -> BookResults {
+        self.learning
+            .positions
+            .get(&position_key(&board.to_string()))
+            .and_then(|moves| moves.get(&chess_move.to_string()))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// One of the book's moves at random, the ones it plays more often
+    /// more likely, and with `learn` those the engine did well with too;
+    /// None once the game has left the book.
+    pub fn pick(&self, board: &Board, learn: bool) -> Option<ChessMove> {
+        let moves = self.moves(board);
+        let weights = moves.iter().map(|&(chess_move, count)| match learn {
+            true => f64::from(count) * self.results(board, chess_move).factor(),
+            false => f64::from(count),
+        });
+        let weights = WeightedIndex::new(weights).ok()?;
+        Some(moves[weights.sample(&mut rand::thread_rng())].0)
+    }
+
+    /// Count a finished game's `outcome` for the engine against each of
+    /// its `moves` that came from the book.
+    pub fn learn(&mut self, moves: &[(Board, ChessMove)], outcome: Outcome) {
+        for &(board, chess_move) in moves {
+            if !self.moves(&board).iter().any(|&(m, _)| m == chess_move) {
+                continue;
+            }
+            let position = self.learning.positions.entry(position_key(&board.to_string())).or_default();
+            position.entry(chess_move.to_string()).or_default().add(outcome);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::parse_uci_move;
+
+    #[test]
+    fn test_book_moves() {
+        let text = "[Event \"Book\"]\n\n1. e4 e5 (1... c5 2. Nf3) (1... e5 2. Bc4) 2. Nf3 *\n\n\
+                    [Event \"More\"]\n\n1. e4 c5 *\n";
+        let book = Book::from_pgn(text).unwrap();
+
+        let start = Board::default();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        assert_eq!(book.moves(&start), [(e4, 2)]);
+        assert_eq!(book.pick(&start, false), Some(e4));
+
+        let after_e4 = start.make_move_new(e4);
+        let replies: Vec<(String, u32)> =
+            book.moves(&after_e4).iter().map(|(m, count)| (m.to_string(), *count)).collect();
+        assert_eq!(replies, [("e7e5".to_string(), 1), ("c7c5".to_string(), 2)]);
+
+        // Out of book after 1. d4
+        let d4 = start.make_move_new(parse_uci_move(&start, "d2d4").unwrap());
+        assert_eq!(book.pick(&d4, true), None);
+        assert!(Book::from_pgn("[Event \"Empty\"]\n\n*\n").is_err());
+    }
+
+    #[test]
+    fn test_book_learning() {
+        let mut book = Book::from_pgn("[Event \"Book\"]\n\n1. e4 e5 (1... c5) *\n").unwrap();
+        let start = Board::default();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        let after_e4 = start.make_move_new(e4);
+        let c5 = parse_uci_move(&after_e4, "c7c5").unwrap();
+        let d5 = parse_uci_move(&after_e4, "d7d5").unwrap();
+
+        for _ in 0..3 {
+            book.learn(&[(after_e4, c5), (after_e4, d5)], Outcome::Loss);
+        }
+        book.learn(&[(after_e4, c5)], Outcome::Draw);
+        let results = BookResults {
+            wins: 0,
+            draws: 1,
+            losses: 3,
+        };
+        assert_eq!(book.results(&after_e4, c5), results);
+        // Moves out of book aren't tracked
+        assert_eq!(book.results(&after_e4, d5), BookResults::default());
+
+        // 1. Nf3 Nf6 2. Ng1 Ng8 reaches the start again with other counters
+        let mut board = start;
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            board = board.make_move_new(parse_uci_move(&board, uci).unwrap());
+        }
+        book.learn(&[(board, e4)], Outcome::Win);
+        assert_eq!(book.results(&start, e4).wins, 1);
+        // Files written with the counters in the key
+        let placement = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -";
+        let saved = BookLearning {
+            positions: HashMap::from([
+                (format!("{} 0 1", placement), HashMap::from([("e2e4".to_string(), results)])),
+                (format!("{} 4 3", placement), HashMap::from([("e2e4".to_string(), results)])),
+            ]),
+        };
+        let merged = saved.rekey();
+        assert_eq!(merged.positions.len(), 1);
+        assert_eq!(merged.positions[&position_key(&start.to_string())]["e2e4"].losses, 6);
+
+        assert_eq!(BookResults::default().factor(), 1.0);
+        assert_eq!(results.factor(), 0.5);
+        let winning = BookResults {
+            wins: 4,
+            draws: 0,
+            losses: 0,
+        };
+        assert!(winning.factor() > 1.5);
+    }
+
+    #[test]
+    fn test_book_load_refuses_polyglot() {
+        let error = Book::load(Path::new("performance.bin")).unwrap_err().to_string();
+        assert!(error.contains("Polyglot"), "{}", error);
+
+        // A Polyglot book under another name is caught as not being text
+        let path = std::env::temp_dir().join(format!("cep-book-{}.dat", std::process::id()));
+        fs::write(&path, [0x46, 0x3b, 0x96, 0x18, 0xff, 0xfe, 0x00, 0x01]).unwrap();
+        let error = Book::load(&path).unwrap_err().to_string();
+        let _ = fs::remove_file(&path);
+        assert!(error.contains("not a PGN file"), "{}", error);
+    }
+}
@@ -0,0 +1,458 @@
+use chess::{Board, BoardBuilder, ChessMove, Color, File, Piece, Rank, Square};
+use rand::Rng;
+
+/// Number of Chess960 starting positions
+pub const POSITION_COUNT: u16 = 960;
+
+// Knight placements over the five squares left after the bishops and queen
+const KNIGHT_TABLE: [(usize, usize); 10] = [
+    (0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4),
+];
+
+/// Back rank of Chess960 position `index` (0..960), by Scharnagl's
+/// numbering, from the a-file to the h-file.
+pub fn back_rank(index: u16) -> [Piece; 8] {
+    let mut rank: [Option<Piece>; 8] = [None; 8];
+    let mut n = (index % POSITION_COUNT) as usize;
+
+    // Light-squared bishop on b, d, f or h; dark-squared on a, c, e or g
+    rank[(n % 4) * 2 + 1] = Some(Piece::Bishop);
+    n /= 4;
+    rank[(n % 4) * 2] = Some(Piece::Bishop);
+    n /= 4;
+
+    let place = |rank: &mut [Option<Piece>; 8], nth_empty: usize, piece: Piece| {
+        let file = (0..8).filter(|&f| rank[f].is_none()).nth(nth_empty).unwrap();
+        rank[file] = Some(piece);
+    };
+
+    place(&mut rank, n % 6, Piece::Queen);
+    n /= 6;
+
+    // Place the second knight first so the first one's index is unaffected
+    let (first, second) = KNIGHT_TABLE[n];
+    place(&mut rank, second, Piece::Knight);
+    place(&mut rank, first, Piece::Knight);
+
+    // The king always stands between the two rooks
+    for piece in [Piece::Rook, Piece::King, Piece::Rook] {
+        place(&mut rank, 0, piece);
+    }
+
+    rank.map(|piece| piece.unwrap())
+}
+
+pub fn random_index() -> u16 {
+    rand::thread_rng().gen_range(0..POSITION_COUNT)
+}
+
+/// Shredder-FEN of the starting position `index`, castling rights
+/// included.
+pub fn start_fen(index: u16) -> String {
+    let rank = back_rank(index);
+    let white: String = rank.iter().map(|&piece| piece_char(piece, Color::White)).collect();
+    let rooks: String = (0..8)
+        .rev()
+        .filter(|&file| rank[file] == Piece::Rook)
+        .map(|file| (b'A' + file as u8) as char)
+        .collect();
+    format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w {}{} - 0 1",
+        white.to_ascii_lowercase(),
+        white,
+        rooks,
+        rooks.to_ascii_lowercase()
+    )
+}
+
+/// Shredder-FEN castling field for the given rights: the file of each
+/// castling rook, upper case for White.  A right is only kept when the
+/// king is on its back rank with a rook beyond it on that wing; the
+/// outermost rook is the castling rook.
+pub fn castling_field(
+    piece_at: impl Fn(Square) -> Option<(Piece, Color)>,
+    rights: [(Color, bool, bool); 2],
+) -> String {
+    let mut field = String::new();
+    for (color, kingside, queenside) in rights {
+        let back = if color == Color::White { Rank::First } else { Rank::Eighth };
+        let is = |file: usize, piece: Piece| {
+            piece_at(Square::make_square(back, File::from_index(file))) == Some((piece, color))
+        };
+        let Some(king) = (0..8).find(|&f| is(f, Piece::King)) else {
+            continue;
+        };
+        let letter = |file: usize| {
+            let letter = (b'a' + file as u8) as char;
+            if color == Color::White { letter.to_ascii_uppercase() } else { letter }
+        };
+
+        if let Some(rook) = (king + 1..8).rev().find(|&f| kingside && is(f, Piece::Rook)) {
+            field.push(letter(rook));
+        }
+        if let Some(rook) = (0..king).find(|&f| queenside && is(f, Piece::Rook)) {
+            field.push(letter(rook));
+        }
+    }
+    if field.is_empty() {
+        field.push('-');
+    }
+    field
+}
+
+/// Castling rights named in an X-FEN or Shredder-FEN castling field, as
+/// (white kingside, white queenside, black kingside, black queenside).
+/// File letters are resolved against the king's file in `placement`.
+pub fn parse_castling_field(placement: &str, field: &str) -> (bool, bool, bool, bool) {
+    let ranks: Vec<&str> = placement.split('/').collect();
+    let king_file = |rank: Option<&&str>, king: char| {
+        rank.and_then(|rank| expand_rank(rank).iter().position(|&c| c == king))
+            .unwrap_or(4)
+    };
+    let white_king = king_file(ranks.get(7), 'K');
+    let black_king = king_file(ranks.first(), 'k');
+
+    let mut rights = (false, false, false, false);
+    for c in field.chars() {
+        match c {
+            'K' => rights.0 = true,
+            'Q' => rights.1 = true,
+            'k' => rights.2 = true,
+            'q' => rights.3 = true,
+            'A'..='H' if (c as u8 - b'A') as usize > white_king => rights.0 = true,
+            'A'..='H' => rights.1 = true,
+            'a'..='h' if (c as u8 - b'a') as usize > black_king => rights.2 = true,
+            'a'..='h' => rights.3 = true,
+            _ => {}
+        }
+    }
+    rights
+}
+
+/// The FEN with any castling rights the chess crate can't play removed,
+/// written as X-FEN.  The crate only knows castling from the standard king
+/// and rook squares, so an engine without Chess960 mode plays most
+/// positions without castling.
+pub fn playable_fen(fen: &str) -> String {
+    let mut parts: Vec<String> = fen.split_whitespace().map(str::to_string).collect();
+    if parts.len() < 3 {
+        return fen.to_string();
+    }
+    let (white_king, white_queen, black_king, black_queen) = parse_castling_field(&parts[0], &parts[2]);
+    let ranks: Vec<&str> = parts[0].split('/').collect();
+    // Which of the kingside and queenside rooks are on standard squares
+    let standard = |rank: Option<&&str>, rook: char, king: char| {
+        let files = rank.map(|rank| expand_rank(rank)).unwrap_or_default();
+        let at = |f: usize| files.get(f).copied();
+        let king_home = at(4) == Some(king);
+        (king_home && at(7) == Some(rook), king_home && at(0) == Some(rook))
+    };
+    let (wk, wq) = standard(ranks.get(7), 'R', 'K');
+    let (bk, bq) = standard(ranks.first(), 'r', 'k');
+
+    let mut castling = String::new();
+    let rights = [
+        ('K', wk && white_king),
+        ('Q', wq && white_queen),
+        ('k', bk && black_king),
+        ('q', bq && black_queen),
+    ];
+    for (symbol, keep) in rights {
+        if keep {
+            castling.push(symbol);
+        }
+    }
+    if castling.is_empty() {
+        castling.push('-');
+    }
+    parts[2] = castling;
+    parts.join(" ")
+}
+
+/// The FEN with its castling field replaced.  The chess crate reads a
+/// Chess960 position with "-", the game keeping its rights in a
+/// `Castling` instead.
+pub fn with_castling(fen: &str, field: &str) -> String {
+    let mut parts: Vec<&str> = fen.split_whitespace().collect();
+    if let Some(castling) = parts.get_mut(2) {
+        *castling = field;
+    }
+    parts.join(" ")
+}
+
+/// Whether a FEN names its castling rights by file, Shredder-FEN style,
+/// as Chess960 positions are written.
+pub fn is_shredder_fen(fen: &str) -> bool {
+    fen.split_whitespace()
+        .nth(2)
+        .is_some_and(|field| field.chars().any(|c| matches!(c.to_ascii_lowercase(), 'a'..='h')))
+}
+
+/// Castling rights of a Chess960 game: the file of the rook each side may
+/// still castle with on either wing.  The chess crate only castles from
+/// the standard squares, so a Chess960 game's boards carry no rights of
+/// their own and castling is played through these.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Castling {
+    // By colour, kingside before queenside
+    rooks: [[Option<File>; 2]; 2],
+}
+
+impl Castling {
+    /// The rights in a FEN's castling field, X-FEN or Shredder-FEN, that
+    /// have a king and rook on the back rank to go with them.
+    pub fn from_fen(fen: &str) -> Castling {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let ranks: Vec<&str> = fields.first().map_or_else(Vec::new, |fen| fen.split('/').collect());
+        let field = fields.get(2).copied().unwrap_or("-");
+
+        let mut castling = Castling::default();
+        for color in [Color::White, Color::Black] {
+            let rank = if color == Color::White { ranks.get(7) } else { ranks.first() };
+            let files = rank.map(|rank| expand_rank(rank)).unwrap_or_default();
+            let (king, rook) = match color {
+                Color::White => ('K', 'R'),
+                Color::Black => ('k', 'r'),
+            };
+            let Some(king) = files.iter().position(|&c| c == king) else {
+                continue;
+            };
+            let rooks: Vec<usize> = (0..files.len()).filter(|&f| files[f] == rook).collect();
+            let letters = field.chars().filter(|c| c.is_ascii_uppercase() == (color == Color::White));
+            for letter in letters {
+                let (wing, file) = match letter.to_ascii_lowercase() {
+                    'k' => (0, rooks.iter().rev().find(|&&f| f > king).copied()),
+                    'q' => (1, rooks.iter().find(|&&f| f < king).copied()),
+                    letter @ 'a'..='h' => {
+                        let file = (letter as u8 - b'a') as usize;
+                        (usize::from(file < king), rooks.contains(&file).then_some(file))
+                    }
+                    _ => continue,
+                };
+                if let Some(file) = file {
+                    castling.rooks[color.to_index()][wing] = Some(File::from_index(file));
+                }
+            }
+        }
+        castling
+    }
+
+    /// Shredder-FEN castling field, or "-" with no rights left.
+    pub fn field(&self) -> String {
+        let mut field = String::new();
+        for color in [Color::White, Color::Black] {
+            for file in self.rooks[color.to_index()].iter().flatten() {
+                let letter = (b'a' + file.to_index() as u8) as char;
+                field.push(if color == Color::White { letter.to_ascii_uppercase() } else { letter });
+            }
+        }
+        if field.is_empty() {
+            field.push('-');
+        }
+        field
+    }
+
+    /// The castling moves open to the side to move in `board`, each
+    /// written as the king taking its own rook.  Every square either piece
+    /// crosses or lands on must be empty but for the two of them, and the
+    /// king can't castle out of, through or into check.
+    pub fn moves(&self, board: &Board) -> Vec<ChessMove> {
+        let color = board.side_to_move();
+        let back = color.to_my_backrank();
+        let king = board.king_square(color);
+        if king.get_rank() != back || board.checkers().popcnt() > 0 {
+            return Vec::new();
+        }
+        let on_back = |file: usize| Square::make_square(back, File::from_index(file));
+
+        self.rooks[color.to_index()]
+            .iter()
+            .flatten()
+            .filter_map(|&file| {
+                let rook = Square::make_square(back, file);
+                if board.piece_on(rook) != Some(Piece::Rook) || board.color_on(rook) != Some(color) {
+                    return None;
+                }
+                let castle = ChessMove::new(king, rook, None);
+                let (king_to, rook_to) = castled_squares(castle);
+                let files = [king, rook, king_to, rook_to].map(|square| square.get_file().to_index());
+                let (low, high) = (*files.iter().min()?, *files.iter().max()?);
+                let clear = (low..=high)
+                    .map(on_back)
+                    .all(|square| square == king || square == rook || board.piece_on(square).is_none());
+
+                let (from, to) = (king.get_file().to_index(), king_to.get_file().to_index());
+                let mut path = from.min(to)..=from.max(to);
+                let safe = path.all(|file| !attacked(board, king, rook, on_back(file)));
+                (clear && safe).then_some(castle)
+            })
+            .collect()
+    }
+
+    /// The rights left once `chess_move` is played in `board`: a king move
+    /// gives up both wings, and a rook leaving or taken on its square gives
+    /// up its own.
+    pub fn after(&self, board: &Board, chess_move: ChessMove) -> Castling {
+        let mut castling = *self;
+        if board.piece_on(chess_move.get_source()) == Some(Piece::King) {
+            castling.rooks[board.side_to_move().to_index()] = [None; 2];
+        }
+        for color in [Color::White, Color::Black] {
+            for rook in castling.rooks[color.to_index()].iter_mut() {
+                let square = rook.map(|file| Square::make_square(color.to_my_backrank(), file));
+                if square == Some(chess_move.get_source()) || square == Some(chess_move.get_dest()) {
+                    *rook = None;
+                }
+            }
+        }
+        castling
+    }
+}
+
+/// Whether `chess_move` castles in `board`: the king moving onto a rook
+/// of its own.
+pub fn is_castle(board: &Board, chess_move: ChessMove) -> bool {
+    let color = board.side_to_move();
+    let own = |square, piece| {
+        board.piece_on(square) == Some(piece) && board.color_on(square) == Some(color)
+    };
+    own(chess_move.get_source(), Piece::King) && own(chess_move.get_dest(), Piece::Rook)
+}
+
+/// `board` after `chess_move`, which may be a castle written as the king
+/// taking its own rook.
+pub fn make_move(board: &Board, chess_move: ChessMove) -> Board {
+    if !is_castle(board, chess_move) {
+        return board.make_move_new(chess_move);
+    }
+    let color = board.side_to_move();
+    let (king_to, rook_to) = castled_squares(chess_move);
+    let mut builder = BoardBuilder::from(board);
+    builder
+        .clear_square(chess_move.get_source())
+        .clear_square(chess_move.get_dest())
+        .piece(king_to, Piece::King, color)
+        .piece(rook_to, Piece::Rook, color)
+        .side_to_move(!color)
+        .en_passant(None);
+    Board::try_from(builder).unwrap_or(*board)
+}
+
+/// Where a castle leaves the king and rook: the g- and f-files castling
+/// kingside, the c- and d-files queenside, whatever they started on.
+pub fn castled_squares(castle: ChessMove) -> (Square, Square) {
+    let back = castle.get_source().get_rank();
+    let kingside = castle.get_dest().get_file() > castle.get_source().get_file();
+    let (king, rook) = if kingside { (File::G, File::F) } else { (File::C, File::D) };
+    (Square::make_square(back, king), Square::make_square(back, rook))
+}
+
+// Whether the king would be in check on `square`, with the castling rook
+// off the board
+fn attacked(board: &Board, king: Square, rook: Square, square: Square) -> bool {
+    let color = board.side_to_move();
+    let mut builder = BoardBuilder::from(board);
+    builder.clear_square(king).clear_square(rook).piece(square, Piece::King, color);
+    Board::try_from(builder).map_or(true, |board| board.checkers().popcnt() > 0)
+}
+
+// One FEN rank as a character per file, '.' for empty squares
+fn expand_rank(rank: &str) -> Vec<char> {
+    let mut files = Vec::new();
+    for c in rank.chars() {
+        match c.to_digit(10) {
+            Some(n) => files.extend(std::iter::repeat_n('.', n as usize)),
+            None => files.push(c),
+        }
+    }
+    files
+}
+
+fn piece_char(piece: Piece, color: Color) -> char {
+    let c = match piece {
+        Piece::Pawn => 'p',
+        Piece::Knight => 'n',
+        Piece::Bishop => 'b',
+        Piece::Rook => 'r',
+        Piece::Queen => 'q',
+        Piece::King => 'k',
+    };
+    if color == Color::White { c.to_ascii_uppercase() } else { c }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::Board;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_start_positions() {
+        // Position 518 is the standard starting array
+        assert_eq!(start_fen(518), with_castling(&Board::default().to_string(), "HAha"));
+        assert_eq!(
+            start_fen(0),
+            "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+        );
+        for index in 0..POSITION_COUNT {
+            let rank = back_rank(index);
+            let files = |piece| (0..8).filter(move |&f| rank[f] == piece);
+            let rooks: Vec<usize> = files(Piece::Rook).collect();
+            let king = files(Piece::King).next().unwrap();
+            assert!(rooks[0] < king && king < rooks[1]);
+            let bishops: Vec<usize> = files(Piece::Bishop).collect();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
+                assert!(Board::from_str(&playable_fen(&start_fen(index))).is_ok());
+            assert_eq!(Castling::from_fen(&start_fen(index)).field().len(), 4);
+        }
+        assert_eq!(playable_fen(&start_fen(518)), Board::default().to_string());
+    }
+
+    #[test]
+    fn test_castling_fields() {
+        let board = Board::from_str("4k3/8/8/8/8/8/8/RR2K2R w - - 0 1").unwrap();
+        let piece_at = |sq| board.piece_on(sq).zip(board.color_on(sq));
+        let all = [(Color::White, true, true), (Color::Black, true, true)];
+        assert_eq!(castling_field(piece_at, all), "HA");
+
+        assert_eq!(parse_castling_field("1r2k1r1/8/8/8/8/8/8/1R2K1R1", "GBgb"), (true, true, true, true));
+        assert_eq!(parse_castling_field("1r2k1r1/8/8/8/8/8/8/1R2K1R1", "Kq"), (true, false, false, true));
+
+        // X-FEN letters name the outermost rook
+        let castling = Castling::from_fen("1r2k1r1/8/8/8/8/8/8/RR2K1R1 w KQq - 0 1");
+        assert_eq!(castling.field(), "GAb");
+        assert!(is_shredder_fen("1r2k1r1/8/8/8/8/8/8/RR2K1R1 w GAb - 0 1"));
+        assert!(!is_shredder_fen("1r2k1r1/8/8/8/8/8/8/RR2K1R1 w KQq - 0 1"));
+    }
+
+    #[test]
+    fn test_castling() {
+        // King on b1 with rooks on a1 and f1; black's king is on g8
+        let fen = "6k1/8/8/8/8/8/8/RK3R2 w FA - 0 1";
+        let board = Board::from_str(&with_castling(fen, "-")).unwrap();
+        let castling = Castling::from_fen(fen);
+        let moves = castling.moves(&board);
+        let kingside = ChessMove::new(Square::B1, Square::F1, None);
+        let queenside = ChessMove::new(Square::B1, Square::A1, None);
+        assert_eq!(moves, vec![kingside, queenside]);
+        assert!(is_castle(&board, kingside));
+
+        let after = make_move(&board, kingside);
+        assert_eq!(after.piece_on(Square::G1), Some(Piece::King));
+        assert_eq!(after.piece_on(Square::F1), Some(Piece::Rook));
+        assert_eq!(after.piece_on(Square::B1), None);
+        assert_eq!(after.side_to_move(), Color::Black);
+        assert_eq!(castling.after(&board, kingside).field(), "-");
+        let rook_move = ChessMove::new(Square::A1, Square::A2, None);
+        assert_eq!(castling.after(&board, rook_move).field(), "F");
+
+        // The king can't pass through d1, which a rook attacks
+        let fen = "3rk3/8/8/8/8/8/8/RK3R2 w FA - 0 1";
+        let board = Board::from_str(&with_castling(fen, "-")).unwrap();
+        assert_eq!(Castling::from_fen(fen).moves(&board), vec![queenside]);
+        // Nor castle with a piece in the way
+        let fen = "4k3/8/8/8/8/8/8/RK2NR2 w FA - 0 1";
+        let board = Board::from_str(&with_castling(fen, "-")).unwrap();
+        assert_eq!(Castling::from_fen(fen).moves(&board), vec![queenside]);
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use chess::Color;
+use serde::{Deserialize, Serialize};
+
+/// Starting time on each clock plus a per-move increment or delay.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    pub base_ms: u64,
+    pub increment_ms: u64,
+    pub mode: ClockMode,
+}
+
+/// What a player gets back for each move.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ClockMode {
+    /// Fischer increment: the full increment after every move
+    #[default]
+    Increment,
+    /// Bronstein delay: the time the move took, up to the increment
+    Delay,
+}
+
+impl TimeControl {
+    /// Time given back for a move that took `used_ms`.
+    pub fn credit_ms(&self, used_ms: u64) -> u64 {
+        match self.mode {
+            ClockMode::Increment => self.increment_ms,
+            ClockMode::Delay => used_ms.min(self.increment_ms),
+        }
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = String;
+
+    /// "SECONDS", "SECONDS+INCREMENT" or "SECONDSdDELAY", e.g. "60+0.5"
+    /// or "300d2"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let seconds = |part: &str| {
+            part.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|secs| secs.is_finite() && *secs >= 0.0)
+                .map(|secs| (secs * 1000.0).round() as u64)
+                .ok_or_else(|| format!("invalid time control '{}', expected SECONDS[+INCREMENT]", s))
+        };
+        let (mode, (base, increment)) = match (s.split_once('+'), s.split_once('d')) {
+            (Some(split), _) => (ClockMode::Increment, split),
+            (None, Some(split)) => (ClockMode::Delay, split),
+            (None, None) => (ClockMode::Increment, (s, "0")),
+        };
+        let base_ms = seconds(base)?;
+        if base_ms == 0 {
+            return Err(format!("time control '{}' has no time on the clock", s));
+        }
+        Ok(TimeControl {
+            base_ms,
+            increment_ms: seconds(increment)?,
+            mode,
+        })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    /// PGN TimeControl tag form, e.g. "60+0.5"; PGN has no form for a
+    /// delay, so it is written as "300d2", the way it is parsed
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = |ms: u64| ms as f64 / 1000.0;
+        write!(f, "{}", secs(self.base_ms))?;
+        if self.increment_ms > 0 {
+            let separator = if self.mode == ClockMode::Delay { 'd' } else { '+' };
+            write!(f, "{}{}", separator, secs(self.increment_ms))?;
+        }
+        Ok(())
+    }
+}
+
+/// A two-sided chess clock.  One side's time runs at a time; pressing the
+/// clock after a move stops it, adds the increment or delay, and starts
+/// the other.
+#[derive(Clone, Debug)]
+pub struct GameClock {
+    /// White's and Black's time controls, which differ in time-odds games
+    controls: [TimeControl; 2],
+    /// Time left for White and Black as of the last press
+    remaining: [Duration; 2],
+    /// Side whose time is running, and since when
+    running: Option<(Color, Instant)>,
+    /// Time the side to move had when its turn began, to work out a delay
+    turn_start: Duration,
+}
+
+impl GameClock {
+    /// Full clocks for White and Black, on their own time controls, with
+    /// `to_move`'s time already running.
+    pub fn new(white: TimeControl, black: TimeControl, to_move: Color) -> Self {
+        let base = |control: TimeControl| Duration::from_millis(control.base_ms);
+        let remaining = [base(white), base(black)];
+        GameClock {
+            controls: [white, black],
+            remaining,
+            running: Some((to_move, Instant::now())),
+            turn_start: remaining[to_move.to_index()],
+        }
+    }
+
+    /// Time left for `side`, counting down while its clock runs.
+    pub fn remaining(&self, side: Color) -> Duration {
+        let left = self.remaining[side.to_index()];
+        match self.running {
+            Some((running, since)) if running == side => left.saturating_sub(since.elapsed()),
+            _ => left,
+        }
+    }
+
+    pub fn running(&self) -> Option<Color> {
+        self.running.map(|(side, _)| side)
+    }
+
+    /// End the running side's turn: charge its time, add the increment or
+    /// delay and start the opponent.  Returns the mover's time left after
+    /// the move.
+    pub fn press(&mut self) -> Option<Duration> {
+        let (side, _) = self.running?;
+        self.stop();
+        let left = &mut self.remaining[side.to_index()];
+        let used = self.turn_start.saturating_sub(*left).as_millis() as u64;
+        *left += Duration::from_millis(self.controls[side.to_index()].credit_ms(used));
+        let left = *left;
+        self.start_turn(!side);
+        Some(left)
+    }
+
+    /// Run `side`'s time instead of whoever's is running, without an
+    /// increment, as a new turn; used when moves are taken back.
+    pub fn run_for(&mut self, side: Color) {
+        self.stop();
+        self.start_turn(side);
+    }
+
+    /// Carry on with `side`'s turn after the clocks were stopped, keeping
+    /// the time it has already used.
+    pub fn resume(&mut self, side: Color) {
+        if self.running.is_none() {
+            self.running = Some((side, Instant::now()));
+        }
+    }
+
+    /// Put saved times back on the clocks, with `to_move`'s time running
+    /// from now.
+    pub fn set_remaining(&mut self, white: Duration, black: Duration, to_move: Color) {
+        self.remaining = [white, black];
+        self.start_turn(to_move);
+    }
+
+    fn start_turn(&mut self, side: Color) {
+        self.turn_start = self.remaining[side.to_index()];
+        self.running = Some((side, Instant::now()));
+    }
+
+    /// Stop both clocks, charging the running side for its time so far.
+    pub fn stop(&mut self) {
+        if let Some((side, _)) = self.running {
+            self.remaining[side.to_index()] = self.remaining(side);
+            self.running = None;
+        }
+    }
+}
+
+/// Clock time as PGN `[%clk]` commands write it, e.g. "0:04:58".
+pub fn format_clock(time: Duration) -> String {
+    let secs = time.as_secs();
+    format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Reads "h:mm:ss" or "m:ss", with optional fractional seconds.
+pub fn parse_clock(s: &str) -> Option<Duration> {
+    let mut secs = 0.0;
+    for part in s.trim().split(':') {
+        let value = part.parse::<f64>().ok().filter(|v| v.is_finite() && *v >= 0.0)?;
+        secs = secs * 60.0 + value;
+    }
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// Clock time for the side panel: "m:ss", with hours once they matter and
+/// tenths in the last ten seconds.
+pub fn display_clock(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs >= 3600 {
+        format_clock(time)
+    } else if secs < 10 {
+        format!("0:{:02}.{}", secs, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+/// How long a move took, for the move list: "0.8s" under ten seconds,
+/// "42s" under a minute, then "m:ss".
+pub fn display_move_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    if secs < 10 {
+        format!("{:.1}s", time.as_secs_f64())
+    } else if secs < 60 {
+        format!("{}s", secs)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_control() {
+        let tc: TimeControl = "60+0.5".parse().unwrap();
+        assert_eq!((tc.base_ms, tc.increment_ms), (60_000, 500));
+        assert_eq!(tc.to_string(), "60+0.5");
+        assert_eq!("10".parse::<TimeControl>().unwrap().to_string(), "10");
+        let tc: TimeControl = "300d2".parse().unwrap();
+        assert_eq!((tc.base_ms, tc.increment_ms, tc.mode), (300_000, 2_000, ClockMode::Delay));
+        assert_eq!(tc.to_string(), "300d2");
+        assert_eq!((tc.credit_ms(500), tc.credit_ms(5_000)), (500, 2_000));
+        assert!("0+1".parse::<TimeControl>().is_err());
+        assert!("fast".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn test_clock_press() {
+        let tc = TimeControl { base_ms: 300_000, increment_ms: 2_000, mode: ClockMode::Increment };
+        let mut clock = GameClock::new(tc, tc, Color::White);
+        let left = clock.press().unwrap();
+        // White's time barely moved and gained the increment
+        assert!(left > Duration::from_secs(301) && left <= Duration::from_secs(302));
+        assert_eq!(clock.running(), Some(Color::Black));
+        clock.stop();
+        assert_eq!(clock.press(), None);
+        assert!(clock.remaining(Color::Black) <= Duration::from_secs(300));
+
+        let odds = TimeControl { base_ms: 60_000, ..tc };
+        let clock = GameClock::new(tc, odds, Color::White);
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+
+        // A delay gives back no more than the move took
+        let delay = TimeControl { mode: ClockMode::Delay, ..tc };
+        let mut clock = GameClock::new(delay, delay, Color::White);
+        std::thread::sleep(Duration::from_millis(20));
+        let left = clock.press().unwrap();
+        assert!(left > Duration::from_millis(299_990) && left <= Duration::from_secs(300));
+
+        assert_eq!(format_clock(Duration::from_secs(3_725)), "1:02:05");
+        assert_eq!(parse_clock("0:04:58"), Some(Duration::from_secs(298)));
+        assert_eq!(parse_clock("1:30.5"), Some(Duration::from_millis(90_500)));
+        assert_eq!(parse_clock("soon"), None);
+        assert_eq!(display_clock(Duration::from_millis(9_450)), "0:09.4");
+        assert_eq!(display_move_time(Duration::from_millis(840)), "0.8s");
+        assert_eq!(display_move_time(Duration::from_millis(42_900)), "42s");
+        assert_eq!(display_move_time(Duration::from_secs(125)), "2:05");
+    }
+}
@@ -0,0 +1,564 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::AppError;
+use crate::stats::Outcome;
+
+/// A finished game as stored in the local database.
+#[derive(Debug, Clone)]
+pub struct StoredGame {
+    pub id: i64,
+    /// Local time the game finished, "YYYY-MM-DD HH:MM"
+    pub played_at: String,
+    pub engine: String,
+    pub skill_level: u8,
+    /// "w" or "b", the side the user played
+    pub player_color: String,
+    /// PGN result token ("1-0", "0-1", "1/2-1/2")
+    pub result: String,
+    pub start_fen: String,
+    /// Space-separated UCI moves, used to replay the game exactly
+    pub moves: String,
+    pub pgn: String,
+    /// The player took moves back, had the coach check them or looked at
+    /// engine analysis
+    pub assisted: bool,
+    /// Labels the user attached, e.g. "opening prep" or "lost on time"
+    pub tags: Vec<String>,
+}
+
+impl StoredGame {
+    pub fn move_list(&self) -> Vec<String> {
+        self.moves.split_whitespace().map(str::to_string).collect()
+    }
+
+    /// Value of a tag in the PGN header, e.g. "Opening".
+    pub fn pgn_tag(&self, name: &str) -> Option<&str> {
+        let mut header = self.pgn.lines().map(str::trim).take_while(|line| line.starts_with('['));
+        header.find_map(|line| {
+            let value = line.strip_prefix('[')?.strip_prefix(name)?.trim_start();
+            value.strip_prefix('"')?.strip_suffix("\"]")
+        })
+    }
+}
+
+/// What the game browser is narrowed down to; blank fields match any game.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GameFilter {
+    /// One of the user's tags
+    pub tag: Option<String>,
+    /// The result from the user's side
+    pub outcome: Option<Outcome>,
+    /// Part of the engine's name
+    pub engine: String,
+    /// Part of the opening's name, or its ECO code
+    pub opening: String,
+    /// First and last day, "YYYY-MM-DD"; "2024-03" covers the whole month
+    pub from: String,
+    pub to: String,
+}
+
+impl GameFilter {
+    pub fn matches(&self, game: &StoredGame) -> bool {
+        let contains = |text: &str, part: &str| {
+            text.to_lowercase().contains(&part.trim().to_lowercase())
+        };
+        let opening = self.opening.trim();
+        let (from, to) = (self.from.trim(), self.to.trim());
+        let day = game.played_at.as_str();
+        self.tag.as_ref().is_none_or(|tag| game.tags.contains(tag))
+            && self.outcome.is_none_or(|outcome| {
+                Outcome::for_player(&game.result, &game.player_color) == Some(outcome)
+            })
+            && contains(&game.engine, &self.engine)
+            && (opening.is_empty()
+                || [game.pgn_tag("Opening"), game.pgn_tag("ECO")]
+                    .into_iter()
+                    .flatten()
+                    .any(|name| contains(name, opening)))
+            && day >= from
+            && (to.is_empty() || day.get(..to.len()).unwrap_or(day) <= to)
+    }
+}
+
+/// A position where the user blundered, saved for another try.
+#[derive(Debug, Clone)]
+pub struct StoredPuzzle {
+    pub id: i64,
+    /// Local time the puzzle was saved, "YYYY-MM-DD HH:MM"
+    pub created_at: String,
+    /// Position before the blunder; the side to move is the user's
+    pub fen: String,
+    /// Engine's best move in the position (UCI)
+    pub solution: String,
+    /// The blunder that was played (UCI)
+    pub played: String,
+    pub attempts: u32,
+    pub solved: bool,
+}
+
+/// Totals for one run of the application, kept with the games it played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredSession {
+    pub id: i64,
+    /// Local time the session started, "YYYY-MM-DD HH:MM"
+    pub started_at: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Time spent on moves, by both sides
+    pub seconds: u64,
+    /// Average accuracy over the reviewed games, if any were
+    pub accuracy: Option<f64>,
+}
+
+impl StoredSession {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+}
+
+/// SQLite-backed archive of every finished game.
+pub struct GameDatabase {
+    conn: Connection,
+}
+
+impl GameDatabase {
+    /// Open (creating if needed) the database at `path`.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AppError> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Self::init(Connection::open(path)?)
+    }
+
+    /// Open the database in the application's data directory.
+    pub fn open_default() -> Result<Self, AppError> {
+        let dir = crate::persistence::data_dir()
+            .ok_or_else(|| AppError::Database("No data directory available".to_string()))?;
+        Self::open(dir.join("games.sqlite3"))
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, AppError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, AppError> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS games (
+                id           INTEGER PRIMARY KEY AUTOINCREMENT,
+                played_at    TEXT NOT NULL,
+                engine       TEXT NOT NULL,
+                skill_level  INTEGER NOT NULL,
+                player_color TEXT NOT NULL,
+                result       TEXT NOT NULL,
+                start_fen    TEXT NOT NULL,
+                moves        TEXT NOT NULL,
+                pgn          TEXT NOT NULL,
+                assisted     INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS game_tags (
+                game_id      INTEGER NOT NULL,
+                tag          TEXT NOT NULL,
+                PRIMARY KEY (game_id, tag)
+            );
+            CREATE TABLE IF NOT EXISTS puzzles (
+                id           INTEGER PRIMARY KEY AUTOINCREMENT,
+                created_at   TEXT NOT NULL,
+                fen          TEXT NOT NULL UNIQUE,
+                solution     TEXT NOT NULL,
+                played       TEXT NOT NULL,
+                attempts     INTEGER NOT NULL DEFAULT 0,
+                solved       INTEGER NOT NULL DEFAULT 0
+            );
+            CREATE TABLE IF NOT EXISTS sessions (
+                id           INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at   TEXT NOT NULL,
+                wins         INTEGER NOT NULL,
+                draws        INTEGER NOT NULL,
+                losses       INTEGER NOT NULL,
+                seconds      INTEGER NOT NULL,
+                accuracy     REAL
+            );",
+        )?;
+        // Databases from before assisted games were marked lack the column
+        if conn.prepare("SELECT assisted FROM games LIMIT 0").is_err() {
+            conn.execute_batch("ALTER TABLE games ADD COLUMN assisted INTEGER NOT NULL DEFAULT 0")?;
+        }
+        Ok(GameDatabase { conn })
+    }
+
+    /// Insert a finished game, returning its new id.  The `id` and `tags`
+    /// fields of `game` are ignored.
+    pub fn insert_game(&self, game: &StoredGame) -> Result<i64, AppError> {
+        self.conn.execute(
+            "INSERT INTO games (played_at, engine, skill_level, player_color, result, start_fen, moves, pgn,
+                                assisted)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                game.played_at,
+                game.engine,
+                game.skill_level,
+                game.player_color,
+                game.result,
+                game.start_fen,
+                game.moves,
+                game.pgn,
+                game.assisted,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// The latest stored game with the same start, moves and players (the
+    /// engine and the user's side) as `game`: its id and when it was played.
+    pub fn find_duplicate(&self, game: &StoredGame) -> Result<Option<(i64, String)>, AppError> {
+        let duplicate = self
+            .conn
+            .query_row(
+                "SELECT id, played_at FROM games
+                 WHERE start_fen = ?1 AND moves = ?2 AND engine = ?3 AND player_color = ?4
+                 ORDER BY id DESC LIMIT 1",
+                params![game.start_fen, game.moves, game.engine, game.player_color],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        Ok(duplicate)
+    }
+
+    /// Overwrite the stored game `id` with `game`, keeping the id.
+    pub fn replace_game(&self, id: i64, game: &StoredGame) -> Result<(), AppError> {
+        self.conn.execute(
+            "UPDATE games SET played_at = ?1, engine = ?2, skill_level = ?3, player_color = ?4,
+                              result = ?5, start_fen = ?6, moves = ?7, pgn = ?8, assisted = ?9
+             WHERE id = ?10",
+            params![
+                game.played_at,
+                game.engine,
+                game.skill_level,
+                game.player_color,
+                game.result,
+                game.start_fen,
+                game.moves,
+                game.pgn,
+                game.assisted,
+                id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// All stored games, most recent first.
+    pub fn list_games(&self) -> Result<Vec<StoredGame>, AppError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, played_at, engine, skill_level, player_color, result, start_fen, moves, pgn, assisted
+             FROM games ORDER BY id DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(StoredGame {
+                id: row.get(0)?,
+                played_at: row.get(1)?,
+                engine: row.get(2)?,
+                skill_level: row.get(3)?,
+                player_color: row.get(4)?,
+                result: row.get(5)?,
+                start_fen: row.get(6)?,
+                moves: row.get(7)?,
+                pgn: row.get(8)?,
+                assisted: row.get(9)?,
+                tags: Vec::new(),
+            })
+        })?;
+        let mut games = rows.collect::<Result<Vec<_>, _>>()?;
+
+        let index: HashMap<i64, usize> =
+            games.iter().enumerate().map(|(i, game)| (game.id, i)).collect();
+        let mut stmt = self.conn.prepare("SELECT game_id, tag FROM game_tags ORDER BY tag")?;
+        let tags = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+        for tag in tags {
+            let (id, tag) = tag?;
+            if let Some(&i) = index.get(&id) {
+                games[i].tags.push(tag);
+            }
+        }
+        Ok(games)
+    }
+
+    /// Attach a tag to a stored game; one it already has is left alone.
+    pub fn add_tag(&self, id: i64, tag: &str) -> Result<(), AppError> {
+        self.conn.execute(
+            "INSERT OR IGNORE INTO game_tags (game_id, tag) VALUES (?1, ?2)",
+            params![id, tag],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_tag(&self, id: i64, tag: &str) -> Result<(), AppError> {
+        self.conn.execute("DELETE FROM game_tags WHERE game_id = ?1 AND tag = ?2", params![id, tag])?;
+        Ok(())
+    }
+
+    /// Replace the PGN of a stored game, e.g. after its moves were annotated.
+    pub fn update_pgn(&self, id: i64, pgn: &str) -> Result<(), AppError> {
+        self.conn.execute("UPDATE games SET pgn = ?1 WHERE id = ?2", params![pgn, id])?;
+        Ok(())
+    }
+
+    pub fn delete_game(&self, id: i64) -> Result<(), AppError> {
+        self.conn.execute("DELETE FROM games WHERE id = ?1", params![id])?;
+        self.conn.execute("DELETE FROM game_tags WHERE game_id = ?1", params![id])?;
+        Ok(())
+    }
+
+    /// Insert a session's totals, returning its new id.  The `id` field of
+    /// `session` is ignored.
+    pub fn insert_session(&self, session: &StoredSession) -> Result<i64, AppError> {
+        self.conn.execute(
+            "INSERT INTO sessions (started_at, wins, draws, losses, seconds, accuracy)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                session.started_at,
+                session.wins,
+                session.draws,
+                session.losses,
+                session.seconds,
+                session.accuracy,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Replace the totals of the stored session with `session`'s id.
+    pub fn update_session(&self, session: &StoredSession) -> Result<(), AppError> {
+        self.conn.execute(
+            "UPDATE sessions SET wins = ?2, draws = ?3, losses = ?4, seconds = ?5, accuracy = ?6
+             WHERE id = ?1",
+            params![
+                session.id,
+                session.wins,
+                session.draws,
+                session.losses,
+                session.seconds,
+                session.accuracy,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// All stored sessions, most recent first.
+    pub fn list_sessions(&self) -> Result<Vec<StoredSession>, AppError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, started_at, wins, draws, losses, seconds, accuracy
+             FROM sessions ORDER BY id DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(StoredSession {
+                id: row.get(0)?,
+                started_at: row.get(1)?,
+                wins: row.get(2)?,
+                draws: row.get(3)?,
+                losses: row.get(4)?,
+                seconds: row.get(5)?,
+                accuracy: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Save a puzzle unless its position is already in the queue.  Returns
+    /// whether a new puzzle was added.
+    pub fn insert_puzzle(&self, puzzle: &StoredPuzzle) -> Result<bool, AppError> {
+        let inserted = self.conn.execute(
+            "INSERT OR IGNORE INTO puzzles (created_at, fen, solution, played)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![puzzle.created_at, puzzle.fen, puzzle.solution, puzzle.played],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    /// All puzzles, unsolved ones first, oldest first within each group.
+    pub fn list_puzzles(&self) -> Result<Vec<StoredPuzzle>, AppError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, created_at, fen, solution, played, attempts, solved
+             FROM puzzles ORDER BY solved, id",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(StoredPuzzle {
+                id: row.get(0)?,
+                created_at: row.get(1)?,
+                fen: row.get(2)?,
+                solution: row.get(3)?,
+                played: row.get(4)?,
+                attempts: row.get(5)?,
+                solved: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<Result<Vec<_>, _>>()?)
+    }
+
+    /// Count one attempt at a puzzle, marking it solved on success.
+    pub fn record_puzzle_attempt(&self, id: i64, solved: bool) -> Result<(), AppError> {
+        self.conn.execute(
+            "UPDATE puzzles SET attempts = attempts + 1, solved = MAX(solved, ?2) WHERE id = ?1",
+            params![id, solved],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_puzzle(&self, id: i64) -> Result<(), AppError> {
+        self.conn.execute("DELETE FROM puzzles WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_game(result: &str) -> StoredGame {
+        StoredGame {
+            id: 0,
+            played_at: "2024-01-01 12:00".to_string(),
+            engine: "stockfish".to_string(),
+            skill_level: 10,
+            player_color: "w".to_string(),
+            result: result.to_string(),
+            start_fen: chess::Board::default().to_string(),
+            moves: "f2f3 e7e5 g2g4 d8h4".to_string(),
+            pgn: String::new(),
+            assisted: false,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_insert_list_delete() {
+        let db = GameDatabase::open_in_memory().unwrap();
+        let first = db.insert_game(&sample_game("0-1")).unwrap();
+        let second = db.insert_game(&sample_game("1-0")).unwrap();
+
+        let games = db.list_games().unwrap();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].id, second);
+        assert_eq!(games[0].move_list().len(), 4);
+
+        db.delete_game(first).unwrap();
+        let games = db.list_games().unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].result, "1-0");
+    }
+
+    #[test]
+    fn test_tags_and_filter() {
+        let db = GameDatabase::open_in_memory().unwrap();
+        let lost = db.insert_game(&sample_game("0-1")).unwrap();
+        let won = db
+            .insert_game(&StoredGame {
+                played_at: "2024-03-05 18:00".to_string(),
+                engine: "lc0".to_string(),
+                pgn: "[ECO \"C00\"]\n[Opening \"French Defense\"]\n\n1. e4 e6 1-0".to_string(),
+                ..sample_game("1-0")
+            })
+            .unwrap();
+        db.add_tag(lost, "blitz").unwrap();
+        db.add_tag(lost, "lost on time").unwrap();
+        db.add_tag(lost, "blitz").unwrap();
+        db.add_tag(won, "blitz").unwrap();
+        db.remove_tag(won, "blitz").unwrap();
+
+        let games = db.list_games().unwrap();
+        assert_eq!(games[1].tags, ["blitz", "lost on time"]);
+        assert!(games[0].tags.is_empty());
+        assert_eq!(games[0].pgn_tag("Opening"), Some("French Defense"));
+
+        let shown = |filter: GameFilter| -> Vec<i64> {
+            games.iter().filter(|game| filter.matches(game)).map(|game| game.id).collect()
+        };
+        assert_eq!(shown(GameFilter::default()), [won, lost]);
+        assert_eq!(shown(GameFilter { tag: Some("blitz".to_string()), ..Default::default() }), [lost]);
+        assert_eq!(shown(GameFilter { outcome: Some(Outcome::Win), ..Default::default() }), [won]);
+        assert_eq!(shown(GameFilter { engine: "LC0".to_string(), ..Default::default() }), [won]);
+        assert_eq!(shown(GameFilter { opening: "french".to_string(), ..Default::default() }), [won]);
+        assert_eq!(shown(GameFilter { opening: "C0".to_string(), ..Default::default() }), [won]);
+        let march = GameFilter {
+            from: "2024-03".to_string(),
+            to: "2024-03".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(shown(march), [won]);
+        assert_eq!(shown(GameFilter { to: "2024-01-01".to_string(), ..Default::default() }), [lost]);
+    }
+
+    #[test]
+    fn test_find_and_replace_duplicate() {
+        let db = GameDatabase::open_in_memory().unwrap();
+        let id = db.insert_game(&sample_game("0-1")).unwrap();
+
+        let mut again = sample_game("0-1");
+        again.played_at = "2024-02-01 09:30".to_string();
+        assert_eq!(db.find_duplicate(&again).unwrap(), Some((id, "2024-01-01 12:00".to_string())));
+        // Another opponent, or other moves, make another game
+        let other_engine = StoredGame { engine: "lc0".to_string(), ..again.clone() };
+        assert_eq!(db.find_duplicate(&other_engine).unwrap(), None);
+        let other_moves = StoredGame { moves: "e2e4".to_string(), ..again.clone() };
+        assert_eq!(db.find_duplicate(&other_moves).unwrap(), None);
+
+        db.replace_game(id, &again).unwrap();
+        let games = db.list_games().unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!((games[0].id, games[0].played_at.as_str()), (id, "2024-02-01 09:30"));
+    }
+
+    #[test]
+    fn test_puzzle_queue() {
+        let db = GameDatabase::open_in_memory().unwrap();
+        let puzzle = |fen: &str| StoredPuzzle {
+            id: 0,
+            created_at: "2024-01-01 12:00".to_string(),
+            fen: fen.to_string(),
+            solution: "e2e4".to_string(),
+            played: "f2f3".to_string(),
+            attempts: 0,
+            solved: false,
+        };
+
+        assert!(db.insert_puzzle(&puzzle("first")).unwrap());
+        assert!(db.insert_puzzle(&puzzle("second")).unwrap());
+        // The same position is only queued once
+        assert!(!db.insert_puzzle(&puzzle("first")).unwrap());
+
+        let first = db.list_puzzles().unwrap()[0].id;
+        db.record_puzzle_attempt(first, false).unwrap();
+        db.record_puzzle_attempt(first, true).unwrap();
+
+        let puzzles = db.list_puzzles().unwrap();
+        assert_eq!(puzzles.len(), 2);
+        // Solved puzzles move to the back of the queue
+        assert_eq!(puzzles[0].fen, "second");
+        assert!(puzzles[1].solved);
+        assert_eq!(puzzles[1].attempts, 2);
+    }
+
+    #[test]
+    fn test_session_totals() {
+        let db = GameDatabase::open_in_memory().unwrap();
+        let mut session = StoredSession {
+            id: 0,
+            started_at: "2024-01-01 12:00".to_string(),
+            wins: 1,
+            draws: 0,
+            losses: 0,
+            seconds: 300,
+            accuracy: None,
+        };
+        session.id = db.insert_session(&session).unwrap();
+        session.losses = 1;
+        session.accuracy = Some(81.5);
+        db.update_session(&session).unwrap();
+
+        assert_eq!(db.list_sessions().unwrap(), vec![session]);
+    }
+}
@@ -0,0 +1,238 @@
+use std::{fmt::Write as _, fs, path::Path};
+
+use chess::{Board, ChessMove, Color, File, Piece, Rank, Square};
+use resvg::tiny_skia;
+use resvg::usvg::{self, fontdb, TreeParsing, TreeTextToPath};
+
+use crate::error::AppError;
+
+/// Side of a square, in the units of the 45x45 piece drawings
+const SQUARE: usize = 45;
+/// Border around the board, wide enough for coordinate labels
+const MARGIN: usize = 16;
+/// PNG pixels per SVG unit
+const PNG_SCALE: f32 = 2.0;
+
+// Board colours, matching the board on screen
+const LIGHT_SQUARE: &str = "#ededcc";
+const DARK_SQUARE: &str = "#759657";
+const LAST_MOVE: &str = "#f6f669";
+const BORDER: &str = "#302e2b";
+
+/// What to draw besides the pieces.
+#[derive(Clone, Copy, Debug)]
+pub struct DiagramOptions {
+    /// Side shown at the bottom
+    pub orientation: Color,
+    /// Move to highlight, if any
+    pub last_move: Option<ChessMove>,
+    /// Files and ranks along the edges
+    pub coordinates: bool,
+}
+
+/// The bundled drawing of a piece, a 45x45 SVG document.
+pub fn piece_svg(piece: Piece, color: Color) -> &'static str {
+    match (color, piece) {
+        (Color::White, Piece::Pawn) => include_str!("../assets/wp.svg"),
+        (Color::White, Piece::Knight) => include_str!("../assets/wn.svg"),
+        (Color::White, Piece::Bishop) => include_str!("../assets/wb.svg"),
+        (Color::White, Piece::Rook) => include_str!("../assets/wr.svg"),
+        (Color::White, Piece::Queen) => include_str!("../assets/wq.svg"),
+        (Color::White, Piece::King) => include_str!("../assets/wk.svg"),
+        (Color::Black, Piece::Pawn) => include_str!("../assets/bp.svg"),
+        (Color::Black, Piece::Knight) => include_str!("../assets/bn.svg"),
+        (Color::Black, Piece::Bishop) => include_str!("../assets/bb.svg"),
+        (Color::Black, Piece::Rook) => include_str!("../assets/br.svg"),
+        (Color::Black, Piece::Queen) => include_str!("../assets/bq.svg"),
+        (Color::Black, Piece::King) => include_str!("../assets/bk.svg"),
+    }
+}
+
+/// A standalone SVG document of `board`.
+pub fn board_svg(board: &Board, options: &DiagramOptions) -> String {
+    let margin = if options.coordinates { MARGIN } else { 0 };
+    let size = SQUARE * 8 + margin * 2;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{0}\" viewBox=\"0 0 {0} {0}\">\n",
+        size
+    );
+    if options.coordinates {
+        let _ = writeln!(svg, "<rect width=\"{0}\" height=\"{0}\" fill=\"{1}\"/>", size, BORDER);
+    }
+
+    // Top-left corner of the square at display row and column
+    let corner = |row: usize, col: usize| (margin + col * SQUARE, margin + row * SQUARE);
+    let highlighted = |square: Square| {
+        options
+            .last_move
+            .is_some_and(|m| m.get_source() == square || m.get_dest() == square)
+    };
+
+    for row in 0..8 {
+        for col in 0..8 {
+            let square = display_square(options.orientation, row, col);
+            let (x, y) = corner(row, col);
+            // a1 is a dark square
+            let light = (square.get_rank().to_index() + square.get_file().to_index()) % 2 == 1;
+            let fill = if light { LIGHT_SQUARE } else { DARK_SQUARE };
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\"/>",
+                x, y, SQUARE, fill
+            );
+            if highlighted(square) {
+                let _ = writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{2}\" height=\"{2}\" fill=\"{3}\" fill-opacity=\"0.5\"/>",
+                    x, y, SQUARE, LAST_MOVE
+                );
+            }
+            if let (Some(piece), Some(color)) = (board.piece_on(square), board.color_on(square)) {
+                svg.push_str(&place_piece(piece_svg(piece, color), x, y));
+                svg.push('\n');
+            }
+        }
+    }
+
+    if options.coordinates {
+        let label = |svg: &mut String, x: usize, y: usize, text: char| {
+            let _ = writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" font-family=\"Noto Sans\" font-size=\"11\" fill=\"#d0d0d0\" \
+                 text-anchor=\"middle\">{}</text>",
+                x, y, text
+            );
+        };
+        for i in 0..8 {
+            let square = display_square(options.orientation, 7 - i, i);
+            let file = (b'a' + square.get_file().to_index() as u8) as char;
+            let rank = (b'1' + square.get_rank().to_index() as u8) as char;
+            let (x, y) = corner(7 - i, i);
+            label(&mut svg, x + SQUARE / 2, size - 4, file);
+            label(&mut svg, margin / 2, y + SQUARE / 2 + 4, rank);
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A text diagram of `board` for pasting into chats: Unicode figurines,
+/// or piece letters with `ascii`, ranks down the left and files below.
+pub fn board_text(board: &Board, orientation: Color, ascii: bool) -> String {
+    let mut text = String::new();
+    for row in 0..8 {
+        let rank = display_square(orientation, row, 0).get_rank().to_index() + 1;
+        let _ = write!(text, "{}", rank);
+        for col in 0..8 {
+            let square = display_square(orientation, row, col);
+            let symbol = match (board.piece_on(square), board.color_on(square)) {
+                (Some(piece), Some(color)) if ascii => {
+                    piece.to_string(color).chars().next().unwrap_or('?')
+                }
+                (Some(piece), Some(color)) => figurine(piece, color),
+                _ if ascii => '.',
+                _ => '·',
+            };
+            let _ = write!(text, " {}", symbol);
+        }
+        text.push('\n');
+    }
+    text.push(' ');
+    for col in 0..8 {
+        let file = display_square(orientation, 7, col).get_file().to_index() as u8;
+        let _ = write!(text, " {}", (b'a' + file) as char);
+    }
+    text.push('\n');
+    text
+}
+
+/// Unicode chess symbol for a piece.
+pub fn figurine(piece: Piece, color: Color) -> char {
+    let symbols = match color {
+        Color::White => ['♙', '♘', '♗', '♖', '♕', '♔'],
+        Color::Black => ['♟', '♞', '♝', '♜', '♛', '♚'],
+    };
+    symbols[piece.to_index()]
+}
+
+/// Write the position to `path`, as SVG or PNG depending on the extension.
+pub fn save_image(board: &Board, options: &DiagramOptions, path: &Path) -> Result<(), AppError> {
+    let svg = board_svg(board, options);
+    let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or("");
+    if extension.eq_ignore_ascii_case("svg") {
+        fs::write(path, svg)?;
+    } else if extension.eq_ignore_ascii_case("png") {
+        fs::write(path, render_png(&svg)?)?;
+    } else {
+        return Err(AppError::Image(format!(
+            "Unsupported image format '{}', use .png or .svg",
+            extension
+        )));
+    }
+    Ok(())
+}
+
+// Rasterise with the bundled font, so coordinates look the same everywhere
+fn render_png(svg: &str) -> Result<Vec<u8>, AppError> {
+    let mut tree = usvg::Tree::from_str(svg, &usvg::Options::default())
+        .map_err(|e| AppError::Image(e.to_string()))?;
+    let mut fonts = fontdb::Database::new();
+    fonts.load_font_data(include_bytes!("../assets/fonts/NotoSans-Regular.ttf").to_vec());
+    tree.convert_text(&fonts);
+
+    let size = tree.size.to_int_size().scale_by(PNG_SCALE);
+    let mut pixmap = size
+        .and_then(|size| tiny_skia::Pixmap::new(size.width(), size.height()))
+        .ok_or_else(|| AppError::Image("Empty image".to_string()))?;
+    resvg::Tree::from_usvg(&tree).render(
+        tiny_skia::Transform::from_scale(PNG_SCALE, PNG_SCALE),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| AppError::Image(e.to_string()))
+}
+
+// Square drawn at a display row (0 at the top) and column
+fn display_square(orientation: Color, row: usize, col: usize) -> Square {
+    let (file, rank) = match orientation {
+        Color::White => (col, 7 - row),
+        Color::Black => (7 - col, row),
+    };
+    Square::make_square(Rank::from_index(rank), File::from_index(file))
+}
+
+// A piece drawing as a nested <svg> element at (x, y)
+fn place_piece(drawing: &str, x: usize, y: usize) -> String {
+    let start = drawing.find("<svg").unwrap_or(0);
+    drawing[start..].replacen("<svg", &format!("<svg x=\"{}\" y=\"{}\"", x, y), 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_board_svg() {
+        let board = Board::default();
+        let e4 = crate::game::parse_uci_move(&board, "e2e4").unwrap();
+        let options = DiagramOptions {
+            orientation: Color::White,
+            last_move: Some(e4),
+            coordinates: true,
+        };
+        let svg = board_svg(&board.make_move_new(e4), &options);
+        assert_eq!(svg.matches("<svg x=").count(), 32);
+        assert_eq!(svg.matches(LAST_MOVE).count(), 2);
+        assert!(svg.contains(">h</text>"));
+        assert!(!svg.contains("<?xml"));
+
+        let text = board_text(&board.make_move_new(e4), Color::Black, false);
+        assert!(text.starts_with("1 ♖ ♘ ♗ ♔ ♕ ♗ ♘ ♖\n"));
+        assert!(text.ends_with("  h g f e d c b a\n"));
+        let ascii = board_text(&board.make_move_new(e4), Color::White, true);
+        assert_eq!(ascii.lines().nth(4), Some("4 . . . . P . . ."));
+
+        let png = render_png(&svg).unwrap();
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+}
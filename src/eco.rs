@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chess::{Board, ChessMove};
+
+use crate::pgn::parse_san;
+
+/// Opening table bundled into the binary: ECO code, name and the moves in
+/// SAN, one opening per line with a header row.
+const ECO_TABLE: &str = include_str!("../assets/eco.tsv");
+
+/// A named opening from the ECO classification.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+}
+
+impl Opening {
+    /// "C65 Ruy Lopez: Berlin Defense"
+    pub fn display(&self) -> String {
+        format!("{} {}", self.eco, self.name)
+    }
+}
+
+/// A named opening and the moves that reach it from the start position.
+#[derive(Clone, Debug)]
+pub struct OpeningLine {
+    pub opening: Opening,
+    pub moves: Vec<ChessMove>,
+}
+
+/// Openings keyed by the position they reach, so transpositions into a
+/// known line are recognised whatever the move order.
+pub struct EcoTable {
+    by_position: HashMap<u64, Opening>,
+    /// Every line in table order, for picking an opening by name
+    lines: Vec<OpeningLine>,
+}
+
+impl EcoTable {
+    /// The table bundled with the application, parsed on first use.
+    pub fn bundled() -> &'static EcoTable {
+        static TABLE: OnceLock<EcoTable> = OnceLock::new();
+        TABLE.get_or_init(|| EcoTable::parse(ECO_TABLE))
+    }
+
+    fn parse(text: &str) -> EcoTable {
+        let mut by_position = HashMap::new();
+        let mut lines = Vec::new();
+        for line in text.lines().skip(1) {
+            let mut fields = line.split('\t');
+            let (Some(eco), Some(name), Some(moves)) = (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Some(moves) = play_san(moves) else {
+                eprintln!("Skipping unplayable ECO line: {}", line);
+                continue;
+            };
+            let board = moves.iter().fold(Board::default(), |board, &m| board.make_move_new(m));
+            let opening = Opening {
+                eco: eco.to_string(),
+                name: name.to_string(),
+            };
+            // The first entry for a position wins
+            by_position.entry(board.get_hash()).or_insert_with(|| opening.clone());
+            lines.push(OpeningLine { opening, moves });
+        }
+        EcoTable { by_position, lines }
+    }
+
+    /// Lines whose name has every word of `query` in it, in any case, or
+    /// whose ECO code is `query`; e.g. "najdorf" or "B90".
+    pub fn search(&self, query: &str) -> Vec<&OpeningLine> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        self.lines
+            .iter()
+            .filter(|line| {
+                let name = line.opening.name.to_lowercase();
+                line.opening.eco.eq_ignore_ascii_case(query.trim())
+                    || words.iter().all(|word| name.contains(word.as_str()))
+            })
+            .collect()
+    }
+
+    pub fn lookup(&self, board: &Board) -> Option<&Opening> {
+        self.by_position.get(&board.get_hash())
+    }
+
+    /// The opening of a game: the last position in `positions` that is still
+    /// in the table, so the name stops changing once the game leaves theory.
+    pub fn classify(&self, positions: &[Board]) -> Option<&Opening> {
+        positions.iter().rev().find_map(|board| self.lookup(board))
+    }
+}
+
+// Read a SAN move list ("1. e4 e5 2. Nf3") from the standard start position
+fn play_san(moves: &str) -> Option<Vec<ChessMove>> {
+    let mut board = Board::default();
+    let mut played = Vec::new();
+    for san in moves.split_whitespace().filter(|token| !token.ends_with('.')) {
+        let m = parse_san(&board, san)?;
+        board = board.make_move_new(m);
+        played.push(m);
+    }
+    Some(played)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_table_lines_are_playable() {
+        let lines = ECO_TABLE.lines().skip(1).count();
+        let playable = ECO_TABLE
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split('\t').nth(2))
+            .filter(|moves| play_san(moves).is_some())
+            .count();
+        assert_eq!(playable, lines);
+    }
+
+    #[test]
+    fn test_classify_follows_transpositions_and_stops_out_of_book() {
+        let table = EcoTable::bundled();
+
+        // 1. Nf3 d5 2. d4 transposes to the Zukertort line of 1. d4 d5 2. Nf3
+        let positions: Vec<Board> = ["Nf3", "d5", "d4", "h6", "h3"]
+            .iter()
+            .scan(Board::default(), |board, san| {
+                *board = board.make_move_new(ChessMove::from_san(board, san).unwrap());
+                Some(*board)
+            })
+            .collect();
+
+        let opening = table.classify(&positions).unwrap();
+        assert_eq!(opening.eco, "D02");
+        assert_eq!(opening.name, "Queen's Pawn Game: Zukertort Variation");
+    }
+
+    #[test]
+    fn test_search_by_name_and_code() {
+        let table = EcoTable::bundled();
+        let najdorf = table.search("sicilian  NAJDORF");
+        assert!(najdorf.len() >= 4);
+        assert_eq!(najdorf[0].opening.eco, "B90");
+        assert_eq!(najdorf[0].moves.len(), 10);
+        assert!(table.search("b90").iter().all(|line| line.opening.eco == "B90"));
+        assert!(table.search("  ").is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+use chess::{Board, BoardStatus, Color, Piece, Rank, Square};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::i18n::{fill, Strings};
+
+/// What the player has to achieve in an endgame exercise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndgameGoal {
+    /// Deliver checkmate
+    Checkmate,
+    /// Promote the pawn and keep the new queen
+    Promote,
+    /// Hold the position for this many moves without losing
+    Draw { moves: usize },
+}
+
+/// A classic endgame position to practise against the engine.
+#[derive(Clone, Copy, Debug)]
+pub struct Endgame {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub fen: &'static str,
+    pub player: Color,
+    pub goal: EndgameGoal,
+}
+
+/// The endgame training menu, easiest first.
+pub const ENDGAMES: &[Endgame] = &[
+    Endgame {
+        name: "Queen vs King",
+        description: "Drive the king to the edge and mate it with king and queen.",
+        fen: "8/8/8/4k3/8/8/8/4K2Q w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Checkmate,
+    },
+    Endgame {
+        name: "Rook vs King",
+        description: "Use the rook to box the king in and mate on the edge.",
+        fen: "8/8/8/4k3/8/8/8/R3K3 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Checkmate,
+    },
+    Endgame {
+        name: "Two Bishops",
+        description: "Mate with the bishop pair working side by side.",
+        fen: "8/8/8/4k3/8/8/8/2B1KB2 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Checkmate,
+    },
+    Endgame {
+        name: "Bishop and Knight",
+        description: "The hardest basic mate: force the king into a corner of the bishop's colour.",
+        fen: "8/8/8/4k3/8/8/8/1N2KB2 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Checkmate,
+    },
+    Endgame {
+        name: "King and Pawn",
+        description: "Use the opposition to escort the pawn to promotion.",
+        fen: "4k3/8/4K3/4P3/8/8/8/8 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Promote,
+    },
+    Endgame {
+        name: "King and Pawn Defence",
+        description: "Keep your king in front of the pawn and hold the draw.",
+        fen: "8/8/4k3/8/4P3/4K3/8/8 b - - 0 1",
+        player: Color::Black,
+        goal: EndgameGoal::Draw { moves: 30 },
+    },
+    Endgame {
+        name: "Lucena Position",
+        description: "Build a bridge with the rook to shelter your king and promote.",
+        fen: "1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1",
+        player: Color::White,
+        goal: EndgameGoal::Promote,
+    },
+    Endgame {
+        name: "Philidor Position",
+        description: "Hold the third rank with your rook, then check from behind.",
+        fen: "4k3/1R6/7r/3KP3/8/8/8/8 b - - 0 1",
+        player: Color::Black,
+        goal: EndgameGoal::Draw { moves: 30 },
+    },
+];
+
+/// How an endgame exercise ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EndgameOutcome {
+    Converted(&'static str),
+    Botched(&'static str),
+}
+
+impl Endgame {
+    /// Judge the current position of the exercise; None while it is still
+    /// being played out.  `player_moves` counts the player's moves so far
+    /// and `draw_claimable` reports a repetition or fifty-move draw.
+    pub fn assess(
+        &self,
+        board: &Board,
+        player_moves: usize,
+        draw_claimable: bool,
+    ) -> Option<EndgameOutcome> {
+        use EndgameOutcome::{Botched, Converted};
+        let opponent = !self.player;
+
+        match board.status() {
+            BoardStatus::Checkmate if board.side_to_move() == opponent => {
+                return Some(Converted("Checkmate!"))
+            }
+            BoardStatus::Checkmate => return Some(Botched("You were checkmated.")),
+            BoardStatus::Stalemate => {
+                return Some(match self.goal {
+                    EndgameGoal::Draw { .. } => Converted("Stalemate: drawn."),
+                    _ => Botched("Stalemate: the win slipped away."),
+                })
+            }
+            BoardStatus::Ongoing => {}
+        }
+
+        match self.goal {
+            EndgameGoal::Checkmate | EndgameGoal::Promote if draw_claimable => {
+                Some(Botched("Drawn by repetition or the fifty-move rule."))
+            }
+            EndgameGoal::Checkmate if !can_mate(board, self.player) => {
+                Some(Botched("You no longer have the material to mate."))
+            }
+            // The new queen has to survive the opponent's reply
+            EndgameGoal::Promote
+                if board.side_to_move() == self.player && count(board, self.player, Piece::Queen) > 0 =>
+            {
+                Some(Converted("Pawn promoted!"))
+            }
+            EndgameGoal::Promote
+                if count(board, self.player, Piece::Pawn) == 0
+                    && count(board, self.player, Piece::Queen) == 0 =>
+            {
+                Some(Botched("The pawn was lost."))
+            }
+            EndgameGoal::Draw { .. } if count(board, opponent, Piece::Queen) > 0 => {
+                Some(Botched("Your opponent promoted."))
+            }
+            EndgameGoal::Draw { .. } if !can_mate(board, opponent) || draw_claimable => {
+                Some(Converted("Drawn: your opponent can't make progress."))
+            }
+            EndgameGoal::Draw { moves } if player_moves >= moves => {
+                Some(Converted("You held the position!"))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Men of each side in a material signature such as "KRP vs KR" or
+/// "KQvK", white's first.  Kings may be left out; anything but piece letters
+/// and the "vs" between the sides is an error, given in `t`'s language.
+pub fn parse_material(signature: &str, t: &Strings) -> Result<[Vec<Piece>; 2], String> {
+    let upper = signature.to_ascii_uppercase().replace("VS", "V");
+    let sides: Vec<&str> = upper.split(['V', '-']).collect();
+    let [white, black] = sides.as_slice() else {
+        return Err(t.material_format.to_string());
+    };
+    let men = |side: &str| -> Result<Vec<Piece>, String> {
+        let mut men = vec![Piece::King];
+        for letter in side.chars().filter(|c| !c.is_whitespace()) {
+            let piece = match letter {
+                'K' => continue,
+                'Q' => Piece::Queen,
+                'R' => Piece::Rook,
+                'B' => Piece::Bishop,
+                'N' => Piece::Knight,
+                'P' => Piece::Pawn,
+                _ => return Err(fill(t.not_a_piece_letter, letter)),
+            };
+            men.push(piece);
+        }
+        Ok(men)
+    };
+    Ok([men(white)?, men(black)?])
+}
+
+/// FEN of `material` scattered over random squares with white to move.
+/// Pawns stay off the first and last ranks; anything else, such as kings
+/// side by side, is left for the caller to reject and roll again.
+pub fn random_position(material: &[Vec<Piece>; 2], rng: &mut impl Rng) -> String {
+    let mut squares: Vec<Square> = chess::ALL_SQUARES.to_vec();
+    squares.shuffle(rng);
+    let mut board = [[None; 8]; 8];
+    for (color, men) in [Color::White, Color::Black].into_iter().zip(material) {
+        for &piece in men {
+            let fits = |sq: &Square| {
+                piece != Piece::Pawn || !matches!(sq.get_rank(), Rank::First | Rank::Eighth)
+            };
+            let Some(index) = squares.iter().position(fits) else {
+                break;
+            };
+            let square = squares.swap_remove(index);
+            board[square.get_rank().to_index()][square.get_file().to_index()] = Some((piece, color));
+        }
+    }
+    let ranks: Vec<String> = (0..8)
+        .rev()
+        .map(|rank| {
+            let mut row = String::new();
+            let mut empty = 0;
+            for &man in &board[rank] {
+                match man {
+                    Some((piece, color)) => {
+                        if empty > 0 {
+                            row.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        row.push_str(&piece.to_string(color));
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            row
+        })
+        .collect();
+    format!("{} w - - 0 1", ranks.join("/"))
+}
+
+fn count(board: &Board, color: Color, piece: Piece) -> u32 {
+    (board.pieces(piece) & board.color_combined(color)).popcnt()
+}
+
+// Whether `color` still has enough material to force mate: any pawn, rook or
+// queen, or at least two minor pieces
+fn can_mate(board: &Board, color: Color) -> bool {
+    let heavy = [Piece::Pawn, Piece::Rook, Piece::Queen]
+        .iter()
+        .any(|&piece| count(board, color, piece) > 0);
+    let minors = count(board, color, Piece::Knight) + count(board, color, Piece::Bishop);
+    heavy || minors >= 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::ENGLISH;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_endgame_positions_are_valid() {
+        for endgame in ENDGAMES {
+            let board = Board::from_str(endgame.fen).unwrap();
+            assert_eq!(board.status(), BoardStatus::Ongoing, "{}", endgame.name);
+            assert_eq!(board.side_to_move(), endgame.player, "{}", endgame.name);
+        }
+    }
+
+    #[test]
+    fn test_random_position() {
+        use rand::SeedableRng;
+
+        assert!(parse_material("KRP", &ENGLISH).is_err());
+        assert!(parse_material("KRX vs K", &ENGLISH).is_err());
+        let material = parse_material("krp vs kr", &ENGLISH).unwrap();
+        assert_eq!(material, parse_material("RP-R", &ENGLISH).unwrap());
+        assert_eq!(material[1], vec![Piece::King, Piece::Rook]);
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let fen = random_position(&material, &mut rng);
+            let placement = fen.split_whitespace().next().unwrap();
+            let ranks: Vec<&str> = placement.split('/').collect();
+            assert_eq!(ranks.len(), 8, "{}", fen);
+            assert!(!ranks[0].contains('P') && !ranks[7].contains('P'), "{}", fen);
+            let mut men: Vec<char> = placement.chars().filter(char::is_ascii_alphabetic).collect();
+            men.sort_unstable();
+            assert_eq!(men.iter().collect::<String>(), "KPRkr", "{}", fen);
+        }
+    }
+
+    #[test]
+    fn test_assess_outcomes() {
+        let rook_mate = &ENDGAMES[1];
+        let mated = Board::from_str("k6R/8/K7/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(rook_mate.assess(&mated, 20, false), Some(EndgameOutcome::Converted("Checkmate!")));
+        let rook_lost = Board::from_str("k7/8/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert!(matches!(rook_mate.assess(&rook_lost, 20, false), Some(EndgameOutcome::Botched(_))));
+        let start = Board::from_str(rook_mate.fen).unwrap();
+        assert_eq!(rook_mate.assess(&start, 0, false), None);
+
+        let philidor = &ENDGAMES[7];
+        let held = Board::from_str(philidor.fen).unwrap();
+        assert_eq!(philidor.assess(&held, 29, false), None);
+        assert_eq!(
+            philidor.assess(&held, 30, false),
+            Some(EndgameOutcome::Converted("You held the position!"))
+        );
+    }
+}
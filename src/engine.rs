@@ -1,18 +1,449 @@
 use std::{
+    collections::VecDeque,
     io::{BufRead, BufReader, Write},
     path::Path,
     process::{Child, Command, Stdio},
-    sync::mpsc,
+    sync::{mpsc, Arc, Mutex},
     thread,
+    time::Duration,
 };
 
+use chess::ChessMove;
+use rand::distributions::{Distribution, WeightedIndex};
+use serde::{Deserialize, Serialize};
+
 use crate::error::AppError;
 
+/// Centipawn value used in place of a forced mate when scores are compared
+pub const MATE_SCORE: i32 = 100_000;
+
+/// An engine score, relative to the side to move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Score {
+    Cp(i32),
+    /// Moves until mate; negative when the side to move is getting mated
+    Mate(i32),
+}
+
+impl Score {
+    /// Centipawn value with mates mapped beyond any material score, nearer
+    /// mates scoring higher.
+    pub fn to_cp(self) -> i32 {
+        match self {
+            Score::Cp(cp) => cp,
+            Score::Mate(moves) if moves > 0 => MATE_SCORE - moves,
+            Score::Mate(moves) => -MATE_SCORE - moves,
+        }
+    }
+}
+
+/// One principal variation reported by an `info` line.
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub multipv: u32,
+    pub score: Score,
+    /// First move of the principal variation (UCI)
+    pub first_move: Option<String>,
+    /// Second move of the principal variation: the answer the engine
+    /// expects to its first
+    pub reply: Option<String>,
+    /// Tablebase probes that hit so far; nonzero only for engines with
+    /// tablebases configured
+    pub tbhits: u64,
+    /// Plies searched
+    pub depth: u32,
+    /// The whole principal variation (UCI), first move first
+    pub pv: Vec<String>,
+}
+
+impl SearchInfo {
+    /// Parse an `info ... score ...` line; lines without a score (currmove
+    /// updates, strings, ...) yield None.
+    pub fn parse(line: &str) -> Option<SearchInfo> {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("info") {
+            return None;
+        }
+
+        let mut multipv = 1;
+        let mut score = None;
+        let mut first_move = None;
+        let mut reply = None;
+        let mut tbhits = 0;
+        let mut depth = 0;
+        let mut pv = Vec::new();
+        while let Some(token) = tokens.next() {
+            match token {
+                "multipv" => multipv = tokens.next()?.parse().ok()?,
+                "depth" => depth = tokens.next()?.parse().ok()?,
+                "tbhits" => tbhits = tokens.next()?.parse().ok()?,
+                "score" => {
+                    score = match tokens.next()? {
+                        "cp" => Some(Score::Cp(tokens.next()?.parse().ok()?)),
+                        "mate" => Some(Score::Mate(tokens.next()?.parse().ok()?)),
+                        _ => None,
+                    }
+                }
+                // Nothing else of interest follows the pv
+                "pv" => {
+                    pv = tokens.by_ref().map(str::to_string).collect::<Vec<_>>();
+                    first_move = pv.first().cloned();
+                    reply = pv.get(1).cloned();
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        Some(SearchInfo {
+            multipv,
+            score: score?,
+            first_move,
+            reply,
+            tbhits,
+            depth,
+            pv,
+        })
+    }
+}
+
+/// The answer to a `go`: the move chosen and, when the engine names one,
+/// the reply it expects (which it would ponder on).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestMove {
+    pub best_move: String,
+    pub ponder: Option<String>,
+}
+
+impl BestMove {
+    /// Parse a `bestmove <move> [ponder <move>]` line.  Tokens the engine
+    /// adds after these are ignored, and a `(none)` or `0000` ponder move
+    /// counts as none.
+    pub fn parse(line: &str) -> Option<BestMove> {
+        let mut tokens = line.split_whitespace();
+        if tokens.next() != Some("bestmove") {
+            return None;
+        }
+        let best_move = tokens.next()?.to_string();
+        let mut ponder = None;
+        while let Some(token) = tokens.next() {
+            if token == "ponder" {
+                ponder = tokens
+                    .next()
+                    .filter(|m| !matches!(*m, "(none)" | "0000"))
+                    .map(str::to_string);
+                break;
+            }
+        }
+        Some(BestMove { best_move, ponder })
+    }
+}
+
+/// Lines of engine traffic kept for the console
+const LOG_LIMIT: usize = 2000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    ToEngine,
+    FromEngine,
+    /// Written to the engine's stderr: warnings, missing files, crashes
+    Stderr,
+}
+
+/// One line sent to or received from the engine.
+#[derive(Clone, Debug)]
+pub struct UciLine {
+    pub direction: Direction,
+    pub text: String,
+}
+
+/// The most recent UCI traffic, oldest first.
+#[derive(Default)]
+pub struct UciLog {
+    lines: VecDeque<UciLine>,
+    total: u64,
+    // Stderr lines logged so far, and the latest
+    stderr_total: u64,
+    last_stderr: Option<String>,
+}
+
+impl UciLog {
+    pub fn push(&mut self, direction: Direction, text: &str) {
+        if self.lines.len() == LOG_LIMIT {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(UciLine {
+            direction,
+            text: text.to_string(),
+        });
+        self.total += 1;
+        if direction == Direction::Stderr {
+            self.stderr_total += 1;
+            self.last_stderr = Some(text.to_string());
+        }
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &UciLine> {
+        self.lines.iter()
+    }
+
+    /// Lines logged so far, including those dropped off the front; this
+    /// changes whenever anything new arrives.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// The engine's latest stderr line, numbered by how many came before
+    /// it so a caller can tell when a new one arrives.
+    pub fn last_stderr(&self) -> Option<(u64, &str)> {
+        Some((self.stderr_total, self.last_stderr.as_deref()?))
+    }
+
+    /// Up to `count` of the most recent stderr lines, oldest first.
+    pub fn stderr_tail(&self, count: usize) -> Vec<String> {
+        let mut tail: Vec<String> = self
+            .lines
+            .iter()
+            .rev()
+            .filter(|line| line.direction == Direction::Stderr)
+            .take(count)
+            .map(|line| line.text.clone())
+            .collect();
+        tail.reverse();
+        tail
+    }
+}
+
+/// Engine name for PGN tags and reports: the executable's file name.
+pub fn engine_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+/// Parse a `Name=Value` option assignment from the command line.  The
+/// value may itself contain '=' and may be empty (for button options).
+pub fn parse_option_assignment(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected Name=Value, got '{}'", s))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(format!("missing option name in '{}'", s));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
+/// Stockfish's option naming its NNUE network
+pub const EVAL_FILE: &str = "EvalFile";
+
+/// The `EvalFile` option pointing the engine at an NNUE network, once the
+/// file is known to exist.  The path is made absolute, as engines resolve
+/// relative ones against their own directory.
+pub fn eval_file_option(path: &Path) -> Result<(String, String), String> {
+    let absolute = path
+        .canonicalize()
+        .map_err(|e| format!("Eval file {}: {}", path.display(), e))?;
+    if !absolute.is_file() {
+        return Err(format!("Eval file {} is not a file", path.display()));
+    }
+    Ok((EVAL_FILE.to_string(), absolute.display().to_string()))
+}
+
+/// Pick one of the lines scoring within `window` centipawns of the best,
+/// weighted towards the stronger ones.  None if no line has a move.
+pub fn pick_varied_move(lines: &[SearchInfo], window: i32) -> Option<String> {
+    let best = lines.iter().map(|line| line.score.to_cp()).max()?;
+    let candidates: Vec<(&str, i32)> = lines
+        .iter()
+        .filter_map(|line| {
+            let loss = best - line.score.to_cp();
+            let first_move = line.first_move.as_deref()?;
+            (loss <= window).then_some((first_move, window - loss + 1))
+        })
+        .collect();
+    let weights = WeightedIndex::new(candidates.iter().map(|(_, weight)| *weight)).ok()?;
+    Some(candidates[weights.sample(&mut rand::thread_rng())].0.to_string())
+}
+
+/// Search threads and hash table size given to an engine.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Resources {
+    pub threads: u32,
+    pub hash_mb: u32,
+}
+
+impl Resources {
+    /// A fair share of this machine: all cores but one, and a power of two
+    /// near a sixteenth of physical memory (16 MB to 2 GB) for the hash.
+    pub fn for_host() -> Resources {
+        let cores = thread::available_parallelism().map_or(1, |n| n.get() as u32);
+        let hash_mb = total_memory_mb().map_or(128, |mb| {
+            let share = (mb / 16).clamp(16, 2048) as u32;
+            // Largest power of two not above the share
+            1 << (31 - share.leading_zeros())
+        });
+        Resources {
+            threads: cores.saturating_sub(1).max(1),
+            hash_mb,
+        }
+    }
+}
+
+impl Default for Resources {
+    fn default() -> Self {
+        Resources::for_host()
+    }
+}
+
+/// Physical memory from /proc/meminfo; None where that isn't available.
+pub fn total_memory_mb() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb / 1024)
+}
+
+/// How the engine introduced itself with `id name` and `id author`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EngineId {
+    pub name: Option<String>,
+    pub author: Option<String>,
+}
+
+impl EngineId {
+    /// Take the value from an `id name ...` or `id author ...` line.
+    fn read(&mut self, line: &str) {
+        let mut parts = line.splitn(3, ' ');
+        if parts.next() != Some("id") {
+            return;
+        }
+        let field = match parts.next() {
+            Some("name") => &mut self.name,
+            Some("author") => &mut self.author,
+            _ => return,
+        };
+        *field = parts.next().map(|value| value.trim().to_string());
+    }
+
+    /// "Stockfish 16 by the Stockfish developers", or just the name when
+    /// no author was given.
+    pub fn label(&self) -> Option<String> {
+        let name = self.name.as_deref().filter(|name| !name.is_empty())?;
+        Some(match self.author.as_deref().filter(|author| !author.is_empty()) {
+            Some(author) => format!("{} by {}", name, author),
+            None => name.to_string(),
+        })
+    }
+}
+
+/// An option the engine declared during the UCI handshake.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UciOption {
+    pub name: String,
+    /// "check", "spin", "combo", "button" or "string"
+    pub kind: String,
+    pub default: Option<String>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+}
+
+impl UciOption {
+    /// Parse an `option name <name> type <kind> ...` line.
+    pub fn parse(line: &str) -> Option<UciOption> {
+        let rest = line.strip_prefix("option name ")?;
+        let (name, rest) = rest.split_once(" type ")?;
+        let mut tokens = rest.split_whitespace();
+        let mut option = UciOption {
+            name: name.trim().to_string(),
+            kind: tokens.next()?.to_string(),
+            default: None,
+            min: None,
+            max: None,
+        };
+
+        // Values run until the next keyword; string defaults may hold spaces
+        let mut key = "";
+        let mut value: Vec<&str> = Vec::new();
+        for token in tokens.chain(std::iter::once("var")) {
+            if matches!(token, "default" | "min" | "max" | "var") {
+                let joined = value.join(" ");
+                match key {
+                    "default" => option.default = Some(joined),
+                    "min" => option.min = joined.parse().ok(),
+                    "max" => option.max = joined.parse().ok(),
+                    _ => {}
+                }
+                key = token;
+                value.clear();
+            } else {
+                value.push(token);
+            }
+        }
+
+        Some(option)
+    }
+}
+
+/// How long a blocking analysis should search.
+#[derive(Clone, Copy, Debug)]
+pub enum SearchLimit {
+    Depth(u32),
+    /// Play to a clock: both sides' remaining time and increment, in
+    /// milliseconds
+    Clock {
+        wtime: u64,
+        btime: u64,
+        winc: u64,
+        binc: u64,
+    },
+}
+
+impl SearchLimit {
+    fn go_command(&self) -> String {
+        match self {
+            SearchLimit::Depth(depth) => format!("go depth {}", depth),
+            SearchLimit::Clock {
+                wtime,
+                btime,
+                winc,
+                binc,
+            } => format!("go wtime {} btime {} winc {} binc {}", wtime, btime, winc, binc),
+        }
+    }
+}
+
+/// Result of a completed blocking search.
+#[derive(Clone, Debug)]
+pub struct Analysis {
+    pub best_move: String,
+    /// Latest line for each multipv index, best first
+    pub lines: Vec<SearchInfo>,
+}
+
+impl Analysis {
+    pub fn score(&self) -> Option<Score> {
+        self.lines.first().map(|line| line.score)
+    }
+}
+
 pub struct ChessEngine {
     process: Option<Child>,
-    move_receiver: mpsc::Receiver<String>,
-    move_sender: mpsc::Sender<String>,
+    move_receiver: mpsc::Receiver<BestMove>,
+    move_sender: mpsc::Sender<BestMove>,
+    // The answer to the last search collected
+    last_best: Option<BestMove>,
     think_time: u64,
+    // A `go` was sent and its bestmove hasn't been collected yet
+    searching: bool,
+    // Latest info line per multipv index for the current search
+    lines: Arc<Mutex<Vec<SearchInfo>>>,
+    // Options the engine declared before `uciok`
+    options: Vec<UciOption>,
+    // Name and author the engine reported before `uciok`
+    id: EngineId,
+    // Everything written to and read from the engine
+    log: Arc<Mutex<UciLog>>,
 }
 
 impl ChessEngine {
@@ -23,7 +454,13 @@ impl ChessEngine {
             process: None,
             move_receiver: rx,
             move_sender: tx,
+            last_best: None,
             think_time: 2000,
+            searching: false,
+            lines: Arc::new(Mutex::new(Vec::new())),
+            options: Vec::new(),
+            id: EngineId::default(),
+            log: Arc::new(Mutex::new(UciLog::default())),
         }
     }
 
@@ -32,6 +469,8 @@ impl ChessEngine {
         engine_path: P,
         skill_level: u8,
         think_time: u64,
+        resources: Resources,
+        uci_options: &[(String, String)],
     ) -> Result<(), AppError> {
         let path = engine_path.as_ref();
         if !path.exists() {
@@ -41,91 +480,510 @@ impl ChessEngine {
             )));
         }
 
-        let process = Command::new(path)
+        // A previous, possibly failed or stuck, start is replaced along
+        // with any search it never finished
+        if let Some(mut old) = self.process.take() {
+            let _ = old.kill();
+        }
+        self.searching = false;
+        while self.move_receiver.try_recv().is_ok() {}
+
+        let mut process = Command::new(path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .spawn()?;
 
+        // Keep stderr for the console, passing it on to ours as before
+        let stderr_reader = process.stderr.take().map(|stderr| {
+            let log = Arc::clone(&self.log);
+            thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    eprintln!("{}", line);
+                    if let Ok(mut log) = log.lock() {
+                        log.push(Direction::Stderr, line.trim());
+                    }
+                }
+            })
+        });
+
         self.process = Some(process);
         self.think_time = think_time;
 
         // Initialize UCI engine
-        if let Some(ref mut process) = self.process {
-            let mut stdin = process.stdin.take().unwrap();
-            stdin.write_all(b"uci\n")?;
-            stdin.write_all(b"isready\n")?;
+        if let Some(stdout) = self.process.as_mut().and_then(|p| p.stdout.take()) {
+            let mut reader = BufReader::new(stdout);
+            self.send("uci")?;
+
+            // Collect the declared options; the engine ends the list with uciok
+            self.options.clear();
+            self.id = EngineId::default();
+            let mut line = String::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    // Make sure the engine has gone, so its stderr ends too
+                    if let Some(mut process) = self.process.take() {
+                        let _ = process.kill();
+                        let _ = process.wait();
+                    }
+                    if let Some(reader) = stderr_reader {
+                        let _ = reader.join();
+                    }
+                    let mut error = "Engine exited during the UCI handshake".to_string();
+                    let tail = self.log.lock().map(|log| log.stderr_tail(3)).unwrap_or_default();
+                    if !tail.is_empty() {
+                        error = format!("{}: {}", error, tail.join(" / "));
+                    }
+                    return Err(AppError::Engine(error));
+                }
+                let line = line.trim();
+                self.log_line(Direction::FromEngine, line);
+                if line == "uciok" {
+                    break;
+                }
+                if let Some(option) = UciOption::parse(line) {
+                    self.options.push(option);
+                } else {
+                    self.id.read(line);
+                }
+            }
+            self.send("isready")?;
 
             // Set engine options
-            stdin.write_all(
-                format!("setoption name Skill Level value {}\n", skill_level).as_bytes(),
-            )?;
-            stdin.write_all(b"setoption name Threads value 4\n")?;
-            stdin.write_all(b"setoption name Hash value 128\n")?;
-            stdin.write_all(b"setoption name UCI_AnalyseMode value false\n")?;
-            stdin.write_all(b"setoption name UCI_LimitStrength value false\n")?;
-            stdin.flush()?;
+            self.send(&format!("setoption name Skill Level value {}", skill_level))?;
+            self.send(&format!("setoption name Threads value {}", resources.threads))?;
+            self.send(&format!("setoption name Hash value {}", resources.hash_mb))?;
+            self.send("setoption name UCI_AnalyseMode value false")?;
+            self.send("setoption name UCI_LimitStrength value false")?;
+
+            // User options go last so they override the defaults above
+            for (name, value) in uci_options {
+                if self.option(name).is_none() {
+                    eprintln!("Engine does not declare the option '{}', sending it anyway", name);
+                }
+                self.send(&format!("setoption name {} value {}", name, value))?;
+            }
 
             // Read engine output in a separate thread
-            let stdout = process.stdout.take().unwrap();
-            let reader = BufReader::new(stdout);
 
             // Get a clone of the sender to pass to the thread
             let tx_clone = self.move_sender.clone();
+            let lines = Arc::clone(&self.lines);
+            let log = Arc::clone(&self.log);
 
             thread::spawn(move || {
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        if line.starts_with("bestmove") {
-                            let parts: Vec<&str> = line.split_whitespace().collect();
-                            if parts.len() >= 2 {
-                                tx_clone.send(parts[1].to_string()).unwrap_or(());
+                for line in reader.lines().map_while(Result::ok) {
+                    if let Ok(mut log) = log.lock() {
+                        log.push(Direction::FromEngine, &line);
+                    }
+                    if let Some(info) = SearchInfo::parse(&line) {
+                        if let Ok(mut lines) = lines.lock() {
+                            match lines.iter_mut().find(|l| l.multipv == info.multipv) {
+                                Some(existing) => *existing = info,
+                                None => {
+                                    lines.push(info);
+                                    lines.sort_by_key(|l| l.multipv);
+                                }
                             }
                         }
+                    } else if let Some(best) = BestMove::parse(&line) {
+                        tx_clone.send(best).unwrap_or(());
                     }
                 }
             });
+        }
+
+        Ok(())
+    }
+
+    /// Change the playing strength between games.
+    pub fn set_skill_level(&mut self, skill_level: u8) -> Result<(), AppError> {
+        self.set_option("Skill Level", &skill_level.to_string())
+    }
+
+    /// Change the threads and hash size of a running engine.
+    pub fn set_resources(&mut self, resources: Resources) -> Result<(), AppError> {
+        self.set_option("Threads", &resources.threads.to_string())?;
+        self.set_option("Hash", &resources.hash_mb.to_string())
+    }
+
+    /// Report this many principal variations per search.
+    pub fn set_multipv(&mut self, lines: u32) -> Result<(), AppError> {
+        self.set_option("MultiPV", &lines.to_string())
+    }
+
+    /// Latest line for each multipv index of the last search, best first.
+    pub fn current_lines(&self) -> Vec<SearchInfo> {
+        self.lines.lock().map(|lines| lines.clone()).unwrap_or_default()
+    }
+
+    /// Switch the engine between standard chess and Chess960 castling rules.
+    /// Returns false if the engine doesn't declare UCI_Chess960.
+    pub fn set_chess960(&mut self, enabled: bool) -> Result<bool, AppError> {
+        self.set_declared_option("UCI_Chess960", if enabled { "true" } else { "false" })
+    }
+
+    /// Whether the engine can play Chess960, castling king-takes-rook.
+    pub fn plays_chess960(&self) -> bool {
+        self.option("UCI_Chess960").is_some()
+    }
+
+    /// The engine's `id` lines from its last start.
+    pub fn id(&self) -> &EngineId {
+        &self.id
+    }
+
+    /// The declared option called `name`, if the engine has one.
+    pub fn option(&self, name: &str) -> Option<&UciOption> {
+        self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name))
+    }
 
-            // Return stdin to the process
-            process.stdin = Some(stdin);
+    /// Range of ratings `set_elo` accepts; None if the engine can't limit
+    /// its strength by Elo.
+    pub fn elo_range(&self) -> Option<(u32, u32)> {
+        self.option("UCI_LimitStrength")?;
+        let elo = self.option("UCI_Elo")?;
+        let min = elo.min?.max(0) as u32;
+        let max = elo.max?.max(0) as u32;
+        Some((min, max))
+    }
+
+    /// Limit the engine to roughly `elo`, clamped to the range it supports,
+    /// or lift the limit with None.  Returns the rating actually set.
+    pub fn set_elo(&mut self, elo: Option<u32>) -> Result<Option<u32>, AppError> {
+        let Some(range) = self.elo_range() else {
+            return Ok(None);
+        };
+        match elo {
+            Some(elo) => {
+                let elo = elo.clamp(range.0, range.1);
+                self.set_option("UCI_LimitStrength", "true")?;
+                self.set_option("UCI_Elo", &elo.to_string())?;
+                Ok(Some(elo))
+            }
+            None => {
+                self.set_option("UCI_LimitStrength", "false")?;
+                Ok(None)
+            }
         }
+    }
 
+    // Options can't change mid-search, so any search in flight is dropped
+    fn set_option(&mut self, name: &str, value: &str) -> Result<(), AppError> {
+        self.abandon_search();
+        if self.is_running() {
+            self.send(&format!("setoption name {} value {}", name, value))?;
+        }
         Ok(())
     }
 
-    pub fn get_move(&mut self, fen: &str) -> Result<(), AppError> {
-        if let Some(ref mut process) = self.process {
-            if let Some(stdin) = process.stdin.as_mut() {
-                // Send position to engine
-                let position_cmd = format!("position fen {}\n", fen);
-                stdin.write_all(position_cmd.as_bytes())?;
-
-                // Ask engine to think
-                let think_cmd = format!("go movetime {}\n", self.think_time);
-                stdin.write_all(think_cmd.as_bytes())?;
-                stdin.flush()?;
-                return Ok(());
+    /// Set `name` if the engine declares it, keeping numbers within the
+    /// declared range.  Returns whether the option was sent.
+    pub fn set_declared_option(&mut self, name: &str, value: &str) -> Result<bool, AppError> {
+        let Some(option) = self.option(name) else {
+            return Ok(false);
+        };
+        let name = option.name.clone();
+        let value = match (value.parse::<i64>(), option.kind.as_str()) {
+            (Ok(number), "spin") => {
+                let min = option.min.unwrap_or(i64::MIN);
+                number.max(min).min(option.max.unwrap_or(i64::MAX)).to_string()
+            }
+            _ => value.to_string(),
+        };
+        self.set_option(&name, &value)?;
+        Ok(true)
+    }
+
+    /// Write a line typed into the UCI console straight to the engine.  A
+    /// `go` is tracked like any other search, so its bestmove is stopped
+    /// and discarded before the game asks for a move.
+    pub fn send_command(&mut self, command: &str) -> Result<(), AppError> {
+        self.send(command)?;
+        if command.split_whitespace().next() == Some("go") {
+            self.searching = true;
+        }
+        Ok(())
+    }
+
+    /// Shared record of the traffic with the engine, for the UCI console.
+    pub fn log(&self) -> Arc<Mutex<UciLog>> {
+        Arc::clone(&self.log)
+    }
+
+    // Write one command line to the engine and record it in the log
+    fn send(&mut self, command: &str) -> Result<(), AppError> {
+        let stdin = self
+            .process
+            .as_mut()
+            .and_then(|p| p.stdin.as_mut())
+            .ok_or_else(|| AppError::Engine("Engine process not available".to_string()))?;
+        stdin.write_all(format!("{}\n", command).as_bytes())?;
+        stdin.flush()?;
+        self.log_line(Direction::ToEngine, command);
+        Ok(())
+    }
+
+    fn log_line(&self, direction: Direction, line: &str) {
+        if let Ok(mut log) = self.log.lock() {
+            log.push(direction, line);
+        }
+    }
+
+    /// Tell the engine the next search belongs to a different game.
+    pub fn new_game(&mut self) -> Result<(), AppError> {
+        self.abandon_search();
+        if self.is_running() {
+            self.send("ucinewgame")?;
+        }
+        Ok(())
+    }
+
+    /// Whether `start` has launched the engine process.
+    pub fn is_running(&self) -> bool {
+        self.process.is_some()
+    }
+
+    /// Start a search of `fen` for `movetime` milliseconds instead of the
+    /// configured think time.
+    pub fn get_move_within(&mut self, fen: &str, movetime: u64) -> Result<(), AppError> {
+        self.abandon_search();
+        self.clear_lines();
+
+        self.send(&format!("position fen {}", fen))?;
+        self.send(&format!("go movetime {}", movetime))?;
+        self.searching = true;
+        Ok(())
+    }
+
+    /// Like `get_move_within`, but the engine decides how long to think
+    /// from the clocks in `limit`.
+    pub fn get_move_by_limit(&mut self, fen: &str, limit: SearchLimit) -> Result<(), AppError> {
+        self.abandon_search();
+        self.clear_lines();
+
+        self.send(&format!("position fen {}", fen))?;
+        self.send(&limit.go_command())?;
+        self.searching = true;
+        Ok(())
+    }
+
+    /// Search `fen` until told otherwise, for a kibitzer; the lines are
+    /// read with `current_lines` as they come in.
+    pub fn analyse_infinite(&mut self, fen: &str) -> Result<(), AppError> {
+        self.abandon_search();
+        self.clear_lines();
+
+        self.send(&format!("position fen {}", fen))?;
+        self.send("go infinite")?;
+        self.searching = true;
+        Ok(())
+    }
+
+    /// Stop the search in progress, if any, and throw away its result.
+    pub fn stop_search(&mut self) {
+        self.abandon_search();
+    }
+
+    pub fn try_receive_move(&mut self) -> Option<String> {
+        let best = self.move_receiver.try_recv().ok()?;
+        self.searching = false;
+        let best_move = best.best_move.clone();
+        self.last_best = Some(best);
+        Some(best_move)
+    }
+
+    /// The answer to the last search collected, with the engine's
+    /// expected reply.
+    pub fn last_best(&self) -> Option<&BestMove> {
+        self.last_best.as_ref()
+    }
+
+    /// Search `fen` to the given limit and wait for the result.  Meant for
+    /// analysis instances running off the UI thread.
+    pub fn analyse(&mut self, fen: &str, limit: SearchLimit) -> Result<Analysis, AppError> {
+        self.search(&format!("position fen {}", fen), limit)
+    }
+
+    /// Like `analyse`, but for the position reached by `moves` from
+    /// `start_fen`, so the engine sees the game's history (repetitions and
+    /// the fifty-move count).
+    pub fn analyse_game(
+        &mut self,
+        start_fen: &str,
+        moves: &[ChessMove],
+        limit: SearchLimit,
+    ) -> Result<Analysis, AppError> {
+        let mut position_cmd = format!("position fen {}", start_fen);
+        if !moves.is_empty() {
+            position_cmd.push_str(" moves");
+            for m in moves {
+                position_cmd.push_str(&format!(" {}", m));
             }
         }
+        self.search(&position_cmd, limit)
+    }
+
+    fn search(&mut self, position_cmd: &str, limit: SearchLimit) -> Result<Analysis, AppError> {
+        self.abandon_search();
+        self.clear_lines();
 
-        Err(AppError::Engine("Engine process not available".to_string()))
+        let timeout = match limit {
+            SearchLimit::Depth(_) => Duration::from_secs(120),
+            // Overstepping is the caller's to judge; just don't wait forever
+            SearchLimit::Clock { wtime, btime, .. } => Duration::from_millis(wtime.max(btime) + 5000),
+        };
+        self.send(position_cmd)?;
+        self.send(&limit.go_command())?;
+        self.searching = true;
+
+        let best = self
+            .move_receiver
+            .recv_timeout(timeout)
+            .map_err(|_| AppError::Engine("Engine did not finish the search".to_string()))?;
+        self.searching = false;
+        let lines = self.current_lines();
+        self.last_best = Some(best.clone());
+
+        Ok(Analysis {
+            best_move: best.best_move,
+            lines,
+        })
     }
 
-    pub fn try_receive_move(&self) -> Option<String> {
-        match self.move_receiver.try_recv() {
-            Ok(best_move) => Some(best_move),
-            Err(_) => None,
+    fn clear_lines(&self) {
+        if let Ok(mut lines) = self.lines.lock() {
+            lines.clear();
         }
     }
+
+    /// Stop a search whose result is no longer wanted (the position changed
+    /// underneath it) and throw away its bestmove, so it can't be mistaken
+    /// for the reply to the next request.
+    fn abandon_search(&mut self) {
+        self.last_best = None;
+        if self.searching {
+            let _ = self.send("stop");
+            let _ = self.move_receiver.recv_timeout(Duration::from_secs(2));
+            self.searching = false;
+        }
+        while self.move_receiver.try_recv().is_ok() {}
+    }
 }
 
 impl Drop for ChessEngine {
     fn drop(&mut self) {
+        let _ = self.send("quit");
         if let Some(ref mut process) = self.process {
-            if let Some(stdin) = process.stdin.as_mut() {
-                let _ = stdin.write_all(b"quit\n");
-                let _ = stdin.flush();
-            }
             let _ = process.kill();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_uci_option() {
+        let elo = UciOption::parse("option name UCI_Elo type spin default 1320 min 1320 max 3190").unwrap();
+        assert_eq!(elo.name, "UCI_Elo");
+        assert_eq!(elo.kind, "spin");
+        assert_eq!((elo.min, elo.max), (Some(1320), Some(3190)));
+
+        let path = UciOption::parse("option name Debug Log File type string default <empty>").unwrap();
+        assert_eq!(path.name, "Debug Log File");
+        assert_eq!(path.default.as_deref(), Some("<empty>"));
+
+        let info = SearchInfo::parse("info depth 12 multipv 2 score cp -15 nodes 100 pv e7e5 g1f3").unwrap();
+        assert_eq!((info.multipv, info.score), (2, Score::Cp(-15)));
+        assert_eq!(info.first_move.as_deref(), Some("e7e5"));
+        assert_eq!(info.reply.as_deref(), Some("g1f3"));
+        assert_eq!((info.depth, info.pv.len()), (12, 2));
+        let info = SearchInfo::parse("info depth 30 score cp 19980 tbhits 42 pv a1a8").unwrap();
+        assert_eq!((info.score, info.tbhits), (Score::Cp(19980), 42));
+
+        let mut id = EngineId::default();
+        for line in ["id name Stockfish 16", "id author the Stockfish developers", "uciok"] {
+            id.read(line);
+        }
+        assert_eq!(id.label().as_deref(), Some("Stockfish 16 by the Stockfish developers"));
+
+        let combo = UciOption::parse("option name Style type combo default Normal var Solid var Normal").unwrap();
+        assert_eq!(combo.default.as_deref(), Some("Normal"));
+        assert!(UciOption::parse("id name Stockfish 16").is_none());
+
+        assert_eq!(
+            parse_option_assignment("SyzygyPath = /tb/a=b").unwrap(),
+            ("SyzygyPath".to_string(), "/tb/a=b".to_string())
+        );
+        assert!(parse_option_assignment("Contempt").is_err());
+        assert!(parse_option_assignment("=10").is_err());
+    }
+
+    #[test]
+    fn test_parse_bestmove() {
+        let best = |line| BestMove::parse(line).unwrap();
+        assert_eq!(best("bestmove e2e4 ponder e7e5").ponder.as_deref(), Some("e7e5"));
+        assert_eq!(best("bestmove e2e4").best_move, "e2e4");
+        assert_eq!(best("bestmove e2e4").ponder, None);
+        // Extra whitespace and unknown trailing tokens
+        let padded = best("bestmove  g1f3   ponder d7d5  draw");
+        assert_eq!((padded.best_move.as_str(), padded.ponder.as_deref()), ("g1f3", Some("d7d5")));
+        assert_eq!(best("bestmove a7a8q ponder (none)").ponder, None);
+        assert_eq!(best("bestmove (none)").best_move, "(none)");
+        assert!(BestMove::parse("bestmove").is_none());
+        assert!(BestMove::parse("info depth 1 score cp 0 pv e2e4").is_none());
+    }
+
+    #[test]
+    fn test_host_resources() {
+        let resources = Resources::for_host();
+        assert!(resources.threads >= 1);
+        assert!(resources.hash_mb.is_power_of_two());
+        assert!((16..=2048).contains(&resources.hash_mb));
+    }
+
+    #[test]
+    fn test_pick_varied_move() {
+        let line = |multipv, cp, mv: &str| SearchInfo {
+            multipv,
+            score: Score::Cp(cp),
+            first_move: Some(mv.to_string()),
+            reply: None,
+            tbhits: 0,
+            depth: 20,
+            pv: vec![mv.to_string()],
+        };
+        let lines = [line(1, 40, "e2e4"), line(2, 30, "d2d4"), line(3, -80, "g2g4")];
+        for _ in 0..20 {
+            let chosen = pick_varied_move(&lines, 25).unwrap();
+            assert!(chosen == "e2e4" || chosen == "d2d4");
+        }
+        assert_eq!(pick_varied_move(&lines, 0).as_deref(), Some("e2e4"));
+        assert_eq!(pick_varied_move(&[], 25), None);
+    }
+
+    #[test]
+    fn test_stderr_log() {
+        let mut log = UciLog::default();
+        log.push(Direction::ToEngine, "uci");
+        assert_eq!(log.last_stderr(), None);
+        log.push(Direction::Stderr, "info string NNUE file not found");
+        log.push(Direction::FromEngine, "uciok");
+        log.push(Direction::Stderr, "Unknown option: Foo");
+        assert_eq!(log.last_stderr(), Some((2, "Unknown option: Foo")));
+        assert_eq!(log.stderr_tail(1), ["Unknown option: Foo"]);
+        assert_eq!(log.stderr_tail(5).len(), 2);
+    }
+
+    #[test]
+    fn test_eval_file_option() {
+        let (name, value) = eval_file_option(Path::new("Cargo.toml")).unwrap();
+        assert_eq!(name, "EvalFile");
+        assert!(Path::new(&value).is_absolute());
+        assert!(eval_file_option(Path::new("no-such-network.nnue")).is_err());
+        assert!(eval_file_option(Path::new("src")).is_err());
+    }
+}
@@ -0,0 +1,663 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use chess::{Board, ChessMove, Color, GameResult};
+
+use crate::clock::TimeControl;
+use crate::engine::{engine_name, Analysis, ChessEngine, Resources, Score, SearchLimit};
+use crate::error::AppError;
+use crate::fen;
+use crate::game::{ChessGame, DrawReason, MoveAnnotation};
+use crate::i18n::Language;
+use crate::pgn::{self, export_pgn};
+
+/// Settings for a headless match or round-robin tournament.
+pub struct MatchConfig {
+    /// Two engines for a match; with more, every pair plays
+    pub engines: Vec<PathBuf>,
+    /// Games each pair of engines plays, alternating colours
+    pub games: u32,
+    pub time_control: TimeControl,
+    /// Games still going after this many moves are drawn
+    pub max_moves: u32,
+    /// Threads and hash for each engine
+    pub resources: Resources,
+    pub uci_options: Vec<(String, String)>,
+    /// Where the PGN of every game is written
+    pub pgn_path: PathBuf,
+    pub adjudication: Adjudication,
+    /// Positions to start game pairs from, in order; empty for the
+    /// standard starting position
+    pub openings: Vec<SuitePosition>,
+}
+
+/// A starting point from an opening suite: a position, plus the moves of
+/// the opening line when the suite is a PGN file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SuitePosition {
+    pub start: Board,
+    pub moves: Vec<ChessMove>,
+}
+
+/// Read an opening suite: one position per line from an .epd file, or the
+/// main line of every game in a PGN file.
+pub fn load_opening_suite(path: &Path) -> Result<Vec<SuitePosition>, AppError> {
+    let text = fs::read_to_string(path)?;
+    let is_epd = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("epd"));
+
+    let openings = if is_epd {
+        parse_epd(&text)?
+    } else {
+        pgn::parse_pgn(&text)?
+            .into_iter()
+            .map(|game| SuitePosition {
+                start: game.start,
+                moves: game.lines.into_iter().next().unwrap_or_default(),
+            })
+            .collect()
+    };
+    if openings.is_empty() {
+        return Err(AppError::Pgn(format!("No openings in {}", path.display())));
+    }
+    Ok(openings)
+}
+
+// EPD lines carry the first four FEN fields followed by operations
+fn parse_epd(text: &str) -> Result<Vec<SuitePosition>, AppError> {
+    let mut positions = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let fen = format!("{} 0 1", fields.join(" "));
+        let start = fen::safe_parse_board(&fen, Language::default().strings()).map_err(|e| {
+            AppError::Pgn(format!("Invalid EPD position on line {}: {} ({})", n + 1, line, e))
+        })?;
+        positions.push(SuitePosition {
+            start,
+            moves: Vec::new(),
+        });
+    }
+    Ok(positions)
+}
+
+/// Scores a tablebase-backed engine reports for won endings start here
+/// (Stockfish sends 20000 minus the distance to the win)
+const TB_WIN_CP: i32 = 10_000;
+
+/// When to end engine games early instead of playing them out.  A count
+/// of zero switches the rule off.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Adjudication {
+    /// Resign once both engines have seen one side ahead by `resign_score`
+    /// centipawns for this many moves in a row
+    pub resign_moves: u32,
+    pub resign_score: i32,
+    /// Draw once, from move `draw_after` on, both engines have scored the
+    /// position within `draw_score` centipawns of equal for this many
+    /// moves in a row
+    pub draw_moves: u32,
+    pub draw_after: u32,
+    pub draw_score: i32,
+    /// With this many pieces or fewer on the board, take the verdict of an
+    /// engine that is probing tablebases
+    pub tb_pieces: u32,
+}
+
+impl Adjudication {
+    /// Verdict on a game from the evaluations (White's point of view, one
+    /// per ply so far) the engines gave before each of their moves.
+    pub fn verdict(&self, white_evals: &[i32]) -> Option<(MatchResult, &'static str)> {
+        let recent = |moves: u32| {
+            let plies = moves as usize * 2;
+            let start = white_evals.len().checked_sub(plies)?;
+            (moves > 0).then(|| &white_evals[start..])
+        };
+
+        if let Some(evals) = recent(self.resign_moves) {
+            if evals.iter().all(|&cp| cp >= self.resign_score) {
+                return Some((MatchResult::WhiteWins, "resign adjudication"));
+            }
+            if evals.iter().all(|&cp| cp <= -self.resign_score) {
+                return Some((MatchResult::BlackWins, "resign adjudication"));
+            }
+        }
+
+        let move_number = white_evals.len() as u32 / 2 + 1;
+        if let Some(evals) = recent(self.draw_moves) {
+            let level = evals.iter().all(|&cp| cp.abs() <= self.draw_score);
+            if move_number > self.draw_after && level {
+                return Some((MatchResult::Draw, "draw adjudication"));
+            }
+        }
+        None
+    }
+
+    /// Verdict on `board` from an engine's search of it, if few enough
+    /// pieces remain and the engine's score came from tablebases.
+    pub fn tablebase_verdict(
+        &self,
+        board: &Board,
+        analysis: &Analysis,
+    ) -> Option<(MatchResult, &'static str)> {
+        if self.tb_pieces == 0 || board.combined().popcnt() > self.tb_pieces {
+            return None;
+        }
+        let line = analysis.lines.first().filter(|line| line.tbhits > 0)?;
+        let mover = board.side_to_move();
+        let result = match line.score.to_cp() {
+            cp if cp >= TB_WIN_CP => MatchResult::win_for(mover),
+            cp if cp <= -TB_WIN_CP => MatchResult::win_for(!mover),
+            0 => MatchResult::Draw,
+            _ => return None,
+        };
+        Some((result, "tablebase adjudication"))
+    }
+}
+
+/// Most pieces covered by the Syzygy tables under the engines'
+/// `SyzygyPath` (directories separated as in PATH), from the names of the
+/// WDL files found there; 0 without any.
+pub fn syzygy_pieces(uci_options: &[(String, String)]) -> u32 {
+    let mut options = uci_options.iter();
+    let Some((_, paths)) = options.find(|(name, _)| name.eq_ignore_ascii_case("SyzygyPath")) else {
+        return 0;
+    };
+    std::env::split_paths(paths)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| table_pieces(&entry.ok()?.file_name().to_string_lossy()))
+        .max()
+        .unwrap_or(0)
+}
+
+// Pieces in a table named like "KRPvKR.rtbw"
+fn table_pieces(file_name: &str) -> Option<u32> {
+    let material = file_name.strip_suffix(".rtbw")?;
+    let (white, black) = material.split_once('v')?;
+    let pieces = |side: &str| {
+        let valid = side.starts_with('K') && side.chars().all(|c| "KQRBNP".contains(c));
+        valid.then_some(side.len() as u32)
+    };
+    Some(pieces(white)? + pieces(black)?)
+}
+
+/// How a match game ended, from White's point of view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl MatchResult {
+    fn win_for(color: Color) -> MatchResult {
+        match color {
+            Color::White => MatchResult::WhiteWins,
+            Color::Black => MatchResult::BlackWins,
+        }
+    }
+
+    pub fn pgn(self) -> &'static str {
+        match self {
+            MatchResult::WhiteWins => "1-0",
+            MatchResult::BlackWins => "0-1",
+            MatchResult::Draw => "1/2-1/2",
+        }
+    }
+
+    /// Points scored by `color`
+    fn points(self, color: Color) -> f64 {
+        match (self, color) {
+            (MatchResult::Draw, _) => 0.5,
+            (MatchResult::WhiteWins, Color::White) | (MatchResult::BlackWins, Color::Black) => 1.0,
+            _ => 0.0,
+        }
+    }
+}
+
+/// A finished match game.
+pub struct PlayedGame {
+    pub game: ChessGame,
+    pub result: MatchResult,
+    /// Why the game ended, e.g. "checkmate" or "time forfeit"
+    pub reason: String,
+}
+
+/// Wins, draws and losses of one engine.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl MatchScore {
+    fn add(&mut self, points: f64) {
+        if points == 1.0 {
+            self.wins += 1;
+        } else if points == 0.5 {
+            self.draws += 1;
+        } else {
+            self.losses += 1;
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 * 0.5
+    }
+
+    /// Elo difference to the opposition implied by the score, with its 95%
+    /// confidence margin.  None without games or with a perfect or zero
+    /// score, where the difference is unbounded.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        let games = self.games() as f64;
+        if games == 0.0 {
+            return None;
+        }
+        let score = self.points() / games;
+        let elo = elo_from_score(score);
+        if !elo.is_finite() {
+            return None;
+        }
+
+        // Standard error of the mean score per game
+        let variance = (self.wins as f64 * (1.0 - score).powi(2)
+            + self.draws as f64 * (0.5 - score).powi(2)
+            + self.losses as f64 * score.powi(2))
+            / games;
+        let error = 1.959964 * (variance / games).sqrt();
+        let margin = (elo_from_score(score + error) - elo_from_score(score - error)) / 2.0;
+        Some((elo, margin))
+    }
+}
+
+// Rating difference that gives an expected score of `score`; infinite at
+// 0 and 1
+fn elo_from_score(score: f64) -> f64 {
+    let score = score.clamp(0.0, 1.0);
+    400.0 * (score / (1.0 - score)).log10()
+}
+
+/// One engine's results in a match or tournament.
+#[derive(Clone, Debug)]
+pub struct Standing {
+    pub name: String,
+    pub score: MatchScore,
+}
+
+/// Standings sorted by points, one row per engine, with each engine's Elo
+/// relative to the field.
+pub fn standings_table(standings: &[Standing]) -> String {
+    let mut sorted: Vec<&Standing> = standings.iter().collect();
+    sorted.sort_by(|a, b| b.score.points().total_cmp(&a.score.points()));
+    let name_width = sorted.iter().map(|s| s.name.len()).max().unwrap_or(0).max(4);
+
+    let mut table = format!(
+        "{:>4}  {:<name_width$}  {:>7}  {:>6}  {:>5}  {:>6}  {:>6}\n",
+        "Rank", "Name", "Elo", "+/-", "Games", "Score", "W-D-L"
+    );
+    for (rank, standing) in sorted.iter().enumerate() {
+        let score = standing.score;
+        let (elo, margin) = match score.elo() {
+            Some((elo, margin)) => (format!("{:.0}", elo), format!("{:.0}", margin)),
+            None => ("-".to_string(), "-".to_string()),
+        };
+        table.push_str(&format!(
+            "{:>4}  {:<name_width$}  {:>7}  {:>6}  {:>5}  {:>6.1}  {}-{}-{}\n",
+            rank + 1,
+            standing.name,
+            elo,
+            margin,
+            score.games(),
+            score.points(),
+            score.wins,
+            score.draws,
+            score.losses
+        ));
+    }
+    table
+}
+
+/// Play every pair of engines `games` times, alternating colours, printing
+/// each result as it comes in and writing all games to the configured PGN
+/// file.  Ends with the score (for two engines) or a standings table.
+pub fn run_match(config: &MatchConfig) -> Result<Vec<Standing>, AppError> {
+    let mut engines = Vec::new();
+    for path in &config.engines {
+        let mut engine = ChessEngine::new();
+        engine.start(path, 20, 0, config.resources, &config.uci_options)?;
+        engines.push(engine);
+    }
+    let mut standings: Vec<Standing> = config
+        .engines
+        .iter()
+        .map(|path| Standing {
+            name: engine_name(path),
+            score: MatchScore::default(),
+        })
+        .collect();
+
+    let event = if engines.len() == 2 { "Engine match" } else { "Engine tournament" };
+    let date = chrono::Local::now().format("%Y.%m.%d").to_string();
+    let mut pgn = String::new();
+    let mut game_number = 0;
+
+    let count = engines.len();
+    let pairings: Vec<(usize, usize)> = (0..count)
+        .flat_map(|i| (i + 1..count).map(move |j| (i, j)))
+        .collect();
+    for (first, second) in pairings {
+        for game in 0..config.games {
+            // The first engine of the pair has White in even games; both
+            // games of a pair start from the same opening
+            let (white_ix, black_ix) = if game % 2 == 0 { (first, second) } else { (second, first) };
+            let opening = match config.openings.len() {
+                0 => SuitePosition::default(),
+                count => config.openings[(game / 2) as usize % count].clone(),
+            };
+            let (white, black) = pair_mut(&mut engines, white_ix, black_ix);
+            let played = play_game(white, black, config, &opening)?;
+            standings[white_ix].score.add(played.result.points(Color::White));
+            standings[black_ix].score.add(played.result.points(Color::Black));
+
+            game_number += 1;
+            let white_name = standings[white_ix].name.clone();
+            let black_name = standings[black_ix].name.clone();
+            println!(
+                "Game {}: {} - {} {} ({})",
+                game_number,
+                white_name,
+                black_name,
+                played.result.pgn(),
+                played.reason
+            );
+
+            let mut tags: Vec<(String, String)> = [
+                ("Event", event.to_string()),
+                ("Site", "Chess Engine Player".to_string()),
+                ("Date", date.clone()),
+                ("Round", game_number.to_string()),
+                ("White", white_name),
+                ("Black", black_name),
+                ("Result", played.result.pgn().to_string()),
+                ("TimeControl", config.time_control.to_string()),
+                ("Termination", played.reason.clone()),
+            ]
+            .map(|(name, value)| (name.to_string(), value))
+            .into();
+            if opening.start != Board::default() {
+                tags.push(("SetUp".to_string(), "1".to_string()));
+                tags.push(("FEN".to_string(), opening.start.to_string()));
+            }
+            pgn.push_str(&export_pgn(&tags, played.game.get_move_records(), played.result.pgn()));
+            pgn.push('\n');
+            // Keep what has been played so far if a later game fails
+            fs::write(&config.pgn_path, &pgn)?;
+        }
+    }
+
+    println!();
+    if let [first, second] = &standings[..] {
+        let score = first.score;
+        println!(
+            "Score of {} vs {}: {} - {} - {} [{:.1}/{}]",
+            first.name,
+            second.name,
+            score.wins,
+            score.losses,
+            score.draws,
+            score.points(),
+            score.games()
+        );
+        match score.elo() {
+            Some((elo, margin)) => println!("Elo difference: {:.1} +/- {:.1}", elo, margin),
+            None => println!("Elo difference: unbounded (one engine scored every point)"),
+        }
+    } else {
+        print!("{}", standings_table(&standings));
+    }
+    Ok(standings)
+}
+
+// Two distinct elements of a slice, mutably
+fn pair_mut<T>(items: &mut [T], a: usize, b: usize) -> (&mut T, &mut T) {
+    if a < b {
+        let (left, right) = items.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = items.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    }
+}
+
+/// Play one game from `opening` with each engine on its own
+/// clock.  An engine that oversteps its time or sends an illegal move
+/// loses; an engine that stops responding is an error.  Games may end
+/// early by the configured adjudication rules.
+pub fn play_game(
+    white: &mut ChessEngine,
+    black: &mut ChessEngine,
+    config: &MatchConfig,
+    opening: &SuitePosition,
+) -> Result<PlayedGame, AppError> {
+    let time_control = config.time_control;
+    white.new_game()?;
+    black.new_game()?;
+    let mut game = ChessGame::new();
+    let start_fen = opening.start.to_string();
+    let opening_moves: Vec<String> = opening.moves.iter().map(|m| m.to_string()).collect();
+    game.load_moves(&start_fen, &opening_moves, Color::White).map_err(AppError::Pgn)?;
+    // Remaining time for White and Black
+    let mut clocks = [time_control.base_ms; 2];
+    // Each engine's score before each of its moves, White's point of view
+    let mut white_evals = Vec::new();
+
+    let finish = |game, result, reason: &str| {
+        Ok(PlayedGame {
+            game,
+            result,
+            reason: reason.to_string(),
+        })
+    };
+
+    loop {
+        match game.game_result() {
+            Some(GameResult::WhiteCheckmates) => {
+                return finish(game, MatchResult::WhiteWins, "checkmate")
+            }
+            Some(GameResult::BlackCheckmates) => {
+                return finish(game, MatchResult::BlackWins, "checkmate")
+            }
+            Some(_) => {
+                let reason = game.draw_reason().map_or("draw", DrawReason::description);
+                return finish(game, MatchResult::Draw, reason);
+            }
+            None => {}
+        }
+        if game.move_history().len() >= config.max_moves as usize * 2 {
+            return finish(game, MatchResult::Draw, "move limit");
+        }
+
+        let board = game.current_position();
+        let side = board.side_to_move();
+        let engine = match side {
+            Color::White => &mut *white,
+            Color::Black => &mut *black,
+        };
+        // UCI has no delay; engines budget for it as an increment
+        let limit = SearchLimit::Clock {
+            wtime: clocks[0],
+            btime: clocks[1],
+            winc: time_control.increment_ms,
+            binc: time_control.increment_ms,
+        };
+
+        let started = Instant::now();
+        let analysis = engine.analyse_game(&start_fen, game.move_history(), limit)?;
+        let elapsed = started.elapsed().as_millis() as u64;
+
+        let clock = &mut clocks[side.to_index()];
+        if elapsed > *clock {
+            return finish(game, MatchResult::win_for(!side), "time forfeit");
+        }
+        *clock = *clock - elapsed + time_control.credit_ms(elapsed);
+
+        if let Some((result, reason)) = config.adjudication.tablebase_verdict(&board, &analysis) {
+            note_tablebase_verdict(&mut game, result);
+            return finish(game, result, reason);
+        }
+        if !game.make_engine_move(&analysis.best_move) {
+            return finish(game, MatchResult::win_for(!side), "illegal move");
+        }
+        game.set_last_move_clock(Duration::from_millis(clocks[side.to_index()]));
+
+        let side_cp = analysis.score().map_or(0, Score::to_cp);
+        white_evals.push(if side == Color::White { side_cp } else { -side_cp });
+        if let Some((result, reason)) = config.adjudication.verdict(&white_evals) {
+            return finish(game, result, reason);
+        }
+    }
+}
+
+// Say in a comment on the last move that the tablebase decided the game,
+// for readers of the PGN that don't show the Termination tag
+fn note_tablebase_verdict(game: &mut ChessGame, result: MatchResult) {
+    let pieces = game.current_position().combined().popcnt();
+    let comment = format!("Adjudicated by tablebase with {} pieces left: {}", pieces, result.pgn());
+    game.annotate_move(
+        game.move_history().len(),
+        MoveAnnotation {
+            glyph: None,
+            comment: Some(comment),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::SearchInfo;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_match_score() {
+        let mut score = MatchScore::default();
+        for result in [MatchResult::WhiteWins, MatchResult::Draw, MatchResult::WhiteWins] {
+            score.add(result.points(Color::Black));
+        }
+        assert_eq!((score.wins, score.draws, score.losses), (0, 1, 2));
+        assert_eq!(score.points(), 0.5);
+    }
+
+    #[test]
+    fn test_elo_from_results() {
+        // 60% is about +70 Elo
+        let score = MatchScore { wins: 40, draws: 40, losses: 20 };
+        let (elo, margin) = score.elo().unwrap();
+        assert!((elo - 70.4).abs() < 0.1, "elo {}", elo);
+        assert!(margin > 40.0 && margin < 70.0, "margin {}", margin);
+
+        let even = MatchScore { wins: 5, draws: 0, losses: 5 };
+        assert_eq!(even.elo().unwrap().0, 0.0);
+        assert_eq!(MatchScore { wins: 3, draws: 0, losses: 0 }.elo(), None);
+
+        let standings = [
+            Standing { name: "weak".to_string(), score: MatchScore { wins: 1, draws: 0, losses: 3 } },
+            Standing { name: "strong".to_string(), score: even },
+        ];
+        let table = standings_table(&standings);
+        let rows: Vec<&str> = table.lines().collect();
+        assert!(rows[1].contains("strong") && rows[2].contains("weak"), "{}", table);
+    }
+
+    #[test]
+    fn test_adjudication_rules() {
+        let rules = Adjudication {
+            resign_moves: 2,
+            resign_score: 500,
+            draw_moves: 2,
+            draw_after: 3,
+            draw_score: 10,
+            tb_pieces: 5,
+        };
+        // One engine alone thinking White is winning isn't enough
+        assert_eq!(rules.verdict(&[20, 30, 600, 200, 650, 700]), None);
+        assert_eq!(
+            rules.verdict(&[20, 30, 600, 550, 650, 700]),
+            Some((MatchResult::WhiteWins, "resign adjudication"))
+        );
+        // Level positions only count from move 3
+        assert_eq!(rules.verdict(&[0, 5, -5, 0]), None);
+        assert_eq!(
+            rules.verdict(&[40, 0, 5, -5, 0, 10]),
+            Some((MatchResult::Draw, "draw adjudication"))
+        );
+
+        let krk = Board::from_str("8/8/8/4k3/8/8/8/R3K3 w - - 0 1").unwrap();
+        let analysis = |cp, tbhits| Analysis {
+            best_move: "a1a5".to_string(),
+            lines: vec![SearchInfo {
+                multipv: 1,
+                score: Score::Cp(cp),
+                first_move: None,
+                reply: None,
+                tbhits,
+                depth: 30,
+                pv: Vec::new(),
+            }],
+        };
+        assert_eq!(
+            rules.tablebase_verdict(&krk, &analysis(19_950, 7)),
+            Some((MatchResult::WhiteWins, "tablebase adjudication"))
+        );
+        assert_eq!(rules.tablebase_verdict(&krk, &analysis(19_950, 0)), None);
+        assert_eq!(rules.tablebase_verdict(&Board::default(), &analysis(0, 7)), None);
+    }
+
+    #[test]
+    fn test_parse_epd_suite() {
+        let text = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - id \"King's pawn\";\n\n\
+                    rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq -\n";
+        let suite = parse_epd(text).unwrap();
+        assert_eq!(suite.len(), 2);
+        assert_eq!(suite[0].start.side_to_move(), Color::Black);
+        assert_eq!(suite[1].start.piece_on(chess::Square::D4), Some(chess::Piece::Pawn));
+        assert!(parse_epd("not a position\n").is_err());
+        // A kingless line is turned away with its line number
+        let kingless = "4k3/8/8/8/8/8/8/8 w - -\n";
+        match parse_epd(&format!("{}{}", text, kingless)) {
+            Err(AppError::Pgn(message)) => assert!(message.contains("line 4")),
+            other => panic!("expected an EPD error, got {:?}", other.map(|suite| suite.len())),
+        }
+    }
+
+    #[test]
+    fn test_syzygy_pieces() {
+        assert_eq!(table_pieces("KRPvKR.rtbw"), Some(5));
+        assert_eq!(table_pieces("KRPvKR.rtbz"), None);
+        assert_eq!(table_pieces("KXvK.rtbw"), None);
+
+        let dir = std::env::temp_dir().join(format!("cep-syzygy-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["KQvK.rtbw", "KRPvKR.rtbw", "KRPPvKRP.rtbz", "README.txt"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let options = [("SyzygyPath".to_string(), dir.display().to_string())];
+        let pieces = syzygy_pieces(&options);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(pieces, 5);
+        assert_eq!(syzygy_pieces(&[]), 0);
+    }
+}
@@ -8,6 +8,27 @@ pub enum AppError {
 
     #[error("Engine error: {0}")]
     Engine(String),
+
+    #[error("Save file error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    #[error("Database error: {0}")]
+    Database(String),
+
+    #[error("PGN error: {0}")]
+    Pgn(String),
+
+    #[error("Image error: {0}")]
+    Image(String),
+
+    #[error("Network error: {0}")]
+    Network(String),
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Database(e.to_string())
+    }
 }
 
 impl From<&str> for AppError {
@@ -0,0 +1,38 @@
+use std::sync::OnceLock;
+
+use crate::pgn::{self, PgnGame};
+
+/// Annotated classic games bundled into the binary, for replaying and as a
+/// tour of the move list and history navigation.
+const FAMOUS_GAMES: &str = include_str!("../assets/famous_games.pgn");
+
+/// The bundled games, parsed on first use.
+pub fn bundled() -> &'static [PgnGame] {
+    static GAMES: OnceLock<Vec<PgnGame>> = OnceLock::new();
+    GAMES.get_or_init(|| {
+        pgn::parse_pgn(FAMOUS_GAMES).unwrap_or_else(|e| {
+            eprintln!("Failed to read the bundled games: {}", e);
+            Vec::new()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::{Board, BoardStatus};
+
+    #[test]
+    fn test_bundled_games_end_in_mate() {
+        let games = bundled();
+        assert_eq!(games.len(), 4);
+        for game in games {
+            let end = game.lines[0].iter().fold(game.start, |board: Board, &m| board.make_move_new(m));
+            assert_eq!(end.status(), BoardStatus::Checkmate, "{:?}", game.tag("Event"));
+            assert!(game.annotations.iter().any(|a| a.comment.is_some()));
+        }
+        assert_eq!(games[0].tag("White"), Some("Paul Morphy"));
+        // The Reti game carries the alternative mate as a variation
+        assert_eq!(games[3].lines.len(), 2);
+    }
+}
@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use chess::{Board, BoardBuilder, EMPTY};
+
+use crate::i18n::{fill, Strings};
+
+/// Parse a FEN string without risking a panic from the chess crate.
+///
+/// The chess crate aborts on positions it considers structurally broken
+/// (missing kings, pawns on the back ranks, etc.).  We catch those cases
+/// with cheap string checks before ever handing the FEN to the library.
+/// The reason a position is refused is given in `t`'s language.
+pub fn safe_parse_board(fen: &str, t: &Strings) -> Result<Board, String> {
+    let placement = fen.split_whitespace().next().unwrap_or("");
+
+    // Both kings must be present.
+    if !placement.contains('K') {
+        return Err(t.missing_white_king.to_string());
+    }
+    if !placement.contains('k') {
+        return Err(t.missing_black_king.to_string());
+    }
+
+    // FEN rank order: rank 8 first, rank 1 last.
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() == 8 {
+        if ranks[0].contains('P') || ranks[0].contains('p') {
+            return Err(fill(t.pawns_on_rank, 8));
+        }
+        if ranks[7].contains('P') || ranks[7].contains('p') {
+            return Err(fill(t.pawns_on_rank, 1));
+        }
+    }
+
+    let white = ['K', 'Q', 'R', 'B', 'N', 'P'];
+    for (name, pieces) in [(t.white, white), (t.black, white.map(|c| c.to_ascii_lowercase()))] {
+        if let Some(problem) = material_problem(t, name, placement, pieces) {
+            return Err(problem);
+        }
+    }
+
+    Board::from_str(fen).map_err(|e| match e {
+        // The chess crate doesn't say why; the usual reason is a check on
+        // the side that just moved
+        chess::Error::InvalidBoard if opponent_in_check(fen) => {
+            let waiting = match fen.split_whitespace().nth(1) {
+                Some("b") => t.white,
+                _ => t.black,
+            };
+            fill(t.check_not_to_move, waiting)
+        }
+        e => fill(t.invalid_fen, format!("{:?}", e)),
+    })
+}
+
+/// Why one side's men, `pieces` as FEN letters from king to pawn, can't
+/// all be on the board: more than one king, more than eight pawns, or more
+/// extra queens, rooks, bishops and knights than pawns have gone missing
+/// to promote into them.
+fn material_problem(t: &Strings, side: &str, placement: &str, pieces: [char; 6]) -> Option<String> {
+    let count = |letter| placement.chars().filter(|&c| c == letter).count();
+    let [king, queen, rook, bishop, knight, pawn] = pieces.map(count);
+    let total = king + queen + rook + bishop + knight + pawn;
+    if king > 1 {
+        return Some(fill(&fill(t.too_many_kings, side), king));
+    }
+    if pawn > 8 {
+        return Some(fill(&fill(t.too_many_pawns, side), pawn));
+    }
+    if total > 16 {
+        return Some(fill(&fill(t.too_many_pieces, side), total));
+    }
+    let promoted = queen.saturating_sub(1)
+        + rook.saturating_sub(2)
+        + bishop.saturating_sub(2)
+        + knight.saturating_sub(2);
+    if promoted > 8 - pawn {
+        return Some(fill(&fill(t.too_many_promoted, side), promoted - (8 - pawn)));
+    }
+    None
+}
+
+/// The side that isn't to move is in check, which no legal game reaches.
+fn opponent_in_check(fen: &str) -> bool {
+    let Ok(mut position) = BoardBuilder::from_str(fen) else {
+        return false;
+    };
+    let mover = position.get_side_to_move();
+    position.side_to_move(!mover);
+    Board::try_from(&position).is_ok_and(|board| *board.checkers() != EMPTY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::{ENGLISH, GERMAN};
+
+    #[test]
+    fn test_material_problem() {
+        let white = ['K', 'Q', 'R', 'B', 'N', 'P'];
+        let full = "4k3/8/8/8/8/8/PPPPPPPP/RNBQKBNR";
+        assert_eq!(material_problem(&ENGLISH, "White", full, white), None);
+        let nine_pawns = "4k3/8/8/8/8/P7/PPPPPPPP/4K3";
+        assert_eq!(
+            material_problem(&GERMAN, GERMAN.white, nine_pawns, white).as_deref(),
+            Some("Weiß hat 9 Bauern; höchstens 8 sind möglich")
+        );
+    }
+
+    #[test]
+    fn test_opponent_in_check() {
+        assert!(opponent_in_check("4r2k/8/8/8/8/8/8/4K3 b - - 0 1"));
+        assert!(!opponent_in_check("4r2k/8/8/8/8/8/8/4K3 w - - 0 1"));
+        // Kingless positions are turned away before the chess crate sees them
+        let kingless = safe_parse_board("8/8/8/8/8/8/8/4K3 w - - 0 1", &ENGLISH);
+        assert_eq!(kingless.err().as_deref(), Some("Missing black king (k)"));
+    }
+}
@@ -1,8 +1,17 @@
+use std::time::{Duration, Instant};
+
 use chess::{Board, ChessMove, Color, File, Game, MoveGen, Piece, Rank, Square};
 
+use crate::analysis::{GameAccuracy, MoveEvaluation};
+use crate::chess960::{self, Castling};
+use crate::clock::{GameClock, TimeControl};
+use crate::eco::{EcoTable, Opening};
+use crate::fen;
+use crate::i18n::{fill, Language, Strings};
+use crate::variation::VariationTree;
+
 #[derive(Clone, Debug)]
 pub struct MoveDetails {
-    #[allow(dead_code)]
     pub notation: String,
     pub piece: Piece,
     #[allow(dead_code)]
@@ -11,6 +20,35 @@ pub struct MoveDetails {
     pub is_capture: bool,
     /// Display text without piece letter (since we show the SVG)
     pub display_text: String,
+    /// Post-game review verdict, once the game has been analysed
+    pub review: Option<MoveEvaluation>,
+    /// The player's own glyph and comment
+    pub annotation: MoveAnnotation,
+    /// Time left on the mover's clock after the move, when clocks are used
+    pub clock: Option<Duration>,
+    /// How long the move took, for moves played here
+    pub time_spent: Option<Duration>,
+}
+
+/// Move assessment glyphs, best to worst, with their PGN NAG numbers.
+pub const MOVE_GLYPHS: [(&str, u8); 6] = [("!!", 3), ("!", 1), ("!?", 5), ("?!", 6), ("?", 2), ("??", 4)];
+
+/// A glyph and free-text comment attached to a move.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MoveAnnotation {
+    /// One of the `MOVE_GLYPHS`
+    pub glyph: Option<&'static str>,
+    pub comment: Option<String>,
+}
+
+/// Both sides' clock readings for the side panel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClockTimes {
+    /// Unknown when a loaded game recorded no time for that side yet
+    pub white: Option<Duration>,
+    pub black: Option<Duration>,
+    /// Side whose clock is ticking, in a live game
+    pub running: Option<Color>,
 }
 
 #[derive(Clone, Debug)]
@@ -20,6 +58,309 @@ pub struct MoveRecord {
     pub black_move: Option<MoveDetails>,
 }
 
+/// The move that reached position `ply` (1 for the first move) in a
+/// record list.
+pub fn move_details(records: &[MoveRecord], ply: usize) -> Option<&MoveDetails> {
+    records
+        .iter()
+        .flat_map(|r| [&r.white_move, &r.black_move])
+        .flatten()
+        .nth(ply.checked_sub(1)?)
+}
+
+/// The records of the first `ply` moves, the last one cut after White's
+/// move if that's where they end.
+pub fn records_until(records: &[MoveRecord], ply: usize) -> Vec<MoveRecord> {
+    let mut left = ply;
+    let mut kept = Vec::new();
+    for record in records {
+        if left == 0 {
+            break;
+        }
+        let mut record = record.clone();
+        for side in [&mut record.white_move, &mut record.black_move] {
+            if side.is_some() {
+                match left {
+                    0 => *side = None,
+                    _ => left -= 1,
+                }
+            }
+        }
+        kept.push(record);
+    }
+    kept
+}
+
+/// Whether neither side can ever checkmate: kings with at most one minor
+/// piece between them, or only bishops all on squares of one colour.
+pub fn insufficient_material(board: &Board) -> bool {
+    let heavy = board.pieces(Piece::Pawn) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    if heavy.popcnt() > 0 {
+        return false;
+    }
+    let knights = board.pieces(Piece::Knight).popcnt();
+    let bishops = *board.pieces(Piece::Bishop);
+    let light = bishops
+        .into_iter()
+        .filter(|sq| (sq.get_rank().to_index() + sq.get_file().to_index()) % 2 == 1)
+        .count() as u32;
+    knights + bishops.popcnt() <= 1 || (knights == 0 && (light == 0 || light == bishops.popcnt()))
+}
+
+/// Why a game ended in a draw.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawReason {
+    Stalemate,
+    Repetition,
+    FiftyMoves,
+    InsufficientMaterial,
+}
+
+impl DrawReason {
+    /// Lower-case English, for reports and PGN
+    pub fn description(self) -> &'static str {
+        match self {
+            DrawReason::Stalemate => "stalemate",
+            DrawReason::Repetition => "threefold repetition",
+            DrawReason::FiftyMoves => "fifty-move rule",
+            DrawReason::InsufficientMaterial => "insufficient material",
+        }
+    }
+
+    /// In the interface language, for the status line
+    pub fn label(self, t: &Strings) -> &'static str {
+        match self {
+            DrawReason::Stalemate => t.stalemate,
+            DrawReason::Repetition => t.threefold_repetition,
+            DrawReason::FiftyMoves => t.fifty_move_rule,
+            DrawReason::InsufficientMaterial => t.insufficient_material,
+        }
+    }
+}
+
+/// Whether `side` has enough material left to ever checkmate: anything
+/// more than a lone king, or a king with a single knight or bishop.
+pub fn has_mating_material(board: &Board, side: Color) -> bool {
+    let own = board.color_combined(side);
+    let heavy = board.pieces(Piece::Pawn) | board.pieces(Piece::Rook) | board.pieces(Piece::Queen);
+    let minors = board.pieces(Piece::Knight) | board.pieces(Piece::Bishop);
+    (heavy & own).popcnt() > 0 || (minors & own).popcnt() > 1
+}
+
+/// The halfmove clock and move number at the end of a FEN, defaulting to
+/// those of a fresh game.
+pub fn fen_counters(fen: &str) -> (u32, u32) {
+    let mut fields = fen.split_whitespace().skip(4).map(|field| field.parse().ok());
+    let halfmove = fields.next().flatten().unwrap_or(0);
+    let fullmove = fields.next().flatten().unwrap_or(1).max(1);
+    (halfmove, fullmove)
+}
+
+/// A board's FEN with the given counters in place of the "0 1" the chess
+/// crate always writes.
+pub fn with_counters(board: &Board, halfmove: u32, fullmove: u32) -> String {
+    let fen = board.to_string();
+    let position: Vec<&str> = fen.split_whitespace().take(4).collect();
+    format!("{} {} {}", position.join(" "), halfmove, fullmove.max(1))
+}
+
+/// The first position at or after index `from`, wrapping round to the
+/// start, that matches `pattern`: a FEN, of which only the piece placement
+/// and side to move count, or just a placement in which `?` stands for any
+/// square and squares or ranks left off the end match anything.  None if
+/// no position matches or the pattern can't be read.
+pub fn find_position(positions: &[Board], pattern: &str, from: usize) -> Option<usize> {
+    let mut fields = pattern.split_whitespace();
+    let squares = placement_pattern(fields.next()?)?;
+    let side = match fields.next() {
+        None => None,
+        Some("w") => Some(Color::White),
+        Some("b") => Some(Color::Black),
+        Some(_) => return None,
+    };
+    let matches = |board: &Board| {
+        side.is_none_or(|side| board.side_to_move() == side)
+            && chess::ALL_SQUARES.iter().all(|&square| match squares[square.to_index()] {
+                None => true,
+                Some(wanted) => board.piece_on(square).zip(board.color_on(square)) == wanted,
+            })
+    };
+    (0..positions.len())
+        .map(|offset| (from + offset) % positions.len())
+        .find(|&index| matches(&positions[index]))
+}
+
+// What a square of a placement pattern asks for: None for anything,
+// Some(None) for an empty square
+type SquarePattern = Option<Option<(Piece, Color)>>;
+
+fn placement_pattern(placement: &str) -> Option<[SquarePattern; 64]> {
+    let mut squares = [None; 64];
+    for (row, rank) in placement.split('/').enumerate() {
+        let rank_index = 7usize.checked_sub(row)?;
+        let mut file = 0;
+        for c in rank.chars() {
+            let (wanted, width) = match c {
+                '?' => (None, 1),
+                '1'..='8' => (Some(None), c.to_digit(10)? as usize),
+                _ => {
+                    let piece = match c.to_ascii_lowercase() {
+                        'p' => Piece::Pawn,
+                        'n' => Piece::Knight,
+                        'b' => Piece::Bishop,
+                        'r' => Piece::Rook,
+                        'q' => Piece::Queen,
+                        'k' => Piece::King,
+                        _ => return None,
+                    };
+                    let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+                    (Some(Some((piece, color))), 1)
+                }
+            };
+            if file + width > 8 {
+                return None;
+            }
+            for offset in 0..width {
+                squares[rank_index * 8 + file + offset] = wanted;
+            }
+            file += width;
+        }
+    }
+    Some(squares)
+}
+
+/// The ply reached by a move written as its number and side, e.g. "23" or
+/// "23w" for White's 23rd move and "23b" or "23..." for Black's.  None if
+/// the game has no such move.
+pub fn find_ply(records: &[MoveRecord], input: &str) -> Option<usize> {
+    let input = input.trim().to_lowercase();
+    let (number, side) = if let Some(number) = input.strip_suffix("...") {
+        (number, Color::Black)
+    } else if let Some(number) = input.strip_suffix('b') {
+        (number, Color::Black)
+    } else {
+        (input.trim_end_matches(['w', '.']), Color::White)
+    };
+    let number: usize = number.trim().parse().ok()?;
+
+    let mut ply = 0;
+    for record in records {
+        let sides = [(&record.white_move, Color::White), (&record.black_move, Color::Black)];
+        for (details, color) in sides {
+            if details.is_some() {
+                ply += 1;
+                if record.move_num == number && color == side {
+                    return Some(ply);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Everything the board view needs to render one frame.
+///
+/// The application keeps one of these cached and only rebuilds it when the
+/// game's revision counter changes, so idle ticks don't clone the history.
+#[derive(Clone, Debug)]
+pub struct GameSnapshot {
+    pub revision: u64,
+    pub board: Board,
+    pub selected_square: Option<Square>,
+    pub possible_moves: Vec<ChessMove>,
+    /// Every legal move in the position, for previewing a piece's moves
+    /// under the mouse
+    pub legal_moves: Vec<ChessMove>,
+    pub message: String,
+    pub thinking: bool,
+    pub player_color: Color,
+    pub game_result: Option<chess::GameResult>,
+    pub move_records: Vec<MoveRecord>,
+    pub view_mode: bool,
+    pub view_move_index: usize,
+    pub pending_promotion: Option<(Square, Square)>,
+    pub reviewed: bool,
+    pub accuracy: Option<GameAccuracy>,
+    pub coach_mode: bool,
+    pub pending_confirmation: Option<PendingConfirmation>,
+    pub opening: Option<Opening>,
+    pub paused: bool,
+    /// Side whose clock ran out, if the game ended on time
+    pub flagged: Option<Color>,
+    pub draw_reason: Option<DrawReason>,
+    pub rated: bool,
+    pub variations: Option<VariationTree>,
+    /// Nodes of the variation tree the moves played go through
+    pub line_nodes: Vec<usize>,
+}
+
+impl GameSnapshot {
+    /// How a finished game ended, in the interface language: who lost on
+    /// time, why it was drawn, or the result.  None while it goes on.
+    pub fn result_text(&self, t: &Strings) -> Option<String> {
+        let result = self.game_result;
+        if let (Some(side), Some(result)) = (self.flagged, result) {
+            let name = if side == Color::White { t.white } else { t.black };
+            let drawn = result == chess::GameResult::DrawAccepted;
+            return Some(fill(if drawn { t.time_out_draw } else { t.lost_on_time }, name));
+        }
+        if let Some(reason) = self.draw_reason {
+            return Some(fill(t.draw_by, reason.label(t)));
+        }
+        let result = match result? {
+            chess::GameResult::WhiteCheckmates => t.white_checkmates,
+            chess::GameResult::BlackCheckmates => t.black_checkmates,
+            chess::GameResult::WhiteResigns => t.white_resigns,
+            chess::GameResult::BlackResigns => t.black_resigns,
+            chess::GameResult::Stalemate => t.stalemate,
+            chess::GameResult::DrawAccepted => t.draw_agreed,
+            chess::GameResult::DrawDeclared => t.draw_declared,
+        };
+        Some(fill(t.game_over, result))
+    }
+}
+
+impl Default for GameSnapshot {
+    fn default() -> Self {
+        GameSnapshot {
+            // Never matches a real game revision, so the first refresh
+            // always populates the snapshot
+            revision: u64::MAX,
+            board: Board::default(),
+            selected_square: None,
+            possible_moves: Vec::new(),
+            legal_moves: Vec::new(),
+            message: String::new(),
+            thinking: false,
+            player_color: Color::White,
+            game_result: None,
+            move_records: Vec::new(),
+            view_mode: false,
+            view_move_index: 0,
+            pending_promotion: None,
+            reviewed: false,
+            accuracy: None,
+            coach_mode: false,
+            pending_confirmation: None,
+            opening: None,
+            paused: false,
+            flagged: None,
+            draw_reason: None,
+            rated: false,
+            variations: None,
+            line_nodes: Vec::new(),
+        }
+    }
+}
+
+/// A move held back in coach mode until the engine has checked it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingConfirmation {
+    pub chess_move: ChessMove,
+    /// Coach's objection once the check is done; None while it still runs
+    pub warning: Option<String>,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PromotionPiece {
     Queen,
@@ -37,10 +378,43 @@ pub struct ChessGame {
     player_color: Color,
     move_history: Vec<ChessMove>,
     position_history: Vec<Board>,
+    /// Halfmove clock and move number of the start position, which `Board`
+    /// doesn't keep
+    start_counters: (u32, u32),
     move_records: Vec<MoveRecord>,
     view_mode: bool,
     view_move_index: usize,
     pending_promotion: Option<(Square, Square)>,
+    coach_mode: bool,
+    pending_confirmation: Option<PendingConfirmation>,
+    time_control: Option<TimeControl>,
+    /// The engine's own time control in a time-odds game
+    time_odds: Option<TimeControl>,
+    clock: Option<GameClock>,
+    /// The clocks are stopped and no moves are taken until resumed
+    paused: bool,
+    /// Side whose clock ran out, ending the game
+    flagged: Option<Color>,
+    /// Draw rule that ended the game; stalemate is the board's own
+    drawn_by: Option<DrawReason>,
+    /// No takebacks or engine help until the game is over
+    rated: bool,
+    /// The player had a takeback, the coach or engine analysis to help, so
+    /// the result says less about their strength
+    assisted: bool,
+    /// Time the side to move has thought so far: what was banked before a
+    /// pause, plus the stretch running since
+    turn_used: Duration,
+    turn_since: Option<Instant>,
+    /// Side lines of an imported game, with the line played here among
+    /// them; None for a game without any
+    variations: Option<VariationTree>,
+    /// Castling rights at the start of a Chess960 game, whose boards carry
+    /// none; None in a standard game
+    castling: Option<Castling>,
+    /// Status messages in the chosen language
+    strings: &'static Strings,
+    revision: u64,
 }
 
 impl ChessGame {
@@ -49,7 +423,7 @@ impl ChessGame {
             game: Game::new(),
             selected_square: None,
             possible_moves: Vec::new(),
-            message: String::from("Welcome to Chess Engine Player! Make a move to begin."),
+            message: Language::default().strings().welcome.to_string(),
             thinking: false,
             player_color: Color::White,
             move_history: Vec::new(),
@@ -58,6 +432,23 @@ impl ChessGame {
             view_mode: false,
             view_move_index: 0,
             pending_promotion: None,
+            coach_mode: false,
+            pending_confirmation: None,
+            time_control: None,
+            time_odds: None,
+            clock: None,
+            paused: false,
+            flagged: None,
+            drawn_by: None,
+            rated: false,
+            assisted: false,
+            turn_used: Duration::ZERO,
+            turn_since: Some(Instant::now()),
+            start_counters: (0, 1),
+            variations: None,
+            castling: None,
+            strings: Language::default().strings(),
+            revision: 0,
         };
 
         // Save initial position
@@ -67,42 +458,67 @@ impl ChessGame {
     }
 
     pub fn reset(&mut self) {
+        self.touch();
         self.game = Game::new();
         self.selected_square = None;
         self.possible_moves.clear();
-        self.message = String::from("Game reset. Make a move to begin.");
+        self.message = self.strings.game_reset.to_string();
         self.thinking = false;
         self.move_history.clear();
         self.position_history.clear();
         self.move_records.clear();
+        self.start_counters = (0, 1);
+        self.variations = None;
+        self.castling = None;
         self.view_mode = false;
         self.view_move_index = 0;
         self.pending_promotion = None;
+        self.pending_confirmation = None;
+        self.paused = false;
+        self.flagged = None;
+        self.drawn_by = None;
+        // Another game of the same kind, so a rated one stays rated
+        self.assisted = false;
         self.position_history.push(self.game.current_position());
+        self.restart_clock();
     }
 
-    pub fn reset_from_fen(&mut self, fen: &str, player_color: Color) {
-        use std::str::FromStr;
-        match Board::from_str(fen) {
-            Ok(board) => {
-                self.game = Game::new_with_board(board);
-                self.player_color = player_color;
-                self.selected_square = None;
-                self.possible_moves.clear();
-                self.message = String::from("Custom position loaded. Make a move to begin.");
-                self.thinking = false;
-                self.move_history.clear();
-                self.position_history.clear();
-                self.move_records.clear();
-                self.view_mode = false;
-                self.view_move_index = 0;
-                self.pending_promotion = None;
-                self.position_history.push(board);
-            }
-            Err(e) => {
-                eprintln!("Failed to load position from FEN: {:?}", e);
-            }
-        }
+    /// Start again from a FEN.  Castling rights written by file, as in
+    /// Shredder-FEN, make it a Chess960 game that castles king-takes-rook.
+    /// A position the chess crate can't take is refused, leaving the game
+    /// as it was.
+    pub fn reset_from_fen(&mut self, fen: &str, player_color: Color) -> Result<(), String> {
+        let castling = chess960::is_shredder_fen(fen).then(|| Castling::from_fen(fen));
+        let readable = match castling {
+            Some(_) => chess960::with_castling(fen, "-"),
+            None => fen.to_string(),
+        };
+        let board = fen::safe_parse_board(&readable, self.strings)?;
+        self.touch();
+        self.game = Game::new_with_board(board);
+        self.player_color = player_color;
+        self.selected_square = None;
+        self.possible_moves.clear();
+        self.message = self.strings.position_loaded.to_string();
+        self.thinking = false;
+        self.move_history.clear();
+        self.position_history.clear();
+        self.move_records.clear();
+        self.start_counters = fen_counters(fen);
+        self.variations = None;
+        self.castling = castling;
+        self.view_mode = false;
+        self.view_move_index = 0;
+        self.pending_promotion = None;
+        self.pending_confirmation = None;
+        self.paused = false;
+        self.flagged = None;
+        self.drawn_by = None;
+        self.rated = false;
+        self.assisted = false;
+        self.position_history.push(board);
+        self.restart_clock();
+        Ok(())
     }
 
     pub fn current_position(&self) -> chess::Board {
@@ -122,6 +538,7 @@ impl ChessGame {
     }
 
     pub fn set_view_mode(&mut self, enabled: bool) {
+        self.touch();
         self.view_mode = enabled;
         if !enabled {
             self.view_move_index = self.position_history.len().saturating_sub(1);
@@ -129,6 +546,7 @@ impl ChessGame {
     }
 
     pub fn view_move_at(&mut self, index: usize) {
+        self.touch();
         if index < self.position_history.len() {
             self.view_move_index = index;
             self.view_mode = true;
@@ -137,6 +555,21 @@ impl ChessGame {
         }
     }
 
+    /// Step `plies` back (negative) or forward through the game from the
+    /// position shown; reaching the last position leaves view mode.
+    pub fn step_view(&mut self, plies: isize) {
+        let last = self.position_history.len().saturating_sub(1);
+        let current = if self.view_mode { self.view_move_index } else { last };
+        let target = current.saturating_add_signed(plies).min(last);
+        if target == last {
+            if self.view_mode {
+                self.set_view_mode(false);
+            }
+        } else {
+            self.view_move_at(target);
+        }
+    }
+
     pub fn get_move_records(&self) -> &Vec<MoveRecord> {
         &self.move_records
     }
@@ -166,41 +599,47 @@ impl ChessGame {
     }
 
     pub fn set_player_color(&mut self, color: Color) {
+        self.touch();
         self.player_color = color;
-        self.message = format!(
-            "You are playing as {}.",
-            if color == Color::White {
-                "White"
-            } else {
-                "Black"
-            }
-        );
+        let name = if color == Color::White { self.strings.white } else { self.strings.black };
+        self.message = fill(self.strings.playing_as, name);
     }
 
     pub fn flip_side(&mut self) {
+        self.touch();
         self.player_color = if self.player_color == Color::White {
             Color::Black
         } else {
             Color::White
         };
-        self.message = format!(
-            "You are playing as {}.",
-            if self.player_color == Color::White {
-                "White"
-            } else {
-                "Black"
-            }
-        );
+        let name = if self.player_color == Color::White {
+            self.strings.white
+        } else {
+            self.strings.black
+        };
+        self.message = fill(self.strings.playing_as, name);
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        self.strings = language.strings();
+    }
+
+    pub fn set_message(&mut self, message: String) {
+        self.touch();
+        self.message = message;
     }
 
     pub fn set_thinking(&mut self, thinking: bool) {
+        self.touch();
         self.thinking = thinking;
         if thinking {
-            self.message = "Engine is thinking...".to_string();
+            self.message = self.strings.engine_thinking.to_string();
         }
     }
 
     pub fn select_square(&mut self, square: Square) -> bool {
+        self.touch();
+
         // Only allow selecting squares when it's the player's turn
         if self.game.side_to_move() != self.player_color {
             return false;
@@ -211,6 +650,15 @@ impl ChessGame {
             return false;
         }
 
+        if self.paused {
+            return false;
+        }
+
+        // The coach is still looking at the previous choice
+        if self.pending_confirmation.is_some() {
+            return false;
+        }
+
         let board = self.game.current_position();
 
         if let Some(_selected) = self.selected_square {
@@ -233,28 +681,11 @@ impl ChessGame {
                 if needs_promotion {
                     // Set pending promotion - don't make the move yet
                     self.pending_promotion = Some((chess_move.get_source(), chess_move.get_dest()));
-                    self.message = "Select promotion piece".to_string();
+                    self.message = self.strings.select_promotion.to_string();
                     return true;
                 }
 
-                // Get move details BEFORE making the move (need the board position)
-                // White is moving (player's turn)
-                let details = self.move_to_details(chess_move, &board, Color::White);
-
-                if self.game.make_move(chess_move) {
-                    // Save position after making the move
-                    self.position_history.push(self.game.current_position());
-
-                    // Record the move
-                    self.record_move(details);
-
-                    self.message = format!("Move: {}", chess_move);
-                    self.selected_square = None;
-                    self.possible_moves.clear();
-                    self.move_history.push(chess_move);
-                    self.view_move_index = self.position_history.len() - 1;
-                    return true;
-                }
+                return self.submit_move(chess_move);
             } else {
                 // Select a new square if it has a piece of the current player's color
                 if let Some(_piece) = board.piece_on(square) {
@@ -296,6 +727,7 @@ impl ChessGame {
     }
 
     pub fn promote_pawn(&mut self, promotion_piece: PromotionPiece) -> bool {
+        self.touch();
         if let Some((from, to)) = self.pending_promotion.take() {
             let promotion = match promotion_piece {
                 PromotionPiece::Queen => Some(Piece::Queen),
@@ -305,111 +737,459 @@ impl ChessGame {
             };
 
             let chess_move = ChessMove::new(from, to, promotion);
-            let board = self.game.current_position();
-            let details = self.move_to_details(chess_move, &board, Color::White);
+            return self.submit_move(chess_move);
+        }
+        false
+    }
+
+    /// Put the pawn back and let the player choose another move.
+    pub fn cancel_promotion(&mut self) {
+        self.touch();
+        if self.pending_promotion.take().is_some() {
+            self.selected_square = None;
+            self.possible_moves.clear();
+            self.message = self.strings.promotion_cancelled.to_string();
+        }
+    }
+
+    // Play the player's chosen move, or in coach mode hold it back for the
+    // engine to check first.  True when the move was played or held.
+    fn submit_move(&mut self, chess_move: ChessMove) -> bool {
+        if self.coach_mode && !self.rated {
+            self.assisted = true;
+            self.pending_confirmation = Some(PendingConfirmation {
+                chess_move,
+                warning: None,
+            });
+            self.message = self.strings.coach_checking.to_string();
+            return true;
+        }
+
+        if self.commit_move(chess_move) {
+            self.end_turn();
+            self.message = fill(self.strings.move_played, chess_move);
+            self.selected_square = None;
+            self.possible_moves.clear();
+            return true;
+        }
+        false
+    }
+
+    pub fn coach_mode(&self) -> bool {
+        self.coach_mode
+    }
+
+    pub fn is_rated(&self) -> bool {
+        self.rated
+    }
+
+    /// Make the game rated, with no takebacks, hints or coaching until it
+    /// ends, or casual again.  Takes effect from the next move.
+    pub fn set_rated(&mut self, rated: bool) {
+        self.touch();
+        self.rated = rated;
+    }
+
+    /// Takebacks and engine help are allowed: always in a casual game, and
+    /// in a rated one once it is over.
+    pub fn assists_allowed(&self) -> bool {
+        !self.rated || self.game_result().is_some()
+    }
+
+    /// The player had help, so the result stays out of the statistics.
+    pub fn assisted(&self) -> bool {
+        self.assisted
+    }
+
+    /// Record help from outside the game, like engine analysis shown while
+    /// playing.
+    pub fn mark_assisted(&mut self) {
+        self.assisted = true;
+    }
 
-            if self.game.make_move(chess_move) {
-                self.position_history.push(self.game.current_position());
-                self.record_move(details);
-                self.message = format!("Move: {}", chess_move);
+    pub fn set_coach_mode(&mut self, enabled: bool) {
+        self.touch();
+        self.coach_mode = enabled;
+        self.pending_confirmation = None;
+    }
+
+    /// The move waiting for the coach's check or the player's confirmation.
+    pub fn pending_confirmation(&self) -> Option<&PendingConfirmation> {
+        self.pending_confirmation.as_ref()
+    }
+
+    /// Record the coach's objection to the held move.
+    pub fn set_coach_warning(&mut self, warning: String) {
+        self.touch();
+        if let Some(ref mut pending) = self.pending_confirmation {
+            self.message = self.strings.coach_doubts.to_string();
+            pending.warning = Some(warning);
+        }
+    }
+
+    /// Play the held move.  Returns true if a move was made.
+    pub fn confirm_pending_move(&mut self) -> bool {
+        self.touch();
+        match self.pending_confirmation.take() {
+            Some(pending) if self.commit_move(pending.chess_move) => {
+                self.end_turn();
+                self.message = fill(self.strings.move_played, pending.chess_move);
                 self.selected_square = None;
                 self.possible_moves.clear();
-                self.move_history.push(chess_move);
-                self.view_move_index = self.position_history.len() - 1;
-                return true;
+                true
             }
+            _ => false,
+        }
+    }
+
+    /// Drop the held move and let the player choose again.
+    pub fn cancel_pending_move(&mut self) {
+        self.touch();
+        if self.pending_confirmation.take().is_some() {
+            self.selected_square = None;
+            self.possible_moves.clear();
+            self.message = self.strings.move_taken_back.to_string();
         }
-        false
     }
 
     pub fn update_possible_moves(&mut self) {
+        self.touch();
         self.possible_moves.clear();
 
         if let Some(square) = self.selected_square {
-            let board = self.game.current_position();
-            let move_gen = MoveGen::new_legal(&board);
+            let moves = self.legal_moves();
+            self.possible_moves.extend(moves.into_iter().filter(|m| m.get_source() == square));
+        }
+    }
 
-            for m in move_gen {
-                if m.get_source() == square {
-                    self.possible_moves.push(m);
-                }
-            }
+    /// Every legal move in the position shown, Chess960 castling included.
+    pub fn legal_moves(&self) -> Vec<ChessMove> {
+        let ply = if self.view_mode { self.view_move_index } else { self.move_history.len() };
+        let board = self.current_position();
+        let mut moves: Vec<ChessMove> = MoveGen::new_legal(&board).collect();
+        if let Some(castling) = self.castling_at(ply) {
+            moves.extend(castling.moves(&board));
         }
+        moves
+    }
+
+    // Chess960 castling rights after `ply` moves
+    fn castling_at(&self, ply: usize) -> Option<Castling> {
+        let start = self.castling?;
+        let played = self.move_history.iter().zip(&self.position_history).take(ply);
+        Some(played.fold(start, |castling, (&chess_move, board)| castling.after(board, chess_move)))
+    }
+
+    // The legal move a UCI string names on the board, a Chess960 castle
+    // written as the king taking its rook included
+    fn find_move(&self, uci_move: &str) -> Option<ChessMove> {
+        let board = self.game.current_position();
+        parse_uci_move(&board, uci_move).or_else(|| {
+            let castling = self.castling_at(self.move_history.len())?;
+            castling.moves(&board).into_iter().find(|castle| castle.to_string() == uci_move)
+        })
     }
 
     pub fn make_engine_move(&mut self, uci_move: &str) -> bool {
-        if uci_move.len() < 4 {
-            return false;
+        self.touch();
+        if let Some(m) = self.find_move(uci_move) {
+            if self.commit_move(m) {
+                self.end_turn();
+                self.message = fill(self.strings.engine_moved, uci_move);
+                self.thinking = false;
+                return true;
+            }
         }
 
-        let from_file = (uci_move.chars().nth(0).unwrap() as u8 - b'a') as usize;
-        let from_rank = (uci_move.chars().nth(1).unwrap() as u8 - b'1') as usize;
-        let to_file = (uci_move.chars().nth(2).unwrap() as u8 - b'a') as usize;
-        let to_rank = (uci_move.chars().nth(3).unwrap() as u8 - b'1') as usize;
+        false
+    }
 
-        if from_file >= 8 || from_rank >= 8 || to_file >= 8 || to_rank >= 8 {
-            return false;
+    /// Restart from `start_fen` and replay a list of UCI moves, stopping at
+    /// the first one that isn't legal.  Returns the number of moves applied,
+    /// or why the start position can't be loaded.
+    pub fn load_moves(
+        &mut self,
+        start_fen: &str,
+        moves: &[String],
+        player_color: Color,
+    ) -> Result<usize, String> {
+        self.reset_from_fen(start_fen, player_color)?;
+
+        let mut applied = 0;
+        for uci_move in moves {
+            match self.find_move(uci_move) {
+                Some(m) if self.commit_move(m) => applied += 1,
+                _ => break,
+            }
         }
+        // Replayed moves cost no time; the clocks start over from here
+        self.restart_clock();
+        if self.game_result().is_some() {
+            self.clock = None;
+        }
+        Ok(applied)
+    }
 
-        let from_square =
-            Square::make_square(Rank::from_index(from_rank), File::from_index(from_file));
-        let to_square = Square::make_square(Rank::from_index(to_rank), File::from_index(to_file));
+    /// The position the game started from (the standard start unless a
+    /// custom position was loaded).
+    pub fn start_position(&self) -> Board {
+        self.position_history[0]
+    }
 
-        // Find the move in legal moves
-        let board = self.game.current_position();
-        let move_gen = MoveGen::new_legal(&board);
-
-        for m in move_gen {
-            if m.get_source() == from_square && m.get_dest() == to_square {
-                // Handle promotion if needed
-                let promotion = if uci_move.len() >= 5 {
-                    match uci_move.chars().nth(4).unwrap() {
-                        'q' => Some(Piece::Queen),
-                        'r' => Some(Piece::Rook),
-                        'b' => Some(Piece::Bishop),
-                        'n' => Some(Piece::Knight),
-                        _ => None,
-                    }
-                } else {
-                    None
-                };
+    /// FEN of the start position, keeping the halfmove clock and move
+    /// number it was loaded with.
+    pub fn start_fen(&self) -> String {
+        self.fen_after(0).unwrap_or_else(|| self.start_position().to_string())
+    }
+
+    /// FEN of the position reached, with the halfmove clock and move number
+    /// carried on from the start position's.
+    pub fn current_fen(&self) -> String {
+        self.fen_after(self.move_history.len())
+            .unwrap_or_else(|| self.game.current_position().to_string())
+    }
+
+    /// FEN of the position after `ply` moves, counters included, if the
+    /// game is that long.
+    pub fn fen_after(&self, ply: usize) -> Option<String> {
+        let position = self.position_history.get(ply)?;
+        let (halfmove, fullmove) = self.counters_after(ply);
+        let fen = with_counters(position, halfmove, fullmove);
+        Some(match self.castling_at(ply) {
+            Some(castling) => chess960::with_castling(&fen, &castling.field()),
+            None => fen,
+        })
+    }
 
-                if promotion.is_none() || m.get_promotion() == promotion {
-                    // Get move details BEFORE making the move
-                    let side_to_move = board.side_to_move();
-                    let details = self.move_to_details(m, &board, side_to_move);
+    // Halfmove clock and move number after `ply` moves
+    fn counters_after(&self, ply: usize) -> (u32, u32) {
+        let (mut halfmove, mut fullmove) = self.start_counters;
+        for (chess_move, board) in self.move_history.iter().zip(&self.position_history).take(ply) {
+            let pawn_move = board.piece_on(chess_move.get_source()) == Some(Piece::Pawn);
+            let capture = board.color_on(chess_move.get_dest()) == Some(!board.side_to_move());
+            halfmove = if pawn_move || capture { 0 } else { halfmove + 1 };
+            if board.side_to_move() == Color::Black {
+                fullmove += 1;
+            }
+        }
+        (halfmove, fullmove)
+    }
 
-                    if self.game.make_move(m) {
-                        // Save position after making the move
-                        self.position_history.push(self.game.current_position());
+    pub fn move_history(&self) -> &[ChessMove] {
+        &self.move_history
+    }
 
-                        // Record the move
-                        self.record_move(details);
+    pub fn position_history(&self) -> &[Board] {
+        &self.position_history
+    }
 
-                        self.message = format!("Engine moved: {}", uci_move);
-                        self.thinking = false;
-                        self.move_history.push(m);
-                        self.view_move_index = self.position_history.len() - 1;
-                        return true;
-                    }
-                }
+    /// The named opening this game is in, or last was before leaving theory.
+    pub fn opening(&self) -> Option<&'static Opening> {
+        EcoTable::bundled().classify(&self.position_history)
+    }
+
+    /// Attach review verdicts (one per ply, in game order) to the move records.
+    pub fn apply_review(&mut self, evaluations: &[MoveEvaluation]) {
+        self.touch();
+        let mut evaluations = evaluations.iter();
+        for record in self.move_records.iter_mut() {
+            for details in [&mut record.white_move, &mut record.black_move].into_iter().flatten() {
+                details.review = evaluations.next().cloned();
+            }
+        }
+    }
+
+    /// Attach glyphs and comments (one per ply, in game order) to the move
+    /// records.
+    pub fn apply_annotations(&mut self, annotations: &[MoveAnnotation]) {
+        self.touch();
+        let mut annotations = annotations.iter();
+        for record in self.move_records.iter_mut() {
+            for details in [&mut record.white_move, &mut record.black_move].into_iter().flatten() {
+                details.annotation = annotations.next().cloned().unwrap_or_default();
             }
         }
+    }
+
+    /// Set the glyph and comment of the move that reached position `ply`.
+    pub fn annotate_move(&mut self, ply: usize, annotation: MoveAnnotation) {
+        let Some(index) = ply.checked_sub(1) else {
+            return;
+        };
+        let details = self
+            .move_records
+            .iter_mut()
+            .flat_map(|r| [&mut r.white_move, &mut r.black_move])
+            .flatten()
+            .nth(index);
+        let Some(details) = details else {
+            return;
+        };
+        if let Some(tree) = &mut self.variations {
+            let node = tree.find_line(&self.move_history[..ply]).and_then(|nodes| nodes.last().copied());
+            if let Some(node) = node {
+                *tree.annotation_mut(node) = annotation.clone();
+            }
+        }
+        details.annotation = annotation;
+        self.touch();
+    }
+
+    /// Keep the variations of an imported game; the moves played must be
+    /// one of its lines.
+    pub fn set_variations(&mut self, tree: VariationTree) {
+        self.touch();
+        self.variations = Some(tree);
+    }
+
+    /// Make the line through `node` the main line of the variation tree.
+    pub fn promote_variation(&mut self, node: usize) {
+        if let Some(tree) = &mut self.variations {
+            tree.promote(node);
+            self.touch();
+        }
+    }
+
+    /// Move the line through `node` one place down among the variations.
+    pub fn demote_variation(&mut self, node: usize) {
+        if let Some(tree) = &mut self.variations {
+            tree.demote(node);
+            self.touch();
+        }
+    }
+
+    /// Switch to the line through `node` of the variation tree, showing
+    /// the position its move reached.
+    pub fn follow_variation(&mut self, node: usize) {
+        let Some(tree) = self.variations.take() else {
+            return;
+        };
+        let line = tree.line_through(node);
+        let moves: Vec<String> = tree.moves(&line).iter().map(ToString::to_string).collect();
+        let message = std::mem::take(&mut self.message);
+        if let Err(e) = self.load_moves(&self.start_fen(), &moves, self.player_color) {
+            self.message = e;
+            self.variations = Some(tree);
+            return;
+        }
+        let annotations: Vec<MoveAnnotation> =
+            line.iter().map(|&id| tree.node(id).annotation.clone()).collect();
+        self.apply_annotations(&annotations);
+        self.message = message;
+        self.view_move_at(tree.path(node).len());
+        self.variations = Some(tree);
+    }
+
+    /// Whether the move records carry review verdicts.
+    pub fn is_reviewed(&self) -> bool {
+        self.move_records
+            .iter()
+            .flat_map(|r| [&r.white_move, &r.black_move])
+            .flatten()
+            .any(|details| details.review.is_some())
+    }
+
+    /// Time both sides spent on the moves played, pauses left out.
+    pub fn time_played(&self) -> Duration {
+        self.move_records
+            .iter()
+            .flat_map(|r| [&r.white_move, &r.black_move])
+            .flatten()
+            .filter_map(|details| details.time_spent)
+            .sum()
+    }
 
+    /// Accuracy of both sides, available once the game has been reviewed.
+    pub fn accuracy(&self) -> Option<GameAccuracy> {
+        let evaluations: Vec<MoveEvaluation> = self
+            .move_records
+            .iter()
+            .flat_map(|r| [&r.white_move, &r.black_move])
+            .flatten()
+            .map(|details| details.review.clone())
+            .collect::<Option<_>>()?;
+        GameAccuracy::compute(&evaluations, self.start_position().side_to_move())
+    }
+
+    // Play a legal move on the live game and record it in every history list
+    fn commit_move(&mut self, chess_move: ChessMove) -> bool {
+        // Get move details BEFORE making the move (need the board position)
+        let board = self.game.current_position();
+        let details = Self::move_to_details(chess_move, &board, board.side_to_move());
+
+        if self.play(chess_move, self.move_history.len()) {
+            // Save position after making the move
+            self.position_history.push(self.game.current_position());
+
+            // Record the move
+            self.record_move(details);
+
+            self.move_history.push(chess_move);
+            self.view_move_index = self.position_history.len() - 1;
+            // A move off the imported lines starts a variation of its own
+            if let Some(tree) = &mut self.variations {
+                tree.add_line(&self.move_history);
+            }
+            return true;
+        }
         false
     }
 
+    // Play the move at `ply` on the chess::Game, which can't castle
+    // Chess960 style: a castle starts it again from the castled board
+    fn play(&mut self, chess_move: ChessMove, ply: usize) -> bool {
+        let board = self.game.current_position();
+        if !chess960::is_castle(&board, chess_move) {
+            return self.game.make_move(chess_move);
+        }
+        let castling = self.castling_at(ply);
+        let legal = castling.is_some_and(|castling| castling.moves(&board).contains(&chess_move));
+        if legal {
+            self.game = Game::new_with_board(chess960::make_move(&board, chess_move));
+        }
+        legal
+    }
+
+    /// Take back just the last half-move, whoever played it.
+    pub fn take_back_move(&mut self) {
+        self.touch();
+        if self.move_history.pop().is_none() {
+            return;
+        }
+        self.position_history.pop();
+        if let Some(record) = self.move_records.last_mut() {
+            if record.black_move.is_some() && record.white_move.is_some() {
+                record.black_move = None;
+            } else {
+                self.move_records.pop();
+            }
+        }
+
+        self.replay_moves();
+        self.pending_confirmation = None;
+        self.selected_square = None;
+        self.possible_moves.clear();
+        self.view_move_index = self.position_history.len().saturating_sub(1);
+        self.resume_clock();
+    }
+
     pub fn undo_move_pair(&mut self) {
+        self.touch();
+        self.pending_confirmation = None;
+        if self.rated {
+            self.message = self.strings.no_takebacks_rated.to_string();
+            return;
+        }
         if self.thinking {
             // Engine is thinking - undo just the player's last move
-            if self.move_history.len() >= 1 && self.position_history.len() >= 2 {
+            if !self.move_history.is_empty() && self.position_history.len() >= 2 {
                 self.move_history.pop();
                 self.position_history.pop();
                 self.move_records.pop(); // Remove the incomplete move record
                 self.thinking = false;
-                self.message = "Undid your move.".to_string();
+                self.message = self.strings.undid_your_move.to_string();
             } else {
-                self.message = "No moves to undo.".to_string();
+                self.message = self.strings.no_moves_to_undo.to_string();
                 return;
             }
         } else {
@@ -424,8 +1204,8 @@ impl ChessGame {
                 // Remove the last complete move record
                 self.move_records.pop();
 
-                self.message = "Undid last move pair.".to_string();
-            } else if self.move_history.len() >= 1 && self.position_history.len() >= 2 {
+                self.message = self.strings.undid_move_pair.to_string();
+            } else if !self.move_history.is_empty() && self.position_history.len() >= 2 {
                 // Only one move to undo
                 self.move_history.pop();
                 self.position_history.pop();
@@ -433,27 +1213,420 @@ impl ChessGame {
                 // Remove the incomplete move record
                 self.move_records.pop();
 
-                self.message = "Undid last move.".to_string();
+                self.message = self.strings.undid_move.to_string();
             } else {
-                self.message = "No moves to undo.".to_string();
+                self.message = self.strings.no_moves_to_undo.to_string();
                 return;
             }
         }
 
-        // Restore to the new last position
-        let previous_position = self.position_history.last().unwrap().clone();
-        self.game = Game::new_with_board(previous_position);
+        self.replay_moves();
+        self.assisted = true;
 
         self.selected_square = None;
         self.possible_moves.clear();
         self.view_move_index = self.position_history.len().saturating_sub(1);
+        self.resume_clock();
+    }
+
+    /// Delete the move that reached position `ply` and every move after
+    /// it, and play on from the position before it.
+    pub fn delete_from(&mut self, ply: usize) {
+        let Some(kept) = ply.checked_sub(1).filter(|&kept| kept < self.move_history.len()) else {
+            return;
+        };
+        self.touch();
+        if self.rated {
+            self.message = self.strings.no_takebacks_rated.to_string();
+            return;
+        }
+        if let Some(tree) = &mut self.variations {
+            if let Some(nodes) = tree.find_line(&self.move_history[..ply]) {
+                tree.remove(nodes[kept]);
+            }
+            if !tree.has_variations() {
+                self.variations = None;
+            }
+        }
+        self.move_history.truncate(kept);
+        self.position_history.truncate(kept + 1);
+        self.move_records = records_until(&self.move_records, kept);
+
+        self.replay_moves();
+        self.assisted = true;
+        self.thinking = false;
+        self.pending_confirmation = None;
+        self.pending_promotion = None;
+        self.selected_square = None;
+        self.possible_moves.clear();
+        self.view_mode = false;
+        self.view_move_index = kept;
+        self.message = self.strings.deleted_moves.to_string();
+        self.resume_clock();
+    }
+
+    // Rebuild the game from the start position and the moves that are
+    // left, rather than from the last board, so the game keeps the
+    // history that repetition and the fifty-move rule depend on
+    fn replay_moves(&mut self) {
+        self.game = Game::new_with_board(self.position_history[0]);
+        for (ply, chess_move) in self.move_history.clone().into_iter().enumerate() {
+            self.play(chess_move, ply);
+        }
+        self.flagged = None;
+        self.drawn_by = None;
+    }
+
+    /// Play with a clock from the next game on, or without one.
+    pub fn set_time_control(&mut self, time_control: Option<TimeControl>) {
+        self.touch();
+        self.time_control = time_control;
+        self.restart_clock();
+    }
+
+    pub fn time_control(&self) -> Option<TimeControl> {
+        self.time_control
+    }
+
+    /// Give the engine a time control of its own, or the player's again
+    /// with None.  Only counts while there are clocks.
+    pub fn set_time_odds(&mut self, time_odds: Option<TimeControl>) {
+        self.touch();
+        self.time_odds = time_odds;
+        self.restart_clock();
+    }
+
+    pub fn time_odds(&self) -> Option<TimeControl> {
+        self.time_odds.filter(|_| self.time_control.is_some())
+    }
+
+    /// The time control `side` plays with, None without clocks.
+    pub fn control_for(&self, side: Color) -> Option<TimeControl> {
+        let control = self.time_control?;
+        match self.time_odds {
+            Some(odds) if side != self.player_color => Some(odds),
+            _ => Some(control),
+        }
+    }
+
+    /// Stop the clocks and the player's moves, or carry on with the side
+    /// to move's time running again.  A finished game can't be paused.
+    pub fn set_paused(&mut self, paused: bool) {
+        if paused == self.paused || (paused && self.game.result().is_some()) {
+            return;
+        }
+        self.touch();
+        self.paused = paused;
+        if paused {
+            if let Some(since) = self.turn_since.take() {
+                self.turn_used += since.elapsed();
+            }
+            if let Some(clock) = self.clock.as_mut() {
+                clock.stop();
+            }
+            self.selected_square = None;
+            self.possible_moves.clear();
+        } else if self.game.result().is_none() {
+            self.turn_since = Some(Instant::now());
+            let to_move = self.game.side_to_move();
+            if let Some(clock) = self.clock.as_mut() {
+                clock.resume(to_move);
+            }
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// End the game once the running clock reaches zero: a loss for that
+    /// side, or a draw if the opponent couldn't mate anyway.  Returns the
+    /// side whose flag fell.
+    pub fn check_flag(&mut self) -> Option<Color> {
+        let clock = self.clock.as_mut()?;
+        let side = clock.running()?;
+        if !clock.remaining(side).is_zero() || self.game.result().is_some() {
+            return None;
+        }
+        clock.stop();
+        self.touch();
+        self.flagged = Some(side);
+        let name = if side == Color::White { self.strings.white } else { self.strings.black };
+        // chess::Game has no result for time; these give the same score
+        if has_mating_material(&self.game.current_position(), !side) {
+            self.game.resign(side);
+            self.message = fill(self.strings.flag_lost, name);
+        } else {
+            self.end_in_draw();
+            self.message = fill(self.strings.flag_draw, name);
+        }
+        self.selected_square = None;
+        self.possible_moves.clear();
+        self.pending_promotion = None;
+        self.pending_confirmation = None;
+        Some(side)
+    }
+
+    pub fn flagged(&self) -> Option<Color> {
+        self.flagged
+    }
+
+    // chess::Game only ends drawn by agreement or a claim it can check
+    // itself; an accepted offer gives the same result
+    fn end_in_draw(&mut self) {
+        let side = self.game.side_to_move();
+        self.game.offer_draw(side);
+        self.game.accept_draw();
+    }
+
+    // End the game once a draw rule applies to the position reached
+    fn adjudicate_draw(&mut self) {
+        if self.game.result().is_some() {
+            return;
+        }
+        let board = self.game.current_position();
+        let reason = if insufficient_material(&board) {
+            DrawReason::InsufficientMaterial
+        } else if self.repetitions() >= 3 {
+            DrawReason::Repetition
+        } else if self.halfmove_clock() >= 100 {
+            DrawReason::FiftyMoves
+        } else {
+            return;
+        };
+        self.end_in_draw();
+        self.drawn_by = Some(reason);
+        self.message = fill(self.strings.drawn_by, reason.label(self.strings));
+    }
+
+    /// Why the game was drawn, if it was.
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        match self.game.result() {
+            Some(chess::GameResult::Stalemate) => Some(DrawReason::Stalemate),
+            Some(_) => self.drawn_by,
+            None => None,
+        }
+    }
+
+    /// How many times the position on the board has occurred, this time
+    /// included.
+    pub fn repetitions(&self) -> usize {
+        let Some(current) = self.position_history.last() else {
+            return 0;
+        };
+        self.position_history.iter().filter(|board| *board == current).count()
+    }
+
+    /// Moves by either side since the last capture or pawn move, counting
+    /// on from the start position's.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.counters_after(self.move_history.len()).0
+    }
+
+    // Time the move just committed, and start timing the next; a draw by
+    // rule ends the game here, so replayed moves are never cut short
+    fn end_turn(&mut self) {
+        self.adjudicate_draw();
+        let spent = self.turn_used + self.turn_since.map_or(Duration::ZERO, |since| since.elapsed());
+        if let Some(details) = self.last_move_details_mut() {
+            details.time_spent = Some(spent);
+        }
+        self.start_turn();
+        self.press_clock();
+    }
+
+    fn start_turn(&mut self) {
+        self.turn_used = Duration::ZERO;
+        self.turn_since = (!self.paused).then(Instant::now);
+    }
+
+    // Full time for both sides, running for the side to move
+    fn restart_clock(&mut self) {
+        self.start_turn();
+        let to_move = self.game.side_to_move();
+        self.clock = match (self.control_for(Color::White), self.control_for(Color::Black)) {
+            (Some(white), Some(black)) => Some(GameClock::new(white, black, to_move)),
+            _ => None,
+        };
+    }
+
+    // Charge the move just committed to its player's clock and record the
+    // time left; the clocks stop once the game is over
+    fn press_clock(&mut self) {
+        let Some(clock) = self.clock.as_mut() else {
+            return;
+        };
+        let left = clock.press();
+        if self.game.result().is_some() {
+            clock.stop();
+        }
+        if let Some(left) = left {
+            self.set_last_move_clock(left);
+        }
+    }
+
+    // After a take-back, run the clock of whoever is now to move, unless
+    // the game is paused
+    fn resume_clock(&mut self) {
+        self.start_turn();
+        if self.paused {
+            return;
+        }
+        let to_move = self.game.side_to_move();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.run_for(to_move);
+        }
+    }
+
+    /// Record the time left on the mover's clock after the last move.
+    pub fn set_last_move_clock(&mut self, left: Duration) {
+        if let Some(details) = self.last_move_details_mut() {
+            details.clock = Some(left);
+            self.touch();
+        }
+    }
+
+    fn last_move_details_mut(&mut self) -> Option<&mut MoveDetails> {
+        self.move_records.last_mut().and_then(|record| {
+            if record.black_move.is_some() {
+                record.black_move.as_mut()
+            } else {
+                record.white_move.as_mut()
+            }
+        })
+    }
+
+    /// Restore the live clocks of a saved game; the side to move's time
+    /// runs from now.
+    pub fn set_clocks(&mut self, white: Duration, black: Duration) {
+        let to_move = self.game.side_to_move();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.set_remaining(white, black, to_move);
+            self.touch();
+        }
+    }
+
+    /// Time left on both live clocks, White's first; None without a clock
+    /// or once the game is over.
+    pub fn clock_remaining(&self) -> Option<(Duration, Duration)> {
+        let clock = self.clock.as_ref()?;
+        Some((clock.remaining(Color::White), clock.remaining(Color::Black)))
+    }
+
+    /// Recorded clock times, one per ply in game order.
+    pub fn move_clocks(&self) -> Vec<Option<Duration>> {
+        self.move_records
+            .iter()
+            .flat_map(|record| [&record.white_move, &record.black_move])
+            .flatten()
+            .map(|details| details.clock)
+            .collect()
+    }
+
+    /// Attach recorded clock times (one per ply, in game order) to the move
+    /// records.
+    pub fn apply_clocks(&mut self, clocks: &[Option<Duration>]) {
+        self.touch();
+        let mut clocks = clocks.iter();
+        for record in self.move_records.iter_mut() {
+            for details in [&mut record.white_move, &mut record.black_move].into_iter().flatten() {
+                details.clock = clocks.next().copied().flatten();
+            }
+        }
+    }
+
+    /// Time left for both sides: the live clocks while playing, or the
+    /// times recorded up to the position being viewed.  None for games
+    /// without clocks.
+    pub fn clock_times(&self) -> Option<ClockTimes> {
+        if let (Some(clock), false) = (&self.clock, self.view_mode) {
+            return Some(ClockTimes {
+                white: Some(clock.remaining(Color::White)),
+                black: Some(clock.remaining(Color::Black)),
+                running: clock.running(),
+            });
+        }
+
+        let ply = if self.view_mode { self.view_move_index } else { self.move_history.len() };
+        let base = self.time_control.map(|control| Duration::from_millis(control.base_ms));
+        let mut times = [base; 2];
+        let mut recorded = false;
+        let first_mover = self.start_position().side_to_move();
+        let played = self
+            .move_records
+            .iter()
+            .flat_map(|r| [&r.white_move, &r.black_move])
+            .flatten()
+            .take(ply);
+        for (i, details) in played.enumerate() {
+            let side = if i % 2 == 0 { first_mover } else { !first_mover };
+            if let Some(left) = details.clock {
+                times[side.to_index()] = Some(left);
+                recorded = true;
+            }
+        }
+        (recorded || self.time_control.is_some()).then_some(ClockTimes {
+            white: times[0],
+            black: times[1],
+            running: None,
+        })
+    }
+
+    /// Whether a draw can be claimed by threefold repetition or the
+    /// fifty-move rule.
+    pub fn can_claim_draw(&self) -> bool {
+        self.repetitions() >= 3 || self.halfmove_clock() >= 100
     }
 
     pub fn game_result(&self) -> Option<chess::GameResult> {
         self.game.result()
     }
 
-    fn move_to_details(&self, chess_move: ChessMove, board: &Board, _side: Color) -> MoveDetails {
+    /// Monotonic counter bumped by every mutating method.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Copy out the state needed to render the game screen.
+    pub fn snapshot(&self) -> GameSnapshot {
+        let board = self.current_position();
+        GameSnapshot {
+            revision: self.revision,
+            board,
+            selected_square: self.selected_square(),
+            possible_moves: self.possible_moves().clone(),
+            legal_moves: self.legal_moves(),
+            message: self.message().to_string(),
+            thinking: self.is_thinking(),
+            player_color: self.player_color(),
+            game_result: self.game_result(),
+            move_records: self.get_move_records().clone(),
+            view_mode: self.is_view_mode(),
+            view_move_index: self.view_move_index(),
+            pending_promotion: self.pending_promotion(),
+            reviewed: self.is_reviewed(),
+            accuracy: self.accuracy(),
+            coach_mode: self.coach_mode,
+            pending_confirmation: self.pending_confirmation.clone(),
+            opening: self.opening().cloned(),
+            paused: self.paused,
+            flagged: self.flagged,
+            draw_reason: self.draw_reason(),
+            rated: self.rated,
+            variations: self.variations.clone(),
+            line_nodes: self
+                .variations
+                .as_ref()
+                .and_then(|tree| tree.find_line(&self.move_history))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn touch(&mut self) {
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    fn move_to_details(chess_move: ChessMove, board: &Board, _side: Color) -> MoveDetails {
         // Get the piece that moved
         let piece = match board.piece_on(chess_move.get_source()) {
             Some(p) => p,
@@ -487,6 +1660,10 @@ impl ChessGame {
                     destination: dest_str.clone(),
                     is_capture: false,
                     display_text: dest_str,
+                    review: None,
+                    annotation: MoveAnnotation::default(),
+                    clock: None,
+                    time_spent: None,
                 };
             }
         };
@@ -496,29 +1673,31 @@ impl ChessGame {
         let to_file = (chess_move.get_dest().get_file().to_index() as u8 + b'a') as char;
         let to_rank = (chess_move.get_dest().get_rank().to_index() as u8 + b'1') as char;
 
-        // Check for castling
-        if piece == Piece::King {
-            let from_file_idx = chess_move.get_source().get_file().to_index();
-            let to_file_idx = chess_move.get_dest().get_file().to_index();
-            if from_file_idx == 4 {
-                if to_file_idx == 6 {
-                    return MoveDetails {
-                        notation: "O-O".to_string(),
-                        piece,
-                        destination: "O-O".to_string(),
-                        is_capture: false,
-                        display_text: "O-O".to_string(),
-                    };
-                } else if to_file_idx == 2 {
-                    return MoveDetails {
-                        notation: "O-O-O".to_string(),
-                        piece,
-                        destination: "O-O-O".to_string(),
-                        is_capture: false,
-                        display_text: "O-O-O".to_string(),
-                    };
-                }
-            }
+        // Check for castling: the king's two-square step, or in Chess960
+        // the king taking its own rook
+        let from_file_idx = chess_move.get_source().get_file().to_index();
+        let to_file_idx = chess_move.get_dest().get_file().to_index();
+        let castle = if chess960::is_castle(board, chess_move) {
+            Some(if to_file_idx > from_file_idx { "O-O" } else { "O-O-O" })
+        } else if piece == Piece::King && from_file_idx == 4 && to_file_idx == 6 {
+            Some("O-O")
+        } else if piece == Piece::King && from_file_idx == 4 && to_file_idx == 2 {
+            Some("O-O-O")
+        } else {
+            None
+        };
+        if let Some(castle) = castle {
+            return MoveDetails {
+                notation: castle.to_string(),
+                piece,
+                destination: castle.to_string(),
+                is_capture: false,
+                display_text: castle.to_string(),
+                review: None,
+                annotation: MoveAnnotation::default(),
+                clock: None,
+                time_spent: None,
+            };
         }
 
         let is_capture = board.piece_on(chess_move.get_dest()).is_some();
@@ -639,6 +1818,10 @@ impl ChessGame {
             destination,
             is_capture,
             display_text,
+            review: None,
+            annotation: MoveAnnotation::default(),
+            clock: None,
+            time_spent: None,
         }
     }
 
@@ -647,7 +1830,7 @@ impl ChessGame {
 
         if current_side == Color::Black {
             // White just moved, create new record
-            let move_num = self.move_records.len() + 1;
+            let move_num = self.start_counters.1 as usize + self.move_records.len();
             self.move_records.push(MoveRecord {
                 move_num,
                 white_move: Some(details),
@@ -657,32 +1840,194 @@ impl ChessGame {
             // Black just moved, update last record
             if let Some(last_record) = self.move_records.last_mut() {
                 last_record.black_move = Some(details);
+            } else {
+                // Game started from a position with Black to move
+                self.move_records.push(MoveRecord {
+                    move_num: self.start_counters.1 as usize,
+                    white_move: None,
+                    black_move: Some(details),
+                });
             }
         }
     }
 }
 
+/// A side's pieces at the start of a standard game, kings left out.
+const ARMY: [(Piece, u32); 5] = [
+    (Piece::Queen, 1),
+    (Piece::Rook, 2),
+    (Piece::Bishop, 2),
+    (Piece::Knight, 2),
+    (Piece::Pawn, 8),
+];
+
+/// Conventional value of a piece in pawns.
+pub fn piece_value(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 1,
+        Piece::Knight | Piece::Bishop => 3,
+        Piece::Rook => 5,
+        Piece::Queen => 9,
+        Piece::King => 0,
+    }
+}
+
+/// The pieces `color` is missing from a full army, most valuable first.
+/// A promoted pawn shows as a lost pawn rather than an extra piece.
+pub fn captured_pieces(board: &Board, color: Color) -> Vec<Piece> {
+    ARMY.iter()
+        .flat_map(|&(piece, count)| {
+            let left = (board.pieces(piece) & board.color_combined(color)).popcnt();
+            std::iter::repeat_n(piece, count.saturating_sub(left) as usize)
+        })
+        .collect()
+}
+
+/// White's material minus Black's, in pawns.
+pub fn material_balance(board: &Board) -> i32 {
+    ARMY.iter()
+        .map(|&(piece, _)| {
+            let count = |color| (board.pieces(piece) & board.color_combined(color)).popcnt() as i32;
+            piece_value(piece) * (count(Color::White) - count(Color::Black))
+        })
+        .sum()
+}
+
+/// SAN for a legal move in `board`, with check and mate marks.
+pub fn move_to_san(board: &Board, chess_move: ChessMove) -> String {
+    ChessGame::move_to_details(chess_move, board, board.side_to_move()).notation
+}
+
+/// A SAN move in words, for reading aloud: "White knight takes f3, check".
+pub fn describe_move(san: &str, mover: Color, t: &Strings) -> String {
+    let side = if mover == Color::White { t.white } else { t.black };
+    let core = san.trim_end_matches(['+', '#', '!', '?']);
+    let ending = if san.contains('#') {
+        t.announce_checkmate
+    } else if san.contains('+') {
+        t.announce_check
+    } else {
+        ""
+    };
+    match core {
+        "O-O" => return format!("{}{}", fill(t.castles_kingside, side), ending),
+        "O-O-O" => return format!("{}{}", fill(t.castles_queenside, side), ending),
+        _ => {}
+    }
+
+    let name = |letter| match letter {
+        'K' => t.king,
+        'Q' => t.queen,
+        'R' => t.rook,
+        'B' => t.bishop,
+        'N' => t.knight,
+        _ => t.pawn,
+    };
+    let (core, promotion) = match core.split_once('=') {
+        Some((moved, piece)) => (moved, piece.chars().next().map(name)),
+        None => (core, None),
+    };
+    let piece = name(core.chars().next().unwrap_or('P'));
+    let destination = core.get(core.len().saturating_sub(2)..).unwrap_or(core);
+    let action = if core.contains('x') { t.takes } else { t.moves_to };
+    let promotion = promotion.map(|piece| fill(t.promotes_to, piece)).unwrap_or_default();
+    format!("{} {} {} {}{}{}", side, piece, action, destination, promotion, ending)
+}
+
+/// Resolve a UCI move string ("e2e4", "e7e8q") against the legal moves of
+/// `board`.
+pub fn parse_uci_move(board: &Board, uci_move: &str) -> Option<ChessMove> {
+    let bytes = uci_move.as_bytes();
+    if bytes.len() < 4 {
+        return None;
+    }
+
+    let from_file = bytes[0].wrapping_sub(b'a') as usize;
+    let from_rank = bytes[1].wrapping_sub(b'1') as usize;
+    let to_file = bytes[2].wrapping_sub(b'a') as usize;
+    let to_rank = bytes[3].wrapping_sub(b'1') as usize;
+
+    if from_file >= 8 || from_rank >= 8 || to_file >= 8 || to_rank >= 8 {
+        return None;
+    }
+
+    let from_square = Square::make_square(Rank::from_index(from_rank), File::from_index(from_file));
+    let to_square = Square::make_square(Rank::from_index(to_rank), File::from_index(to_file));
+
+    // Handle promotion if needed
+    let promotion = match bytes.get(4) {
+        Some(b'q') => Some(Piece::Queen),
+        Some(b'r') => Some(Piece::Rook),
+        Some(b'b') => Some(Piece::Bishop),
+        Some(b'n') => Some(Piece::Knight),
+        _ => None,
+    };
+
+    // Find the move in legal moves
+    MoveGen::new_legal(board).find(|m| {
+        m.get_source() == from_square
+            && m.get_dest() == to_square
+            && (promotion.is_none() || m.get_promotion() == promotion)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::ClockMode;
+    use crate::i18n::{ENGLISH, GERMAN};
     use chess::{Board, ChessMove, Color, File, Rank, Square};
     use std::str::FromStr;
 
+    #[test]
+    fn test_describe_move() {
+        let t = &ENGLISH;
+        assert_eq!(describe_move("Nxf3+", Color::Black, t), "Black knight takes f3, check");
+        assert_eq!(describe_move("e4", Color::White, t), "White pawn to e4");
+        assert_eq!(
+            describe_move("exd8=Q#", Color::White, t),
+            "White pawn takes d8 and promotes to queen, checkmate"
+        );
+        assert_eq!(describe_move("O-O-O", Color::Black, t), "Black castles queenside");
+        assert_eq!(describe_move("Rae1", Color::White, t), "White rook to e1");
+        assert_eq!(describe_move("Nxf3+", Color::Black, &GERMAN), "Schwarz Springer schlägt f3, Schach");
+    }
+
+    #[test]
+    fn test_result_text() {
+        let mut snapshot = GameSnapshot::default();
+        assert_eq!(snapshot.result_text(&ENGLISH), None);
+        snapshot.game_result = Some(chess::GameResult::BlackResigns);
+        assert_eq!(snapshot.result_text(&ENGLISH).as_deref(), Some("Game over: Black resigns"));
+        snapshot.flagged = Some(Color::Black);
+        assert_eq!(
+            snapshot.result_text(&GERMAN).as_deref(),
+            Some("Partie beendet: Schwarz hat die Zeit überschritten")
+        );
+    }
+
+    #[test]
+    fn test_material() {
+        // White has won a knight and a pawn for a pawn
+        let board = Board::from_str("rnbqkb1r/ppp1pppp/8/8/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 4").unwrap();
+        assert_eq!(captured_pieces(&board, Color::Black), vec![Piece::Knight, Piece::Pawn]);
+        assert_eq!(captured_pieces(&board, Color::White), vec![Piece::Pawn]);
+        assert_eq!(material_balance(&board), 3);
+        assert!(captured_pieces(&Board::default(), Color::White).is_empty());
+    }
+
     #[test]
     fn test_rook_disambiguation_file() {
         // Test position: White rooks on a1 and h1, both can move to d1
-        // FEN: 8/8/8/8/8/8/8/R6R w KQ - 0 1
-        let board = Board::from_str("8/8/8/8/8/8/8/R6R w KQ - 0 1").unwrap();
-
-        // Create a ChessGame to access move_to_details
-        let game = ChessGame::new();
+        // FEN: 3k4/8/8/8/8/8/K7/R6R w - - 0 1
+        let board = Board::from_str("3k4/8/8/8/8/8/K7/R6R w - - 0 1").unwrap();
 
         // Move from a1 to d1
         let from = Square::make_square(Rank::First, File::A);
         let to = Square::make_square(Rank::First, File::D);
         let chess_move = ChessMove::new(from, to, None);
 
-        let details = game.move_to_details(chess_move, &board, Color::White);
+        let details = ChessGame::move_to_details(chess_move, &board, Color::White);
 
         // Should be "Rad1" (file disambiguation needed)
         assert!(
@@ -695,17 +2040,15 @@ mod tests {
     #[test]
     fn test_rook_disambiguation_rank() {
         // Test position: White rooks on a1 and a8, both can move to a4
-        // FEN: R7/8/8/8/8/8/8/R7 w KQ - 0 1
-        let board = Board::from_str("R7/8/8/8/8/8/8/R7 w KQ - 0 1").unwrap();
-
-        let game = ChessGame::new();
+        // FEN: R7/8/8/4k3/8/8/8/R6K w - - 0 1
+        let board = Board::from_str("R7/8/8/4k3/8/8/8/R6K w - - 0 1").unwrap();
 
         // Move from a1 to a4
         let from = Square::make_square(Rank::First, File::A);
         let to = Square::make_square(Rank::Fourth, File::A);
         let chess_move = ChessMove::new(from, to, None);
 
-        let details = game.move_to_details(chess_move, &board, Color::White);
+        let details = ChessGame::move_to_details(chess_move, &board, Color::White);
 
         // Should be "R1a4" (rank disambiguation needed since files are same)
         assert!(
@@ -717,25 +2060,304 @@ mod tests {
 
     #[test]
     fn test_knight_disambiguation_both() {
-        // Test position: White knights on b1 and d2, both can move to c3
-        // FEN: 8/8/8/8/8/8/3N4/1N6 w KQ - 0 1
-        let board = Board::from_str("8/8/8/8/8/8/3N4/1N6 w KQ - 0 1").unwrap();
-
-        let game = ChessGame::new();
+        // Test position: White knights on b1 and e2, both can move to c3
+        // FEN: 4k3/8/8/8/8/8/4N3/1N5K w - - 0 1
+        let board = Board::from_str("4k3/8/8/8/8/8/4N3/1N5K w - - 0 1").unwrap();
 
         // Move from b1 to c3
         let from = Square::make_square(Rank::First, File::B);
         let to = Square::make_square(Rank::Third, File::C);
         let chess_move = ChessMove::new(from, to, None);
 
-        let details = game.move_to_details(chess_move, &board, Color::White);
+        let details = ChessGame::move_to_details(chess_move, &board, Color::White);
 
-        // Should be "Nb1c3" (both file and rank needed since b1 and d2 differ in both)
-        // Actually, they differ in file (b vs d), so only file should be needed
+        // Should be "Nb1c3" (both file and rank needed since b1 and e2 differ in both)
+        // Actually, they differ in file (b vs e), so only file should be needed
         assert!(
             details.notation.starts_with("Nb"),
             "Expected Nb1c3 or Nbc3, got: {}",
             details.notation
         );
     }
+
+    #[test]
+    fn test_load_moves_stops_at_illegal_move() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["e2e4", "e7e5", "g1f3", "e5e4"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+
+        let applied = game.load_moves(&Board::default().to_string(), &moves, Color::Black).unwrap();
+
+        // e5e4 is blocked by the pawn on e4, so only three moves replay
+        assert_eq!(applied, 3);
+        assert_eq!(game.move_history().len(), 3);
+        assert_eq!(game.get_move_records().len(), 2);
+        assert_eq!(game.player_color(), Color::Black);
+        assert_eq!(game.current_position().side_to_move(), Color::Black);
+
+        // A kingless start position is refused without touching the game
+        let kingless = game.load_moves("8/8/8/8/8/8/8/4K3 w - - 0 1", &moves, Color::White);
+        assert_eq!(kingless, Err("Missing black king (k)".to_string()));
+        assert_eq!(game.move_history().len(), 3);
+    }
+
+    #[test]
+    fn test_chess960_castling() {
+        let mut game = ChessGame::new();
+        let fen = "1rk2r2/pppppppp/8/8/8/8/PPPPPPPP/1RK2R2 w FBfb - 0 1";
+        let moves: Vec<String> = ["c1f1", "c8b8"].iter().map(|m| m.to_string()).collect();
+        assert_eq!(game.load_moves(fen, &moves, Color::White), Ok(2));
+        assert_eq!(game.start_fen(), fen);
+        assert_eq!(game.current_fen(), "2kr1r2/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 w - - 2 2");
+        let record = &game.get_move_records()[0];
+        assert_eq!(record.white_move.as_ref().unwrap().notation, "O-O");
+        assert_eq!(record.black_move.as_ref().unwrap().notation, "O-O-O");
+
+        // Taking a move back replays the castle and gives the right back
+        game.take_back_move();
+        assert_eq!(game.current_fen(), "1rk2r2/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 b fb - 1 1");
+        assert!(game.legal_moves().contains(&ChessMove::new(Square::C8, Square::B8, None)));
+    }
+
+    #[test]
+    fn test_records_until() {
+        let mut game = ChessGame::new();
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1";
+        let moves: Vec<String> =
+            ["e7e5", "g1f3", "b8c6", "f1c4"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(fen, &moves, Color::White).unwrap();
+        let records = game.get_move_records();
+
+        assert!(records_until(records, 0).is_empty());
+        let line = records_until(records, 2);
+        assert_eq!(line.len(), 2);
+        assert!(line[0].white_move.is_none() && line[0].black_move.is_some());
+        assert!(line[1].white_move.is_some() && line[1].black_move.is_none());
+        assert_eq!(records_until(records, 10).len(), records.len());
+    }
+
+    #[test]
+    fn test_delete_from() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> =
+            ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::White).unwrap();
+        game.view_move_at(2);
+
+        game.delete_from(4);
+        assert_eq!(game.move_history().len(), 3);
+        assert_eq!(game.position_history().len(), 4);
+        assert_eq!(game.get_move_records().len(), 2);
+        assert!(game.get_move_records()[1].black_move.is_none());
+        assert!(!game.is_view_mode());
+        assert_eq!(game.current_position().side_to_move(), Color::Black);
+        // Past the end there is nothing to delete
+        game.delete_from(4);
+        assert_eq!(game.move_history().len(), 3);
+    }
+
+    #[test]
+    fn test_start_counters() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 17 42";
+        assert_eq!(fen_counters(fen), (17, 42));
+        assert_eq!(fen_counters("4k3/8/8/8/8/8/4P3/4K3 w - -"), (0, 1));
+
+        let mut game = ChessGame::new();
+        game.load_moves(fen, &["e8d7".to_string(), "e1d2".to_string()], Color::White).unwrap();
+        assert_eq!(game.start_fen(), fen);
+        assert_eq!(game.current_fen(), "8/3k4/8/8/8/8/3KP3/8 b - - 19 43");
+        assert_eq!(game.fen_after(1).as_deref(), Some("8/3k4/8/8/8/8/4P3/4K3 w - - 18 43"));
+        let records = game.get_move_records();
+        assert_eq!((records[0].move_num, records[1].move_num), (42, 43));
+    }
+
+    #[test]
+    fn test_find_ply() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["e2e4", "e7e5", "g1f3"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::Black).unwrap();
+        let records = game.get_move_records();
+
+        assert_eq!(find_ply(records, "1"), Some(1));
+        assert_eq!(find_ply(records, "1b"), Some(2));
+        assert_eq!(find_ply(records, "1..."), Some(2));
+        assert_eq!(find_ply(records, " 2W "), Some(3));
+        assert_eq!(find_ply(records, "2b"), None);
+        assert_eq!(find_ply(records, "move"), None);
+    }
+
+    #[test]
+    fn test_find_position() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> =
+            ["e2e4", "e7e5", "g1f3", "b8c6"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::White).unwrap();
+        let positions = game.position_history();
+
+        let after_e5 = "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2";
+        assert_eq!(find_position(positions, after_e5, 0), Some(2));
+        // Squares and ranks not given match anything
+        assert_eq!(find_position(positions, "?/?/?/?/?/5N", 0), Some(3));
+        assert_eq!(find_position(positions, "?/?/??n", 0), Some(4));
+        // The search carries on from the position shown, wrapping round
+        assert_eq!(find_position(positions, "?/?/?/?/4P", 3), Some(3));
+        assert_eq!(find_position(positions, "?/?/?/?/4P", 5), Some(1));
+        assert_eq!(find_position(positions, "?/?/?/?/4P b", 2), Some(3));
+        assert_eq!(find_position(positions, "?/?/?/?/?/?/?/?/?", 0), None);
+        assert_eq!(find_position(positions, "4x", 0), None);
+    }
+
+    #[test]
+    fn test_step_view() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["e2e4", "e7e5", "g1f3"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::Black).unwrap();
+
+        game.step_view(-1);
+        assert!(game.is_view_mode());
+        assert_eq!(game.view_move_index(), 2);
+        game.step_view(-10);
+        assert_eq!(game.view_move_index(), 0);
+        // Scrolling forward off the end returns to the live position
+        game.step_view(10);
+        assert!(!game.is_view_mode());
+        assert_eq!(game.current_position().side_to_move(), Color::Black);
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut game = ChessGame::new();
+        let control = TimeControl { base_ms: 60_000, increment_ms: 0, mode: ClockMode::Increment };
+        game.set_time_control(Some(control));
+        game.set_paused(true);
+        assert!(game.is_paused());
+        assert_eq!(game.clock_times().unwrap().running, None);
+        game.select_square(Square::E2);
+        assert_eq!(game.selected_square(), None);
+
+        game.set_paused(false);
+        assert_eq!(game.clock_times().unwrap().running, Some(Color::White));
+        game.select_square(Square::E2);
+        assert_eq!(game.selected_square(), Some(Square::E2));
+    }
+
+    #[test]
+    fn test_flag_fall() {
+        let mut game = ChessGame::new();
+        let control = TimeControl { base_ms: 1, increment_ms: 0, mode: ClockMode::Increment };
+        game.set_time_control(Some(control));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(game.check_flag(), Some(Color::White));
+        assert_eq!(game.game_result(), Some(chess::GameResult::WhiteResigns));
+        assert_eq!(game.check_flag(), None);
+        assert!(!game.make_engine_move("e2e4"));
+
+        // A lone king can't win on time
+        game.reset_from_fen("8/8/4k3/8/8/8/4P3/4K3 w - - 0 1", Color::White).unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(game.check_flag(), Some(Color::White));
+        assert_eq!(game.game_result(), Some(chess::GameResult::DrawAccepted));
+    }
+
+    #[test]
+    fn test_mating_material() {
+        let board = Board::from_str("8/8/4k3/8/8/2n5/4P3/4K2B w - - 0 1").unwrap();
+        assert!(has_mating_material(&board, Color::White));
+        assert!(!has_mating_material(&board, Color::Black));
+    }
+
+    #[test]
+    fn test_time_odds() {
+        let mut game = ChessGame::new();
+        game.set_player_color(Color::Black);
+        let control = TimeControl { base_ms: 600_000, increment_ms: 0, mode: ClockMode::Increment };
+        game.set_time_odds(Some(TimeControl { base_ms: 60_000, ..control }));
+        assert_eq!(game.time_odds(), None);
+        game.set_time_control(Some(control));
+        assert_eq!(game.control_for(Color::Black), Some(control));
+        assert_eq!(game.control_for(Color::White).map(|c| c.base_ms), Some(60_000));
+        let (white, black) = game.clock_remaining().unwrap();
+        assert!(white <= Duration::from_secs(60) && black == Duration::from_secs(600));
+    }
+
+    #[test]
+    fn test_time_spent() {
+        let mut game = ChessGame::new();
+        game.load_moves(&Board::default().to_string(), &["e2e4".to_string()], Color::White).unwrap();
+        assert_eq!(game.get_move_records()[0].white_move.as_ref().unwrap().time_spent, None);
+        std::thread::sleep(Duration::from_millis(20));
+        game.set_paused(true);
+        std::thread::sleep(Duration::from_millis(50));
+        game.set_paused(false);
+        assert!(game.make_engine_move("e7e5"));
+        let spent = game.get_move_records()[0].black_move.as_ref().unwrap().time_spent.unwrap();
+        assert!(spent >= Duration::from_millis(20) && spent < Duration::from_millis(70));
+    }
+
+    #[test]
+    fn test_draw_reasons() {
+        let mut game = ChessGame::new();
+        for uci in ["g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1"] {
+            assert!(game.make_engine_move(uci));
+        }
+        assert_eq!(game.game_result(), None);
+        assert!(game.make_engine_move("f6g8"));
+        assert_eq!(game.draw_reason(), Some(DrawReason::Repetition));
+        assert!(!game.make_engine_move("e2e4"));
+
+        game.reset_from_fen("8/8/4k3/8/8/8/4K3/R7 w - - 99 80", Color::White).unwrap();
+        assert!(game.make_engine_move("a1a2"));
+        assert_eq!(game.draw_reason(), Some(DrawReason::FiftyMoves));
+
+        game.reset_from_fen("7k/8/8/3p4/8/4K3/8/8 w - - 0 1", Color::White).unwrap();
+        assert!(game.make_engine_move("e3d4"));
+        assert_eq!(game.draw_reason(), None);
+        assert!(game.make_engine_move("h8g8"));
+        assert!(game.make_engine_move("d4d5"));
+        assert_eq!(game.draw_reason(), Some(DrawReason::InsufficientMaterial));
+
+        assert!(insufficient_material(&Board::from_str("8/8/4k3/3b4/8/8/4K3/5B2 w - - 0 1").unwrap()));
+        assert!(!insufficient_material(&Board::from_str("8/8/4k3/2b5/8/8/4K3/5B2 w - - 0 1").unwrap()));
+    }
+
+    #[test]
+    fn test_undo_keeps_history() {
+        let mut game = ChessGame::new();
+        for uci in ["e2e4", "e7e5", "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6"] {
+            assert!(game.make_engine_move(uci));
+        }
+        game.undo_move_pair();
+        game.take_back_move();
+        assert_eq!(game.halfmove_clock(), 3);
+        assert_eq!(game.game.actions().len(), 5);
+        assert!(game.make_engine_move("f6g8"));
+        assert!(game.make_engine_move("g1f3"));
+        assert!(game.make_engine_move("g8f6"));
+        assert!(game.make_engine_move("f3g1"));
+        assert!(game.make_engine_move("f6g8"));
+        assert_eq!(game.draw_reason(), Some(DrawReason::Repetition));
+    }
+
+    #[test]
+    fn test_rated_game() {
+        let mut game = ChessGame::new();
+        game.set_rated(true);
+        assert!(game.make_engine_move("e2e4"));
+        assert!(game.make_engine_move("e7e5"));
+        game.undo_move_pair();
+        assert_eq!(game.move_history().len(), 2);
+        assert!(!game.assists_allowed() && !game.assisted());
+
+        // Reset starts another rated game; a new position is casual
+        game.reset();
+        assert!(game.is_rated());
+        game.reset_from_fen(&Board::default().to_string(), Color::White).unwrap();
+        assert!(!game.is_rated() && game.assists_allowed());
+        assert!(game.make_engine_move("e2e4"));
+        assert!(game.make_engine_move("e7e5"));
+        game.undo_move_pair();
+        assert!(game.move_history().is_empty() && game.assisted());
+    }
 }
@@ -0,0 +1,1454 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+/// Languages the interface can be shown in, saved in the settings file by
+/// their ISO 639-1 code.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "de")]
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    /// The language's name in itself, for the Settings screen.
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::English => &ENGLISH,
+            Language::German => &GERMAN,
+        }
+    }
+}
+
+/// Put `value` in place of the `{}` in a translated template.
+pub fn fill(template: &str, value: impl Display) -> String {
+    template.replacen("{}", &value.to_string(), 1)
+}
+
+/// The interface text in one language.  Entries with `{}` are templates for
+/// `fill`.
+pub struct Strings {
+    pub white: &'static str,
+    pub black: &'static str,
+    pub back: &'static str,
+    pub save: &'static str,
+    pub cancel: &'static str,
+
+    // Game screen
+    pub to_move: &'static str,
+    pub viewing_move: &'static str,
+    pub game_over: &'static str,
+    pub lost_on_time: &'static str,
+    pub draw_by: &'static str,
+    pub stalemate: &'static str,
+    pub threefold_repetition: &'static str,
+    pub fifty_move_rule: &'static str,
+    pub insufficient_material: &'static str,
+    pub time_out_draw: &'static str,
+    pub white_checkmates: &'static str,
+    pub black_checkmates: &'static str,
+    pub white_resigns: &'static str,
+    pub black_resigns: &'static str,
+    pub draw_agreed: &'static str,
+    pub draw_declared: &'static str,
+    pub engine_thinking: &'static str,
+    pub title_your_move: &'static str,
+    pub title_their_move: &'static str,
+    pub playing_as: &'static str,
+    pub engine_opponent: &'static str,
+    pub reset: &'static str,
+    pub undo: &'static str,
+    pub kibitzer: &'static str,
+    pub pause: &'static str,
+    pub game_paused: &'static str,
+    pub flip: &'static str,
+    pub flip_board: &'static str,
+    pub new_game: &'static str,
+    pub setup_position: &'static str,
+    pub saved_games: &'static str,
+    pub session: &'static str,
+    pub quit: &'static str,
+    pub save_game: &'static str,
+    pub repertoire: &'static str,
+    pub endgames: &'static str,
+    pub coach: &'static str,
+    pub threats: &'static str,
+    pub candidates: &'static str,
+    pub limit_elo: &'static str,
+    pub skill: &'static str,
+    pub think: &'static str,
+    pub settings: &'static str,
+    pub uci_console: &'static str,
+    pub online: &'static str,
+    pub export_png: &'static str,
+    pub board_text: &'static str,
+    pub last_move: &'static str,
+    pub coordinates_short: &'static str,
+    pub scoresheet: &'static str,
+    pub export_pgn: &'static str,
+    pub figurines: &'static str,
+    pub review_game: &'static str,
+    pub move_history: &'static str,
+    pub move_table: &'static str,
+    pub eval: &'static str,
+    pub accuracy_short: &'static str,
+    pub go_to_move: &'static str,
+    pub find_position: &'static str,
+    pub position_not_found: &'static str,
+    pub variations: &'static str,
+    pub main_line: &'static str,
+    pub export_line: &'static str,
+    pub promote_variation: &'static str,
+    pub demote_variation: &'static str,
+    pub delete_from_here: &'static str,
+    pub delete_from_here_confirm: &'static str,
+    pub delete: &'static str,
+    pub exit_view_mode: &'static str,
+    pub unfinished_game: &'static str,
+    pub resume: &'static str,
+    pub discard: &'static str,
+    pub duplicate_game: &'static str,
+    pub skip: &'static str,
+    pub replace: &'static str,
+    pub promote_to: &'static str,
+    pub promotion_keys: &'static str,
+    pub move_comment_placeholder: &'static str,
+    pub engine_failed: &'static str,
+    pub engine_path_placeholder: &'static str,
+    pub retry: &'static str,
+    pub use_this_engine: &'static str,
+    pub engine_overrun: &'static str,
+    pub stop_search: &'static str,
+    pub restart_engine: &'static str,
+    pub engine_says: &'static str,
+    pub console: &'static str,
+    pub dismiss: &'static str,
+    pub stop: &'static str,
+    pub repertoire_training: &'static str,
+    pub puzzle_solved: &'static str,
+    pub mistakes_puzzle: &'static str,
+    pub next: &'static str,
+    pub endgame_drill: &'static str,
+    pub endgame_success: &'static str,
+    pub endgame_failure: &'static str,
+    pub menu: &'static str,
+    pub play_anyway_question: &'static str,
+    pub play_anyway: &'static str,
+    pub take_back: &'static str,
+    pub accuracy: &'static str,
+    pub best_was: &'static str,
+    pub console_filter: &'static str,
+    pub console_command_placeholder: &'static str,
+    pub send: &'static str,
+    pub kibitzer_depth: &'static str,
+    pub engine_error: &'static str,
+    pub thinking: &'static str,
+    pub engines_disagree: &'static str,
+
+    // Setup screen
+    pub eraser: &'static str,
+    pub side_to_move: &'static str,
+    pub castling: &'static str,
+    pub castling_white: &'static str,
+    pub castling_black: &'static str,
+    pub chess960_rules: &'static str,
+    pub en_passant: &'static str,
+    pub halfmove_clock: &'static str,
+    pub move_number: &'static str,
+    pub you_play_as: &'static str,
+    pub clocks: &'static str,
+    pub clock_placeholder: &'static str,
+    pub increment: &'static str,
+    pub delay: &'static str,
+    pub clock_note: &'static str,
+    pub engine_clock: &'static str,
+    pub engine_clock_placeholder: &'static str,
+    pub game_mode: &'static str,
+    pub opening: &'static str,
+    pub opening_placeholder: &'static str,
+    pub opening_note: &'static str,
+    pub rated: &'static str,
+    pub casual: &'static str,
+    pub rated_note: &'static str,
+    pub piece_palette: &'static str,
+    pub fen_placeholder: &'static str,
+    pub fen_label: &'static str,
+    pub fen_error: &'static str,
+    pub clear: &'static str,
+    pub starting_position: &'static str,
+    pub paste_fen: &'static str,
+    pub paste_pgn: &'static str,
+    pub pgn_move_placeholder: &'static str,
+    pub pgn_hint: &'static str,
+    pub random_960: &'static str,
+    pub random_endgame: &'static str,
+    pub setup_keys: &'static str,
+    pub endgame_material_placeholder: &'static str,
+    pub start_game: &'static str,
+
+    // Settings screen
+    pub engine: &'static str,
+    pub threads: &'static str,
+    pub hash: &'static str,
+    pub engine_note: &'static str,
+    pub opponent_style: &'static str,
+    pub style_default: &'static str,
+    pub style_note: &'static str,
+    pub eval_file: &'static str,
+    pub eval_file_placeholder: &'static str,
+    pub engine_book: &'static str,
+    pub engine_book_placeholder: &'static str,
+    pub book_learning: &'static str,
+    pub book_learning_note: &'static str,
+    pub board: &'static str,
+    pub moves_instant: &'static str,
+    pub moves_ms: &'static str,
+    pub animation_note: &'static str,
+    pub background_analysis: &'static str,
+    pub background_note: &'static str,
+    pub pause_unfocused: &'static str,
+    pub pause_unfocused_note: &'static str,
+    pub power_saver: &'static str,
+    pub power_saver_off: &'static str,
+    pub power_saver_on_battery: &'static str,
+    pub power_saver_always: &'static str,
+    pub power_saver_note: &'static str,
+    pub show_reply: &'static str,
+    pub reply_note: &'static str,
+    pub show_coordinates: &'static str,
+    pub touch_mode: &'static str,
+    pub touch_note: &'static str,
+    pub palette: &'static str,
+    pub palette_classic: &'static str,
+    pub palette_high_contrast: &'static str,
+    pub palette_colorblind: &'static str,
+    pub language: &'static str,
+    pub interface_size: &'static str,
+    pub interface_size_note: &'static str,
+    pub machine_defaults: &'static str,
+
+    // New game screen
+    pub standard: &'static str,
+    pub standard_description: &'static str,
+    pub chess960_description: &'static str,
+    pub material_odds: &'static str,
+    pub play: &'static str,
+
+    // Saved games screen
+    pub date: &'static str,
+    pub level: &'static str,
+    pub side: &'static str,
+    pub result: &'static str,
+    pub moves: &'static str,
+    pub tags: &'static str,
+    pub tag: &'static str,
+    pub open: &'static str,
+    pub error: &'static str,
+    pub no_games_yet: &'static str,
+    pub no_games_match: &'static str,
+    pub downloading: &'static str,
+    pub import: &'static str,
+    pub import_url_placeholder: &'static str,
+    pub import_failed: &'static str,
+    pub resume_path_placeholder: &'static str,
+    pub resume_failed: &'static str,
+    pub filter_all: &'static str,
+    pub filter_won: &'static str,
+    pub filter_drawn: &'static str,
+    pub filter_lost: &'static str,
+    pub filter_opening: &'static str,
+    pub filter_from: &'static str,
+    pub filter_to: &'static str,
+    pub new_tag_placeholder: &'static str,
+    pub statistics: &'static str,
+    pub famous_games: &'static str,
+    pub my_mistakes: &'static str,
+
+    // Statistics and session screens
+    pub games: &'static str,
+    pub score: &'static str,
+    pub streak: &'static str,
+    pub best: &'static str,
+    pub recent_form: &'static str,
+    pub no_statistics: &'static str,
+    pub your_rating: &'static str,
+    pub rating_note: &'static str,
+    pub adaptive_strength: &'static str,
+    pub on: &'static str,
+    pub off: &'static str,
+    pub adaptive_note: &'static str,
+    pub average_accuracy: &'static str,
+    pub no_accuracy: &'static str,
+    pub session_game: &'static str,
+    pub session_since: &'static str,
+    pub game_count: &'static str,
+    pub time_played: &'static str,
+    pub earlier_sessions: &'static str,
+
+    // Training screens
+    pub versus: &'static str,
+    pub replay: &'static str,
+    pub famous_games_note: &'static str,
+    pub goal_checkmate: &'static str,
+    pub goal_promote: &'static str,
+    pub goal_draw: &'static str,
+    pub you_play: &'static str,
+    pub endgame_training: &'static str,
+    pub endgame_training_note: &'static str,
+    pub saved: &'static str,
+    pub to_move_header: &'static str,
+    pub you_played: &'static str,
+    pub attempts: &'static str,
+    pub status: &'static str,
+    pub solved: &'static str,
+    pub unsolved: &'static str,
+    pub solve: &'static str,
+    pub no_puzzles: &'static str,
+    pub start_queue: &'static str,
+    pub repertoire_line: &'static str,
+    pub repertoire_lines: &'static str,
+    pub due: &'static str,
+    pub train: &'static str,
+    pub repertoire_import: &'static str,
+    pub pgn_path_placeholder: &'static str,
+    pub lines_are_for: &'static str,
+    pub repertoire_note: &'static str,
+    pub repertoire_spacing_note: &'static str,
+
+    // Online play
+    pub lichess_login: &'static str,
+    pub lichess_token_note: &'static str,
+    pub token: &'static str,
+    pub connecting: &'static str,
+    pub log_in: &'static str,
+    pub challenges: &'static str,
+    pub no_challenges: &'static str,
+    pub accept: &'static str,
+    pub decline: &'static str,
+    pub cancel_seek: &'static str,
+    pub seek: &'static str,
+    pub logged_in_as: &'static str,
+    pub log_out: &'static str,
+    pub time_control: &'static str,
+    pub seek_note: &'static str,
+    pub lichess_user: &'static str,
+    pub challenge: &'static str,
+    pub lichess_ai_level: &'static str,
+    pub play_ai: &'static str,
+    pub play_online: &'static str,
+    pub online_note: &'static str,
+    pub online_vs: &'static str,
+    pub rated_lower: &'static str,
+    pub casual_lower: &'static str,
+    pub leave: &'static str,
+    pub resign: &'static str,
+    pub kibitz: &'static str,
+
+    // Status messages
+    pub pawn: &'static str,
+    pub knight: &'static str,
+    pub bishop: &'static str,
+    pub rook: &'static str,
+    pub queen: &'static str,
+    pub king: &'static str,
+    pub empty_square: &'static str,
+    pub castles_kingside: &'static str,
+    pub castles_queenside: &'static str,
+    pub moves_to: &'static str,
+    pub takes: &'static str,
+    pub promotes_to: &'static str,
+    pub announce_check: &'static str,
+    pub announce_checkmate: &'static str,
+    pub saved_position: &'static str,
+    pub image_failed: &'static str,
+    pub sending_to_lichess: &'static str,
+    pub open_yourself: &'static str,
+    pub opened_lichess: &'static str,
+    pub lichess_import_failed: &'static str,
+    pub saved_scoresheet: &'static str,
+    pub scoresheet_failed: &'static str,
+    pub saved_pgn: &'static str,
+    pub pgn_failed: &'static str,
+    pub saved_line: &'static str,
+    pub saved_game_file: &'static str,
+    pub save_game_failed: &'static str,
+    pub resumed_game: &'static str,
+    pub game_unloadable: &'static str,
+    pub no_elo_limit: &'static str,
+    pub restarting_engine: &'static str,
+    pub send_failed: &'static str,
+    pub copied_board_text: &'static str,
+    pub replaying_stored: &'static str,
+    pub review_summary: &'static str,
+    pub added_to_mistakes: &'static str,
+    pub review_failed: &'static str,
+    pub coach_on: &'static str,
+    pub coach_off: &'static str,
+    pub training_stopped: &'static str,
+    pub read_notes: &'static str,
+    pub reviewing_game: &'static str,
+    pub training_repertoire: &'static str,
+    pub not_in_repertoire: &'static str,
+    pub or: &'static str,
+    pub line_ends: &'static str,
+    pub opponent_replies: &'static str,
+    pub line_complete: &'static str,
+    pub imported_line: &'static str,
+    pub imported_lines: &'static str,
+    pub no_lines_to_train: &'static str,
+    pub adaptive_now: &'static str,
+    pub new_game_started: &'static str,
+    pub new_game_opening: &'static str,
+    pub replaying_pgn: &'static str,
+    pub chess960_started: &'static str,
+    pub chess960_no_castling: &'static str,
+    pub exercise_complete: &'static str,
+    pub exercise_failed: &'static str,
+    pub puzzle_unloadable: &'static str,
+    pub puzzle_start: &'static str,
+    pub puzzle_correct: &'static str,
+    pub puzzle_same_move: &'static str,
+    pub puzzle_hint: &'static str,
+    pub puzzle_wrong: &'static str,
+    pub no_puzzles_left: &'static str,
+    pub lichess_disconnected: &'static str,
+    pub lichess_follow_failed: &'static str,
+    pub lichess_game_found: &'static str,
+    pub lichess_stopped: &'static str,
+    pub opponent_played: &'static str,
+    pub playing_on_lichess: &'static str,
+    pub welcome: &'static str,
+    pub game_reset: &'static str,
+    pub position_loaded: &'static str,
+    pub select_promotion: &'static str,
+    pub promotion_cancelled: &'static str,
+    pub coach_checking: &'static str,
+    pub move_played: &'static str,
+    pub coach_doubts: &'static str,
+    pub move_taken_back: &'static str,
+    pub engine_moved: &'static str,
+    pub no_takebacks_rated: &'static str,
+    pub undid_your_move: &'static str,
+    pub no_moves_to_undo: &'static str,
+    pub undid_move_pair: &'static str,
+    pub undid_move: &'static str,
+    pub deleted_moves: &'static str,
+    pub flag_lost: &'static str,
+    pub flag_draw: &'static str,
+    pub drawn_by: &'static str,
+    pub database_unavailable: &'static str,
+    pub no_pgn_game: &'static str,
+    pub no_such_move: &'static str,
+    pub pgn_position: &'static str,
+    pub clipboard_empty: &'static str,
+    pub clipboard_no_fen: &'static str,
+    pub no_endgame_position: &'static str,
+    pub material_format: &'static str,
+    pub not_a_piece_letter: &'static str,
+    pub lichess_login_failed: &'static str,
+    pub looking_for_opponent: &'static str,
+    pub starting_ai_game: &'static str,
+    pub enter_challenge_user: &'static str,
+    pub challenge_sent: &'static str,
+    pub not_a_time_control: &'static str,
+    pub download_no_game: &'static str,
+    pub allows_mate: &'static str,
+    pub loses_piece: &'static str,
+    pub loses_pawn: &'static str,
+    pub spoils_position: &'static str,
+    pub class_best: &'static str,
+    pub class_good: &'static str,
+    pub class_inaccuracy: &'static str,
+    pub class_mistake: &'static str,
+    pub class_blunder: &'static str,
+
+    // Position checks
+    pub missing_white_king: &'static str,
+    pub missing_black_king: &'static str,
+    pub pawns_on_rank: &'static str,
+    pub too_many_kings: &'static str,
+    pub too_many_pawns: &'static str,
+    pub too_many_pieces: &'static str,
+    pub too_many_promoted: &'static str,
+    pub check_not_to_move: &'static str,
+    pub invalid_fen: &'static str,
+}
+
+pub static ENGLISH: Strings = Strings {
+    white: "White",
+    black: "Black",
+    back: "Back",
+    save: "Save",
+    cancel: "Cancel",
+
+    to_move: "{} to move",
+    viewing_move: "Viewing position after move {}",
+    game_over: "Game over: {}",
+    lost_on_time: "Game over: {} lost on time",
+    draw_by: "Game over: draw by {}",
+    stalemate: "stalemate",
+    threefold_repetition: "threefold repetition",
+    fifty_move_rule: "the fifty-move rule",
+    insufficient_material: "insufficient material",
+    time_out_draw: "Game over: {} ran out of time, drawn for lack of mating material",
+    white_checkmates: "White checkmates",
+    black_checkmates: "Black checkmates",
+    white_resigns: "White resigns",
+    black_resigns: "Black resigns",
+    draw_agreed: "draw by agreement",
+    draw_declared: "draw declared",
+    engine_thinking: "Engine is thinking...",
+    title_your_move: "Your move",
+    title_their_move: "Opponent's move",
+    playing_as: "You are playing as {}",
+    engine_opponent: "Engine: {}",
+    reset: "Reset",
+    undo: "Undo",
+    kibitzer: "Kibitzer",
+    pause: "Pause",
+    game_paused: "Paused: the clocks are stopped",
+    flip: "Flip",
+    flip_board: "Flip Board",
+    new_game: "New Game",
+    setup_position: "Setup Position",
+    saved_games: "Saved Games",
+    session: "Session",
+    quit: "Quit",
+    save_game: "Save Game",
+    repertoire: "Repertoire",
+    endgames: "Endgames",
+    coach: "Coach",
+    threats: "Threats",
+    candidates: "Candidates",
+    limit_elo: "Limit Elo",
+    skill: "Skill",
+    think: "Think",
+    settings: "Settings",
+    uci_console: "UCI Console",
+    online: "Online",
+    export_png: "Export PNG",
+    board_text: "Text",
+    last_move: "Last Move",
+    coordinates_short: "Coords",
+    scoresheet: "Scoresheet",
+    export_pgn: "Export PGN",
+    figurines: "Figurines",
+    review_game: "Review Game",
+    move_history: "Move History",
+    move_table: "Table",
+    eval: "Eval",
+    accuracy_short: "Acc.",
+    go_to_move: "Go to: 23b",
+    find_position: "Find a position: FEN, or a placement with ? for any square",
+    position_not_found: "No position in this game matches.",
+    variations: "Variations",
+    main_line: "Main line",
+    export_line: "Export this line",
+    promote_variation: "Make this the main line",
+    demote_variation: "Move below the next variation",
+    delete_from_here: "Delete from here",
+    delete_from_here_confirm: "Delete this move and all after it?",
+    delete: "Delete",
+    exit_view_mode: "Exit View Mode",
+    unfinished_game: "Unfinished game found ({} moves).",
+    resume: "Resume",
+    discard: "Discard",
+    duplicate_game: "This game is already in the database, played {}. Skip it, or replace that copy?",
+    skip: "Skip",
+    replace: "Replace",
+    promote_to: "Promote to:",
+    promotion_keys: "Keys: Q, R, B, N \u{2022} Esc to cancel",
+    move_comment_placeholder: "Comment on this move",
+    engine_failed: "The engine failed to start: {}",
+    engine_path_placeholder: "Path to a UCI engine",
+    retry: "Retry",
+    use_this_engine: "Use This Engine",
+    engine_overrun: "The engine is {}s past its move time and hasn't replied.",
+    stop_search: "Stop Search",
+    restart_engine: "Restart Engine",
+    engine_says: "The engine says: {}",
+    console: "Console",
+    dismiss: "Dismiss",
+    stop: "Stop",
+    repertoire_training: "Repertoire training: {} ({} due)",
+    puzzle_solved: "Puzzle solved",
+    mistakes_puzzle: "My Mistakes puzzle",
+    next: "Next",
+    endgame_drill: "Endgame: {}",
+    endgame_success: "{}: success",
+    endgame_failure: "{}: failed",
+    menu: "Menu",
+    play_anyway_question: "{} — play anyway?",
+    play_anyway: "Play Anyway",
+    take_back: "Take Back",
+    accuracy: "Accuracy: White {}  ·  Black {}",
+    best_was: ", best was {}",
+    console_filter: "Filter",
+    console_command_placeholder: "Command for the engine, e.g. d",
+    send: "Send",
+    kibitzer_depth: "{}, depth {}",
+    engine_error: "Engine error: {}",
+    thinking: "Thinking...",
+    engines_disagree: "The engines disagree on the best move",
+
+    eraser: "Eraser",
+    side_to_move: "Side to move:",
+    castling: "Castling:",
+    castling_white: "W:",
+    castling_black: "B:",
+    chess960_rules: "Chess960 rules",
+    en_passant: "En passant:",
+    halfmove_clock: "Halfmove clock:",
+    move_number: "Move number:",
+    you_play_as: "You play as:",
+    clocks: "Clocks:",
+    clock_placeholder: "SECONDS+SECONDS, e.g. 300+2; empty for none",
+    increment: "Increment",
+    engine_clock: "Engine's clock:",
+    engine_clock_placeholder: "Time odds, e.g. 60; empty for the same as yours",
+    delay: "Delay",
+    clock_note: "Increment adds the extra seconds after every move; a (Bronstein) delay gives back the \
+                 time a move took, up to that many seconds.",
+    game_mode: "Game:",
+    opening: "Opening:",
+    opening_placeholder: "Name or ECO code, e.g. Najdorf",
+    opening_note: "A standard game starts with the chosen opening's moves already played.",
+    rated: "Rated",
+    casual: "Casual",
+    rated_note: "A rated game has no takebacks, coach, candidates, threats, kibitzers or position \
+                 setup until it is over, and always counts in your statistics; a casual game counts \
+                 unless you used them.",
+    piece_palette: "Piece Palette",
+    fen_placeholder: "FEN string...",
+    fen_label: "FEN:",
+    fen_error: "Error: {}",
+    clear: "Clear",
+    starting_position: "Starting Pos",
+    paste_fen: "Paste FEN",
+    paste_pgn: "Paste PGN",
+    pgn_move_placeholder: "At move (e.g. 23b), or the end",
+    pgn_hint: "Sets up a copied game at the move given, with you to move.",
+    random_960: "Random 960",
+    random_endgame: "Random endgame",
+    setup_keys: "Keys: K Q R B N P (Shift for black), X erases, W / Shift+W sets the side to move",
+    endgame_material_placeholder: "e.g. KRP vs KR",
+    start_game: "Start Game",
+
+    engine: "Engine",
+    threads: "Threads: {}",
+    hash: "Hash: {} MB",
+    engine_note: "Saved for later sessions and applied to the running engine.",
+    opponent_style: "Opponent style",
+    style_default: "Default",
+    style_note: "Sets the engine's style options, such as Contempt, where it has them.",
+    eval_file: "Eval file",
+    eval_file_placeholder: "NNUE network (EvalFile); empty for the engine's own",
+    engine_book: "Engine book",
+    engine_book_placeholder: "PGN repertoire the engine opens from; empty for none",
+    book_learning: "Book learning",
+    book_learning_note: "The engine plays the book moves it has won with more often, and those it keeps \
+                         losing with less; results are kept beside the book either way.",
+    board: "Board",
+    moves_instant: "Moves: instant",
+    moves_ms: "Moves: {} ms",
+    animation_note: "How long a moved piece takes to slide to its square.",
+    background_analysis: "Analyse during the game",
+    background_note: "A second engine on one thread evaluates each position as it is played, so the \
+                      review is ready the moment the game ends.",
+    pause_unfocused: "Pause analysis in the background",
+    pause_unfocused_note: "Kibitzers and background analysis stop while the window doesn't have the \
+                           focus and pick up again when you come back; the game itself carries on.",
+    power_saver: "Power saver",
+    power_saver_off: "Off",
+    power_saver_on_battery: "On battery",
+    power_saver_always: "Always",
+    power_saver_note: "Gives the engine a single thread and at most a second per move, and refreshes \
+                       the window less often, to save the battery.",
+    show_reply: "Show the reply the engine expects",
+    reply_note: "A faint arrow after each engine move shows the answer it is counting on.",
+    show_coordinates: "Show coordinates",
+    touch_mode: "Touch mode",
+    touch_note: "Bigger buttons; tap a piece, then its square. A tap promotes to a queen, \
+                 a long press offers the other pieces.",
+    palette: "Colours:",
+    palette_classic: "Classic",
+    palette_high_contrast: "High contrast",
+    palette_colorblind: "Colour-blind",
+    language: "Language",
+    interface_size: "Interface size: {}%",
+    interface_size_note: "Ctrl+= and Ctrl+- change it on any screen; Ctrl+0 goes back to 100%.",
+    machine_defaults: "Machine Defaults",
+
+    standard: "Standard",
+    standard_description: "The usual starting position.",
+    chess960_description: "A random Fischer Random starting position.",
+    material_odds: "Material odds",
+    play: "Play",
+
+    date: "Date",
+    level: "Level",
+    side: "Side",
+    result: "Result",
+    moves: "Moves",
+    tags: "Tags",
+    tag: "Tag",
+    open: "Open",
+    error: "Error: {}",
+    no_games_yet: "No finished games yet.",
+    no_games_match: "No games match the filter.",
+    downloading: "Downloading...",
+    import: "Import",
+    import_url_placeholder: "Lichess or Chess.com game link...",
+    import_failed: "Import failed: {}",
+    resume_path_placeholder: "Game file saved with Save Game...",
+    resume_failed: "Couldn't resume {}",
+    filter_all: "All",
+    filter_won: "Won",
+    filter_drawn: "Drawn",
+    filter_lost: "Lost",
+    filter_opening: "Opening or ECO",
+    filter_from: "From YYYY-MM-DD",
+    filter_to: "To YYYY-MM-DD",
+    new_tag_placeholder: "New tag, then Tag on a game",
+    statistics: "Statistics",
+    famous_games: "Famous Games",
+    my_mistakes: "My Mistakes",
+
+    games: "Games",
+    score: "Score",
+    streak: "Streak",
+    best: "Best",
+    recent_form: "Recent form",
+    no_statistics: "Finish a game to start collecting statistics.",
+    your_rating: "Your rating — {} after {} rated games",
+    rating_note: "Rated games against an engine with an Elo limit move your rating; the engine counts \
+                  as its limit, and odds games are left out.",
+    adaptive_strength: "Adaptive strength — engine at {}",
+    on: "On",
+    off: "Off",
+    adaptive_note: "After each game the engine moves one skill level (or {} Elo) up when you win and \
+                    down when you lose; draws and odds games leave it unchanged. It settles where you \
+                    score about half the points.",
+    average_accuracy: "Average accuracy {}% over {} reviewed games",
+    no_accuracy: "Review a game to see your accuracy",
+    session_game: "Game {}",
+    session_since: "This session — since {}",
+    game_count: "{} games",
+    time_played: "time played {}",
+    earlier_sessions: "Earlier sessions",
+
+    versus: "{} vs {}",
+    replay: "Replay",
+    famous_games_note: "Classic games with notes. They open at the start: click a move in the list, \
+                        scroll the wheel over the board or type a move number in the box above the \
+                        list to step through them.",
+    goal_checkmate: "Goal: checkmate",
+    goal_promote: "Goal: promote the pawn",
+    goal_draw: "Goal: hold the draw for {} moves",
+    you_play: "You play {}.",
+    endgame_training: "Endgame Training",
+    endgame_training_note: "The engine defends at full strength.",
+    saved: "Saved",
+    to_move_header: "To move",
+    you_played: "You played",
+    attempts: "Attempts",
+    status: "Status",
+    solved: "Solved",
+    unsolved: "Open",
+    solve: "Solve",
+    no_puzzles: "No puzzles yet. Blunders found by game review are collected here.",
+    start_queue: "Start Queue",
+    repertoire_line: "{}: {} line",
+    repertoire_lines: "{}: {} lines",
+    due: "{} due",
+    train: "Train",
+    repertoire_import: "Import lines from a PGN file (variations included)",
+    pgn_path_placeholder: "Path to .pgn file...",
+    lines_are_for: "Lines are for",
+    repertoire_note: "Train a side to play through its prepared lines. The trainer answers with your \
+                      prepared replies and corrects any move you haven't prepared.",
+    repertoire_spacing_note: "Positions you get right come back after longer and longer gaps; ones \
+                              you miss are due again straight away, and the trainer steers towards \
+                              them.",
+
+    lichess_login: "Log in with a Lichess personal access token",
+    lichess_token_note: "Create one at lichess.org/account/oauth/token with the board:play, \
+                         challenge:read and challenge:write scopes. It is saved with your settings.",
+    token: "Token",
+    connecting: "Connecting...",
+    log_in: "Log In",
+    challenges: "Challenges",
+    no_challenges: "No challenges waiting.",
+    accept: "Accept",
+    decline: "Decline",
+    cancel_seek: "Cancel Seek",
+    seek: "Seek",
+    logged_in_as: "Logged in as {}",
+    log_out: "Log Out",
+    time_control: "Time control",
+    seek_note: "Lobby seeks from the Board API need a rapid or slower time control.",
+    lichess_user: "Lichess user",
+    challenge: "Challenge",
+    lichess_ai_level: "Lichess AI level {}",
+    play_ai: "Play AI",
+    play_online: "Play Online",
+    online_note: "Games are played on Lichess through its Board API; moves on this board are sent \
+                  there.",
+    online_vs: "Online vs {} ({})",
+    rated_lower: "rated",
+    casual_lower: "casual",
+    leave: "Leave",
+    resign: "Resign",
+    kibitz: "Kibitz",
+
+    pawn: "pawn",
+    knight: "knight",
+    bishop: "bishop",
+    rook: "rook",
+    queen: "queen",
+    king: "king",
+    empty_square: "empty",
+    castles_kingside: "{} castles kingside",
+    castles_queenside: "{} castles queenside",
+    moves_to: "to",
+    takes: "takes",
+    promotes_to: " and promotes to {}",
+    announce_check: ", check",
+    announce_checkmate: ", checkmate",
+    saved_position: "Saved the position to {}",
+    image_failed: "Couldn't export the image: {}",
+    sending_to_lichess: "Sending the game to Lichess...",
+    open_yourself: "{} Open {} yourself.",
+    opened_lichess: "Opened Lichess analysis in your browser.",
+    lichess_import_failed: "Couldn't import the game ({}); opened the position instead.",
+    saved_scoresheet: "Saved the scoresheet to {}; open it in a browser to print it.",
+    scoresheet_failed: "Couldn't save the scoresheet: {}",
+    saved_pgn: "Saved the game to {}",
+    pgn_failed: "Couldn't save the PGN: {}",
+    saved_line: "Saved the line to {}",
+    saved_game_file: "Saved the game to {}; resume it from Saved Games or with --resume.",
+    save_game_failed: "Couldn't save the game: {}",
+    resumed_game: "Resumed saved game ({} moves).",
+    game_unloadable: "Cannot load the game: {}",
+    no_elo_limit: "This engine can't limit its strength by Elo; using the skill level instead.",
+    restarting_engine: "Restarting the engine...",
+    send_failed: "Failed to send '{}': {}",
+    copied_board_text: "Copied the board to the clipboard as text.",
+    replaying_stored: "Replaying game from {}.",
+    review_summary: "Review: {} inaccuracies, {} mistakes, {} blunders.",
+    added_to_mistakes: " {} added to My Mistakes.",
+    review_failed: "Review failed: {}",
+    coach_on: "Coach mode on: moves are checked before they are played.",
+    coach_off: "Coach mode off.",
+    training_stopped: "Training stopped. The engine takes over.",
+    read_notes: "{}. Step through the moves to read the notes.",
+    reviewing_game: "Reviewing game...",
+    training_repertoire: "Training your {} repertoire. Play your prepared move.",
+    not_in_repertoire: "{} is not in your repertoire. Expected {}.",
+    or: " or ",
+    line_ends: "Correct! {} ends this line. Reset to drill another.",
+    opponent_replies: "Correct! The opponent replies {}. Your move.",
+    line_complete: "Correct! That completes the line. Reset to drill another.",
+    imported_line: "Imported {} new line for {}.",
+    imported_lines: "Imported {} new lines for {}.",
+    no_lines_to_train: "No {} lines to train yet.",
+    adaptive_now: "Adaptive strength: the engine is now at {}.",
+    new_game_started: "New game. Make a move to begin.",
+    new_game_opening: "New game from the {}.",
+    replaying_pgn: "Replaying {} moves from PGN. Exit view mode to play on as {}.",
+    chess960_started: "Chess960 game started. Make a move to begin.",
+    chess960_no_castling: "Chess960 game started. Castling is only available with the king and rooks \
+                           on their standard squares.",
+    exercise_complete: "{} Exercise complete.",
+    exercise_failed: "{} Press Reset to try again.",
+    puzzle_unloadable: "This puzzle's position can't be loaded.",
+    puzzle_start: "You went wrong here. Find the best move for {}.",
+    puzzle_correct: "Correct! That's the move you missed.",
+    puzzle_same_move: "That's the move you played in the game. Try again.",
+    puzzle_hint: " Hint: move a {}.",
+    puzzle_wrong: "Not the best move.{} Try again.",
+    no_puzzles_left: "No unsolved puzzles left. Well done!",
+    lichess_disconnected: "Lost the connection to Lichess.",
+    lichess_follow_failed: "Couldn't follow the Lichess game: {}",
+    lichess_game_found: "Lichess game found. Waiting for the board...",
+    lichess_stopped: "Lichess stopped sending this game's moves.",
+    opponent_played: "{} played {}.",
+    playing_on_lichess: "Playing {} on Lichess as {}.",
+    welcome: "Welcome to Chess Engine Player! Make a move to begin.",
+    game_reset: "Game reset. Make a move to begin.",
+    position_loaded: "Custom position loaded. Make a move to begin.",
+    select_promotion: "Select promotion piece",
+    promotion_cancelled: "Promotion cancelled",
+    coach_checking: "Coach is checking your move...",
+    move_played: "Move: {}",
+    coach_doubts: "The coach has doubts about this move.",
+    move_taken_back: "Move taken back. Choose another.",
+    engine_moved: "Engine moved: {}",
+    no_takebacks_rated: "No takebacks in a rated game.",
+    undid_your_move: "Undid your move.",
+    no_moves_to_undo: "No moves to undo.",
+    undid_move_pair: "Undid last move pair.",
+    undid_move: "Undid last move.",
+    deleted_moves: "Deleted the moves from there on.",
+    flag_lost: "{} lost on time.",
+    flag_draw: "{} ran out of time, but the opponent can't mate: draw.",
+    drawn_by: "Draw by {}.",
+    database_unavailable: "Game database is not available",
+    no_pgn_game: "The clipboard holds no PGN game",
+    no_such_move: "The game has no move {}",
+    pgn_position: "{} vs {}: position after {} of {} half-moves",
+    clipboard_empty: "The clipboard is empty",
+    clipboard_no_fen: "Clipboard has no valid FEN: {}",
+    no_endgame_position: "No legal position found for {}: {}",
+    material_format: "Write the material as white's men vs black's, e.g. KRP vs KR",
+    not_a_piece_letter: "'{}' is not a piece letter (K, Q, R, B, N, P)",
+    lichess_login_failed: "Couldn't log in to Lichess: {}",
+    looking_for_opponent: "Looking for an opponent...",
+    starting_ai_game: "Starting a game against the Lichess AI (level {}).",
+    enter_challenge_user: "Enter the Lichess user to challenge.",
+    challenge_sent: "Challenge sent to {}; waiting for an answer.",
+    not_a_time_control: "'{}' isn't a time control like 10+5",
+    download_no_game: "The download had no game in it",
+    allows_mate: "This allows a forced mate",
+    loses_piece: "This loses a piece",
+    loses_pawn: "This loses a pawn",
+    spoils_position: "This spoils your position",
+    class_best: "Best",
+    class_good: "Good",
+    class_inaccuracy: "Inaccuracy",
+    class_mistake: "Mistake",
+    class_blunder: "Blunder",
+
+    missing_white_king: "Missing white king (K)",
+    missing_black_king: "Missing black king (k)",
+    pawns_on_rank: "Pawns cannot be on rank {}",
+    too_many_kings: "{} has {} kings",
+    too_many_pawns: "{} has {} pawns; at most 8 are possible",
+    too_many_pieces: "{} has {} pieces; at most 16 are possible",
+    too_many_promoted: "{} has {} more queens, rooks, bishops or knights than its missing pawns could \
+                        have become",
+    check_not_to_move: "{} is in check but it is not their move",
+    invalid_fen: "Invalid FEN: {}",
+};
+
+pub static GERMAN: Strings = Strings {
+    white: "Weiß",
+    black: "Schwarz",
+    back: "Zurück",
+    save: "Speichern",
+    cancel: "Abbrechen",
+
+    to_move: "{} am Zug",
+    viewing_move: "Stellung nach Zug {}",
+    game_over: "Partie beendet: {}",
+    lost_on_time: "Partie beendet: {} hat die Zeit überschritten",
+    draw_by: "Partie beendet: Remis durch {}",
+    stalemate: "Patt",
+    threefold_repetition: "dreifache Stellungswiederholung",
+    fifty_move_rule: "die 50-Züge-Regel",
+    insufficient_material: "ungenügendes Material",
+    time_out_draw: "Partie beendet: {} hat die Zeit überschritten, remis mangels Mattmaterial",
+    white_checkmates: "Weiß setzt matt",
+    black_checkmates: "Schwarz setzt matt",
+    white_resigns: "Weiß gibt auf",
+    black_resigns: "Schwarz gibt auf",
+    draw_agreed: "Remis durch Einigung",
+    draw_declared: "Remis erklärt",
+    engine_thinking: "Engine rechnet...",
+    title_your_move: "Du bist am Zug",
+    title_their_move: "Gegner am Zug",
+    playing_as: "Du spielst {}",
+    engine_opponent: "Engine: {}",
+    reset: "Neustart",
+    undo: "Zurücknehmen",
+    kibitzer: "Kiebitz",
+    pause: "Pause",
+    game_paused: "Pausiert: die Uhren stehen",
+    flip: "Seiten tauschen",
+    flip_board: "Brett drehen",
+    new_game: "Neue Partie",
+    setup_position: "Stellung aufbauen",
+    saved_games: "Gespeicherte Partien",
+    session: "Sitzung",
+    quit: "Beenden",
+    save_game: "Partie speichern",
+    repertoire: "Repertoire",
+    endgames: "Endspiele",
+    coach: "Trainer",
+    threats: "Drohungen",
+    candidates: "Kandidaten",
+    limit_elo: "Elo begrenzen",
+    skill: "Stärke",
+    think: "Bedenkzeit",
+    settings: "Einstellungen",
+    uci_console: "UCI-Konsole",
+    online: "Online",
+    export_png: "PNG exportieren",
+    board_text: "Text",
+    last_move: "Letzter Zug",
+    coordinates_short: "Koord.",
+    scoresheet: "Partieformular",
+    export_pgn: "PGN exportieren",
+    figurines: "Figurinen",
+    review_game: "Partie analysieren",
+    move_history: "Zugliste",
+    move_table: "Tabelle",
+    eval: "Bew.",
+    accuracy_short: "Gen.",
+    go_to_move: "Gehe zu: 23b",
+    find_position: "Stellung suchen: FEN, oder Aufstellung mit ? für beliebige Felder",
+    position_not_found: "Keine Stellung dieser Partie passt.",
+    variations: "Varianten",
+    main_line: "Hauptvariante",
+    export_line: "Diese Variante exportieren",
+    promote_variation: "Zur Hauptvariante machen",
+    demote_variation: "Unter die nächste Variante schieben",
+    delete_from_here: "Ab hier löschen",
+    delete_from_here_confirm: "Diesen Zug und alle folgenden löschen?",
+    delete: "Löschen",
+    exit_view_mode: "Ansicht verlassen",
+    unfinished_game: "Unbeendete Partie gefunden ({} Züge).",
+    resume: "Fortsetzen",
+    discard: "Verwerfen",
+    duplicate_game: "Diese Partie ist schon in der Datenbank, gespielt am {}. Überspringen oder die \
+                     Kopie ersetzen?",
+    skip: "Überspringen",
+    replace: "Ersetzen",
+    promote_to: "Umwandeln in:",
+    promotion_keys: "Tasten: Q, R, B, N \u{2022} Esc zum Abbrechen",
+    move_comment_placeholder: "Kommentar zu diesem Zug",
+    engine_failed: "Die Engine konnte nicht gestartet werden: {}",
+    engine_path_placeholder: "Pfad zu einer UCI-Engine",
+    retry: "Erneut versuchen",
+    use_this_engine: "Diese Engine verwenden",
+    engine_overrun: "Die Engine hat ihre Zugzeit um {} s überschritten und nicht geantwortet.",
+    stop_search: "Suche abbrechen",
+    restart_engine: "Engine neu starten",
+    engine_says: "Die Engine meldet: {}",
+    console: "Konsole",
+    dismiss: "Ausblenden",
+    stop: "Beenden",
+    repertoire_training: "Repertoiretraining: {} ({} fällig)",
+    puzzle_solved: "Aufgabe gelöst",
+    mistakes_puzzle: "Aufgabe aus Meine Fehler",
+    next: "Weiter",
+    endgame_drill: "Endspiel: {}",
+    endgame_success: "{}: geschafft",
+    endgame_failure: "{}: nicht geschafft",
+    menu: "Menü",
+    play_anyway_question: "{} – trotzdem spielen?",
+    play_anyway: "Trotzdem spielen",
+    take_back: "Zurücknehmen",
+    accuracy: "Genauigkeit: Weiß {}  ·  Schwarz {}",
+    best_was: ", am besten war {}",
+    console_filter: "Filter",
+    console_command_placeholder: "Befehl an die Engine, z. B. d",
+    send: "Senden",
+    kibitzer_depth: "{}, Tiefe {}",
+    engine_error: "Engine-Fehler: {}",
+    thinking: "Rechnet...",
+    engines_disagree: "Die Engines sind sich über den besten Zug uneinig",
+
+    eraser: "Radierer",
+    side_to_move: "Am Zug:",
+    castling: "Rochade:",
+    castling_white: "W:",
+    castling_black: "S:",
+    chess960_rules: "Chess960-Regeln",
+    en_passant: "En passant:",
+    halfmove_clock: "Halbzugzähler:",
+    move_number: "Zugnummer:",
+    you_play_as: "Du spielst:",
+    clocks: "Uhren:",
+    clock_placeholder: "SEKUNDEN+SEKUNDEN, z. B. 300+2; leer für keine",
+    increment: "Inkrement",
+    engine_clock: "Uhr der Engine:",
+    engine_clock_placeholder: "Zeitvorgabe, z. B. 60; leer für dieselbe wie deine",
+    delay: "Verzögerung",
+    clock_note: "Das Inkrement schreibt nach jedem Zug die Zusatzsekunden gut; eine \
+                 (Bronstein-)Verzögerung erstattet die Zeit eines Zuges bis zu dieser Dauer.",
+    game_mode: "Partie:",
+    opening: "Eröffnung:",
+    opening_placeholder: "Name oder ECO-Code, z. B. Najdorf",
+    opening_note: "Eine Standardpartie beginnt mit den Zügen der gewählten Eröffnung.",
+    rated: "Gewertet",
+    casual: "Freie Partie",
+    rated_note: "Eine gewertete Partie hat bis zum Ende keine Zugrücknahmen, keinen Trainer, keine \
+                 Kandidatenzüge, Drohungen, Kiebitze oder Stellungseingabe und zählt immer in der \
+                 Statistik; eine freie Partie zählt, wenn du nichts davon benutzt hast.",
+    piece_palette: "Figuren",
+    fen_placeholder: "FEN-Zeichenkette...",
+    fen_label: "FEN:",
+    fen_error: "Fehler: {}",
+    clear: "Leeren",
+    starting_position: "Grundstellung",
+    paste_fen: "FEN einfügen",
+    paste_pgn: "PGN einfügen",
+    pgn_move_placeholder: "Bei Zug (z. B. 23b) oder am Ende",
+    pgn_hint: "Baut eine kopierte Partie beim angegebenen Zug auf; du bist am Zug.",
+    random_960: "Zufällig 960",
+    random_endgame: "Zufälliges Endspiel",
+    setup_keys: "Tasten: K Q R B N P (Umschalt für Schwarz), X löscht, W / Umschalt+W wählt, wer zieht",
+    endgame_material_placeholder: "z. B. KRP vs KR",
+    start_game: "Partie starten",
+
+    engine: "Engine",
+    threads: "Threads: {}",
+    hash: "Hash: {} MB",
+    engine_note: "Wird für spätere Sitzungen gespeichert und sofort auf die Engine angewendet.",
+    opponent_style: "Spielstil",
+    style_default: "Standard",
+    style_note: "Setzt die Stil-Optionen der Engine, etwa Contempt, sofern sie welche hat.",
+    eval_file: "Bewertungsdatei",
+    eval_file_placeholder: "NNUE-Netz (EvalFile); leer für das der Engine",
+    engine_book: "Eröffnungsbuch",
+    engine_book_placeholder: "PGN-Repertoire, aus dem die Engine eröffnet; leer für keins",
+    book_learning: "Buch lernt",
+    book_learning_note: "Die Engine spielt Buchzüge, mit denen sie gewonnen hat, öfter und solche, mit \
+                         denen sie immer wieder verliert, seltener; die Ergebnisse werden in jedem Fall \
+                         neben dem Buch gespeichert.",
+    board: "Brett",
+    moves_instant: "Züge: sofort",
+    moves_ms: "Züge: {} ms",
+    animation_note: "Wie lange eine gezogene Figur zu ihrem Feld gleitet.",
+    background_analysis: "Während der Partie analysieren",
+    background_note: "Eine zweite Engine bewertet auf einem Thread jede Stellung, sobald sie entsteht, \
+                      damit die Analyse beim Partieende sofort bereitsteht.",
+    pause_unfocused: "Analyse im Hintergrund anhalten",
+    pause_unfocused_note: "Kiebitze und Hintergrundanalyse halten an, solange das Fenster nicht im \
+                           Vordergrund ist, und machen weiter, wenn du zurückkommst; die Partie läuft \
+                           weiter.",
+    power_saver: "Energiesparen",
+    power_saver_off: "Aus",
+    power_saver_on_battery: "Im Akkubetrieb",
+    power_saver_always: "Immer",
+    power_saver_note: "Die Engine bekommt nur einen Thread und höchstens eine Sekunde pro Zug, und das \
+                       Fenster wird seltener aufgefrischt, um den Akku zu schonen.",
+    show_reply: "Erwartete Antwort der Engine zeigen",
+    reply_note: "Ein blasser Pfeil nach jedem Engine-Zug zeigt die Antwort, mit der sie rechnet.",
+    show_coordinates: "Koordinaten zeigen",
+    touch_mode: "Touch-Modus",
+    touch_note: "Größere Knöpfe; Figur antippen, dann ihr Zielfeld. Antippen wandelt in eine Dame um, \
+                 langes Drücken bietet die anderen Figuren an.",
+    palette: "Farben:",
+    palette_classic: "Klassisch",
+    palette_high_contrast: "Hoher Kontrast",
+    palette_colorblind: "Farbenblind",
+    language: "Sprache",
+    interface_size: "Oberflächengröße: {} %",
+    interface_size_note: "Strg+= und Strg+- ändern sie überall; Strg+0 setzt sie auf 100 % zurück.",
+    machine_defaults: "Standardwerte",
+
+    standard: "Standard",
+    standard_description: "Die übliche Grundstellung.",
+    chess960_description: "Eine zufällige Fischer-Random-Grundstellung.",
+    material_odds: "Materialvorgabe",
+    play: "Spielen",
+
+    date: "Datum",
+    level: "Stufe",
+    side: "Seite",
+    result: "Ergebnis",
+    moves: "Züge",
+    tags: "Schlagwörter",
+    tag: "Markieren",
+    open: "Öffnen",
+    error: "Fehler: {}",
+    no_games_yet: "Noch keine beendeten Partien.",
+    no_games_match: "Keine Partie passt zum Filter.",
+    downloading: "Wird geladen...",
+    import: "Importieren",
+    import_url_placeholder: "Link zu einer Partie auf Lichess oder Chess.com...",
+    import_failed: "Import fehlgeschlagen: {}",
+    resume_path_placeholder: "Mit „Partie speichern“ gesicherte Datei...",
+    resume_failed: "Fortsetzen nicht möglich: {}",
+    filter_all: "Alle",
+    filter_won: "Gewonnen",
+    filter_drawn: "Remis",
+    filter_lost: "Verloren",
+    filter_opening: "Eröffnung oder ECO",
+    filter_from: "Von JJJJ-MM-TT",
+    filter_to: "Bis JJJJ-MM-TT",
+    new_tag_placeholder: "Neues Schlagwort, dann Markieren bei einer Partie",
+    statistics: "Statistik",
+    famous_games: "Berühmte Partien",
+    my_mistakes: "Meine Fehler",
+
+    games: "Partien",
+    score: "Punkte",
+    streak: "Serie",
+    best: "Beste",
+    recent_form: "Aktuelle Form",
+    no_statistics: "Beende eine Partie, um Statistiken zu sammeln.",
+    your_rating: "Deine Wertung – {} nach {} gewerteten Partien",
+    rating_note: "Gewertete Partien gegen eine Engine mit Elo-Grenze verändern deine Wertung; die \
+                  Engine zählt mit ihrer Grenze, Vorgabepartien bleiben außen vor.",
+    adaptive_strength: "Anpassende Spielstärke – Engine auf {}",
+    on: "Ein",
+    off: "Aus",
+    adaptive_note: "Nach jeder Partie steigt die Engine um eine Stufe (oder {} Elo), wenn du \
+                    gewinnst, und sinkt, wenn du verlierst; Remis und Vorgabepartien ändern nichts. \
+                    So pendelt sie sich dort ein, wo du etwa die Hälfte der Punkte holst.",
+    average_accuracy: "Durchschnittliche Genauigkeit {} % in {} analysierten Partien",
+    no_accuracy: "Analysiere eine Partie, um deine Genauigkeit zu sehen",
+    session_game: "Partie {}",
+    session_since: "Diese Sitzung – seit {}",
+    game_count: "{} Partien",
+    time_played: "Spielzeit {}",
+    earlier_sessions: "Frühere Sitzungen",
+
+    versus: "{} gegen {}",
+    replay: "Nachspielen",
+    famous_games_note: "Klassische Partien mit Anmerkungen. Sie öffnen sich am Anfang: Klicke einen \
+                        Zug in der Liste an, drehe das Mausrad über dem Brett oder tippe eine \
+                        Zugnummer in das Feld über der Liste, um sie durchzugehen.",
+    goal_checkmate: "Ziel: Matt setzen",
+    goal_promote: "Ziel: den Bauern umwandeln",
+    goal_draw: "Ziel: das Remis {} Züge lang halten",
+    you_play: "Du spielst {}.",
+    endgame_training: "Endspieltraining",
+    endgame_training_note: "Die Engine verteidigt mit voller Stärke.",
+    saved: "Gespeichert",
+    to_move_header: "Am Zug",
+    you_played: "Dein Zug",
+    attempts: "Versuche",
+    status: "Status",
+    solved: "Gelöst",
+    unsolved: "Offen",
+    solve: "Lösen",
+    no_puzzles: "Noch keine Aufgaben. Grobe Fehler aus der Partieanalyse werden hier gesammelt.",
+    start_queue: "Alle lösen",
+    repertoire_line: "{}: {} Linie",
+    repertoire_lines: "{}: {} Linien",
+    due: "{} fällig",
+    train: "Trainieren",
+    repertoire_import: "Linien aus einer PGN-Datei importieren (mit Varianten)",
+    pgn_path_placeholder: "Pfad zur .pgn-Datei...",
+    lines_are_for: "Linien für",
+    repertoire_note: "Trainiere eine Seite, um ihre vorbereiteten Linien durchzuspielen. Der Trainer \
+                      antwortet mit deinen vorbereiteten Erwiderungen und korrigiert jeden Zug, den \
+                      du nicht vorbereitet hast.",
+    repertoire_spacing_note: "Stellungen, die du richtig spielst, kommen in immer größeren Abständen \
+                              wieder; verpasste sind sofort wieder fällig, und der Trainer steuert \
+                              auf sie zu.",
+
+    lichess_login: "Mit einem persönlichen Lichess-Zugangstoken anmelden",
+    lichess_token_note: "Erstelle eines unter lichess.org/account/oauth/token mit den Berechtigungen \
+                         board:play, challenge:read und challenge:write. Es wird mit deinen \
+                         Einstellungen gespeichert.",
+    token: "Token",
+    connecting: "Verbinde...",
+    log_in: "Anmelden",
+    challenges: "Herausforderungen",
+    no_challenges: "Keine offenen Herausforderungen.",
+    accept: "Annehmen",
+    decline: "Ablehnen",
+    cancel_seek: "Suche abbrechen",
+    seek: "Gegner suchen",
+    logged_in_as: "Angemeldet als {}",
+    log_out: "Abmelden",
+    time_control: "Bedenkzeit",
+    seek_note: "Gesuche in der Lobby über die Board-API brauchen eine Schnellschach- oder längere \
+                Bedenkzeit.",
+    lichess_user: "Lichess-Benutzer",
+    challenge: "Herausfordern",
+    lichess_ai_level: "Lichess-KI Stufe {}",
+    play_ai: "Gegen KI spielen",
+    play_online: "Online spielen",
+    online_note: "Partien werden über die Board-API auf Lichess gespielt; Züge auf diesem Brett \
+                  werden dorthin gesendet.",
+    online_vs: "Online gegen {} ({})",
+    rated_lower: "gewertet",
+    casual_lower: "ungewertet",
+    leave: "Verlassen",
+    resign: "Aufgeben",
+    kibitz: "Kiebitzen",
+
+    pawn: "Bauer",
+    knight: "Springer",
+    bishop: "Läufer",
+    rook: "Turm",
+    queen: "Dame",
+    king: "König",
+    empty_square: "leer",
+    castles_kingside: "{} rochiert kurz",
+    castles_queenside: "{} rochiert lang",
+    moves_to: "nach",
+    takes: "schlägt",
+    promotes_to: ", Umwandlung in {}",
+    announce_check: ", Schach",
+    announce_checkmate: ", Schachmatt",
+    saved_position: "Stellung gespeichert unter {}",
+    image_failed: "Bild konnte nicht exportiert werden: {}",
+    sending_to_lichess: "Sende die Partie an Lichess...",
+    open_yourself: "{} Öffne {} selbst.",
+    opened_lichess: "Lichess-Analyse im Browser geöffnet.",
+    lichess_import_failed: "Partie konnte nicht importiert werden ({}); stattdessen wurde die \
+                            Stellung geöffnet.",
+    saved_scoresheet: "Partieformular gespeichert unter {}; zum Drucken im Browser öffnen.",
+    scoresheet_failed: "Partieformular konnte nicht gespeichert werden: {}",
+    saved_pgn: "Partie gespeichert unter {}",
+    pgn_failed: "PGN konnte nicht gespeichert werden: {}",
+    saved_line: "Linie gespeichert unter {}",
+    saved_game_file: "Partie gespeichert unter {}; fortsetzen über Gespeicherte Partien oder mit \
+                      --resume.",
+    save_game_failed: "Partie konnte nicht gespeichert werden: {}",
+    resumed_game: "Gespeicherte Partie fortgesetzt ({} Züge).",
+    game_unloadable: "Die Partie kann nicht geladen werden: {}",
+    no_elo_limit: "Diese Engine kann ihre Stärke nicht per Elo begrenzen; stattdessen gilt die \
+                   Spielstufe.",
+    restarting_engine: "Engine wird neu gestartet...",
+    send_failed: "'{}' konnte nicht gesendet werden: {}",
+    copied_board_text: "Brett als Text in die Zwischenablage kopiert.",
+    replaying_stored: "Partie vom {} wird nachgespielt.",
+    review_summary: "Analyse: {} Ungenauigkeiten, {} Fehler, {} grobe Fehler.",
+    added_to_mistakes: " {} zu Meine Fehler hinzugefügt.",
+    review_failed: "Analyse fehlgeschlagen: {}",
+    coach_on: "Trainermodus an: Züge werden vor dem Ausführen geprüft.",
+    coach_off: "Trainermodus aus.",
+    training_stopped: "Training beendet. Die Engine übernimmt.",
+    read_notes: "{}. Gehe die Züge durch, um die Anmerkungen zu lesen.",
+    reviewing_game: "Partie wird analysiert...",
+    training_repertoire: "Training deines Repertoires mit {}. Spiele deinen vorbereiteten Zug.",
+    not_in_repertoire: "{} gehört nicht zu deinem Repertoire. Erwartet: {}.",
+    or: " oder ",
+    line_ends: "Richtig! {} beendet diese Linie. Neu starten für die nächste.",
+    opponent_replies: "Richtig! Der Gegner antwortet {}. Du bist am Zug.",
+    line_complete: "Richtig! Damit ist die Linie vollständig. Neu starten für die nächste.",
+    imported_line: "{} neue Linie für {} importiert.",
+    imported_lines: "{} neue Linien für {} importiert.",
+    no_lines_to_train: "Noch keine Linien für {} zum Trainieren.",
+    adaptive_now: "Anpassende Spielstärke: Die Engine steht jetzt auf {}.",
+    new_game_started: "Neue Partie. Mach einen Zug, um zu beginnen.",
+    new_game_opening: "Neue Partie mit {}.",
+    replaying_pgn: "{} Züge aus PGN werden nachgespielt. Verlasse den Ansichtsmodus, um als {} \
+                    weiterzuspielen.",
+    chess960_started: "Chess960-Partie begonnen. Mach einen Zug, um zu beginnen.",
+    chess960_no_castling: "Chess960-Partie begonnen. Rochieren geht nur mit König und Türmen auf \
+                           ihren üblichen Feldern.",
+    exercise_complete: "{} Übung geschafft.",
+    exercise_failed: "{} Neu starten für einen weiteren Versuch.",
+    puzzle_unloadable: "Die Stellung dieser Aufgabe lässt sich nicht laden.",
+    puzzle_start: "Hier lagst du daneben. Finde den besten Zug für {}.",
+    puzzle_correct: "Richtig! Das ist der Zug, den du verpasst hast.",
+    puzzle_same_move: "Das ist der Zug aus deiner Partie. Versuch es noch einmal.",
+    puzzle_hint: " Tipp: Zieh mit dieser Figur: {}.",
+    puzzle_wrong: "Nicht der beste Zug.{} Versuch es noch einmal.",
+    no_puzzles_left: "Keine ungelösten Aufgaben mehr. Gut gemacht!",
+    lichess_disconnected: "Verbindung zu Lichess verloren.",
+    lichess_follow_failed: "Der Lichess-Partie kann nicht gefolgt werden: {}",
+    lichess_game_found: "Lichess-Partie gefunden. Warte auf das Brett...",
+    lichess_stopped: "Lichess sendet keine Züge dieser Partie mehr.",
+    opponent_played: "{} spielte {}.",
+    playing_on_lichess: "Partie gegen {} auf Lichess als {}.",
+    welcome: "Willkommen bei Chess Engine Player! Mach einen Zug, um zu beginnen.",
+    game_reset: "Partie zurückgesetzt. Mach einen Zug, um zu beginnen.",
+    position_loaded: "Eigene Stellung geladen. Mach einen Zug, um zu beginnen.",
+    select_promotion: "Umwandlungsfigur wählen",
+    promotion_cancelled: "Umwandlung abgebrochen",
+    coach_checking: "Der Trainer prüft deinen Zug...",
+    move_played: "Zug: {}",
+    coach_doubts: "Der Trainer hat Zweifel an diesem Zug.",
+    move_taken_back: "Zug zurückgenommen. Wähle einen anderen.",
+    engine_moved: "Engine zog: {}",
+    no_takebacks_rated: "Keine Zugrücknahmen in einer gewerteten Partie.",
+    undid_your_move: "Dein Zug wurde zurückgenommen.",
+    no_moves_to_undo: "Keine Züge zum Zurücknehmen.",
+    undid_move_pair: "Letztes Zugpaar zurückgenommen.",
+    undid_move: "Letzter Zug zurückgenommen.",
+    deleted_moves: "Die Züge ab dort wurden gelöscht.",
+    flag_lost: "{} hat die Zeit überschritten.",
+    flag_draw: "{} hat die Zeit überschritten, aber der Gegner kann nicht mattsetzen: remis.",
+    drawn_by: "Remis durch {}.",
+    database_unavailable: "Die Partiedatenbank ist nicht verfügbar",
+    no_pgn_game: "Die Zwischenablage enthält keine PGN-Partie",
+    no_such_move: "Die Partie hat keinen Zug {}",
+    pgn_position: "{} gegen {}: Stellung nach {} von {} Halbzügen",
+    clipboard_empty: "Die Zwischenablage ist leer",
+    clipboard_no_fen: "Die Zwischenablage enthält keine gültige FEN: {}",
+    no_endgame_position: "Keine legale Stellung für {} gefunden: {}",
+    material_format: "Das Material als Weiß gegen Schwarz angeben, z. B. KRP vs KR",
+    not_a_piece_letter: "'{}' ist kein Figurenbuchstabe (K, Q, R, B, N, P)",
+    lichess_login_failed: "Anmeldung bei Lichess fehlgeschlagen: {}",
+    looking_for_opponent: "Suche einen Gegner...",
+    starting_ai_game: "Starte eine Partie gegen die Lichess-KI (Stufe {}).",
+    enter_challenge_user: "Gib den Lichess-Benutzer ein, den du herausfordern willst.",
+    challenge_sent: "Herausforderung an {} gesendet; warte auf Antwort.",
+    not_a_time_control: "'{}' ist keine Bedenkzeit wie 10+5",
+    download_no_game: "Der Download enthielt keine Partie",
+    allows_mate: "Das erlaubt ein erzwungenes Matt",
+    loses_piece: "Das verliert eine Figur",
+    loses_pawn: "Das verliert einen Bauern",
+    spoils_position: "Das verdirbt deine Stellung",
+    class_best: "Bester Zug",
+    class_good: "Gut",
+    class_inaccuracy: "Ungenauigkeit",
+    class_mistake: "Fehler",
+    class_blunder: "Grober Fehler",
+
+    missing_white_king: "Der weiße König fehlt (K)",
+    missing_black_king: "Der schwarze König fehlt (k)",
+    pawns_on_rank: "Bauern können nicht auf der {}. Reihe stehen",
+    too_many_kings: "{} hat {} Könige",
+    too_many_pawns: "{} hat {} Bauern; höchstens 8 sind möglich",
+    too_many_pieces: "{} hat {} Steine; höchstens 16 sind möglich",
+    too_many_promoted: "{} hat {} Damen, Türme, Läufer oder Springer mehr, als aus den fehlenden \
+                        Bauern geworden sein können",
+    check_not_to_move: "{} steht im Schach, ist aber nicht am Zug",
+    invalid_fen: "Ungültige FEN: {}",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_templates() {
+        assert_eq!(fill(GERMAN.to_move, GERMAN.white), "Weiß am Zug");
+        assert_eq!(fill(ENGLISH.moves_ms, 200), "Moves: 200 ms");
+        // Every language keeps the placeholders its templates need
+        for language in Language::ALL {
+            let strings = language.strings();
+            let templates = [
+                strings.to_move,
+                strings.playing_as,
+                strings.unfinished_game,
+                strings.duplicate_game,
+                strings.hash,
+            ];
+            for template in templates {
+                assert!(template.contains("{}"), "{:?}: {}", language, template);
+            }
+            let pairs = [
+                strings.versus,
+                strings.send_failed,
+                strings.not_in_repertoire,
+                strings.replaying_pgn,
+                strings.opponent_played,
+                strings.playing_on_lichess,
+                strings.no_endgame_position,
+                strings.too_many_kings,
+                strings.too_many_pawns,
+                strings.too_many_pieces,
+                strings.too_many_promoted,
+            ];
+            for template in pairs {
+                assert_eq!(template.matches("{}").count(), 2, "{:?}: {}", language, template);
+            }
+            assert_eq!(strings.review_summary.matches("{}").count(), 3);
+            assert_eq!(strings.pgn_position.matches("{}").count(), 4);
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use chess::Color;
+use serde_json::Value;
+
+use crate::clock::{ClockMode, TimeControl};
+use crate::error::AppError;
+use crate::online::{curl, curl_stream, LineStream};
+
+const API: &str = "https://lichess.org/api";
+
+/// A challenge from another player, waiting to be accepted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IncomingChallenge {
+    pub id: String,
+    pub challenger: String,
+    /// Time control and rated or casual, e.g. "5+3 rated"
+    pub description: String,
+}
+
+/// Something that happened on the account's event stream.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccountEvent {
+    Challenge(IncomingChallenge),
+    /// A challenge was withdrawn, declined or accepted
+    ChallengeGone(String),
+    /// A game started (or was already running) with the player as `color`
+    GameStart { id: String, color: Color },
+}
+
+/// The position and clocks of a game, as of its latest move.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoardState {
+    /// Every move so far, in UCI
+    pub moves: Vec<String>,
+    /// Milliseconds left for White and Black
+    pub wtime: u64,
+    pub btime: u64,
+    /// "started" while the game is on, otherwise how it ended
+    pub status: String,
+    pub winner: Option<Color>,
+}
+
+impl BoardState {
+    pub fn is_over(&self) -> bool {
+        !matches!(self.status.as_str(), "created" | "started")
+    }
+
+    /// How a finished game ended, e.g. "White wins by resignation".
+    pub fn outcome(&self) -> String {
+        let reason = match self.status.as_str() {
+            "mate" => "checkmate",
+            "resign" => "resignation",
+            "outoftime" => "time",
+            "timeout" => "abandonment",
+            "stalemate" => return "Draw by stalemate".to_string(),
+            "draw" => return "Draw".to_string(),
+            "aborted" => return "Game aborted".to_string(),
+            status => status,
+        };
+        match self.winner {
+            Some(Color::White) => format!("White wins by {}", reason),
+            Some(Color::Black) => format!("Black wins by {}", reason),
+            None => format!("Game over ({})", reason),
+        }
+    }
+}
+
+/// A message on a game's stream.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    /// Sent first: the players, the starting position and the state so far
+    Full {
+        white: String,
+        black: String,
+        rated: bool,
+        /// One side is the Lichess AI
+        against_ai: bool,
+        /// None for the standard starting position
+        initial_fen: Option<String>,
+        control: Option<TimeControl>,
+        state: BoardState,
+    },
+    State(BoardState),
+}
+
+/// A Lichess account, used through the Board API with a personal access
+/// token that has the `board:play` scope.
+#[derive(Clone)]
+pub struct LichessClient {
+    token: String,
+}
+
+impl LichessClient {
+    pub fn new(token: &str) -> Self {
+        LichessClient {
+            token: token.trim().to_string(),
+        }
+    }
+
+    /// The account's user name; fails if the token isn't valid.
+    pub fn username(&self) -> Result<String, AppError> {
+        let account: Value = serde_json::from_str(&self.request("GET", "/account", &[])?)?;
+        account["username"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| AppError::Network("Lichess didn't return an account".to_string()))
+    }
+
+    /// Incoming challenges and game starts, as they happen.
+    pub fn stream_events(&self) -> Result<LineStream, AppError> {
+        self.stream("GET", "/stream/event", &[])
+    }
+
+    /// Moves and clocks of a game, as they happen.
+    pub fn stream_game(&self, game_id: &str) -> Result<LineStream, AppError> {
+        self.stream("GET", &format!("/board/game/stream/{}", game_id), &[])
+    }
+
+    /// Look for an opponent in the lobby.  The seek stays open until the
+    /// returned stream is dropped or a game starts.
+    pub fn seek(&self, control: TimeControl, rated: bool) -> Result<LineStream, AppError> {
+        let mut form = vec![("rated", rated.to_string())];
+        form.extend(clock_form(control, "time", "increment", 60_000));
+        self.stream("POST", "/board/seek", &form)
+    }
+
+    pub fn challenge(&self, username: &str, control: TimeControl, rated: bool) -> Result<(), AppError> {
+        let mut form = vec![("rated", rated.to_string())];
+        form.extend(clock_form(control, "clock.limit", "clock.increment", 1000));
+        self.request("POST", &format!("/challenge/{}", username.trim()), &form)?;
+        Ok(())
+    }
+
+    /// Play the Lichess AI at `level` (1-8).
+    pub fn challenge_ai(&self, level: u8, control: TimeControl) -> Result<(), AppError> {
+        let mut form = vec![("level", level.clamp(1, 8).to_string())];
+        form.extend(clock_form(control, "clock.limit", "clock.increment", 1000));
+        self.request("POST", "/challenge/ai", &form)?;
+        Ok(())
+    }
+
+    pub fn accept(&self, challenge_id: &str) -> Result<(), AppError> {
+        self.request("POST", &format!("/challenge/{}/accept", challenge_id), &[])?;
+        Ok(())
+    }
+
+    pub fn decline(&self, challenge_id: &str) -> Result<(), AppError> {
+        self.request("POST", &format!("/challenge/{}/decline", challenge_id), &[])?;
+        Ok(())
+    }
+
+    pub fn make_move(&self, game_id: &str, uci_move: &str) -> Result<(), AppError> {
+        self.request("POST", &format!("/board/game/{}/move/{}", game_id, uci_move), &[])?;
+        Ok(())
+    }
+
+    pub fn resign(&self, game_id: &str) -> Result<(), AppError> {
+        self.request("POST", &format!("/board/game/{}/resign", game_id), &[])?;
+        Ok(())
+    }
+
+    fn request(&self, method: &str, path: &str, form: &[(&str, String)]) -> Result<String, AppError> {
+        let fields = form_fields(form);
+        let url = format!("{}{}", API, path);
+        curl(&curl_args(method, &fields, &url), Some(&self.auth_config()))
+    }
+
+    fn stream(&self, method: &str, path: &str, form: &[(&str, String)]) -> Result<LineStream, AppError> {
+        let fields = form_fields(form);
+        let url = format!("{}{}", API, path);
+        curl_stream(&curl_args(method, &fields, &url), Some(&self.auth_config()))
+    }
+
+    // The token goes to curl as a config file on standard input, so it
+    // never shows up in the process list
+    fn auth_config(&self) -> String {
+        format!("header = \"Authorization: Bearer {}\"\n", self.token)
+    }
+}
+
+fn form_fields(form: &[(&str, String)]) -> Vec<String> {
+    form.iter().map(|(name, value)| format!("{}={}", name, value)).collect()
+}
+
+fn curl_args<'a>(method: &'a str, fields: &'a [String], url: &'a str) -> Vec<&'a str> {
+    let mut args = vec!["--config", "-", "-X", method];
+    for field in fields {
+        args.extend(["--data-urlencode", field.as_str()]);
+    }
+    args.push(url);
+    args
+}
+
+// Base time and increment as form fields; the base in `base_unit_ms`
+// units, the increment in seconds
+fn clock_form(
+    control: TimeControl,
+    base: &'static str,
+    increment: &'static str,
+    base_unit_ms: u64,
+) -> [(&'static str, String); 2] {
+    let base_value = control.base_ms as f64 / base_unit_ms as f64;
+    [
+        (base, format!("{}", base_value)),
+        (increment, (control.increment_ms / 1000).to_string()),
+    ]
+}
+
+/// Read a time control in the usual "minutes+increment" form, e.g. "5+3"
+/// or "0.5+0".
+pub fn parse_control(text: &str) -> Option<TimeControl> {
+    let (minutes, increment) = text.trim().split_once('+').unwrap_or((text.trim(), "0"));
+    let minutes: f64 = minutes.trim().parse().ok()?;
+    let increment: u64 = increment.trim().parse().ok()?;
+    (minutes > 0.0 && minutes.is_finite()).then(|| TimeControl {
+        base_ms: (minutes * 60_000.0).round() as u64,
+        increment_ms: increment * 1000,
+        mode: ClockMode::Increment,
+    })
+}
+
+/// A time control in the "minutes+increment" form Lichess shows.
+pub fn format_control(control: TimeControl) -> String {
+    format!("{}+{}", control.base_ms as f64 / 60_000.0, control.increment_ms / 1000)
+}
+
+/// Read a line of the account event stream; None for events of no
+/// interest here.
+pub fn parse_account_event(line: &str, username: &str) -> Option<AccountEvent> {
+    let event: Value = serde_json::from_str(line).ok()?;
+    match event["type"].as_str()? {
+        "challenge" => {
+            let challenge = &event["challenge"];
+            let challenger = challenge["challenger"]["name"].as_str()?;
+            // Our own outgoing challenges show up too
+            if challenger.eq_ignore_ascii_case(username) {
+                return None;
+            }
+            let rated = if challenge["rated"].as_bool() == Some(true) { "rated" } else { "casual" };
+            let control = challenge["timeControl"]["show"].as_str().unwrap_or("unlimited");
+            Some(AccountEvent::Challenge(IncomingChallenge {
+                id: challenge["id"].as_str()?.to_string(),
+                challenger: challenger.to_string(),
+                description: format!("{} {}", control, rated),
+            }))
+        }
+        "challengeCanceled" | "challengeDeclined" => {
+            Some(AccountEvent::ChallengeGone(event["challenge"]["id"].as_str()?.to_string()))
+        }
+        "gameStart" => {
+            let game = &event["game"];
+            let id = game["gameId"].as_str().or_else(|| game["id"].as_str())?;
+            let color = if game["color"].as_str()? == "black" { Color::Black } else { Color::White };
+            Some(AccountEvent::GameStart {
+                id: id.to_string(),
+                color,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Read a line of a game stream; None for chat and other messages.
+pub fn parse_game_event(line: &str) -> Option<GameEvent> {
+    let event: Value = serde_json::from_str(line).ok()?;
+    match event["type"].as_str()? {
+        "gameFull" => {
+            let player = |side: &Value| {
+                side["name"]
+                    .as_str()
+                    .map(str::to_string)
+                    .or_else(|| side["aiLevel"].as_u64().map(|level| format!("Lichess AI level {}", level)))
+                    .unwrap_or_else(|| "Anonymous".to_string())
+            };
+            let initial_fen = event["initialFen"].as_str().filter(|fen| *fen != "startpos");
+            let control = match (event["clock"]["initial"].as_u64(), event["clock"]["increment"].as_u64()) {
+                (Some(base_ms), Some(increment_ms)) if base_ms > 0 => Some(TimeControl {
+                    base_ms,
+                    increment_ms,
+                    mode: ClockMode::Increment,
+                }),
+                _ => None,
+            };
+            Some(GameEvent::Full {
+                white: player(&event["white"]),
+                black: player(&event["black"]),
+                rated: event["rated"].as_bool().unwrap_or(false),
+                against_ai: event["white"]["aiLevel"].is_u64() || event["black"]["aiLevel"].is_u64(),
+                initial_fen: initial_fen.map(str::to_string),
+                control,
+                state: parse_state(&event["state"])?,
+            })
+        }
+        "gameState" => Some(GameEvent::State(parse_state(&event)?)),
+        _ => None,
+    }
+}
+
+fn parse_state(state: &Value) -> Option<BoardState> {
+    let winner = match state["winner"].as_str() {
+        Some("white") => Some(Color::White),
+        Some("black") => Some(Color::Black),
+        _ => None,
+    };
+    Some(BoardState {
+        moves: state["moves"].as_str()?.split_whitespace().map(str::to_string).collect(),
+        wtime: state["wtime"].as_u64().unwrap_or(0),
+        btime: state["btime"].as_u64().unwrap_or(0),
+        status: state["status"].as_str().unwrap_or("started").to_string(),
+        winner,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_streams() {
+        let challenge = r#"{"type":"challenge","challenge":{"id":"ch1","challenger":{"name":"Alice"},
+            "rated":true,"timeControl":{"type":"clock","show":"5+3"}}}"#
+            .replace('\n', "");
+        assert_eq!(
+            parse_account_event(&challenge, "bob"),
+            Some(AccountEvent::Challenge(IncomingChallenge {
+                id: "ch1".to_string(),
+                challenger: "Alice".to_string(),
+                description: "5+3 rated".to_string(),
+            }))
+        );
+        assert_eq!(parse_account_event(&challenge, "alice"), None);
+        assert_eq!(
+            parse_account_event(r#"{"type":"gameStart","game":{"gameId":"g1","color":"black"}}"#, "bob"),
+            Some(AccountEvent::GameStart {
+                id: "g1".to_string(),
+                color: Color::Black,
+            })
+        );
+
+        let full = r#"{"type":"gameFull","id":"g1","rated":false,"clock":{"initial":300000,"increment":3000},
+            "white":{"name":"Alice"},"black":{"aiLevel":3},"initialFen":"startpos",
+            "state":{"moves":"e2e4 e7e5","wtime":298000,"btime":300000,"status":"started"}}"#
+            .replace('\n', "");
+        let Some(GameEvent::Full { white, black, initial_fen, control, state, .. }) = parse_game_event(&full)
+        else {
+            panic!("gameFull not parsed");
+        };
+        assert_eq!((white.as_str(), black.as_str()), ("Alice", "Lichess AI level 3"));
+        assert_eq!(initial_fen, None);
+        assert_eq!(control.map(|c| c.to_string()).as_deref(), Some("300+3"));
+        assert_eq!(state.moves, ["e2e4", "e7e5"]);
+        assert!(!state.is_over());
+
+        let over = r#"{"type":"gameState","moves":"e2e4","wtime":1,"btime":2,"status":"resign",
+            "winner":"white"}"#
+            .replace('\n', "");
+        let Some(GameEvent::State(state)) = parse_game_event(&over) else {
+            panic!("gameState not parsed");
+        };
+        assert!(state.is_over());
+        assert_eq!(state.outcome(), "White wins by resignation");
+        assert_eq!(parse_game_event(r#"{"type":"chatLine","text":"hi"}"#), None);
+    }
+
+    #[test]
+    fn test_time_controls() {
+        let control = TimeControl {
+            base_ms: 180_000,
+            increment_ms: 2_000,
+            mode: ClockMode::Increment,
+        };
+        assert_eq!(
+            clock_form(control, "time", "increment", 60_000),
+            [("time", "3".to_string()), ("increment", "2".to_string())]
+        );
+        assert_eq!(clock_form(control, "clock.limit", "clock.increment", 1000)[0].1, "180");
+        assert_eq!(parse_control("3+2"), Some(control));
+        assert_eq!(parse_control(" 0.5 ").map(|c| c.base_ms), Some(30_000));
+        assert_eq!(parse_control("fast"), None);
+        assert_eq!(format_control(control), "3+2");
+    }
+}
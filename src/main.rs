@@ -1,24 +1,129 @@
+mod adaptive;
+mod analysis;
+mod book;
+mod board_view;
+mod chess960;
+mod clock;
+mod database;
+mod diagram;
+mod eco;
+mod famous;
+mod fen;
+mod endgames;
 mod engine;
+mod engine_match;
 mod error;
 mod game;
+mod i18n;
+mod lichess;
+mod odds;
+mod online;
+mod pacing;
+mod palette;
+mod perft;
+mod persistence;
+mod personality;
+mod pgn;
+mod power;
+mod rating;
+mod rating_graph;
+mod repertoire;
+mod scoresheet;
+mod session;
+mod settings;
+mod stats;
 mod ui;
+mod variation;
 
 use std::{
     collections::HashMap,
-    path::PathBuf,
-    str::FromStr,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use iced::{
-    executor, window, Application, Command, Element, Event, Settings, Size, Subscription, Theme,
+    event, executor, keyboard, window, Application, Command, Element, Event, Settings, Size,
+    Subscription, Theme,
 };
 
-use crate::engine::ChessEngine;
-use crate::game::{ChessGame, PromotionPiece};
-use crate::ui::ChessUI;
+use crate::adaptive::{AdaptiveStrength, Strength};
+use crate::analysis::{MoveEvaluation, PositionEval};
+use crate::book::Book;
+use crate::board_view::Slide;
+use crate::clock::{ClockMode, TimeControl};
+use crate::database::{GameDatabase, GameFilter, StoredGame, StoredPuzzle, StoredSession};
+use crate::diagram::DiagramOptions;
+use crate::eco::OpeningLine;
+use crate::endgames::{Endgame, EndgameOutcome, ENDGAMES};
+use crate::engine::{
+    ChessEngine, EngineId, Resources, SearchInfo, SearchLimit, UciLine, UciLog, EVAL_FILE,
+};
+use crate::engine_match::{Adjudication, MatchConfig};
+use crate::error::AppError;
+use crate::fen::safe_parse_board;
+use crate::game::{ChessGame, ClockTimes, GameSnapshot, MoveAnnotation, PromotionPiece};
+use crate::i18n::{fill, Language, Strings};
+use crate::lichess::{AccountEvent, BoardState, GameEvent, IncomingChallenge, LichessClient};
+use crate::odds::HANDICAPS;
+use crate::online::LineStream;
+use crate::palette::Palette;
+use crate::persistence::{EngineSetup, SavedClock, SavedGame};
+use crate::personality::Personality;
+use crate::pgn::PgnGame;
+use crate::power::PowerSaver;
+use crate::rating::PlayerRating;
+use crate::repertoire::{Repertoire, RepertoireTrainer, ReviewSchedule};
+use crate::session::{Session, SessionGame};
+use crate::settings::{BoardSettings, UserSettings, WindowGeometry};
+use crate::stats::{LevelStats, Outcome};
+use crate::ui::{
+    AppInfo, Candidate, ChessUI, ConsoleInfo, DrillInfo, EngineFailure, KibitzerInfo, LobbyInfo,
+    OnlineInfo,
+};
+
+/// Minimum time between two autosaves of an in-progress game
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Lines searched when the engine varies its moves
+const VARIETY_LINES: u32 = 4;
+
+/// Depth the kibitzer searches each position of an online game to
+const KIBITZ_DEPTH: u32 = 16;
+
+/// Lines each kibitzer reports, and how many moves of each are shown
+const KIBITZER_LINES: u32 = 3;
+const KIBITZER_PLIES: usize = 10;
+
+/// Lines searched for the opponent's threats, how deep, and how far behind
+/// the strongest a threat may score and still be shown
+const THREAT_LINES: u32 = 3;
+const THREAT_DEPTH: u32 = 12;
+const THREAT_MARGIN: i32 = 100;
+
+/// What the background analysis engine gets: it shouldn't slow down the
+/// engine playing the game
+const BACKGROUND_RESOURCES: Resources = Resources { threads: 1, hash_mb: 16 };
+
+/// Candidate moves the tutor shows, and how deep it searches for them
+const CANDIDATE_LINES: u32 = 3;
+const CANDIDATE_DEPTH: u32 = 14;
+
+/// How often the power saver looks whether the computer is on battery,
+/// the longest the engine thinks while it saves power, and how often the
+/// window refreshes then instead of every 100 ms
+const POWER_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const POWER_SAVER_THINK_MS: u64 = 1000;
+const POWER_SAVER_TICK: Duration = Duration::from_millis(500);
+
+/// Lines the UCI console shows at once; older matches stay in the log
+const CONSOLE_LINES: usize = 300;
+
+/// How long past its move time the engine may go without a bestmove
+/// before it is reported as stuck
+const ENGINE_GRACE: Duration = Duration::from_secs(10);
 
 // ─── Position Setup State ─────────────────────────────────────────────────────
 
@@ -31,14 +136,502 @@ pub struct SetupState {
     pub castle_bk: bool,
     pub castle_bq: bool,
     pub en_passant_file: Option<chess::File>,
+    /// Halfmoves since the last capture or pawn move, for the 50-move rule
+    pub halfmove_clock: u32,
+    /// Number of the next move; 0 while the field is being retyped, and
+    /// written as 1
+    pub fullmove_number: u32,
+    /// Castling follows Chess960 rules: the king may start on any file
+    pub chess960: bool,
     pub fen_string: String,
     pub fen_error: Option<String>,
     pub player_color: chess::Color,
+    /// A game pasted from the clipboard to take a position from
+    pub pgn: Option<String>,
+    /// The move of that game to set up, e.g. "23b"; blank for its end
+    pub pgn_move: String,
+    /// Which position of the pasted game is shown, or why none could be
+    pub pgn_status: Option<String>,
+    /// Men for the random endgame button, e.g. "KRP vs KR"
+    pub endgame_material: String,
+}
+
+// ─── Game Browser State ───────────────────────────────────────────────────────
+
+pub struct BrowserState {
+    pub games: Vec<StoredGame>,
+    pub error: Option<String>,
+    /// Lichess or Chess.com game link to import
+    pub import_url: String,
+    /// A download is running
+    pub importing: bool,
+    pub import_error: Option<String>,
+    /// Path of a game file to resume
+    pub resume_path: String,
+    pub resume_error: Option<String>,
+    /// Which games are listed
+    pub filter: GameFilter,
+    /// Tag typed in for adding to games
+    pub new_tag: String,
+}
+
+impl BrowserState {
+    pub fn load(database: Option<&GameDatabase>, t: &Strings) -> Self {
+        let (games, error) = match database.map(|db| db.list_games()) {
+            Some(Ok(games)) => (games, None),
+            Some(Err(e)) => (Vec::new(), Some(e.to_string())),
+            None => (Vec::new(), Some(t.database_unavailable.to_string())),
+        };
+        BrowserState {
+            games,
+            error,
+            import_url: String::new(),
+            importing: false,
+            import_error: None,
+            resume_path: String::new(),
+            resume_error: None,
+            filter: GameFilter::default(),
+            new_tag: String::new(),
+        }
+    }
+
+    /// The games the filter lets through, most recent first.
+    pub fn shown_games(&self) -> impl Iterator<Item = &StoredGame> {
+        self.games.iter().filter(|game| self.filter.matches(game))
+    }
+
+    /// Every tag in use, in alphabetical order.
+    pub fn all_tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> =
+            self.games.iter().flat_map(|game| &game.tags).map(String::as_str).collect();
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+}
+
+// ─── Statistics State ─────────────────────────────────────────────────────────
+
+pub struct StatsState {
+    pub levels: Vec<LevelStats>,
+    pub error: Option<String>,
+    pub adaptive: AdaptiveStrength,
+    /// The engine's current strength setting
+    pub strength: Strength,
+    pub rating: PlayerRating,
+}
+
+impl StatsState {
+    pub fn load(
+        database: Option<&GameDatabase>,
+        adaptive: &AdaptiveStrength,
+        strength: Strength,
+        rating: &PlayerRating,
+        t: &Strings,
+    ) -> Self {
+        let browser = BrowserState::load(database, t);
+        StatsState {
+            levels: stats::compute(&browser.games),
+            error: browser.error,
+            adaptive: adaptive.clone(),
+            strength,
+            rating: rating.clone(),
+        }
+    }
+}
+
+// ─── Session State ────────────────────────────────────────────────────────────
+
+pub struct SessionState {
+    pub session: Session,
+    /// Earlier sessions, most recent first
+    pub past: Vec<StoredSession>,
+    pub error: Option<String>,
+    /// Shown as the window closes; closing again quits
+    pub quitting: bool,
+}
+
+impl SessionState {
+    pub fn load(database: Option<&GameDatabase>, session: &Session, quitting: bool) -> Self {
+        let (past, error) = match database.map(|db| db.list_sessions()) {
+            Some(Ok(sessions)) => (
+                sessions
+                    .into_iter()
+                    .filter(|stored| Some(stored.id) != session.id)
+                    .take(10)
+                    .collect(),
+                None,
+            ),
+            Some(Err(e)) => (Vec::new(), Some(e.to_string())),
+            None => (Vec::new(), None),
+        };
+        SessionState {
+            session: session.clone(),
+            past,
+            error,
+            quitting,
+        }
+    }
+}
+
+// ─── Repertoire State ─────────────────────────────────────────────────────────
+
+pub struct RepertoireState {
+    pub repertoire: Repertoire,
+    /// Path of a PGN file to import
+    pub import_path: String,
+    /// Side the imported lines are prepared for
+    pub import_color: chess::Color,
+    pub schedule: ReviewSchedule,
+    /// Positions due for review, White's then Black's
+    pub due: [usize; 2],
+    pub status: Option<String>,
+    pub error: Option<String>,
+}
+
+impl RepertoireState {
+    pub fn load() -> Self {
+        let mut state = RepertoireState {
+            repertoire: repertoire::load_repertoire(),
+            import_path: String::new(),
+            import_color: chess::Color::White,
+            schedule: repertoire::load_schedule(),
+            due: [0; 2],
+            status: None,
+            error: None,
+        };
+        state.count_due();
+        state
+    }
+
+    fn count_due(&mut self) {
+        for color in [chess::Color::White, chess::Color::Black] {
+            self.due[color.to_index()] =
+                RepertoireTrainer::new(&self.repertoire, color, self.schedule.clone()).due_count();
+        }
+    }
+
+    fn import(&mut self, t: &Strings) {
+        self.status = None;
+        self.error = None;
+        let result = std::fs::read_to_string(self.import_path.trim())
+            .map_err(AppError::from)
+            .and_then(|text| self.repertoire.import_pgn(&text, self.import_color))
+            .and_then(|added| {
+                repertoire::save_repertoire(&self.repertoire)?;
+                Ok(added)
+            });
+        match result {
+            Ok(added) => {
+                let template = if added == 1 { t.imported_line } else { t.imported_lines };
+                self.status = Some(fill(&fill(template, added), color_name(t, self.import_color)))
+            }
+            Err(e) => self.error = Some(e.to_string()),
+        }
+        self.count_due();
+    }
+}
+
+// ─── Puzzle State ─────────────────────────────────────────────────────────────
+
+pub struct PuzzleState {
+    pub puzzles: Vec<StoredPuzzle>,
+    pub error: Option<String>,
+}
+
+impl PuzzleState {
+    pub fn load(database: Option<&GameDatabase>, t: &Strings) -> Self {
+        match database.map(GameDatabase::list_puzzles) {
+            Some(Ok(puzzles)) => PuzzleState { puzzles, error: None },
+            Some(Err(e)) => PuzzleState {
+                puzzles: Vec::new(),
+                error: Some(e.to_string()),
+            },
+            None => PuzzleState {
+                puzzles: Vec::new(),
+                error: Some(t.database_unavailable.to_string()),
+            },
+        }
+    }
+}
+
+// ─── Settings State ───────────────────────────────────────────────────────────
+
+/// The New Game menu's choices, used when a game is picked.
+pub struct NewGameState {
+    pub player_color: chess::Color,
+    /// Time control typed in as SECONDS[+SECONDS], empty for no clocks
+    pub clock: String,
+    /// The engine's time control for time odds, empty to match the player's
+    pub engine_clock: String,
+    pub clock_mode: ClockMode,
+    pub clock_error: Option<String>,
+    /// No takebacks or engine help, and the result always counts
+    pub rated: bool,
+    /// Text typed to find an opening to start from
+    pub opening_query: String,
+    /// Opening a standard game starts from, its moves already played
+    pub opening: Option<OpeningLine>,
+}
+
+impl NewGameState {
+    /// Start from the current game's colour, clocks and mode.
+    pub fn new(
+        player_color: chess::Color,
+        control: Option<TimeControl>,
+        odds: Option<TimeControl>,
+        rated: bool,
+    ) -> Self {
+        // The mode has buttons of its own
+        let typed = |control: Option<TimeControl>| {
+            control
+                .map(|control| TimeControl { mode: ClockMode::Increment, ..control }.to_string())
+                .unwrap_or_default()
+        };
+        NewGameState {
+            player_color,
+            clock: typed(control),
+            engine_clock: typed(odds),
+            clock_mode: control.map_or(ClockMode::Increment, |control| control.mode),
+            clock_error: None,
+            rated,
+            opening_query: String::new(),
+            opening: None,
+        }
+    }
+
+    /// The player's time control, None for a game without clocks, and the
+    /// engine's if it gets different time.
+    pub fn time_controls(&self) -> Result<(Option<TimeControl>, Option<TimeControl>), String> {
+        let parse = |clock: &str| -> Result<Option<TimeControl>, String> {
+            if clock.trim().is_empty() {
+                return Ok(None);
+            }
+            let mut control: TimeControl = clock.trim().parse()?;
+            // "300d2" asks for a delay whatever the buttons say
+            if control.mode == ClockMode::Increment {
+                control.mode = self.clock_mode;
+            }
+            Ok(Some(control))
+        };
+        match parse(&self.clock)? {
+            Some(control) => Ok((Some(control), parse(&self.engine_clock)?)),
+            None => Ok((None, None)),
+        }
+    }
+}
+
+/// Values being edited on the Settings screen; nothing changes until they
+/// are saved.
+pub struct SettingsState {
+    pub resources: Resources,
+    pub board: BoardSettings,
+    pub language: Language,
+    pub ui_scale: f64,
+    /// Slider limits: the machine's cores, and half its memory for the hash
+    pub max_threads: u32,
+    pub max_hash_mb: u32,
+    /// Opponent style picked, and the names of all on offer
+    pub personality: Option<String>,
+    pub personalities: Vec<String>,
+    /// Path typed in for the engine's NNUE network, empty for its own
+    pub eval_file: String,
+    /// Why the eval file was refused
+    pub eval_file_error: Option<String>,
+    /// Evaluate positions with a second engine during play
+    pub background_analysis: bool,
+    /// Stop analysis engines while the window is in the background
+    pub pause_unfocused: bool,
+    /// When to run in the low-power profile
+    pub power_saver: PowerSaver,
+    /// Path typed in for the engine's book, empty for none
+    pub engine_book: String,
+    /// Why the book couldn't be read
+    pub engine_book_error: Option<String>,
+    /// Weigh book moves by how the engine did with them
+    pub book_learning: bool,
+}
+
+impl SettingsState {
+    pub fn new(resources: Resources, board: BoardSettings, language: Language, ui_scale: f64) -> Self {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get() as u32);
+        let memory = engine::total_memory_mb().map_or(4096, |mb| mb as u32);
+        SettingsState {
+            resources,
+            board,
+            language,
+            ui_scale,
+            max_threads: cores.max(resources.threads),
+            max_hash_mb: (memory / 2).max(16).max(resources.hash_mb),
+            personality: None,
+            personalities: Vec::new(),
+            eval_file: String::new(),
+            eval_file_error: None,
+            background_analysis: false,
+            pause_unfocused: false,
+            power_saver: PowerSaver::Off,
+            engine_book: String::new(),
+            engine_book_error: None,
+            book_learning: false,
+        }
+    }
+}
+
+// ─── Online Play State ────────────────────────────────────────────────────────
+
+/// The Online screen's form: the Lichess token and how to find a game.
+pub struct OnlineState {
+    pub token: String,
+    /// Time control for seeks and challenges, as "minutes+increment"
+    pub control: String,
+    pub rated: bool,
+    /// Lichess user to challenge
+    pub opponent: String,
+    /// Strength of the Lichess AI, 1-8
+    pub ai_level: u8,
+    /// Checking the token with Lichess
+    pub connecting: bool,
+    pub status: Option<String>,
+    pub error: Option<String>,
+}
+
+impl OnlineState {
+    pub fn new(token: String) -> Self {
+        OnlineState {
+            token,
+            control: "10+5".to_string(),
+            rated: false,
+            opponent: String::new(),
+            ai_level: 3,
+            connecting: false,
+            status: None,
+            error: None,
+        }
+    }
+}
+
+/// A Lichess account the app is logged in to.
+pub struct LichessSession {
+    client: LichessClient,
+    username: String,
+    /// Incoming challenges and game starts
+    events: LineStream,
+    challenges: Vec<IncomingChallenge>,
+    /// Open seek in the lobby; dropping it cancels the seek
+    seek: Option<LineStream>,
+    game: Option<OnlineGame>,
+}
+
+/// A Lichess game shown on the board.  It stays until the player leaves it
+/// after it ends.
+pub struct OnlineGame {
+    id: String,
+    color: chess::Color,
+    /// Moves and clocks; None once Lichess has closed it
+    stream: Option<LineStream>,
+    opponent: String,
+    rated: bool,
+    against_ai: bool,
+    control: Option<TimeControl>,
+    start_fen: String,
+    /// The start position, parsed once when Lichess sends it, or why it
+    /// can't be played from
+    start: Result<chess::Board, String>,
+    /// Latest state from Lichess, and when it arrived so the clock of the
+    /// side to move can run down between updates
+    state: Option<BoardState>,
+    received: Instant,
+    /// The local engine evaluates positions as they arrive; only allowed
+    /// against the Lichess AI
+    kibitz: bool,
+    kibitz_eval: Option<String>,
+}
+
+impl OnlineGame {
+    fn is_over(&self) -> bool {
+        self.state.as_ref().is_some_and(BoardState::is_over)
+    }
+
+    /// Lichess' clocks, the side to move's counting down since the last
+    /// update.  Clocks only start once both sides have moved.
+    fn clock_times(&self) -> Option<ClockTimes> {
+        let state = self.state.as_ref()?;
+        let first_mover = self.start.as_ref().map_or(chess::Color::White, |board| board.side_to_move());
+        let to_move = if state.moves.len() % 2 == 0 { first_mover } else { !first_mover };
+        let running = (state.moves.len() >= 2 && !state.is_over()).then_some(to_move);
+        let left = |side: chess::Color, ms: u64| {
+            let time = Duration::from_millis(ms);
+            Some(if running == Some(side) { time.saturating_sub(self.received.elapsed()) } else { time })
+        };
+        Some(ClockTimes {
+            white: left(chess::Color::White, state.wtime),
+            black: left(chess::Color::Black, state.btime),
+            running,
+        })
+    }
+}
+
+/// A puzzle being attempted on the board.
+pub struct PuzzleSession {
+    pub puzzle: StoredPuzzle,
+    pub solved: bool,
+}
+
+/// An endgame exercise being played against the engine.
+pub struct EndgameSession {
+    pub endgame: &'static Endgame,
+    /// Set once the exercise has been converted or botched
+    pub outcome: Option<EndgameOutcome>,
+}
+
+/// Training session that takes over the opponent's side of the board.
+pub enum Drill {
+    Repertoire(RepertoireTrainer),
+    Puzzle(PuzzleSession),
+    Endgame(EndgameSession),
+}
+
+/// The kinds of game the New Game screen can start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameKind {
+    Standard,
+    Chess960,
+    /// Index into `odds::HANDICAPS`
+    Odds(usize),
+}
+
+fn color_name(t: &Strings, color: chess::Color) -> &'static str {
+    if color == chess::Color::White {
+        t.white
+    } else {
+        t.black
+    }
+}
+
+fn piece_name(t: &Strings, piece: chess::Piece) -> &'static str {
+    match piece {
+        chess::Piece::Pawn => t.pawn,
+        chess::Piece::Knight => t.knight,
+        chess::Piece::Bishop => t.bishop,
+        chess::Piece::Rook => t.rook,
+        chess::Piece::Queen => t.queen,
+        chess::Piece::King => t.king,
+    }
 }
 
 pub enum AppScreen {
     Game,
     Setup(SetupState),
+    Browser(BrowserState),
+    Statistics(StatsState),
+    Session(SessionState),
+    Repertoire(RepertoireState),
+    Puzzles(PuzzleState),
+    Endgames,
+    FamousGames,
+    NewGame(NewGameState),
+    Settings(SettingsState),
+    Online(OnlineState),
 }
 
 impl SetupState {
@@ -90,13 +683,20 @@ impl SetupState {
             castle_bk,
             castle_bq,
             en_passant_file,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            chess960: false,
             fen_string: fen,
             fen_error: None,
             player_color,
+            pgn: None,
+            pgn_move: String::new(),
+            pgn_status: None,
+            endgame_material: "KRP vs KR".to_string(),
         }
     }
 
-    pub fn rebuild_fen(&mut self) {
+    pub fn rebuild_fen(&mut self, t: &Strings) {
         let mut placement = String::new();
         for rank_idx in (0..8usize).rev() {
             let mut empty: u8 = 0;
@@ -139,9 +739,44 @@ impl SetupState {
 
         let stm = if self.side_to_move == chess::Color::White { "w" } else { "b" };
 
-        // Only include a castling right in the FEN when the relevant king and
-        // rook are still on their starting squares; otherwise the chess crate
-        // will reject the position as structurally invalid.
+        let castling = if self.chess960 {
+            chess960::castling_field(
+                |sq| self.pieces.get(&sq).copied(),
+                [
+                    (chess::Color::White, self.castle_wk, self.castle_wq),
+                    (chess::Color::Black, self.castle_bk, self.castle_bq),
+                ],
+            )
+        } else {
+            self.standard_castling()
+        };
+
+        let ep = match self.en_passant_file {
+            Some(file) => {
+                let file_char = (b'a' + file.to_index() as u8) as char;
+                let rank_char = if self.side_to_move == chess::Color::White { '6' } else { '3' };
+                format!("{}{}", file_char, rank_char)
+            }
+            None => "-".to_string(),
+        };
+
+        self.fen_string = format!(
+            "{} {} {} {} {} {}",
+            placement,
+            stm,
+            castling,
+            ep,
+            self.halfmove_clock,
+            self.fullmove_number.max(1)
+        );
+
+        self.fen_error = safe_parse_board(&self.playable_fen(), t).err();
+    }
+
+    // Only include a castling right in the FEN when the relevant king and
+    // rook are still on their starting squares; otherwise the chess crate
+    // will reject the position as structurally invalid.
+    fn standard_castling(&self) -> String {
         let sq = |rank: usize, file: usize| {
             chess::Square::make_square(
                 chess::Rank::from_index(rank),
@@ -164,24 +799,81 @@ impl SetupState {
         if eff_bk { castling.push('k'); }
         if eff_bq { castling.push('q'); }
         if castling.is_empty() { castling.push('-'); }
+        castling
+    }
 
-        let ep = match self.en_passant_file {
-            Some(file) => {
-                let file_char = (b'a' + file.to_index() as u8) as char;
-                let rank_char = if self.side_to_move == chess::Color::White { '6' } else { '3' };
-                format!("{}{}", file_char, rank_char)
+    /// The FEN to start a game from, minus any Chess960 castling rights the
+    /// move generator can't play.
+    pub fn playable_fen(&self) -> String {
+        if self.chess960 {
+            chess960::playable_fen(&self.fen_string)
+        } else {
+            self.fen_string.clone()
+        }
+    }
+
+    /// Set up the position at `pgn_move` of the pasted game, handing the
+    /// move to the player so they can look for a better one.
+    pub fn load_pgn(&mut self, t: &Strings) {
+        let Some(text) = self.pgn.as_deref() else {
+            return;
+        };
+        let games = pgn::parse_pgn(text).ok().unwrap_or_default();
+        let Some(pgn_game) = games.first() else {
+            self.pgn_status = Some(t.no_pgn_game.to_string());
+            return;
+        };
+        let tag = |name: &str| {
+            pgn_game
+                .tags
+                .iter()
+                .find(|(tag, _)| tag == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let start_fen = tag("FEN").map_or_else(|| chess::Board::default().to_string(), str::to_string);
+        let moves: Vec<String> = pgn_game
+            .lines
+            .first()
+            .map(|line| line.iter().map(|m| m.to_string()).collect())
+            .unwrap_or_default();
+        let mut replay = ChessGame::new();
+        let played = match replay.load_moves(&start_fen, &moves, chess::Color::White) {
+            Ok(played) => played,
+            Err(e) => {
+                self.pgn_status = Some(e);
+                return;
             }
-            None => "-".to_string(),
         };
 
-        self.fen_string = format!("{} {} {} {} 0 1", placement, stm, castling, ep);
-
-        self.fen_error = safe_parse_board(&self.fen_string).err();
+        let ply = match self.pgn_move.trim() {
+            "" => played,
+            spec => match game::find_ply(replay.get_move_records(), spec) {
+                Some(ply) => ply,
+                None => {
+                    self.pgn_status = Some(fill(t.no_such_move, spec));
+                    return;
+                }
+            },
+        };
+        let Some(fen) = replay.fen_after(ply) else {
+            return;
+        };
+        self.parse_fen_to_state(&fen, t);
+        self.player_color = self.side_to_move;
+        let white = fill(t.pgn_position, tag("White").unwrap_or("?"));
+        let players = fill(&white, tag("Black").unwrap_or("?"));
+        self.pgn_status = Some(fill(&fill(&players, ply), played));
     }
 
-    pub fn parse_fen_to_state(&mut self, fen: &str) {
+    pub fn parse_fen_to_state(&mut self, fen: &str, t: &Strings) {
         self.fen_string = fen.to_string();
-        match safe_parse_board(fen) {
+        // Shredder-FEN names castling rooks by file, which only Chess960 needs
+        if let Some(castling) = fen.split_whitespace().nth(2) {
+            if castling.chars().any(|c| matches!(c, 'A'..='H' | 'a'..='h')) {
+                self.chess960 = true;
+            }
+        }
+        match safe_parse_board(&self.playable_fen(), t) {
             Ok(board) => {
                 let mut pieces = HashMap::new();
                 for rank_idx in 0..8usize {
@@ -207,11 +899,8 @@ impl SetupState {
                     };
                 }
                 if parts.len() >= 3 {
-                    let c = parts[2];
-                    self.castle_wk = c.contains('K');
-                    self.castle_wq = c.contains('Q');
-                    self.castle_bk = c.contains('k');
-                    self.castle_bq = c.contains('q');
+                    (self.castle_wk, self.castle_wq, self.castle_bk, self.castle_bq) =
+                        chess960::parse_castling_field(parts[0], parts[2]);
                 }
                 if parts.len() >= 4 {
                     if parts[3] == "-" {
@@ -223,6 +912,7 @@ impl SetupState {
                             if idx < 8 { Some(chess::File::from_index(idx)) } else { None };
                     }
                 }
+                (self.halfmove_clock, self.fullmove_number) = game::fen_counters(fen);
                 self.fen_error = None;
             }
             Err(e) => {
@@ -232,34 +922,37 @@ impl SetupState {
     }
 }
 
-/// Parse a FEN string without risking a panic from the chess crate.
-///
-/// The chess crate aborts on positions it considers structurally broken
-/// (missing kings, pawns on the back ranks, etc.).  We catch those cases
-/// with cheap string checks before ever handing the FEN to the library.
-fn safe_parse_board(fen: &str) -> Result<chess::Board, String> {
-    let placement = fen.split_whitespace().next().unwrap_or("");
-
-    // Both kings must be present.
-    if !placement.contains('K') {
-        return Err("Missing white king (K)".to_string());
-    }
-    if !placement.contains('k') {
-        return Err("Missing black king (k)".to_string());
-    }
-
-    // FEN rank order: rank 8 first, rank 1 last.
-    let ranks: Vec<&str> = placement.split('/').collect();
-    if ranks.len() == 8 {
-        if ranks[0].contains('P') || ranks[0].contains('p') {
-            return Err("Pawns cannot be on rank 8".to_string());
-        }
-        if ranks[7].contains('P') || ranks[7].contains('p') {
-            return Err("Pawns cannot be on rank 1".to_string());
+/// A legal position with the men of `signature` ("KRP vs KR"), white to
+/// move and neither side in check or out of moves.  Gives up with an error
+/// on material no position can hold.
+fn random_endgame(signature: &str, t: &Strings) -> Result<String, String> {
+    let material = endgames::parse_material(signature, t)?;
+    let mut rng = rand::thread_rng();
+    let mut last_error = String::new();
+    for _ in 0..1000 {
+        let fen = endgames::random_position(&material, &mut rng);
+        match safe_parse_board(&fen, t) {
+            Ok(board)
+                if *board.checkers() == chess::EMPTY
+                    && board.status() == chess::BoardStatus::Ongoing =>
+            {
+                return Ok(fen)
+            }
+            Ok(_) => {}
+            Err(e) => last_error = e,
         }
     }
+    Err(fill(&fill(t.no_endgame_position, signature.trim()), last_error))
+}
 
-    chess::Board::from_str(fen).map_err(|e| format!("{:?}", e))
+/// A move counter typed into the setup screen: digits only, with an emptied
+/// field reading as 0 until something is typed.
+fn counter_input(input: &str) -> Option<u32> {
+    match input {
+        "" => Some(0),
+        _ if input.chars().all(|c| c.is_ascii_digit()) => input.parse().ok(),
+        _ => None,
+    }
 }
 
 /// A GUI chess game that allows playing against UCI-compatible chess engines
@@ -274,6 +967,11 @@ struct Args {
     #[clap(short, long, default_value = "10")]
     skill_level: u8,
 
+    /// Limit the engine to this Elo rating instead of a skill level
+    /// (engines supporting UCI_LimitStrength/UCI_Elo)
+    #[clap(long)]
+    elo: Option<u32>,
+
     /// Engine thinking time in milliseconds
     #[clap(short, long, default_value = "2000")]
     think_time: u64,
@@ -281,21 +979,374 @@ struct Args {
     /// Play as black (engine plays white)
     #[clap(short, long)]
     black: bool,
+
+    /// Start in coach mode: the engine checks each move before it is played
+    #[clap(long)]
+    coach: bool,
+
+    /// Vary the engine's reply time like a human: quick recaptures, longer
+    /// thinks in busy positions
+    #[clap(long)]
+    human_pacing: bool,
+
+    /// Below full strength, pick randomly among engine moves within this
+    /// many centipawns of the best (0 always plays the best move)
+    #[clap(long, default_value = "25")]
+    variety_window: u32,
+
+    /// Extra UCI option sent to the engine at startup, as "Name=Value"
+    /// (repeatable, e.g. --uci-option "SyzygyPath=/tb")
+    #[clap(long = "uci-option", value_name = "NAME=VALUE", value_parser = engine::parse_option_assignment)]
+    uci_option: Vec<(String, String)>,
+
+    /// Open the first game of this PGN file for replay; you take the side
+    /// to move at its end to continue against the engine
+    #[clap(long, value_name = "FILE")]
+    pgn: Option<PathBuf>,
+
+    /// Resume a game saved with Save Game, with its clocks and the engine
+    /// set up as they were
+    #[clap(long, value_name = "FILE")]
+    resume: Option<PathBuf>,
+
+    /// Play with chess clocks, as SECONDS[+INCREMENT] (e.g. "300+2"), or
+    /// SECONDSdDELAY for a Bronstein delay (e.g. "300d2"); exported PGN
+    /// records the time left after every move
+    #[clap(long, value_name = "SECONDS[+INC]")]
+    clock: Option<TimeControl>,
+
+    /// Print each move and game result in words on standard output, for a
+    /// screen reader following the terminal
+    #[clap(long)]
+    announce: bool,
+
+    /// Start with a kibitzer: this engine analyses the position on the
+    /// board alongside the game without playing (default: a second copy of
+    /// the playing engine, when turned on with the Kibitzer button).
+    /// Repeat to compare several engines side by side
+    #[clap(long, value_name = "PATH")]
+    kibitzer: Vec<PathBuf>,
+
+    /// NNUE network file for the engine, sent as its EvalFile option
+    /// [default: saved setting]
+    #[clap(long, value_name = "FILE")]
+    eval_file: Option<PathBuf>,
+
+    /// Opponent style, such as "aggressive" or "solid", set through the
+    /// engine's style options [default: saved setting]
+    #[clap(long, value_name = "NAME")]
+    personality: Option<String>,
+
+    /// Engine search threads [default: saved setting, or all cores but one]
+    #[clap(long)]
+    threads: Option<u32>,
+
+    /// Engine hash table size in MB [default: saved setting, or about a
+    /// sixteenth of memory]
+    #[clap(long)]
+    hash_mb: Option<u32>,
+
+    #[clap(subcommand)]
+    command: Option<CliCommand>,
+}
+
+/// Tasks run from the command line without opening the GUI
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// Play a match between two engines and save the games as PGN
+    Match {
+        /// First engine executable (White in odd-numbered games)
+        first: PathBuf,
+        /// Second engine executable
+        second: PathBuf,
+        #[clap(flatten)]
+        options: MatchOptions,
+    },
+    /// Play a round robin among three or more engines and rate them
+    Tournament {
+        /// Engine executables
+        #[clap(required = true, num_args = 3..)]
+        engines: Vec<PathBuf>,
+        #[clap(flatten)]
+        options: MatchOptions,
+    },
+    /// Annotate every game in a PGN file with engine evaluations and
+    /// blunder marks, using the engine given by --engine-path
+    Analyze {
+        /// PGN file to analyse
+        input: PathBuf,
+        /// Annotated PGN to write [default: INPUT with an .analyzed.pgn extension]
+        #[clap(short, long)]
+        output: Option<PathBuf>,
+        /// Search depth for each position
+        #[clap(short, long, default_value_t = analysis::REVIEW_DEPTH)]
+        depth: u32,
+    },
+    /// Count the positions reachable from a FEN to a given depth, to check
+    /// move generation or a position built in the setup editor
+    Perft {
+        /// Depth in plies
+        depth: u32,
+        /// Position to start from [default: the standard starting position]
+        #[clap(long)]
+        fen: Option<String>,
+        /// Also list the count below each legal move
+        #[clap(long)]
+        divide: bool,
+    },
+    /// Print a position as a Unicode figurine diagram
+    Diagram {
+        /// Position to show [default: the standard starting position]
+        #[clap(long)]
+        fen: Option<String>,
+        /// Show the board from Black's side
+        #[clap(long)]
+        flip: bool,
+        /// Piece letters instead of figurines, for plain ASCII terminals
+        #[clap(long)]
+        ascii: bool,
+    },
+}
+
+impl Args {
+    /// Threads and hash from the command line, falling back to the saved
+    /// settings
+    fn resources(&self) -> Resources {
+        let saved = settings::load_settings().resources;
+        Resources {
+            threads: self.threads.unwrap_or(saved.threads).max(1),
+            hash_mb: self.hash_mb.unwrap_or(saved.hash_mb).max(1),
+        }
+    }
+
+    /// The --uci-option settings plus the eval file from --eval-file or the
+    /// saved settings, unless an EvalFile option was given directly.  A
+    /// missing --eval-file is an error; a saved one that has since gone is
+    /// only reported.
+    fn uci_options(&self) -> Result<Vec<(String, String)>> {
+        let mut options = self.uci_option.clone();
+        if options.iter().any(|(name, _)| name.eq_ignore_ascii_case(EVAL_FILE)) {
+            return Ok(options);
+        }
+        if let Some(path) = &self.eval_file {
+            options.push(engine::eval_file_option(path).map_err(|e| anyhow::anyhow!("{}", e))?);
+        } else if let Some(path) = settings::load_settings().eval_file {
+            match engine::eval_file_option(&path) {
+                Ok(option) => options.push(option),
+                Err(e) => eprintln!("Ignoring the saved eval file: {}", e),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Settings shared by matches and tournaments
+#[derive(clap::Args, Debug)]
+struct MatchOptions {
+    /// Games each pair of engines plays; colours alternate every game
+    #[clap(short, long, default_value = "2")]
+    games: u32,
+    /// Time control per engine as SECONDS[+INCREMENT]
+    #[clap(long = "tc", default_value = "10+0.1")]
+    time_control: TimeControl,
+    /// Draw games still going after this many moves
+    #[clap(long, default_value = "200")]
+    max_moves: u32,
+    /// File the games are written to [default: match.pgn or tournament.pgn]
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+    /// Adjudicate a win once both engines see one side ahead for this many
+    /// moves in a row (0 disables)
+    #[clap(long, default_value = "3")]
+    resign_moves: u32,
+    /// Centipawn lead that counts towards resign adjudication
+    #[clap(long, default_value = "600")]
+    resign_score: i32,
+    /// Adjudicate a draw once both engines see a level position for this
+    /// many moves in a row (0 disables)
+    #[clap(long, default_value = "8")]
+    draw_moves: u32,
+    /// Draw adjudication only applies after this move number
+    #[clap(long, default_value = "40")]
+    draw_after: u32,
+    /// Largest centipawn score that counts as level for draw adjudication
+    #[clap(long, default_value = "10")]
+    draw_score: i32,
+    /// Take the tablebase verdict of engines with tablebases configured
+    /// (e.g. --uci-option SyzygyPath=...) once this many pieces or fewer
+    /// remain (0 disables) [default: the largest tables in SyzygyPath]
+    #[clap(long)]
+    tb_pieces: Option<u32>,
+    /// Opening suite (.epd, or PGN) to start game pairs from; each opening
+    /// is played twice with colours reversed
+    #[clap(long, value_name = "FILE")]
+    openings: Option<PathBuf>,
+}
+
+impl MatchOptions {
+    fn config(
+        self,
+        engines: Vec<PathBuf>,
+        resources: Resources,
+        uci_options: Vec<(String, String)>,
+        default_output: &str,
+    ) -> Result<MatchConfig> {
+        let tb_pieces = self.tb_pieces.unwrap_or_else(|| engine_match::syzygy_pieces(&uci_options));
+        let openings = match &self.openings {
+            Some(path) => engine_match::load_opening_suite(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path.display(), e))?,
+            None => Vec::new(),
+        };
+        Ok(MatchConfig {
+            engines,
+            games: self.games,
+            time_control: self.time_control,
+            max_moves: self.max_moves,
+            resources,
+            uci_options,
+            pgn_path: self.output.unwrap_or_else(|| PathBuf::from(default_output)),
+            adjudication: Adjudication {
+                resign_moves: self.resign_moves,
+                resign_score: self.resign_score,
+                draw_moves: self.draw_moves,
+                draw_after: self.draw_after,
+                draw_score: self.draw_score,
+                tb_pieces,
+            },
+            openings,
+        })
+    }
+}
+
+fn run_command(command: CliCommand, args: Args) -> Result<()> {
+    match command {
+        CliCommand::Match {
+            first,
+            second,
+            options,
+        } => {
+            let resources = args.resources();
+            let uci_options = args.uci_options()?;
+            let config = options.config(vec![first, second], resources, uci_options, "match.pgn")?;
+            engine_match::run_match(&config)?;
+            println!("Games saved to {}", config.pgn_path.display());
+        }
+        CliCommand::Tournament { engines, options } => {
+            let resources = args.resources();
+            let config = options.config(engines, resources, args.uci_options()?, "tournament.pgn")?;
+            engine_match::run_match(&config)?;
+            println!("Games saved to {}", config.pgn_path.display());
+        }
+        CliCommand::Analyze {
+            input,
+            output,
+            depth,
+        } => {
+            let output = output.unwrap_or_else(|| input.with_extension("analyzed.pgn"));
+            let games = analysis::annotate_pgn_file(
+                &args.engine_path,
+                args.resources(),
+                &args.uci_options()?,
+                &input,
+                &output,
+                depth,
+            )?;
+            println!("{} game{} written to {}", games, if games == 1 { "" } else { "s" }, output.display());
+        }
+        CliCommand::Perft { depth, fen, divide } => {
+            let board = match fen {
+                Some(fen) => safe_parse_board(&fen, Language::default().strings())
+                    .map_err(|e| anyhow::anyhow!("{}", e))?,
+                None => chess::Board::default(),
+            };
+            let started = Instant::now();
+            let nodes = if divide {
+                let split = perft::divide(&board, depth);
+                for (chess_move, nodes) in &split {
+                    println!("{}: {}", chess_move, nodes);
+                }
+                println!();
+                split.iter().map(|(_, nodes)| nodes).sum()
+            } else {
+                perft::perft(&board, depth)
+            };
+            let elapsed = started.elapsed();
+            println!("Nodes searched: {}", nodes);
+            println!(
+                "Time: {} ms ({:.0} nodes/s)",
+                elapsed.as_millis(),
+                nodes as f64 / elapsed.as_secs_f64().max(1e-9)
+            );
+        }
+        CliCommand::Diagram { fen, flip, ascii } => {
+            let board = match fen {
+                Some(fen) => safe_parse_board(&fen, Language::default().strings())
+                    .map_err(|e| anyhow::anyhow!("{}", e))?,
+                None => chess::Board::default(),
+            };
+            let orientation = if flip { chess::Color::Black } else { chess::Color::White };
+            print!("{}", diagram::board_text(&board, orientation, ascii));
+        }
+    }
+    Ok(())
+}
+
+/// A timestamped file name in `folder`, falling back to the home
+/// directory, for files exported from the GUI.
+fn export_path(folder: Option<PathBuf>, stem: &str, extension: &str) -> PathBuf {
+    let folder = folder.or_else(dirs::home_dir).unwrap_or_else(|| PathBuf::from("."));
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    folder.join(format!("{}-{}.{}", stem, stamp, extension))
 }
 
 fn main() -> Result<()> {
     // Parse command line arguments
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if let Some(command) = args.command.take() {
+        return run_command(command, args);
+    }
 
     // Validate arguments
     let skill_level = args.skill_level.clamp(1, 20);
     let think_time = args.think_time.max(100);
+    let resources = args.resources();
+    let uci_options = args.uci_options()?;
+    let pgn_game = match &args.pgn {
+        Some(path) => Some(
+            pgn::read_first_game(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let saved_game = match &args.resume {
+        Some(path) => Some(
+            persistence::load_game_file(path)
+                .map_err(|e| anyhow::anyhow!("Failed to load {}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+
+    let saved = settings::load_settings();
+    let personalities = personality::all(&saved.personalities);
+    let chosen_personality = match args.personality.as_ref().or(saved.personality.as_ref()) {
+        Some(name) => Some(personality::find(&personalities, name).cloned().ok_or_else(|| {
+            let names: Vec<&str> = personalities.iter().map(|p| p.name.as_str()).collect();
+            anyhow::anyhow!("Unknown personality {} (known: {})", name, names.join(", "))
+        })?),
+        None => None,
+    };
+
+    // Reopen the window where it was closed last time
+    let geometry = saved.window.unwrap_or_default();
 
     // Create settings for the Iced application
     let settings = Settings {
         window: window::Settings {
-            size: (1000, 700),
-            position: window::Position::Centered,
+            size: (geometry.width.max(640), geometry.height.max(480)),
+            position: match geometry.position {
+                Some((x, y)) => window::Position::Specific(x, y),
+                None => window::Position::Centered,
+            },
             min_size: Some((640, 480)),
             max_size: None,
             visible: true,
@@ -308,9 +1359,24 @@ fn main() -> Result<()> {
         flags: AppFlags {
             engine_path: args.engine_path,
             skill_level,
+            elo: args.elo,
             think_time,
             play_as_black: args.black,
+            coach_mode: args.coach,
+            human_pacing: args.human_pacing,
+            variety_window: args.variety_window,
+            resources,
+            uci_options,
+            pgn_game,
+            saved_game,
+            clock: args.clock,
+            announce: args.announce,
+            kibitzers: args.kibitzer,
+            personalities,
+            personality: chosen_personality,
         },
+        // The window's geometry is saved before it closes
+        exit_on_close_request: false,
         ..Default::default()
     };
 
@@ -325,8 +1391,143 @@ fn main() -> Result<()> {
 pub struct AppFlags {
     engine_path: PathBuf,
     skill_level: u8,
+    elo: Option<u32>,
     think_time: u64,
     play_as_black: bool,
+    coach_mode: bool,
+    human_pacing: bool,
+    variety_window: u32,
+    resources: Resources,
+    uci_options: Vec<(String, String)>,
+    pgn_game: Option<PgnGame>,
+    saved_game: Option<SavedGame>,
+    clock: Option<TimeControl>,
+    announce: bool,
+    kibitzers: Vec<PathBuf>,
+    personalities: Vec<Personality>,
+    personality: Option<Personality>,
+}
+
+/// The engine's move, remembered with the position it led to so it is only
+/// marked while that position is on the board.
+#[derive(Clone, Copy)]
+struct EngineMove {
+    board: chess::Board,
+    from: chess::Square,
+    to: chess::Square,
+    reply: Option<chess::ChessMove>,
+}
+
+/// The opponent's best moves if they could move again now.  `moves` is
+/// None while the engine is still looking.
+struct Threats {
+    board: chess::Board,
+    moves: Option<Vec<chess::ChessMove>>,
+}
+
+/// The engine's best moves in one position, best first, each with its
+/// evaluation from White's side.  `moves` is None while it is looking.
+struct Candidates {
+    board: chess::Board,
+    moves: Option<Vec<(chess::ChessMove, i32)>>,
+}
+
+/// A finished game the database already has, held back until the player
+/// decides whether to skip it or replace the stored copy.
+struct DuplicateGame {
+    /// Id of the stored copy, and when it was played
+    existing: i64,
+    played_at: String,
+    game: StoredGame,
+    tags: Vec<(String, String)>,
+}
+
+/// A second engine quietly evaluating each position of the game as it is
+/// played, so the review has little or nothing left to search at the end.
+struct BackgroundAnalysis {
+    engine: Arc<Mutex<ChessEngine>>,
+    /// Evaluations of the game's positions so far, by hash
+    evals: HashMap<u64, PositionEval>,
+    /// Position being searched
+    searching: Option<chess::Board>,
+}
+
+impl BackgroundAnalysis {
+    fn new() -> Self {
+        BackgroundAnalysis {
+            engine: Arc::new(Mutex::new(ChessEngine::new())),
+            evals: HashMap::new(),
+            searching: None,
+        }
+    }
+}
+
+/// An analysis-only engine commenting on the position on the board.
+struct Kibitzer {
+    path: PathBuf,
+    engine: Arc<Mutex<ChessEngine>>,
+    /// Position being analysed, once its search has been asked for
+    board: Option<chess::Board>,
+    /// Latest line for each multipv index
+    lines: Vec<SearchInfo>,
+    error: Option<String>,
+}
+
+impl Kibitzer {
+    fn new(path: PathBuf) -> Self {
+        Kibitzer {
+            path,
+            engine: Arc::new(Mutex::new(ChessEngine::new())),
+            board: None,
+            lines: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// What the engine makes of the position it is on, for the side panel.
+    fn info(&self) -> Option<KibitzerInfo> {
+        let board = self.board?;
+        let white_to_move = board.side_to_move() == chess::Color::White;
+        // A finished game leaves the engine nothing to search
+        let lines = match board.status() {
+            chess::BoardStatus::Checkmate => vec![("#0".to_string(), String::new())],
+            chess::BoardStatus::Stalemate => vec![(analysis::format_eval(0), String::new())],
+            chess::BoardStatus::Ongoing => self
+                .lines
+                .iter()
+                .map(|line| {
+                    let side_cp = line.score.to_cp();
+                    (
+                        analysis::format_eval(if white_to_move { side_cp } else { -side_cp }),
+                        analysis::line_to_san(&board, &line.pv, KIBITZER_PLIES),
+                    )
+                })
+                .collect(),
+        };
+        Some(KibitzerInfo {
+            engine: engine::engine_name(&self.path),
+            depth: self.lines.first().map_or(0, |line| line.depth),
+            lines,
+            best_move: self.lines.first().and_then(|line| line.first_move.clone()),
+            moves: self
+                .lines
+                .iter()
+                .filter_map(|line| {
+                    let chess_move = game::parse_uci_move(&board, line.first_move.as_deref()?)?;
+                    Some((chess_move.get_source(), chess_move.get_dest(), line.score.to_cp()))
+                })
+                .collect(),
+            error: self.error.clone(),
+        })
+    }
+}
+
+/// A piece sliding from where it stood to where the last move put it.
+#[derive(Clone, Copy)]
+struct MoveAnimation {
+    from: chess::Square,
+    to: chess::Square,
+    started: Instant,
 }
 
 // Main application state
@@ -337,23 +1538,221 @@ pub struct ChessApp {
     engine_thinking: bool,
     window_size: Size<u32>,
     screen: AppScreen,
+    // Cached render state, rebuilt only when the game revision changes
+    snapshot: GameSnapshot,
+    // Unfinished game found on startup, waiting for the user to resume or discard it
+    resume_offer: Option<SavedGame>,
+    autosaved_revision: u64,
+    last_autosave: Instant,
+    engine_path: PathBuf,
+    // Extra "setoption" pairs from --uci-option, sent to every engine we start
+    uci_options: Vec<(String, String)>,
+    // Threads and hash given to every engine we start
+    resources: Resources,
+    // Playing styles on offer, and the one the playing engine is given
+    personalities: Vec<Personality>,
+    personality: Option<Personality>,
+    // Name and author the engine reported; empty until it has started
+    engine_id: EngineId,
+    // Why the engine failed to start, and the path typed in to try another
+    engine_error: Option<String>,
+    engine_path_input: String,
+    // Number of the last engine stderr line the user dismissed
+    stderr_dismissed: u64,
+    // The board is shown from the opponent's side, whichever side the
+    // player has
+    board_flipped: bool,
+    // The last move's piece sliding into place, and how long that takes
+    animation: Option<MoveAnimation>,
+    // The engine's last move and the reply it expects, while that move is
+    // the one on the board
+    engine_move: Option<EngineMove>,
+    board_settings: BoardSettings,
+    language: Language,
+    // Zoom of the whole interface
+    ui_scale: f64,
+    // The window's size and place, kept for the next session, and whether
+    // it fills the screen instead
+    window_geometry: WindowGeometry,
+    fullscreen: bool,
+    // What the opponent threatens, asked for in one position
+    threats: Option<Threats>,
+    // The engine's candidate moves, asked for in one position
+    candidates: Option<Candidates>,
+    // What exported board images show besides the pieces
+    export_last_move: bool,
+    export_coordinates: bool,
+    // Exported PGN and scoresheets write pieces as symbols
+    export_figurine: bool,
+    // The move history is laid out as a scoresheet table
+    move_table: bool,
+    // Square outlined for playing from the keyboard, once an arrow key
+    // has been pressed
+    board_cursor: Option<chess::Square>,
+    // The last move or result in words, and whether to print it as well
+    announcement: Option<String>,
+    announce: bool,
+    // Text typed into the "go to move" box
+    go_to_move: String,
+    // FEN or placement pattern typed into the position search, and whether
+    // the last search found nothing
+    find_position: String,
+    position_not_found: bool,
+    // "Delete from here" waiting to be confirmed: the position reached by
+    // the first move to go
+    delete_from: Option<usize>,
+    skill_level: u8,
+    think_time: u64,
+    // Reply times vary with the position instead of always taking
+    // `think_time`
+    human_pacing: bool,
+    // With human pacing, when the engine's reply is due, and a move that
+    // arrived early waiting for that moment
+    reply_due: Option<Instant>,
+    held_move: Option<String>,
+    // When the game was paused; the engine's reply waits until it resumes
+    paused_at: Option<Instant>,
+    // When the engine's search should have ended, grace included; later
+    // than that without a bestmove it is reported as stuck
+    search_deadline: Option<Instant>,
+    // Eval window in centipawns for varying the engine's moves, and the
+    // MultiPV count the engine is currently set to
+    variety_window: u32,
+    engine_multipv: u32,
+    // Elo limit in place of the skill level, when the engine supports it
+    elo: Option<u32>,
+    // Ratings the engine accepts for UCI_Elo; None until it has started or
+    // if it has no Elo limit
+    elo_range: Option<(u32, u32)>,
+    // Moves the engine's strength towards the player's level between games
+    adaptive: AdaptiveStrength,
+    // The player's rating from rated games against Elo-limited engines
+    rating: PlayerRating,
+    // Games finished since the application started
+    session: Session,
+    // Archive of finished games; None if it couldn't be opened
+    database: Option<GameDatabase>,
+    // Whether the finished game currently on the board is already archived
+    game_saved: bool,
+    // Database id and PGN tags of that archived game, to rewrite its PGN
+    // when the moves' annotations change
+    archived: Option<(i64, Vec<(String, String)>)>,
+    // A finished game found already archived, until the player says what to
+    // do with it
+    duplicate: Option<DuplicateGame>,
+    // A post-game review is running in the background
+    reviewing: bool,
+    // Evaluating positions while the game is played, when turned on
+    background: Option<BackgroundAnalysis>,
+    // Whether the window has the focus, and whether the analysis engines
+    // stop while it hasn't
+    focused: bool,
+    pause_unfocused: bool,
+    // When to save power, whether the computer was on battery when last
+    // looked, and when that was
+    power_saver: PowerSaver,
+    on_battery: bool,
+    power_checked: Option<Instant>,
+    // Repertoire the engine plays its opening moves from, and the file
+    // it was read from
+    book: Option<(PathBuf, Book)>,
+    // Whether the book's moves are weighed by the engine's results
+    book_learning: bool,
+    // Full-strength instance for coach mode checks, started on first use
+    coach_engine: Arc<Mutex<ChessEngine>>,
+    // Engines analysing alongside the game, while they are on, and the
+    // programs they run
+    kibitzers: Vec<Kibitzer>,
+    kibitzer_paths: Vec<PathBuf>,
+    // Repertoire or puzzle drill in progress; it answers the player's moves
+    // instead of the engine
+    drill: Option<Drill>,
+    // What Reset starts over: a standard game, a fresh Chess960 deal or
+    // the same odds game
+    game_kind: GameKind,
+    // Traffic with the playing engine, shown in the UCI console
+    uci_log: Arc<Mutex<UciLog>>,
+    // The console is open, the text its lines must contain, the command
+    // being typed, and how many lines had been logged when it last
+    // scrolled to the end
+    console_open: bool,
+    console_filter: String,
+    console_command: String,
+    console_seen: u64,
+    // Logged in to Lichess, with the online game if one is on the board
+    lichess: Option<LichessSession>,
 }
 
 // Messages that can be sent to update the application state
 #[derive(Debug, Clone)]
 pub enum Message {
     SquareClicked(chess::Square),
+    // A square held down in touch mode
+    SquareLongPressed(chess::Square),
     ResetGame,
     UndoMove,
     FlipSide,
+    FlipBoard,
+    // Stop the clocks and hold back the engine's reply, or carry on
+    TogglePause,
+    /// The screen is about to redraw while a piece slides
+    AnimationFrame,
     EngineMoved(String),
     CheckEngineMove,
     Tick,
+    /// The engine finished starting up; `elo` is the limit it accepted and
+    /// `id` how it introduced itself
+    EngineStarted {
+        elo_range: Option<(u32, u32)>,
+        elo: Option<u32>,
+        id: EngineId,
+    },
+    EngineFailed(String),
+    RetryEngine,
+    EnginePathChanged(String),
+    UseEnginePath,
+    ToggleEloLimit,
+    EloChanged(u32),
+    SkillLevelChanged(u8),
+    ThinkTimeChanged(u64),
+    ApplyStrength,
     WindowResized(u32, u32),
+    WindowMoved(i32, i32),
+    // Save the window's geometry, then close it
+    WindowCloseRequested,
+    // F11 switches between a window and the full screen
+    ToggleFullscreen,
     ViewMove(usize),
+    // The "go to move" box: its text, and Enter to jump there
+    GoToMoveChanged(String),
+    GoToMove,
+    FindPositionChanged(String),
+    FindPosition,
+    // Switch to the line of an imported game's variation tree through
+    // this node
+    FollowVariation(usize),
+    // "Delete from here" on a move: ask first, then cut the game back to
+    // the position before it
+    DeleteFrom(usize),
+    ConfirmDelete,
+    CancelDelete,
+    // Make a variation the main line, or move it down below the next one
+    PromoteVariation(usize),
+    DemoteVariation(usize),
+    // Plies to step back (negative) or forward through the game, from the
+    // mouse wheel over the board
+    StepMove(isize),
     ExitViewMode,
+    /// Glyph for the move being viewed; None clears it
+    SetMoveGlyph(Option<&'static str>),
+    MoveCommentChanged(String),
     ScrollToBottom,
     PromotePawn(PromotionPiece),
+    CancelPromotion,
+    // Arrow keys move the board cursor by (files, ranks) as seen on screen
+    MoveBoardCursor(i8, i8),
+    // Enter or Space acts as a click on the cursor square
+    ActivateBoardCursor,
     // Setup screen messages
     EnterSetupMode,
     ExitSetupMode,
@@ -363,10 +1762,186 @@ pub enum Message {
     SetupCastlingToggle(u8),
     SetupEnPassant(Option<chess::File>),
     SetupFenChanged(String),
+    SetupHalfmoveClock(String),
+    SetupFullmoveNumber(String),
     SetupClearBoard,
     SetupLoadStart,
+    SetupRandomChess960,
+    SetupEndgameMaterial(String),
+    SetupRandomEndgame,
+    SetupChess960Toggle,
     SetupStartGame,
     SetupPlayerColor(chess::Color),
+    PasteFen,
+    FenPasted(Option<String>),
+    // Take a setup position from a game on the clipboard, at the move typed
+    // beside the button
+    SetupPastePgn,
+    SetupPgnPasted(Option<String>),
+    SetupPgnMove(String),
+    // Image export messages
+    ExportImage(&'static str),
+    CopyBoardText,
+    ExportScoresheet,
+    ExportPgn,
+    // Export the moves up to the one shown, for one line of an imported
+    // game's variations
+    ExportLine,
+    ToggleExportFigurine,
+    /// Switch the move history between the piece list and a scoresheet table
+    ToggleMoveTable,
+    OpenLichessAnalysis,
+    // URL to open, and why the game couldn't be imported if it wasn't
+    LichessAnalysisReady(String, Option<String>),
+    ToggleExportLastMove,
+    ToggleExportCoordinates,
+    SaveGameFile,
+    // Autosave messages
+    ResumeSavedGame,
+    DiscardSavedGame,
+    SkipDuplicate,
+    ReplaceDuplicate,
+    // Game browser messages
+    OpenGameBrowser,
+    CloseGameBrowser,
+    BrowserOpenGame(i64),
+    BrowserDeleteGame(i64),
+    BrowserUrlChanged(String),
+    BrowserImportUrl,
+    BrowserResumePathChanged(String),
+    BrowserResumeFile,
+    BrowserFilter(GameFilter),
+    BrowserNewTagChanged(String),
+    BrowserAddTag(i64),
+    BrowserRemoveTag(i64, String),
+    GameDownloaded(Result<String, String>),
+    // Statistics messages
+    OpenStatistics,
+    ToggleAdaptiveStrength,
+    // Session summary messages
+    OpenSession,
+    CloseSession,
+    // Game review messages
+    StartReview,
+    ReviewFinished(Vec<chess::ChessMove>, Result<Vec<MoveEvaluation>, String>),
+    /// The background analysis engine evaluated this position
+    BackgroundEvaluated(chess::Board, Result<PositionEval, String>),
+    // Coach mode messages
+    ToggleCoachMode,
+    CoachChecked(chess::ChessMove, Result<Option<String>, String>),
+    ConfirmMove,
+    CancelMove,
+    // Repertoire messages
+    OpenRepertoire,
+    CloseRepertoire,
+    RepertoirePathChanged(String),
+    RepertoireColor(chess::Color),
+    RepertoireImport,
+    RepertoireClear(chess::Color),
+    StartTraining(chess::Color),
+    StopDrill,
+    // Puzzle messages
+    OpenPuzzles,
+    ClosePuzzles,
+    SolvePuzzle(i64),
+    NextPuzzle,
+    DeletePuzzle(i64),
+    // Endgame training messages
+    OpenEndgames,
+    CloseEndgames,
+    StartEndgame(usize),
+    // Famous games messages
+    OpenFamousGames,
+    CloseFamousGames,
+    OpenFamousGame(usize),
+    // New game menu messages
+    OpenNewGame,
+    CloseNewGame,
+    NewGameColor(chess::Color),
+    NewGameClock(String),
+    NewGameEngineClock(String),
+    NewGameClockMode(ClockMode),
+    // Rated (true) or casual
+    NewGameRated(bool),
+    NewGameOpeningQuery(String),
+    NewGameOpening(Option<OpeningLine>),
+    StartNewGame(GameKind),
+    // Settings messages
+    OpenSettings,
+    CloseSettings,
+    SettingsThreads(u32),
+    SettingsHash(u32),
+    SettingsAnimation(u32),
+    SettingsReplyArrow(bool),
+    SettingsBackgroundAnalysis(bool),
+    SettingsPauseUnfocused(bool),
+    SettingsPowerSaver(PowerSaver),
+    /// The window gained (true) or lost the keyboard focus
+    WindowFocused(bool),
+    SettingsCoordinates(bool),
+    SettingsLanguage(Language),
+    SettingsPalette(Palette),
+    SettingsTouchMode(bool),
+    SettingsUiScale(f64),
+    // Name of the opponent style picked, None for the engine's own
+    SettingsPersonality(Option<String>),
+    SettingsEvalFile(String),
+    SettingsEngineBook(String),
+    SettingsBookLearning(bool),
+    // Ctrl+= and Ctrl+- zoom the interface a step in or out, Ctrl+0 resets
+    // it (sent as 0)
+    ZoomUi(i8),
+    SettingsDefaults,
+    SaveSettings,
+    // UCI console messages
+    ToggleConsole,
+    // Hide the engine's latest stderr line until another arrives
+    DismissEngineStderr,
+    // The engine has overrun its move time: ask it to stop, or replace it
+    StopEngineSearch,
+    RestartEngine,
+    ConsoleFilterChanged(String),
+    ConsoleCommandChanged(String),
+    SendConsoleCommand,
+    // Online play messages
+    OpenOnline,
+    CloseOnline,
+    OnlineTokenChanged(String),
+    OnlineControlChanged(String),
+    OnlineOpponentChanged(String),
+    OnlineRatedToggle,
+    OnlineAiLevel(u8),
+    LichessConnect,
+    // The account's user name, or why the token was refused
+    LichessConnected(Result<String, String>),
+    LichessDisconnect,
+    LichessSeek,
+    LichessCancelSeek,
+    LichessChallenge,
+    LichessChallengeAi,
+    LichessAccept(String),
+    LichessDecline(String),
+    LichessResign,
+    LichessLeaveGame,
+    // Lichess' answer to a move, challenge or other request
+    LichessReplied(Result<(), String>),
+    ToggleKibitz,
+    // Position evaluated and the kibitzer's verdict on it
+    KibitzEvaluated(String, Result<String, String>),
+    /// Turn the offline kibitzer engines on or off
+    ToggleKibitzer,
+    /// A kibitzer, by index, started searching the position on the board
+    /// or failed to
+    KibitzerStarted(usize, Result<(), String>),
+    /// Show or hide what the opponent threatens in the position on the board
+    ToggleThreats,
+    /// The threats found in the position with this FEN, as UCI moves
+    ThreatsFound(String, Result<Vec<String>, String>),
+    /// Show or hide the engine's best moves in the position on the board
+    ToggleCandidates,
+    /// The candidates found in the position with this FEN: UCI moves with
+    /// their scores for the side to move
+    CandidatesFound(String, Result<Vec<(String, i32)>, String>),
 }
 
 impl Application for ChessApp {
@@ -379,156 +1954,963 @@ impl Application for ChessApp {
         // Create game and engine
         let mut game = ChessGame::new();
         let engine = ChessEngine::new();
+        let uci_log = engine.log();
+        let saved_settings = settings::load_settings();
+        game.set_language(saved_settings.language);
+        game.set_message(saved_settings.language.strings().welcome.to_string());
 
         // Set player color if playing as black
         if flags.play_as_black {
             game.set_player_color(chess::Color::Black);
         }
+        game.set_coach_mode(flags.coach_mode);
+        game.set_time_control(flags.clock);
 
         // Create shared state
         let game = Arc::new(Mutex::new(game));
         let engine = Arc::new(Mutex::new(engine));
 
         // Create UI
-        let ui = ChessUI::new();
+        let mut ui = ChessUI::new();
+        ui.set_language(saved_settings.language);
+        ui.set_palette(saved_settings.board.palette);
+        ui.set_touch_mode(saved_settings.board.touch_mode);
+
+        // An unfinished game from a previous session is offered for resuming;
+        // hold off the engine's opening move until the user decides
+        // A game given on the command line takes its place
+        let resume_offer = persistence::load_autosave()
+            .filter(|saved| !saved.moves.is_empty() && flags.pgn_game.is_none() && flags.saved_game.is_none());
+
+        let database = match GameDatabase::open_default() {
+            Ok(db) => Some(db),
+            Err(e) => {
+                eprintln!("Failed to open game database: {}", e);
+                None
+            }
+        };
 
-        // Create application with engine_thinking set if playing as black
-        let app = ChessApp {
+        let mut app = ChessApp {
             game,
             engine,
             ui,
-            engine_thinking: flags.play_as_black,
+            engine_thinking: false,
             window_size: Size::new(800, 600),
             screen: AppScreen::Game,
+            snapshot: GameSnapshot::default(),
+            resume_offer,
+            autosaved_revision: 0,
+            last_autosave: Instant::now(),
+            engine_path: flags.engine_path.clone(),
+            uci_options: flags.uci_options.clone(),
+            resources: flags.resources,
+            personalities: flags.personalities.clone(),
+            personality: flags.personality.clone(),
+            engine_id: EngineId::default(),
+            engine_error: None,
+            stderr_dismissed: 0,
+            engine_path_input: flags.engine_path.display().to_string(),
+            board_flipped: false,
+            animation: None,
+            engine_move: None,
+            threats: None,
+            candidates: None,
+            board_settings: saved_settings.board,
+            language: saved_settings.language,
+            ui_scale: settings::clamp_ui_scale(saved_settings.ui_scale),
+            window_geometry: saved_settings.window.unwrap_or_default(),
+            fullscreen: false,
+            export_last_move: true,
+            export_coordinates: true,
+            export_figurine: false,
+            move_table: false,
+            board_cursor: None,
+            announcement: None,
+            announce: flags.announce,
+            go_to_move: String::new(),
+            find_position: String::new(),
+            position_not_found: false,
+            delete_from: None,
+            skill_level: flags.skill_level,
+            think_time: flags.think_time,
+            human_pacing: flags.human_pacing,
+            reply_due: None,
+            held_move: None,
+            paused_at: None,
+            search_deadline: None,
+            variety_window: flags.variety_window,
+            engine_multipv: 1,
+            elo: flags.elo,
+            elo_range: None,
+            adaptive: adaptive::load_adaptive(),
+            rating: rating::load_rating(),
+            session: Session::new(chrono::Local::now().format("%Y-%m-%d %H:%M").to_string()),
+            database,
+            game_saved: false,
+            archived: None,
+            duplicate: None,
+            reviewing: false,
+            background: saved_settings.background_analysis.then(BackgroundAnalysis::new),
+            focused: true,
+            pause_unfocused: saved_settings.pause_unfocused,
+            power_saver: saved_settings.power_saver,
+            on_battery: false,
+            power_checked: None,
+            book: saved_settings.engine_book.and_then(|path| match Book::load(&path) {
+                Ok(book) => Some((path, book)),
+                Err(e) => {
+                    eprintln!("Failed to load the engine's book: {}", e);
+                    None
+                }
+            }),
+            book_learning: saved_settings.book_learning,
+            coach_engine: Arc::new(Mutex::new(ChessEngine::new())),
+            kibitzers: flags.kibitzers.iter().cloned().map(Kibitzer::new).collect(),
+            kibitzer_paths: match flags.kibitzers.is_empty() {
+                true => vec![flags.engine_path.clone()],
+                false => flags.kibitzers.clone(),
+            },
+            drill: None,
+            game_kind: GameKind::Standard,
+            uci_log,
+            console_open: false,
+            console_filter: String::new(),
+            console_command: String::new(),
+            console_seen: 0,
+            lichess: None,
         };
 
-        // Set thinking state in game if playing as black
-        if flags.play_as_black {
-            if let Ok(mut game) = app.game.lock() {
-                game.set_thinking(true);
+        if let Some(pgn_game) = &flags.pgn_game {
+            app.open_pgn_game(pgn_game);
+        }
+        // The engine starts below, already set up as the saved game had it
+        if let Some(saved) = &flags.saved_game {
+            if let Err(e) = app.restore_saved_game(saved) {
+                let t = app.language.strings();
+                if let Ok(mut game) = app.game.lock() {
+                    game.set_message(fill(t.game_unloadable, e));
+                }
             }
         }
 
-        // Start the engine and get first move if playing as black
-        let engine_clone = Arc::clone(&app.engine);
-        let game_clone = Arc::clone(&app.game);
-        let engine_path = flags.engine_path.clone();
-        let skill_level = flags.skill_level;
-        let think_time = flags.think_time;
-        let play_as_black = flags.play_as_black;
+        app.refresh_snapshot();
 
-        let command = Command::perform(
-            async move {
-                // Start the engine
-                if let Ok(mut engine) = engine_clone.lock() {
-                    if let Err(e) = engine.start(&engine_path, skill_level, think_time) {
-                        eprintln!("Failed to start engine: {}", e);
-                        return false;
-                    }
-                }
+        // Start the engine; it makes the first move if playing as black
+        let command = app.start_engine();
 
-                // If playing as black, get first move from engine
-                if play_as_black {
-                    // Small delay to ensure engine is ready
-                    tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                    if let Ok(game) = game_clone.lock() {
-                        if let Ok(mut engine) = engine_clone.lock() {
-                            let fen = game.current_position().to_string();
-                            if let Err(e) = engine.get_move(&fen) {
-                                eprintln!("Failed to get engine move: {}", e);
-                                return false;
-                            }
+        (app, command)
+    }
+
+    fn title(&self) -> String {
+        let mut title = match self.engine_id.name {
+            Some(ref name) => format!("Chess Engine Player — {}", name),
+            None => String::from("Chess Engine Player"),
+        };
+        if let Some(status) = self.title_status() {
+            title.push_str(" — ");
+            title.push_str(&status);
+        }
+        title
+    }
+
+    fn update(&mut self, message: Message) -> Command<Message> {
+        let command = self.handle_message(message);
+        self.refresh_snapshot();
+        let command = Command::batch(vec![command, self.continue_background_analysis()]);
+
+        // Archive and review a game the moment it ends
+        if self.record_finished_game() {
+            return Command::batch(vec![command, self.start_review()]);
+        }
+        command
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        match &self.screen {
+            AppScreen::Setup(state) => {
+                return self.ui.view_setup(state, self.window_size.width, self.window_size.height);
+            }
+            AppScreen::Browser(state) => {
+                return self.ui.view_browser(state);
+            }
+            AppScreen::Statistics(state) => {
+                return self.ui.view_statistics(state);
+            }
+            AppScreen::Session(state) => {
+                return self.ui.view_session(state);
+            }
+            AppScreen::Repertoire(state) => {
+                return self.ui.view_repertoire(state);
+            }
+            AppScreen::Puzzles(state) => {
+                return self.ui.view_puzzles(state);
+            }
+            AppScreen::Endgames => {
+                return self.ui.view_endgames();
+            }
+            AppScreen::FamousGames => {
+                return self.ui.view_famous_games();
+            }
+            AppScreen::NewGame(state) => {
+                return self.ui.view_new_game(state);
+            }
+            AppScreen::Settings(state) => {
+                return self.ui.view_settings(state);
+            }
+            AppScreen::Online(state) => {
+                let lobby = self.lichess.as_ref().map(|session| LobbyInfo {
+                    username: &session.username,
+                    challenges: &session.challenges,
+                    seeking: session.seek.is_some(),
+                    playing: session.game.as_ref().is_some_and(|game| !game.is_over()),
+                });
+                return self.ui.view_online(state, lobby);
+            }
+            AppScreen::Game => {}
+        }
+
+        let info = AppInfo {
+            resume_offer: self.resume_offer.as_ref().map(|saved| saved.moves.len()),
+            duplicate: self.duplicate.as_ref().map(|duplicate| duplicate.played_at.clone()),
+            reviewing: self.reviewing,
+            elo: self.elo,
+            elo_range: self.elo_range,
+            skill_level: self.skill_level,
+            think_time: self.think_time,
+            drill: match self.drill {
+                Some(Drill::Repertoire(ref trainer)) => Some(DrillInfo::Repertoire {
+                    color: trainer.color(),
+                    due: trainer.due_count(),
+                }),
+                Some(Drill::Puzzle(ref session)) => Some(DrillInfo::Puzzle {
+                    solved: session.solved,
+                }),
+                Some(Drill::Endgame(ref session)) => Some(DrillInfo::Endgame {
+                    name: session.endgame.name,
+                    outcome: session.outcome.clone(),
+                }),
+                None => None,
+            },
+            engine: self.engine_id.label().unwrap_or_else(|| self.engine_name()),
+            engine_failure: self.engine_error.as_ref().map(|error| EngineFailure {
+                error: error.clone(),
+                path: self.engine_path_input.clone(),
+            }),
+            engine_overrun: self.engine_overrun().map(|overrun| overrun.as_secs()),
+            kibitzers: self.kibitzers.iter().filter_map(Kibitzer::info).collect(),
+            engine_stderr: self.uci_log.lock().ok().and_then(|log| {
+                let (number, line) = log.last_stderr()?;
+                (number > self.stderr_dismissed).then(|| line.to_string())
+            }),
+            console: self.console_open.then(|| self.console_info()),
+            orientation: self.orientation(),
+            board_flipped: self.board_flipped,
+            engine_move: self.shown_engine_move().map(|m| (m.from, m.to)),
+            coordinates: self.board_settings.coordinates,
+            threats_shown: self.shown_threats().is_some(),
+            threats: self
+                .shown_threats()
+                .and_then(|threats| threats.moves.as_ref())
+                .map(|moves| moves.iter().map(|m| (m.get_source(), m.get_dest())).collect())
+                .unwrap_or_default(),
+            candidates_shown: self.shown_candidates().is_some(),
+            candidates: self
+                .shown_candidates()
+                .and_then(|candidates| candidates.moves.as_ref())
+                .map(|moves| {
+                    moves
+                        .iter()
+                        .map(|&(m, white_cp)| Candidate {
+                            from: m.get_source(),
+                            to: m.get_dest(),
+                            san: game::move_to_san(&self.snapshot.board, m),
+                            eval: analysis::format_eval(white_cp),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            expected_reply: self
+                .shown_engine_move()
+                .filter(|_| self.board_settings.reply_arrow)
+                .and_then(|m| m.reply)
+                .map(|reply| (reply.get_source(), reply.get_dest())),
+            slide: self.animation.map(|animation| Slide {
+                from: animation.from,
+                to: animation.to,
+                progress: animation.started.elapsed().as_millis() as f32
+                    / self.board_settings.animation_ms.max(1) as f32,
+            }),
+            export_last_move: self.export_last_move,
+            export_coordinates: self.export_coordinates,
+            export_figurine: self.export_figurine,
+            move_table: self.move_table,
+            board_cursor: self.board_cursor,
+            announcement: self.announcement.clone(),
+            go_to_move: self.go_to_move.clone(),
+            find_position: self.find_position.clone(),
+            position_not_found: self.position_not_found,
+            delete_from: self.delete_from,
+            // Read live so the running clock counts down between revisions
+            clocks: match self.online_game() {
+                Some(online) => online.clock_times(),
+                None => self.game.lock().ok().and_then(|game| game.clock_times()),
+            },
+            online: self.online_game().map(|online| OnlineInfo {
+                opponent: online.opponent.clone(),
+                rated: online.rated,
+                control: online.control.map(lichess::format_control),
+                outcome: online.state.as_ref().filter(|state| state.is_over()).map(BoardState::outcome),
+                kibitz: online.against_ai.then_some(online.kibitz),
+                kibitz_eval: online.kibitz_eval.clone(),
+            }),
+        };
+
+        // Render the UI with current window size
+        self.ui.view(&self.snapshot, &info, self.window_size.width, self.window_size.height)
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        // Subscribe to time ticks for regular updates and window resize events
+        Subscription::batch(vec![
+            iced::time::every(match self.saving_power() {
+                true => POWER_SAVER_TICK,
+                false => Duration::from_millis(100),
+            })
+            .map(|_| Message::Tick),
+            match self.animation {
+                Some(_) => window::frames().map(|_| Message::AnimationFrame),
+                None => Subscription::none(),
+            },
+            iced::subscription::events_with(|event, status| match event {
+                Event::Window(window::Event::Resized { width, height }) => {
+                    Some(Message::WindowResized(width, height))
+                }
+                Event::Window(window::Event::Moved { x, y }) => Some(Message::WindowMoved(x, y)),
+                Event::Window(window::Event::Focused) => Some(Message::WindowFocused(true)),
+                Event::Window(window::Event::Unfocused) => Some(Message::WindowFocused(false)),
+                Event::Window(window::Event::CloseRequested) => Some(Message::WindowCloseRequested),
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::F11,
+                    modifiers,
+                }) if modifiers.is_empty() => Some(Message::ToggleFullscreen),
+                // Q, R, B and N choose the promotion piece, Escape backs
+                // out; arrows and Enter play on the board without a mouse
+                Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers })
+                    if modifiers.is_empty() && status == event::Status::Ignored =>
+                {
+                    match key_code {
+                        keyboard::KeyCode::Q => Some(Message::PromotePawn(PromotionPiece::Queen)),
+                        keyboard::KeyCode::R => Some(Message::PromotePawn(PromotionPiece::Rook)),
+                        keyboard::KeyCode::B => Some(Message::PromotePawn(PromotionPiece::Bishop)),
+                        keyboard::KeyCode::N => Some(Message::PromotePawn(PromotionPiece::Knight)),
+                        keyboard::KeyCode::Escape => Some(Message::CancelPromotion),
+                        keyboard::KeyCode::Left => Some(Message::MoveBoardCursor(-1, 0)),
+                        keyboard::KeyCode::Right => Some(Message::MoveBoardCursor(1, 0)),
+                        keyboard::KeyCode::Up => Some(Message::MoveBoardCursor(0, 1)),
+                        keyboard::KeyCode::Down => Some(Message::MoveBoardCursor(0, -1)),
+                        keyboard::KeyCode::Enter | keyboard::KeyCode::Space => {
+                            Some(Message::ActivateBoardCursor)
                         }
+                        _ => None,
                     }
-                    return true;
                 }
-                false
-            },
-            |needs_check| {
-                if needs_check {
-                    Message::CheckEngineMove
-                } else {
-                    Message::Tick
+                // Ctrl+V outside a text box pastes a FEN into the setup board
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::V,
+                    modifiers,
+                }) if modifiers.command() && status == event::Status::Ignored => {
+                    Some(Message::PasteFen)
+                }
+                // Ctrl+=, Ctrl+- and Ctrl+0 zoom the interface
+                Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers })
+                    if modifiers.command() =>
+                {
+                    use keyboard::KeyCode;
+                    match key_code {
+                        KeyCode::Equals | KeyCode::Plus | KeyCode::NumpadAdd => Some(Message::ZoomUi(1)),
+                        KeyCode::Minus | KeyCode::NumpadSubtract => Some(Message::ZoomUi(-1)),
+                        KeyCode::Key0 | KeyCode::Numpad0 => Some(Message::ZoomUi(0)),
+                        _ => None,
+                    }
                 }
+                _ => None,
+            }),
+            match self.screen {
+                AppScreen::Setup(_) => iced::subscription::events_with(setup_shortcut),
+                _ => Subscription::none(),
             },
+        ])
+    }
+
+    fn scale_factor(&self) -> f64 {
+        self.ui_scale
+    }
+
+    fn theme(&self) -> Theme {
+        Theme::Dark
+    }
+}
+
+/// Setup editor keys: K, Q, R, B, N and P pick a white piece and with Shift
+/// a black one, X, Delete or Backspace the eraser, and W or Shift+W give
+/// the move to white or black (B already being the bishop).
+fn setup_shortcut(event: Event, status: event::Status) -> Option<Message> {
+    use chess::{Color, Piece};
+    use keyboard::KeyCode;
+
+    let Event::Keyboard(keyboard::Event::KeyPressed { key_code, modifiers }) = event else {
+        return None;
+    };
+    let plain = modifiers.is_empty() || modifiers == keyboard::Modifiers::SHIFT;
+    if status == event::Status::Captured || !plain {
+        return None;
+    }
+    let color = if modifiers.shift() { Color::Black } else { Color::White };
+    let piece = match key_code {
+        KeyCode::K => Piece::King,
+        KeyCode::Q => Piece::Queen,
+        KeyCode::R => Piece::Rook,
+        KeyCode::B => Piece::Bishop,
+        KeyCode::N => Piece::Knight,
+        KeyCode::P => Piece::Pawn,
+        KeyCode::X | KeyCode::Delete | KeyCode::Backspace => {
+            return Some(Message::SetupPaletteSelected(None))
+        }
+        KeyCode::W => return Some(Message::SetupSideToMove(color)),
+        _ => return None,
+    };
+    Some(Message::SetupPaletteSelected(Some((piece, color))))
+}
+
+impl ChessApp {
+    /// Save the position on the board as a .png or .svg diagram in the
+    /// pictures folder.
+    fn export_image(&mut self, extension: &str) {
+        let t = self.language.strings();
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let ply = if game.is_view_mode() {
+            game.view_move_index()
+        } else {
+            game.move_history().len()
+        };
+        let last_move = ply.checked_sub(1).and_then(|i| game.move_history().get(i).copied());
+        let options = DiagramOptions {
+            orientation: self.orientation(),
+            last_move: last_move.filter(|_| self.export_last_move),
+            coordinates: self.export_coordinates,
+        };
+        let path = export_path(dirs::picture_dir(), "chess-position", extension);
+        match diagram::save_image(&game.current_position(), &options, &path) {
+            Ok(()) => game.set_message(fill(t.saved_position, path.display())),
+            Err(e) => game.set_message(fill(t.image_failed, e)),
+        }
+    }
+
+    /// Open the Lichess analysis board: the whole game imported when there
+    /// are moves, otherwise (or if the import fails) the position shown.
+    fn open_lichess_analysis(&mut self) -> Command<Message> {
+        let t = self.language.strings();
+        let (position_url, pgn) = {
+            let Ok(mut game) = self.game.lock() else {
+                return Command::none();
+            };
+            let position_url = online::analysis_url(&game.current_position());
+            if game.move_history().is_empty() {
+                (position_url, None)
+            } else {
+                let tags = self.export_tags(&game);
+                let result = pgn::result_string(game.game_result());
+                game.set_message(t.sending_to_lichess.to_string());
+                (position_url, Some(pgn::export_pgn(&tags, game.get_move_records(), result)))
+            }
+        };
+        let Some(pgn) = pgn else {
+            self.show_in_lichess(&position_url, None);
+            return Command::none();
+        };
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    online::import_to_lichess(&pgn).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            move |result| match result {
+                Ok(url) => Message::LichessAnalysisReady(url, None),
+                Err(e) => Message::LichessAnalysisReady(position_url.clone(), Some(e)),
+            },
+        )
+    }
+
+    fn show_in_lichess(&mut self, url: &str, import_error: Option<String>) {
+        let t = self.language.strings();
+        let message = match (online::open_in_browser(url), import_error) {
+            (Err(e), _) => fill(&fill(t.open_yourself, e), url),
+            (Ok(()), None) => t.opened_lichess.to_string(),
+            (Ok(()), Some(e)) => fill(t.lichess_import_failed, e),
+        };
+        if let Ok(mut game) = self.game.lock() {
+            game.set_message(message);
+        }
+    }
+
+    /// Tags for exporting the game: the stored ones for an archived game,
+    /// otherwise fresh ones dated today.
+    fn export_tags(&self, game: &ChessGame) -> Vec<(String, String)> {
+        match self.archived {
+            Some((_, ref tags)) => tags.clone(),
+            None => {
+                let date = chrono::Local::now().format("%Y.%m.%d").to_string();
+                pgn::game_tags(game, &self.engine_name(), &date)
+            }
+        }
+    }
+
+    /// Save a printable HTML scoresheet of the game in the documents
+    /// folder.
+    fn export_scoresheet(&mut self) {
+        let t = self.language.strings();
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let tags = self.export_tags(&game);
+        let final_position = *game.position_history().last().unwrap_or(&game.start_position());
+        let html = scoresheet::scoresheet_html(
+            &tags,
+            game.get_move_records(),
+            pgn::result_string(game.game_result()),
+            &final_position,
+            self.orientation(),
+            self.export_notation(),
         );
+        let path = export_path(dirs::document_dir(), "chess-scoresheet", "html");
+        match std::fs::write(&path, html) {
+            Ok(()) => game.set_message(fill(t.saved_scoresheet, path.display())),
+            Err(e) => game.set_message(fill(t.scoresheet_failed, e)),
+        }
+    }
 
-        (app, command)
+    fn export_notation(&self) -> pgn::Notation {
+        if self.export_figurine {
+            pgn::Notation::Figurine
+        } else {
+            pgn::Notation::Letters
+        }
     }
 
-    fn title(&self) -> String {
-        String::from("Chess Engine Player")
+    /// Save the game as PGN in the documents folder.
+    fn export_pgn(&mut self) {
+        let t = self.language.strings();
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let tags = self.export_tags(&game);
+        let result = pgn::result_string(game.game_result());
+        let text = pgn::export_pgn_in(&tags, game.get_move_records(), result, self.export_notation());
+        let path = export_path(dirs::document_dir(), "chess-game", "pgn");
+        match std::fs::write(&path, text) {
+            Ok(()) => game.set_message(fill(t.saved_pgn, path.display())),
+            Err(e) => game.set_message(fill(t.pgn_failed, e)),
+        }
     }
 
-    fn update(&mut self, message: Message) -> Command<Message> {
+    /// Save the line being viewed, from the start to the move shown, as a
+    /// PGN game of its own in the documents folder.
+    fn export_line(&mut self) {
+        let t = self.language.strings();
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let ply = match game.is_view_mode() {
+            true => game.view_move_index(),
+            false => game.move_history().len(),
+        };
+        // Cut short of the end, the line has no result
+        let result = match ply == game.move_history().len() {
+            true => pgn::result_string(game.game_result()),
+            false => "*",
+        };
+        let mut tags = self.export_tags(&game);
+        for (name, value) in &mut tags {
+            if name == "Result" {
+                *value = result.to_string();
+            }
+        }
+        let records = game::records_until(game.get_move_records(), ply);
+        let text = pgn::export_pgn_in(&tags, &records, result, self.export_notation());
+        let path = export_path(dirs::document_dir(), "chess-line", "pgn");
+        match std::fs::write(&path, text) {
+            Ok(()) => game.set_message(fill(t.saved_line, path.display())),
+            Err(e) => game.set_message(fill(t.pgn_failed, e)),
+        }
+    }
+
+    /// The game in progress with its clocks and the engine's set-up, as
+    /// the save-game file and the autosave keep it.
+    fn saved_game(&self, game: &ChessGame) -> SavedGame {
+        let engine = EngineSetup {
+            path: self.engine_path.clone(),
+            skill_level: self.skill_level,
+            elo: self.elo,
+            think_time: self.think_time,
+            human_pacing: self.human_pacing,
+            variety_window: self.variety_window,
+            uci_options: self.uci_options.clone(),
+            chess960: self.game_kind == GameKind::Chess960,
+        };
+        let clock = game.time_control().map(|control| {
+            let base = Duration::from_millis(control.base_ms);
+            let (white, black) = game.clock_remaining().unwrap_or((base, base));
+            SavedClock {
+                base_ms: control.base_ms,
+                increment_ms: control.increment_ms,
+                mode: control.mode,
+                engine_base_ms: game.time_odds().map(|odds| odds.base_ms),
+                engine_increment_ms: game.time_odds().map(|odds| odds.increment_ms),
+                white_ms: white.as_millis() as u64,
+                black_ms: black.as_millis() as u64,
+                move_clocks: game
+                    .move_clocks()
+                    .iter()
+                    .map(|left| left.map(|left| left.as_millis() as u64))
+                    .collect(),
+            }
+        });
+        SavedGame {
+            start_fen: game.start_fen(),
+            moves: game.move_history().iter().map(|m| m.to_string()).collect(),
+            player_color: SavedGame::color_code(game.player_color()),
+            rated: game.is_rated(),
+            clock,
+            engine: Some(engine),
+        }
+    }
+
+    /// Save the game in progress to a file in the documents folder, with
+    /// its clocks and the engine's set-up, to resume whenever.
+    fn save_game_file(&mut self) {
+        let t = self.language.strings();
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let saved = self.saved_game(&game);
+        let path = export_path(dirs::document_dir(), "chess-game", "json");
+        match persistence::save_game_file(&path, &saved) {
+            Ok(()) => game.set_message(fill(t.saved_game_file, path.display())),
+            Err(e) => game.set_message(fill(t.save_game_failed, e)),
+        }
+    }
+
+    /// Put a game saved to a file back on the board, with its clocks, and
+    /// configure the engine as it was.  Returns whether the engine has to
+    /// be restarted for that, or why the game can't be loaded; nothing is
+    /// changed then.
+    fn restore_saved_game(&mut self, saved: &SavedGame) -> Result<bool, String> {
+        let t = self.language.strings();
+        // Check the start position before the engine is set up for the game
+        ChessGame::new().reset_from_fen(&saved.start_fen, saved.player_color())?;
+        self.set_drill(None);
+        let restart = match saved.engine {
+            Some(ref setup) => {
+                self.engine_path = setup.path.clone();
+                self.engine_path_input = setup.path.display().to_string();
+                self.uci_options = setup.uci_options.clone();
+                self.skill_level = setup.skill_level;
+                self.elo = setup.elo;
+                self.think_time = setup.think_time;
+                self.human_pacing = setup.human_pacing;
+                self.variety_window = setup.variety_window;
+                // The restarted engine is switched to Chess960 once it is up
+                self.game_kind = if setup.chess960 { GameKind::Chess960 } else { GameKind::Standard };
+                true
+            }
+            None => {
+                self.set_game_kind(GameKind::Standard);
+                false
+            }
+        };
+
+        self.archived = None;
+        if let Ok(mut game) = self.game.lock() {
+            game.set_time_control(saved.clock.as_ref().map(|clock| TimeControl {
+                base_ms: clock.base_ms,
+                increment_ms: clock.increment_ms,
+                mode: clock.mode,
+            }));
+            game.set_time_odds(saved.clock.as_ref().and_then(|clock| {
+                Some(TimeControl {
+                    base_ms: clock.engine_base_ms?,
+                    increment_ms: clock.engine_increment_ms.unwrap_or(0),
+                    mode: clock.mode,
+                })
+            }));
+            let applied = game.load_moves(&saved.start_fen, &saved.moves, saved.player_color())?;
+            game.set_rated(saved.rated);
+            if let Some(ref clock) = saved.clock {
+                let move_clocks: Vec<Option<Duration>> =
+                    clock.move_clocks.iter().map(|ms| ms.map(Duration::from_millis)).collect();
+                game.apply_clocks(&move_clocks);
+                game.set_clocks(Duration::from_millis(clock.white_ms), Duration::from_millis(clock.black_ms));
+            }
+            game.set_message(fill(t.resumed_game, applied));
+            self.game_saved = game.game_result().is_some();
+        }
+        Ok(restart)
+    }
+
+    /// Side shown at the bottom of the board: the player's, unless the
+    /// board is flipped.
+    fn orientation(&self) -> chess::Color {
+        if self.board_flipped {
+            !self.snapshot.player_color
+        } else {
+            self.snapshot.player_color
+        }
+    }
+
+    /// Re-copy the game state for rendering if anything changed since the
+    /// last refresh.  Idle ticks only pay for a lock and a comparison.
+    fn refresh_snapshot(&mut self) {
+        let previous = self.snapshot.board;
+        if let Ok(game) = self.game.lock() {
+            if game.revision() == self.snapshot.revision {
+                return;
+            }
+            self.snapshot = game.snapshot();
+        }
+        self.animate_move(previous);
+        self.announce_position();
+    }
+
+    /// Play a click (or, in touch mode, a tap or long press) on a square of
+    /// the game board.
+    fn play_square(&mut self, square: chess::Square, long_press: bool) -> Command<Message> {
+        let touch = self.board_settings.touch_mode;
+        let (needs_engine_move, needs_check, quick_promotion) = if let Ok(mut game) = self.game.lock() {
+            // select_square returns true for both "move completed"
+            // and "promotion dialog opened".  Don't ask the engine
+            // to move until the player has chosen a promotion piece.
+            // In coach mode the move is only held for checking.
+            let selected = game.select_square(square);
+            let moved = selected && game.pending_promotion().is_none();
+            let held = moved && game.pending_confirmation().is_some();
+            // A tap in touch mode promotes to a queen; a long press brings
+            // up the picker
+            let quick_promotion = selected && !moved && touch && !long_press;
+            (moved && !held, held, quick_promotion)
+        } else {
+            (false, false, false)
+        };
+
+        if quick_promotion {
+            self.refresh_snapshot();
+            return self.handle_message(Message::PromotePawn(PromotionPiece::Queen));
+        }
+
+        if needs_check {
+            return self.start_coach_check();
+        }
+
+        // Move was made, get engine response
+        if needs_engine_move {
+            return self.respond_to_player_move();
+        }
+        Command::none()
+    }
+
+    /// Zoom the interface.  The window keeps its size on screen, so the
+    /// room the layout has shrinks or grows by the same factor.
+    fn set_ui_scale(&mut self, scale: f64) {
+        let scale = settings::clamp_ui_scale(scale);
+        let ratio = self.ui_scale / scale;
+        self.window_size = Size::new(
+            (f64::from(self.window_size.width) * ratio).round() as u32,
+            (f64::from(self.window_size.height) * ratio).round() as u32,
+        );
+        self.ui_scale = scale;
+    }
+
+    /// Put the move that led to the board, or the game's result, into
+    /// words, printing it too when asked to on the command line.
+    fn announce_position(&mut self) {
+        let t = self.language.strings();
+        let snapshot = &self.snapshot;
+        let ply = if snapshot.view_mode {
+            snapshot.view_move_index
+        } else {
+            snapshot
+                .move_records
+                .iter()
+                .flat_map(|r| [&r.white_move, &r.black_move])
+                .flatten()
+                .count()
+        };
+        let mover = !snapshot.board.side_to_move();
+        let mut words = game::move_details(&snapshot.move_records, ply)
+            .map(|details| game::describe_move(&details.notation, mover, t));
+        if let (false, Some(result)) = (snapshot.view_mode, snapshot.result_text(t)) {
+            words = Some(match words {
+                Some(last_move) => format!("{}. {}", last_move, result),
+                None => result,
+            });
+        }
+        if words != self.announcement {
+            if let (true, Some(words)) = (self.announce, &words) {
+                println!("{}", words);
+            }
+            self.announcement = words;
+        }
+    }
+
+    /// The engine's last move, if the board still shows the position it
+    /// made.
+    fn shown_engine_move(&self) -> Option<&EngineMove> {
+        self.engine_move.as_ref().filter(|m| m.board == self.snapshot.board)
+    }
+
+    /// The game's state for the window title, so the taskbar shows it:
+    /// the result once it is over, otherwise whose move it is, the move
+    /// number and the mover's clock.  None before the first move.
+    fn title_status(&self) -> Option<String> {
+        let t = self.language.strings();
+        let snapshot = &self.snapshot;
+        if snapshot.move_records.is_empty() && snapshot.game_result.is_none() {
+            return None;
+        }
+        if let Some(result) = snapshot.game_result {
+            return Some(fill(t.game_over, pgn::result_string(Some(result))));
+        }
+
+        let (fen, clocks) = match self.online_game() {
+            Some(online) => (None, online.clock_times()),
+            None => match self.game.lock() {
+                Ok(game) => (Some(game.current_fen()), game.clock_times()),
+                Err(_) => (None, None),
+            },
+        };
+        let side = snapshot.board.side_to_move();
+        let mut status = if side == snapshot.player_color {
+            t.title_your_move.to_string()
+        } else {
+            t.title_their_move.to_string()
+        };
+        // The move number is the FEN's last field
+        if let Some(number) = fen.as_deref().and_then(|fen| fen.split_whitespace().last()) {
+            let dots = if side == chess::Color::White { "." } else { "..." };
+            status.push_str(&format!(" ({}{})", number, dots));
+        }
+        let clock = clocks.and_then(|clocks| match side {
+            chess::Color::White => clocks.white,
+            chess::Color::Black => clocks.black,
+        });
+        if let Some(time) = clock {
+            status.push_str(&format!(" {}", clock::display_clock(time)));
+        }
+        Some(status)
+    }
+
+    /// The threats, if they were found for the position on the board.
+    fn shown_threats(&self) -> Option<&Threats> {
+        self.threats.as_ref().filter(|t| t.board == self.snapshot.board)
+    }
+
+    /// The candidate moves, if they were asked for in the position on the
+    /// board.
+    fn shown_candidates(&self) -> Option<&Candidates> {
+        self.candidates.as_ref().filter(|c| c.board == self.snapshot.board)
+    }
+
+    /// Slide the piece when the board changed by a single move, whoever
+    /// made it; anything else, like loading a game, just redraws.
+    fn animate_move(&mut self, previous: chess::Board) {
+        let board = self.snapshot.board;
+        if previous == board {
+            return;
+        }
+        self.animation = (self.board_settings.animation_ms > 0)
+            .then(|| chess::MoveGen::new_legal(&previous).find(|&m| previous.make_move_new(m) == board))
+            .flatten()
+            .map(|chess_move| MoveAnimation {
+                from: chess_move.get_source(),
+                to: chess_move.get_dest(),
+                started: Instant::now(),
+            });
+    }
+
+    /// The most recent console lines containing the filter text, ignoring
+    /// case.
+    fn console_info(&self) -> ConsoleInfo {
+        let needle = self.console_filter.to_lowercase();
+        let mut lines: Vec<UciLine> = match self.uci_log.lock() {
+            Ok(log) => log
+                .lines()
+                .filter(|line| line.text.to_lowercase().contains(&needle))
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        lines.drain(..lines.len().saturating_sub(CONSOLE_LINES));
+        ConsoleInfo {
+            filter: self.console_filter.clone(),
+            command: self.console_command.clone(),
+            lines,
+        }
+    }
+
+    fn handle_message(&mut self, message: Message) -> Command<Message> {
         match message {
-            Message::SquareClicked(square) => {
-                // Handle square click
-                if let Ok(mut game) = self.game.lock() {
-                    if game.select_square(square) {
-                        // select_square returns true for both "move completed"
-                        // and "promotion dialog opened".  Don't ask the engine
-                        // to move until the player has chosen a promotion piece.
-                        if game.pending_promotion().is_some() {
-                            return Command::none();
-                        }
+            Message::SquareClicked(square) => self.play_square(square, false),
+            Message::SquareLongPressed(square) => self.play_square(square, true),
 
-                        // Move was made, get engine response
-                        if game.game_result().is_none() {
-                            self.engine_thinking = true;
-                            game.set_thinking(true);
-
-                            // Get engine move
-                            let engine_clone = Arc::clone(&self.engine);
-                            let game_clone = Arc::clone(&self.game);
-
-                            return Command::perform(
-                                async move {
-                                    if let Ok(game) = game_clone.lock() {
-                                        if let Ok(mut engine) = engine_clone.lock() {
-                                            let fen = game.current_position().to_string();
-                                            let _ = engine.get_move(&fen);
-                                        }
-                                    }
-                                },
-                                |_| Message::CheckEngineMove,
-                            );
-                        }
+            Message::ResetGame => {
+                if self.playing_online() {
+                    return Command::none();
+                }
+                // In a drill, start over with a fresh line or the same puzzle
+                match self.drill {
+                    Some(Drill::Repertoire(_)) => {
+                        self.start_training_game();
+                        return Command::none();
+                    }
+                    Some(Drill::Puzzle(_)) => {
+                        self.start_puzzle();
+                        return Command::none();
                     }
+                    Some(Drill::Endgame(_)) => return self.start_endgame(),
+                    None => {}
+                }
+
+                if self.game_kind != GameKind::Standard {
+                    let rated = self.snapshot.rated;
+                    let command = self.start_new_game(self.game_kind, self.snapshot.player_color);
+                    if let Ok(mut game) = self.game.lock() {
+                        game.set_rated(rated);
+                    }
+                    return command;
                 }
-                Command::none()
-            }
 
-            Message::ResetGame => {
                 // Reset the game
-                if let Ok(mut game) = self.game.lock() {
+                let needs_engine_move = if let Ok(mut game) = self.game.lock() {
                     game.reset();
+                    game.player_color() == chess::Color::Black
+                } else {
+                    false
+                };
 
-                    // If playing as black, get first move from engine
-                    if game.player_color() == chess::Color::Black {
-                        self.engine_thinking = true;
-                        game.set_thinking(true);
-
-                        let engine_clone = Arc::clone(&self.engine);
-                        let game_clone = Arc::clone(&self.game);
-
-                        return Command::perform(
-                            async move {
-                                if let Ok(game) = game_clone.lock() {
-                                    if let Ok(mut engine) = engine_clone.lock() {
-                                        let fen = game.current_position().to_string();
-                                        let _ = engine.get_move(&fen);
-                                    }
-                                }
-                            },
-                            |_| Message::CheckEngineMove,
-                        );
-                    }
+                // If playing as black, get first move from engine
+                if needs_engine_move {
+                    return self.request_engine_move();
                 }
                 Command::none()
             }
 
             Message::UndoMove => {
+                if self.playing_online() {
+                    return Command::none();
+                }
                 // Undo the last move pair
                 let mut needs_engine_move = false;
                 if let Ok(mut game) = self.game.lock() {
@@ -540,31 +2922,48 @@ impl Application for ChessApp {
                 }
                 
                 // If it's the engine's turn, trigger engine move
-                if needs_engine_move {
-                    self.engine_thinking = true;
-                    if let Ok(mut game) = self.game.lock() {
-                        game.set_thinking(true);
-                    }
-                    let engine_clone = Arc::clone(&self.engine);
-                    let game_clone = Arc::clone(&self.game);
-                    
-                    return Command::perform(
-                        async move {
-                            if let Ok(game) = game_clone.lock() {
-                                if let Ok(mut engine) = engine_clone.lock() {
-                                    let fen = game.current_position().to_string();
-                                    let _ = engine.get_move(&fen);
-                                }
-                            }
-                        },
-                        |_| Message::CheckEngineMove,
-                    );
+                if needs_engine_move && self.drill.is_none() {
+                    return self.request_engine_move();
                 }
                 
                 Command::none()
             }
 
+            Message::TogglePause => {
+                if self.playing_online() {
+                    return Command::none();
+                }
+                let paused = match self.game.lock() {
+                    Ok(mut game) => {
+                        let pause = !game.is_paused();
+                        game.set_paused(pause);
+                        game.is_paused()
+                    }
+                    Err(_) => return Command::none(),
+                };
+                if paused {
+                    self.paused_at = Some(Instant::now());
+                    return Command::none();
+                }
+                // A paced reply and the watchdog keep the time they had left
+                if let Some(since) = self.paused_at.take() {
+                    for due in [&mut self.reply_due, &mut self.search_deadline].into_iter().flatten() {
+                        *due += since.elapsed();
+                    }
+                }
+                if self.engine_thinking || self.held_move.is_some() {
+                    return Command::perform(async {}, |_| Message::CheckEngineMove);
+                }
+                Command::none()
+            }
+
             Message::FlipSide => {
+                if self.playing_online() {
+                    return Command::none();
+                }
+                // Switching sides leaves a repertoire drill
+                self.set_drill(None);
+
                 // Flip the player's side
                 let needs_engine_move = if let Ok(mut game) = self.game.lock() {
                     game.flip_side();
@@ -576,35 +2975,45 @@ impl Application for ChessApp {
 
                 // If it's the engine's turn, trigger engine move
                 if needs_engine_move {
-                    self.engine_thinking = true;
-                    if let Ok(mut game) = self.game.lock() {
-                        game.set_thinking(true);
-                    }
-                    let engine_clone = Arc::clone(&self.engine);
-                    let game_clone = Arc::clone(&self.game);
-
-                    return Command::perform(
-                        async move {
-                            if let Ok(game) = game_clone.lock() {
-                                if let Ok(mut engine) = engine_clone.lock() {
-                                    let fen = game.current_position().to_string();
-                                    let _ = engine.get_move(&fen);
-                                }
-                            }
-                        },
-                        |_| Message::CheckEngineMove,
-                    );
+                    return self.request_engine_move();
                 }
 
                 Command::none()
             }
 
+            Message::FlipBoard => {
+                self.board_flipped = !self.board_flipped;
+                Command::none()
+            }
+
+            Message::AnimationFrame => {
+                let finished = self.animation.is_some_and(|animation| {
+                    animation.started.elapsed().as_millis() >= self.board_settings.animation_ms as u128
+                });
+                if finished {
+                    self.animation = None;
+                }
+                Command::none()
+            }
+
             Message::EngineMoved(best_move) => {
                 // Apply the engine's move
+                let reply = self.expected_reply(&best_move);
                 if let Ok(mut game) = self.game.lock() {
-                    game.make_engine_move(&best_move);
+                    if game.make_engine_move(&best_move) {
+                        let board = game.current_position();
+                        self.engine_move = game.move_history().last().map(|m| EngineMove {
+                            board,
+                            from: m.get_source(),
+                            to: m.get_dest(),
+                            reply: reply.and_then(|uci| game::parse_uci_move(&board, &uci)),
+                        });
+                    }
                     self.engine_thinking = false;
                 }
+                if matches!(self.drill, Some(Drill::Endgame(_))) {
+                    self.assess_endgame();
+                }
                 // Scroll move history to bottom to show latest move
                 iced::widget::scrollable::snap_to(
                     iced::widget::scrollable::Id::new("move_history"),
@@ -613,11 +3022,27 @@ impl Application for ChessApp {
             }
 
             Message::CheckEngineMove => {
+                // A paused game leaves the reply with the engine; resuming
+                // picks it up
+                if self.game.lock().is_ok_and(|game| game.is_paused()) {
+                    return Command::none();
+                }
                 // Check if engine has a move ready
-                if let Ok(engine) = self.engine.lock() {
-                    if let Some(best_move) = engine.try_receive_move() {
-                        return Command::perform(async { best_move }, Message::EngineMoved);
+                let received = match self.held_move.take() {
+                    Some(best_move) => Some(best_move),
+                    None => self.receive_engine_move(),
+                };
+                if let Some(best_move) = received {
+                    // A human-paced reply waits until its time is up
+                    let now = Instant::now();
+                    if let Some(due) = self.reply_due.filter(|due| *due > now) {
+                        self.held_move = Some(best_move);
+                        return Command::perform(tokio::time::sleep(due - now), |_| {
+                            Message::CheckEngineMove
+                        });
                     }
+                    self.reply_due = None;
+                    return Command::perform(async { best_move }, Message::EngineMoved);
                 }
 
                 // Schedule another check if engine is still thinking
@@ -628,10 +3053,187 @@ impl Application for ChessApp {
                 Command::none()
             }
 
+            Message::EngineStarted { elo_range, elo, id } => {
+                let t = self.language.strings();
+                self.elo_range = elo_range;
+                self.engine_id = id;
+                // A restarted engine has forgotten Chess960 castling
+                if self.game_kind == GameKind::Chess960 {
+                    if let Ok(mut engine) = self.engine.lock() {
+                        if let Err(e) = engine.set_chess960(true) {
+                            eprintln!("Failed to switch engine to Chess960: {}", e);
+                        }
+                    }
+                }
+                if self.elo.is_some() && elo.is_none() {
+                    if let Ok(mut game) = self.game.lock() {
+                        game.set_message(t.no_elo_limit.to_string());
+                    }
+                }
+                self.elo = elo;
+                self.engine_error = None;
+                self.engine_multipv = 1;
+
+                // Playing as Black, or retried while it was the engine's turn
+                let needs_engine_move = if let Ok(game) = self.game.lock() {
+                    game.game_result().is_none()
+                        && game.current_position().side_to_move() != game.player_color()
+                } else {
+                    false
+                };
+                if needs_engine_move && self.resume_offer.is_none() && self.drill.is_none() {
+                    return self.request_engine_move();
+                }
+                Command::none()
+            }
+
+            Message::EngineFailed(error) => {
+                self.engine_error = Some(error);
+                self.engine_thinking = false;
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_thinking(false);
+                }
+                Command::none()
+            }
+
+            Message::RetryEngine => self.start_engine(),
+
+            Message::StopEngineSearch => {
+                if let Ok(mut engine) = self.engine.lock() {
+                    if let Err(e) = engine.send_command("stop") {
+                        eprintln!("Failed to stop the engine: {}", e);
+                    }
+                }
+                // Give the engine a moment to answer before offering again
+                self.search_deadline = Some(Instant::now() + ENGINE_GRACE);
+                Command::none()
+            }
+
+            // The engine is replaced mid-search; once it is up again it is
+            // asked for its move afresh
+            Message::RestartEngine => {
+                let t = self.language.strings();
+                self.engine_thinking = false;
+                self.search_deadline = None;
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_thinking(false);
+                    game.set_message(t.restarting_engine.to_string());
+                }
+                self.start_engine()
+            }
+
+            Message::DismissEngineStderr => {
+                if let Ok(log) = self.uci_log.lock() {
+                    if let Some((number, _)) = log.last_stderr() {
+                        self.stderr_dismissed = number;
+                    }
+                }
+                Command::none()
+            }
+
+            Message::EnginePathChanged(path) => {
+                self.engine_path_input = path;
+                Command::none()
+            }
+
+            Message::UseEnginePath => {
+                let path = self.engine_path_input.trim();
+                if path.is_empty() {
+                    return Command::none();
+                }
+                self.engine_path = PathBuf::from(path);
+                self.start_engine()
+            }
+
+            Message::ToggleEloLimit => {
+                let Some((min, max)) = self.elo_range else {
+                    return Command::none();
+                };
+                self.elo = match self.elo {
+                    Some(_) => None,
+                    None => Some(1500.clamp(min, max)),
+                };
+                self.apply_strength()
+            }
+
+            // The slider only moves the value; the engine is told on release
+            Message::EloChanged(elo) => {
+                if self.elo.is_some() {
+                    self.elo = Some(elo);
+                }
+                Command::none()
+            }
+
+            Message::SkillLevelChanged(level) => {
+                self.skill_level = level;
+                Command::none()
+            }
+
+            // Takes effect from the engine's next move
+            Message::ThinkTimeChanged(think_time) => {
+                self.think_time = think_time;
+                Command::none()
+            }
+
+            Message::ApplyStrength => self.apply_strength(),
+
             Message::Tick => {
                 // Regular tick for UI updates
+                self.autosave_if_due();
+                self.check_flag();
+                let mut commands = vec![
+                    self.check_power(),
+                    self.poll_lichess(),
+                    self.follow_with_kibitzers(),
+                ];
                 if self.engine_thinking {
-                    return Command::perform(async {}, |_| Message::CheckEngineMove);
+                    commands.push(Command::perform(async {}, |_| Message::CheckEngineMove));
+                }
+                // Keep the console following new traffic
+                let total = self.uci_log.lock().map(|log| log.total()).unwrap_or(0);
+                if self.console_open && total != self.console_seen {
+                    self.console_seen = total;
+                    commands.push(iced::widget::scrollable::snap_to(
+                        iced::widget::scrollable::Id::new("uci_console"),
+                        iced::widget::scrollable::RelativeOffset::END,
+                    ));
+                }
+                Command::batch(commands)
+            }
+
+            Message::ToggleConsole => {
+                self.console_open = !self.console_open;
+                self.console_seen = 0;
+                Command::none()
+            }
+
+            Message::ConsoleFilterChanged(filter) => {
+                self.console_filter = filter;
+                self.console_seen = 0;
+                Command::none()
+            }
+
+            Message::ConsoleCommandChanged(command) => {
+                self.console_command = command;
+                Command::none()
+            }
+
+            Message::SendConsoleCommand => {
+                let t = self.language.strings();
+                let command = std::mem::take(&mut self.console_command);
+                let command = command.trim();
+                if command.is_empty() {
+                    return Command::none();
+                }
+                // The reply shows up in the console through the reader thread
+                let result = match self.engine.lock() {
+                    Ok(mut engine) => engine.send_command(command),
+                    Err(_) => return Command::none(),
+                };
+                if let Err(e) = result {
+                    if let Ok(mut game) = self.game.lock() {
+                        game.set_message(fill(&fill(t.send_failed, command), e));
+                    }
                 }
                 Command::none()
             }
@@ -639,6 +3241,73 @@ impl Application for ChessApp {
             Message::WindowResized(width, height) => {
                 // Update window size
                 self.window_size = Size::new(width, height);
+                // Events measure in zoomed pixels; the full screen size isn't
+                // the window's own
+                if !self.fullscreen {
+                    self.window_geometry.width = (f64::from(width) * self.ui_scale).round() as u32;
+                    self.window_geometry.height = (f64::from(height) * self.ui_scale).round() as u32;
+                }
+                Command::none()
+            }
+
+            // The kibitzers are restarted on the position by the next tick
+            // once the window has the focus again
+            Message::WindowFocused(focused) => {
+                self.focused = focused;
+                if self.analysis_paused() {
+                    for kibitzer in self.kibitzers.iter_mut() {
+                        if let Ok(mut engine) = kibitzer.engine.try_lock() {
+                            engine.stop_search();
+                            kibitzer.board = None;
+                        }
+                    }
+                }
+                Command::none()
+            }
+
+            Message::WindowMoved(x, y) => {
+                if !self.fullscreen {
+                    let unzoom = |v: i32| (f64::from(v) * self.ui_scale).round() as i32;
+                    self.window_geometry.position = Some((unzoom(x), unzoom(y)));
+                }
+                Command::none()
+            }
+
+            Message::WindowCloseRequested => {
+                // Sum up the session before quitting; closing again quits
+                let summarised = matches!(self.screen, AppScreen::Session(ref state) if state.quitting);
+                if !self.session.games.is_empty() && !summarised {
+                    let state = SessionState::load(self.database.as_ref(), &self.session, true);
+                    self.screen = AppScreen::Session(state);
+                    return Command::none();
+                }
+                let saved = UserSettings {
+                    window: Some(self.window_geometry),
+                    ..settings::load_settings()
+                };
+                if let Err(e) = settings::save_settings(&saved) {
+                    eprintln!("Failed to save settings: {}", e);
+                }
+                window::close()
+            }
+
+            Message::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                window::change_mode(if self.fullscreen {
+                    window::Mode::Fullscreen
+                } else {
+                    window::Mode::Windowed
+                })
+            }
+
+            Message::SetMoveGlyph(glyph) => {
+                self.annotate_viewed_move(|annotation| annotation.glyph = glyph);
+                Command::none()
+            }
+
+            Message::MoveCommentChanged(comment) => {
+                let comment = (!comment.trim().is_empty()).then_some(comment);
+                self.annotate_viewed_move(|annotation| annotation.comment = comment);
                 Command::none()
             }
 
@@ -650,6 +3319,106 @@ impl Application for ChessApp {
                 Command::none()
             }
 
+            Message::GoToMoveChanged(input) => {
+                self.go_to_move = input;
+                Command::none()
+            }
+
+            Message::GoToMove => {
+                let ply = game::find_ply(&self.snapshot.move_records, &self.go_to_move);
+                if let (Some(ply), Ok(mut game)) = (ply, self.game.lock()) {
+                    game.view_move_at(ply);
+                    self.go_to_move.clear();
+                }
+                Command::none()
+            }
+
+            Message::FindPositionChanged(input) => {
+                self.find_position = input;
+                self.position_not_found = false;
+                Command::none()
+            }
+
+            // The text stays, so searching again moves on to the next match
+            Message::FindPosition => {
+                let Ok(mut game) = self.game.lock() else {
+                    return Command::none();
+                };
+                let positions = game.position_history();
+                let from = match game.is_view_mode() {
+                    true => game.view_move_index() + 1,
+                    false => positions.len(),
+                };
+                match game::find_position(positions, &self.find_position, from) {
+                    Some(ply) => game.view_move_at(ply),
+                    None => self.position_not_found = true,
+                }
+                Command::none()
+            }
+
+            Message::FollowVariation(node) => {
+                if let Ok(mut game) = self.game.lock() {
+                    if !game.is_thinking() {
+                        game.follow_variation(node);
+                    }
+                }
+                Command::none()
+            }
+
+            Message::DeleteFrom(ply) => {
+                self.delete_from = Some(ply);
+                Command::none()
+            }
+
+            Message::CancelDelete => {
+                self.delete_from = None;
+                Command::none()
+            }
+
+            Message::ConfirmDelete => {
+                let Some(ply) = self.delete_from.take() else {
+                    return Command::none();
+                };
+                if self.playing_online() {
+                    return Command::none();
+                }
+                let mut needs_engine_move = false;
+                if let Ok(mut game) = self.game.lock() {
+                    if game.is_thinking() {
+                        return Command::none();
+                    }
+                    game.delete_from(ply);
+                    needs_engine_move = !game.is_view_mode()
+                        && game.game_result().is_none()
+                        && game.current_position().side_to_move() != game.player_color();
+                }
+                if needs_engine_move && self.drill.is_none() {
+                    return self.request_engine_move();
+                }
+                Command::none()
+            }
+
+            Message::PromoteVariation(node) => {
+                if let Ok(mut game) = self.game.lock() {
+                    game.promote_variation(node);
+                }
+                Command::none()
+            }
+
+            Message::DemoteVariation(node) => {
+                if let Ok(mut game) = self.game.lock() {
+                    game.demote_variation(node);
+                }
+                Command::none()
+            }
+
+            Message::StepMove(plies) => {
+                if let Ok(mut game) = self.game.lock() {
+                    game.step_view(plies);
+                }
+                Command::none()
+            }
+
             Message::ExitViewMode => {
                 // Exit view mode and return to current position
                 if let Ok(mut game) = self.game.lock() {
@@ -658,34 +3427,70 @@ impl Application for ChessApp {
                 Command::none()
             }
 
-            Message::PromotePawn(promotion_piece) => {
+            Message::MoveBoardCursor(files, ranks) => {
+                if !matches!(self.screen, AppScreen::Game) {
+                    return Command::none();
+                }
+                // Screen directions run the other way with Black at the bottom
+                let (files, ranks) = match self.orientation() {
+                    chess::Color::White => (files, ranks),
+                    chess::Color::Black => (-files, -ranks),
+                };
+                let start = self.board_cursor.or(self.snapshot.selected_square).unwrap_or(
+                    match self.orientation() {
+                        chess::Color::White => chess::Square::E1,
+                        chess::Color::Black => chess::Square::E8,
+                    },
+                );
+                let file = (start.get_file().to_index() as i8 + files).clamp(0, 7);
+                let rank = (start.get_rank().to_index() as i8 + ranks).clamp(0, 7);
+                // The first press only shows the cursor where it starts
+                self.board_cursor = Some(match self.board_cursor {
+                    Some(_) => chess::Square::make_square(
+                        chess::Rank::from_index(rank as usize),
+                        chess::File::from_index(file as usize),
+                    ),
+                    None => start,
+                });
+                Command::none()
+            }
+            Message::ActivateBoardCursor => match (&self.screen, self.board_cursor) {
+                (AppScreen::Game, Some(square)) => self.handle_message(Message::SquareClicked(square)),
+                _ => Command::none(),
+            },
+            Message::CancelPromotion => {
                 if let Ok(mut game) = self.game.lock() {
-                    if game.promote_pawn(promotion_piece) {
-                        // Move was made, get engine response
-                        if game.game_result().is_none() {
-                            self.engine_thinking = true;
-                            game.set_thinking(true);
-
-                            let engine_clone = Arc::clone(&self.engine);
-                            let game_clone = Arc::clone(&self.game);
-
-                            return Command::perform(
-                                async move {
-                                    if let Ok(game) = game_clone.lock() {
-                                        if let Ok(mut engine) = engine_clone.lock() {
-                                            let fen = game.current_position().to_string();
-                                            let _ = engine.get_move(&fen);
-                                        }
-                                    }
-                                },
-                                |_| Message::CheckEngineMove,
-                            );
-                        }
+                    if game.pending_promotion().is_some() {
+                        game.cancel_promotion();
                     }
                 }
                 Command::none()
             }
 
+            Message::PromotePawn(promotion_piece) => {
+                // Keys arrive whether or not the promotion panel is up
+                if self.snapshot.pending_promotion.is_none() {
+                    return Command::none();
+                }
+                let (needs_engine_move, needs_check) = if let Ok(mut game) = self.game.lock() {
+                    let moved = game.promote_pawn(promotion_piece);
+                    let held = moved && game.pending_confirmation().is_some();
+                    (moved && !held, held)
+                } else {
+                    (false, false)
+                };
+
+                if needs_check {
+                    return self.start_coach_check();
+                }
+
+                // Move was made, get engine response
+                if needs_engine_move {
+                    return self.respond_to_player_move();
+                }
+                Command::none()
+            }
+
             Message::ScrollToBottom => {
                 // Scroll move history to bottom - handled by the command
                 Command::none()
@@ -693,6 +3498,9 @@ impl Application for ChessApp {
 
             // ── Setup screen messages ─────────────────────────────────────
             Message::EnterSetupMode => {
+                if !self.assists_allowed() {
+                    return Command::none();
+                }
                 let (board, player_color) = if let Ok(game) = self.game.lock() {
                     (game.current_position(), game.player_color())
                 } else {
@@ -708,6 +3516,7 @@ impl Application for ChessApp {
             }
 
             Message::SetupSquareClicked(sq) => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
                     match state.selected_palette {
                         Some((piece, color)) => {
@@ -717,7 +3526,7 @@ impl Application for ChessApp {
                             state.pieces.remove(&sq);
                         }
                     }
-                    state.rebuild_fen();
+                    state.rebuild_fen(t);
                 }
                 Command::none()
             }
@@ -730,40 +3539,181 @@ impl Application for ChessApp {
             }
 
             Message::SetupSideToMove(color) => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
                     state.side_to_move = color;
-                    state.rebuild_fen();
+                    state.rebuild_fen(t);
                 }
                 Command::none()
             }
 
             Message::SetupCastlingToggle(mask) => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
                     if mask & 1 != 0 { state.castle_wk = !state.castle_wk; }
                     if mask & 2 != 0 { state.castle_wq = !state.castle_wq; }
                     if mask & 4 != 0 { state.castle_bk = !state.castle_bk; }
                     if mask & 8 != 0 { state.castle_bq = !state.castle_bq; }
-                    state.rebuild_fen();
+                    state.rebuild_fen(t);
                 }
                 Command::none()
             }
 
             Message::SetupEnPassant(file) => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
                     state.en_passant_file = file;
-                    state.rebuild_fen();
+                    state.rebuild_fen(t);
+                }
+                Command::none()
+            }
+
+            Message::SetupHalfmoveClock(input) => {
+                let t = self.language.strings();
+                let value = counter_input(&input);
+                if let (AppScreen::Setup(ref mut state), Some(value)) = (&mut self.screen, value) {
+                    state.halfmove_clock = value;
+                    state.rebuild_fen(t);
+                }
+                Command::none()
+            }
+
+            Message::SetupFullmoveNumber(input) => {
+                let t = self.language.strings();
+                let value = counter_input(&input);
+                if let (AppScreen::Setup(ref mut state), Some(value)) = (&mut self.screen, value) {
+                    state.fullmove_number = value;
+                    state.rebuild_fen(t);
                 }
                 Command::none()
             }
 
             Message::SetupFenChanged(fen) => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
-                    state.parse_fen_to_state(&fen);
+                    state.parse_fen_to_state(&fen, t);
+                }
+                Command::none()
+            }
+
+            Message::ExportImage(extension) => {
+                self.export_image(extension);
+                Command::none()
+            }
+
+            Message::OpenLichessAnalysis => self.open_lichess_analysis(),
+
+            Message::LichessAnalysisReady(url, import_error) => {
+                self.show_in_lichess(&url, import_error);
+                Command::none()
+            }
+
+            Message::ExportScoresheet => {
+                self.export_scoresheet();
+                Command::none()
+            }
+
+            Message::ExportPgn => {
+                self.export_pgn();
+                Command::none()
+            }
+
+            Message::ExportLine => {
+                self.export_line();
+                Command::none()
+            }
+
+            Message::ToggleExportFigurine => {
+                self.export_figurine = !self.export_figurine;
+                Command::none()
+            }
+
+            Message::ToggleMoveTable => {
+                self.move_table = !self.move_table;
+                Command::none()
+            }
+
+            Message::CopyBoardText => {
+                let t = self.language.strings();
+                let Ok(mut game) = self.game.lock() else {
+                    return Command::none();
+                };
+                let text = diagram::board_text(&game.current_position(), self.orientation(), false);
+                game.set_message(t.copied_board_text.to_string());
+                iced::clipboard::write(text)
+            }
+
+            Message::ToggleExportLastMove => {
+                self.export_last_move = !self.export_last_move;
+                Command::none()
+            }
+
+            Message::ToggleExportCoordinates => {
+                self.export_coordinates = !self.export_coordinates;
+                Command::none()
+            }
+
+            Message::SaveGameFile => {
+                self.save_game_file();
+                Command::none()
+            }
+
+            Message::PasteFen => iced::clipboard::read(Message::FenPasted),
+
+            Message::SetupPastePgn => iced::clipboard::read(Message::SetupPgnPasted),
+
+            Message::SetupPgnPasted(contents) => {
+                let t = self.language.strings();
+                if let AppScreen::Setup(ref mut state) = self.screen {
+                    state.pgn = contents;
+                    state.pgn_status = None;
+                    state.load_pgn(t);
+                    if state.pgn.is_none() {
+                        state.pgn_status = Some(t.clipboard_empty.to_string());
+                    }
+                }
+                Command::none()
+            }
+
+            Message::SetupPgnMove(input) => {
+                let t = self.language.strings();
+                if let AppScreen::Setup(ref mut state) = self.screen {
+                    state.pgn_move = input;
+                    state.load_pgn(t);
+                }
+                Command::none()
+            }
+
+            Message::FenPasted(contents) => {
+                let t = self.language.strings();
+                let fen = contents.unwrap_or_default();
+                let fen = fen.trim();
+                match safe_parse_board(fen, t) {
+                    Ok(board) => match self.screen {
+                        AppScreen::Setup(ref mut state) => state.parse_fen_to_state(fen, t),
+                        // Anywhere else, open the setup screen on the position
+                        AppScreen::Game => {
+                            let player_color = self
+                                .game
+                                .lock()
+                                .map_or(chess::Color::White, |game| game.player_color());
+                            let mut state = SetupState::from_board(&board, player_color);
+                            state.parse_fen_to_state(fen, t);
+                            self.screen = AppScreen::Setup(state);
+                        }
+                        _ => {}
+                    },
+                    Err(e) => {
+                        if let AppScreen::Setup(ref mut state) = self.screen {
+                            state.fen_error = Some(fill(t.clipboard_no_fen, e));
+                        }
+                    }
                 }
                 Command::none()
             }
 
             Message::SetupClearBoard => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
                     state.pieces.clear();
                     state.castle_wk = false;
@@ -771,7 +3721,7 @@ impl Application for ChessApp {
                     state.castle_bk = false;
                     state.castle_bq = false;
                     state.en_passant_file = None;
-                    state.rebuild_fen();
+                    state.rebuild_fen(t);
                 }
                 Command::none()
             }
@@ -784,130 +3734,3085 @@ impl Application for ChessApp {
                 Command::none()
             }
 
-            Message::SetupPlayerColor(color) => {
+            Message::SetupRandomChess960 => {
+                let t = self.language.strings();
                 if let AppScreen::Setup(ref mut state) = self.screen {
-                    state.player_color = color;
+                    state.chess960 = true;
+                    state.parse_fen_to_state(&chess960::start_fen(chess960::random_index()), t);
                 }
                 Command::none()
             }
 
-            Message::SetupStartGame => {
-                let (fen, player_color) = if let AppScreen::Setup(ref state) = self.screen {
-                    if state.fen_error.is_some() {
-                        return Command::none();
-                    }
-                    (state.fen_string.clone(), state.player_color)
-                } else {
-                    return Command::none();
-                };
+            Message::SetupEndgameMaterial(material) => {
+                if let AppScreen::Setup(ref mut state) = self.screen {
+                    state.endgame_material = material;
+                }
+                Command::none()
+            }
+
+            Message::SetupRandomEndgame => {
+                let t = self.language.strings();
+                if let AppScreen::Setup(ref mut state) = self.screen {
+                    match random_endgame(&state.endgame_material, t) {
+                        Ok(fen) => {
+                            state.chess960 = false;
+                            state.parse_fen_to_state(&fen, t);
+                            state.player_color = chess::Color::White;
+                        }
+                        Err(e) => state.fen_error = Some(e),
+                    }
+                }
+                Command::none()
+            }
+
+            Message::SetupChess960Toggle => {
+                let t = self.language.strings();
+                if let AppScreen::Setup(ref mut state) = self.screen {
+                    state.chess960 = !state.chess960;
+                    state.rebuild_fen(t);
+                }
+                Command::none()
+            }
+
+            Message::SetupPlayerColor(color) => {
+                if let AppScreen::Setup(ref mut state) = self.screen {
+                    state.player_color = color;
+                }
+                Command::none()
+            }
+
+            Message::SetupStartGame => {
+                let (fen, player_color, chess960) = if let AppScreen::Setup(ref state) = self.screen {
+                    if state.fen_error.is_some() {
+                        return Command::none();
+                    }
+                    (state.fen_string.clone(), state.player_color, state.chess960)
+                } else {
+                    return Command::none();
+                };
+
+                self.set_drill(None);
+                self.screen = AppScreen::Game;
+                let kind = if chess960 { GameKind::Chess960 } else { GameKind::Standard };
+                self.start_from_position(&fen, player_color, kind)
+            }
+
+            // ── Autosave messages ─────────────────────────────────────────
+            Message::ResumeSavedGame => {
+                let Some(saved) = self.resume_offer.take() else {
+                    return Command::none();
+                };
+                self.engine_thinking = false;
+                match self.restore_saved_game(&saved) {
+                    Ok(true) => return self.start_engine(),
+                    Ok(false) => {}
+                    Err(e) => {
+                        let t = self.language.strings();
+                        if let Ok(mut game) = self.game.lock() {
+                            game.set_message(fill(t.game_unloadable, e));
+                        }
+                        return Command::none();
+                    }
+                }
+                let needs_engine_move = self.game.lock().is_ok_and(|game| {
+                    game.game_result().is_none()
+                        && game.current_position().side_to_move() != game.player_color()
+                });
+                if needs_engine_move {
+                    return self.request_engine_move();
+                }
+                Command::none()
+            }
+
+            Message::DiscardSavedGame => {
+                self.resume_offer = None;
+                persistence::clear_autosave();
+
+                // Start the fresh game that was held back while the offer was open
+                let needs_engine_move = if let Ok(game) = self.game.lock() {
+                    game.current_position().side_to_move() != game.player_color()
+                } else {
+                    false
+                };
+
+                if needs_engine_move {
+                    return self.request_engine_move();
+                }
+                Command::none()
+            }
+
+            // ── Duplicate game messages ───────────────────────────────────
+            Message::SkipDuplicate => {
+                self.duplicate = None;
+                Command::none()
+            }
+
+            Message::ReplaceDuplicate => {
+                let Some(duplicate) = self.duplicate.take() else {
+                    return Command::none();
+                };
+                let Some(ref database) = self.database else {
+                    return Command::none();
+                };
+                match database.replace_game(duplicate.existing, &duplicate.game) {
+                    Ok(()) => {
+                        self.archived = Some((duplicate.existing, duplicate.tags));
+                        if let Some(game) = self.session.games.last_mut() {
+                            game.archive_id = Some(duplicate.existing);
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to replace the stored game: {}", e),
+                }
+                Command::none()
+            }
+
+            // ── Game browser messages ─────────────────────────────────────
+            Message::OpenGameBrowser => {
+                let t = self.language.strings();
+                self.screen = AppScreen::Browser(BrowserState::load(self.database.as_ref(), t));
+                Command::none()
+            }
+
+            Message::CloseGameBrowser => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::BrowserOpenGame(id) => {
+                let t = self.language.strings();
+                let stored = if let AppScreen::Browser(ref state) = self.screen {
+                    state.games.iter().find(|g| g.id == id).cloned()
+                } else {
+                    None
+                };
+                let Some(stored) = stored else {
+                    return Command::none();
+                };
+
+                let player_color = if stored.player_color == "b" {
+                    chess::Color::Black
+                } else {
+                    chess::Color::White
+                };
+                self.set_drill(None);
+                self.set_game_kind(GameKind::Standard);
+                if let Ok(mut game) = self.game.lock() {
+                    let loaded = game.load_moves(&stored.start_fen, &stored.move_list(), player_color);
+                    if let Err(e) = loaded {
+                        game.set_message(fill(t.game_unloadable, e));
+                    } else {
+                        // The archived PGN carries the moves' glyphs and comments
+                        let archived = pgn::parse_pgn(&stored.pgn).unwrap_or_default();
+                        if let Some(archived) = archived.first() {
+                            game.apply_annotations(&archived.annotations);
+                            game.apply_clocks(&archived.clocks);
+                            self.archived = Some((stored.id, archived.tags.clone()));
+                        }
+                        // Start replaying from the initial position
+                        game.view_move_at(0);
+                        game.set_message(fill(t.replaying_stored, &stored.played_at));
+                        // Already archived; don't store it a second time
+                        self.game_saved = game.game_result().is_some();
+                    }
+                }
+                self.engine_thinking = false;
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::OpenStatistics => {
+                self.screen = AppScreen::Statistics(StatsState::load(
+                    self.database.as_ref(),
+                    &self.adaptive,
+                    self.strength(),
+                    &self.rating,
+                    self.language.strings(),
+                ));
+                Command::none()
+            }
+
+            Message::OpenSession => {
+                self.screen =
+                    AppScreen::Session(SessionState::load(self.database.as_ref(), &self.session, false));
+                Command::none()
+            }
+
+            Message::CloseSession => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::ToggleAdaptiveStrength => {
+                self.adaptive.enabled = !self.adaptive.enabled;
+                if let Err(e) = adaptive::save_adaptive(&self.adaptive) {
+                    eprintln!("Failed to save adaptive strength settings: {}", e);
+                }
+                if let AppScreen::Statistics(ref mut state) = self.screen {
+                    state.adaptive = self.adaptive.clone();
+                }
+                Command::none()
+            }
+
+            // ── Game review messages ──────────────────────────────────────
+            Message::StartReview => self.start_review(),
+
+            Message::ReviewFinished(moves, result) => {
+                let t = self.language.strings();
+                self.reviewing = false;
+                let mut session_changed = false;
+                if let Ok(mut game) = self.game.lock() {
+                    // The board may have moved on to another game meanwhile
+                    if game.move_history() != moves.as_slice() {
+                        return Command::none();
+                    }
+                    match result {
+                        Ok(evaluations) => {
+                            game.apply_review(&evaluations);
+
+                            // Summarise the player's own moves
+                            let player = game.player_color();
+                            let accuracy = game.accuracy().and_then(|accuracy| match player {
+                                chess::Color::White => accuracy.white,
+                                chess::Color::Black => accuracy.black,
+                            });
+                            if let (Some((id, _)), Some(accuracy)) = (&self.archived, accuracy) {
+                                session_changed = self.session.set_accuracy(*id, accuracy);
+                            }
+                            let first_mover = game.start_position().side_to_move();
+                            let count = |class| {
+                                evaluations
+                                    .iter()
+                                    .enumerate()
+                                    .filter(|(ply, e)| {
+                                        let mover = if ply % 2 == 0 { first_mover } else { !first_mover };
+                                        mover == player && e.class == class
+                                    })
+                                    .count()
+                            };
+                            let mut summary = [
+                                analysis::MoveClass::Inaccuracy,
+                                analysis::MoveClass::Mistake,
+                                analysis::MoveClass::Blunder,
+                            ]
+                            .into_iter()
+                            .fold(t.review_summary.to_string(), |summary, class| {
+                                fill(&summary, count(class))
+                            });
+
+                            // Blunders become puzzles in the My Mistakes queue
+                            let puzzles = self.save_blunder_puzzles(&game, &evaluations);
+                            if puzzles > 0 {
+                                summary.push_str(&fill(t.added_to_mistakes, puzzles));
+                            }
+                            game.set_message(summary);
+                        }
+                        Err(e) => game.set_message(fill(t.review_failed, e)),
+                    }
+                }
+                if session_changed {
+                    self.save_session();
+                }
+                Command::none()
+            }
+
+            Message::BackgroundEvaluated(board, result) => {
+                let Some(ref mut background) = self.background else {
+                    return Command::none();
+                };
+                background.searching = None;
+                match result {
+                    Ok(eval) => {
+                        background.evals.insert(board.get_hash(), eval);
+                    }
+                    Err(e) => {
+                        eprintln!("Background analysis stopped: {}", e);
+                        self.background = None;
+                    }
+                }
+                Command::none()
+            }
+
+            // ── Coach mode messages ───────────────────────────────────────
+            Message::ToggleCoachMode => {
+                let t = self.language.strings();
+                // No engine help in online or rated games
+                if !self.assists_allowed() {
+                    return Command::none();
+                }
+                if let Ok(mut game) = self.game.lock() {
+                    let enabled = !game.coach_mode();
+                    game.set_coach_mode(enabled);
+                    game.set_message(if enabled { t.coach_on } else { t.coach_off }.to_string());
+                }
+                Command::none()
+            }
+
+            Message::CoachChecked(chess_move, result) => {
+                let needs_engine_move = if let Ok(mut game) = self.game.lock() {
+                    // Ignore checks for a move that was taken back meanwhile
+                    let still_pending = game
+                        .pending_confirmation()
+                        .is_some_and(|p| p.chess_move == chess_move && p.warning.is_none());
+                    if !still_pending {
+                        return Command::none();
+                    }
+                    match result {
+                        Ok(Some(warning)) => {
+                            game.set_coach_warning(warning);
+                            false
+                        }
+                        // Nothing wrong with the move, or the coach couldn't
+                        // tell; either way don't hold the player up
+                        Ok(None) | Err(_) => {
+                            if let Err(e) = result {
+                                eprintln!("Coach check failed: {}", e);
+                            }
+                            game.confirm_pending_move()
+                        }
+                    }
+                } else {
+                    false
+                };
+
+                if needs_engine_move {
+                    return self.respond_to_player_move();
+                }
+                Command::none()
+            }
+
+            Message::ConfirmMove => {
+                let needs_engine_move = if let Ok(mut game) = self.game.lock() {
+                    game.confirm_pending_move()
+                } else {
+                    false
+                };
+
+                if needs_engine_move {
+                    return self.respond_to_player_move();
+                }
+                Command::none()
+            }
+
+            Message::CancelMove => {
+                if let Ok(mut game) = self.game.lock() {
+                    game.cancel_pending_move();
+                }
+                Command::none()
+            }
+
+            // ── Repertoire messages ───────────────────────────────────────
+            Message::OpenRepertoire => {
+                self.screen = AppScreen::Repertoire(RepertoireState::load());
+                Command::none()
+            }
+
+            Message::CloseRepertoire => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::RepertoirePathChanged(path) => {
+                if let AppScreen::Repertoire(ref mut state) = self.screen {
+                    state.import_path = path;
+                }
+                Command::none()
+            }
+
+            Message::RepertoireColor(color) => {
+                if let AppScreen::Repertoire(ref mut state) = self.screen {
+                    state.import_color = color;
+                }
+                Command::none()
+            }
+
+            Message::RepertoireImport => {
+                let t = self.language.strings();
+                if let AppScreen::Repertoire(ref mut state) = self.screen {
+                    state.import(t);
+                }
+                Command::none()
+            }
+
+            Message::RepertoireClear(color) => {
+                if let AppScreen::Repertoire(ref mut state) = self.screen {
+                    state.repertoire.clear(color);
+                    state.due[color.to_index()] = 0;
+                    state.status = None;
+                    state.error = repertoire::save_repertoire(&state.repertoire)
+                        .err()
+                        .map(|e| e.to_string());
+                }
+                Command::none()
+            }
+
+            Message::StartTraining(color) => {
+                let t = self.language.strings();
+                let AppScreen::Repertoire(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let trainer = RepertoireTrainer::new(&state.repertoire, color, state.schedule.clone());
+                if trainer.is_empty() {
+                    state.error = Some(fill(t.no_lines_to_train, color_name(t, color)));
+                    return Command::none();
+                }
+
+                self.set_drill(Some(Drill::Repertoire(trainer)));
+                self.screen = AppScreen::Game;
+                self.start_training_game();
+                Command::none()
+            }
+
+            Message::StopDrill => {
+                let t = self.language.strings();
+                self.set_drill(None);
+                let needs_engine_move = if let Ok(mut game) = self.game.lock() {
+                    game.set_message(t.training_stopped.to_string());
+                    game.game_result().is_none()
+                        && game.current_position().side_to_move() != game.player_color()
+                } else {
+                    false
+                };
+
+                if needs_engine_move {
+                    return self.request_engine_move();
+                }
+                Command::none()
+            }
+
+            // ── Puzzle messages ───────────────────────────────────────────
+            Message::OpenPuzzles => {
+                let t = self.language.strings();
+                self.screen = AppScreen::Puzzles(PuzzleState::load(self.database.as_ref(), t));
+                Command::none()
+            }
+
+            Message::ClosePuzzles => {
+                let t = self.language.strings();
+                self.screen = AppScreen::Browser(BrowserState::load(self.database.as_ref(), t));
+                Command::none()
+            }
+
+            Message::SolvePuzzle(id) => {
+                let puzzle = if let AppScreen::Puzzles(ref state) = self.screen {
+                    state.puzzles.iter().find(|p| p.id == id).cloned()
+                } else {
+                    None
+                };
+                if let Some(puzzle) = puzzle {
+                    self.set_drill(Some(Drill::Puzzle(PuzzleSession {
+                        puzzle,
+                        solved: false,
+                    })));
+                    self.screen = AppScreen::Game;
+                    self.start_puzzle();
+                }
+                Command::none()
+            }
+
+            Message::NextPuzzle => {
+                self.next_puzzle();
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::DeletePuzzle(id) => {
+                let t = self.language.strings();
+                if let Some(ref database) = self.database {
+                    if let Err(e) = database.delete_puzzle(id) {
+                        eprintln!("Failed to delete puzzle: {}", e);
+                    }
+                }
+                self.screen = AppScreen::Puzzles(PuzzleState::load(self.database.as_ref(), t));
+                Command::none()
+            }
+
+            // ── Endgame training messages ─────────────────────────────────
+            Message::OpenEndgames => {
+                self.screen = AppScreen::Endgames;
+                Command::none()
+            }
+
+            Message::CloseEndgames => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::OpenFamousGames => {
+                self.screen = AppScreen::FamousGames;
+                Command::none()
+            }
+
+            Message::CloseFamousGames => {
+                let t = self.language.strings();
+                self.screen = AppScreen::Browser(BrowserState::load(self.database.as_ref(), t));
+                Command::none()
+            }
+
+            Message::OpenFamousGame(index) => {
+                let t = self.language.strings();
+                let Some(pgn_game) = famous::bundled().get(index) else {
+                    return Command::none();
+                };
+                self.set_drill(None);
+                self.set_game_kind(GameKind::Standard);
+                self.open_pgn_game(pgn_game);
+                self.engine_thinking = false;
+                if let Ok(mut game) = self.game.lock() {
+                    let tag = |name| pgn_game.tag(name).unwrap_or("?");
+                    let players = fill(&fill(t.versus, tag("White")), tag("Black"));
+                    game.set_message(fill(t.read_notes, players));
+                }
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::StartEndgame(index) => {
+                let Some(endgame) = ENDGAMES.get(index) else {
+                    return Command::none();
+                };
+                self.set_drill(Some(Drill::Endgame(EndgameSession {
+                    endgame,
+                    outcome: None,
+                })));
+                self.screen = AppScreen::Game;
+                self.start_endgame()
+            }
+
+            // ── New game menu messages ────────────────────────────────────
+            Message::OpenNewGame => {
+                let (control, odds) = match self.game.lock() {
+                    Ok(game) => (game.time_control(), game.time_odds()),
+                    Err(_) => (None, None),
+                };
+                let state =
+                    NewGameState::new(self.snapshot.player_color, control, odds, self.snapshot.rated);
+                self.screen = AppScreen::NewGame(state);
+                Command::none()
+            }
+
+            Message::CloseNewGame => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            // ── Settings messages ─────────────────────────────────────────
+            Message::OpenSettings => {
+                let mut state =
+                    SettingsState::new(self.resources, self.board_settings, self.language, self.ui_scale);
+                state.personality = self.personality.as_ref().map(|p| p.name.clone());
+                state.personalities = self.personalities.iter().map(|p| p.name.clone()).collect();
+                state.eval_file = self.eval_file().unwrap_or_default().to_string();
+                state.background_analysis = self.background.is_some();
+                state.pause_unfocused = self.pause_unfocused;
+                state.power_saver = self.power_saver;
+                state.engine_book = match self.book {
+                    Some((ref path, _)) => path.display().to_string(),
+                    None => String::new(),
+                };
+                state.book_learning = self.book_learning;
+                self.screen = AppScreen::Settings(state);
+                Command::none()
+            }
+
+            Message::CloseSettings => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::SettingsThreads(threads) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.resources.threads = threads;
+                }
+                Command::none()
+            }
+
+            Message::SettingsHash(hash_mb) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.resources.hash_mb = hash_mb;
+                }
+                Command::none()
+            }
+
+            Message::SettingsAnimation(animation_ms) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.board.animation_ms = animation_ms;
+                }
+                Command::none()
+            }
+
+            Message::SettingsReplyArrow(shown) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.board.reply_arrow = shown;
+                }
+                Command::none()
+            }
+
+            Message::SettingsBackgroundAnalysis(enabled) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.background_analysis = enabled;
+                }
+                Command::none()
+            }
+
+            Message::SettingsPauseUnfocused(enabled) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.pause_unfocused = enabled;
+                }
+                Command::none()
+            }
+
+            Message::SettingsBookLearning(enabled) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.book_learning = enabled;
+                }
+                Command::none()
+            }
+
+            Message::SettingsPowerSaver(power_saver) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.power_saver = power_saver;
+                }
+                Command::none()
+            }
+
+            Message::SettingsLanguage(language) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.language = language;
+                }
+                Command::none()
+            }
+
+            Message::SettingsPalette(palette) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.board.palette = palette;
+                }
+                Command::none()
+            }
+
+            Message::SettingsTouchMode(enabled) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.board.touch_mode = enabled;
+                }
+                Command::none()
+            }
+
+            Message::SettingsUiScale(scale) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.ui_scale = scale;
+                }
+                Command::none()
+            }
+
+            Message::SettingsPersonality(name) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.personality = name;
+                }
+                Command::none()
+            }
+
+            Message::SettingsEvalFile(path) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.eval_file = path;
+                    state.eval_file_error = None;
+                }
+                Command::none()
+            }
+
+            Message::SettingsEngineBook(path) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.engine_book = path;
+                    state.engine_book_error = None;
+                }
+                Command::none()
+            }
+
+            Message::ZoomUi(step) => {
+                let scale = match step {
+                    0 => 1.0,
+                    step => self.ui_scale + f64::from(step) * settings::UI_SCALE_STEP,
+                };
+                self.set_ui_scale(scale);
+                let saved = UserSettings {
+                    ui_scale: self.ui_scale,
+                    ..settings::load_settings()
+                };
+                if let Err(e) = settings::save_settings(&saved) {
+                    eprintln!("Failed to save settings: {}", e);
+                }
+                Command::none()
+            }
+
+            Message::SettingsCoordinates(shown) => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.board.coordinates = shown;
+                }
+                Command::none()
+            }
+
+            Message::SettingsDefaults => {
+                if let AppScreen::Settings(ref mut state) = self.screen {
+                    state.resources = Resources::for_host();
+                }
+                Command::none()
+            }
+
+            Message::SaveSettings => {
+                let was_saving = self.saving_power();
+                let AppScreen::Settings(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let eval_file = match state.eval_file.trim() {
+                    "" => None,
+                    path => match engine::eval_file_option(Path::new(path)) {
+                        Ok((_, path)) => Some(path),
+                        Err(e) => {
+                            state.eval_file_error = Some(e);
+                            return Command::none();
+                        }
+                    },
+                };
+                let book = match state.engine_book.trim() {
+                    "" => None,
+                    path => match Book::load(Path::new(path)) {
+                        Ok(book) => Some((PathBuf::from(path), book)),
+                        Err(e) => {
+                            state.engine_book_error = Some(e.to_string());
+                            return Command::none();
+                        }
+                    },
+                };
+                let resources = state.resources;
+                let chosen = state.personality.as_deref();
+                let personality =
+                    chosen.and_then(|name| personality::find(&self.personalities, name)).cloned();
+                self.board_settings = state.board;
+                self.language = state.language;
+                self.ui.set_language(self.language);
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_language(self.language);
+                }
+                self.ui.set_palette(self.board_settings.palette);
+                self.ui.set_touch_mode(self.board_settings.touch_mode);
+                if state.background_analysis != self.background.is_some() {
+                    self.background = state.background_analysis.then(BackgroundAnalysis::new);
+                }
+                self.pause_unfocused = state.pause_unfocused;
+                self.power_saver = state.power_saver;
+                self.book = book;
+                self.book_learning = state.book_learning;
+                let ui_scale = state.ui_scale;
+                self.set_ui_scale(ui_scale);
+                self.screen = AppScreen::Game;
+                let saved = UserSettings {
+                    resources,
+                    board: self.board_settings,
+                    language: self.language,
+                    ui_scale: self.ui_scale,
+                    personality: personality.as_ref().map(|p| p.name.clone()),
+                    eval_file: eval_file.as_ref().map(PathBuf::from),
+                    background_analysis: self.background.is_some(),
+                    pause_unfocused: self.pause_unfocused,
+                    power_saver: self.power_saver,
+                    engine_book: self.book.as_ref().map(|(path, _)| path.clone()),
+                    book_learning: self.book_learning,
+                    ..settings::load_settings()
+                };
+                if let Err(e) = settings::save_settings(&saved) {
+                    eprintln!("Failed to save settings: {}", e);
+                }
+                let mut commands = Vec::new();
+                if eval_file.as_deref() != self.eval_file() {
+                    commands.push(self.set_eval_file(eval_file));
+                }
+                if personality != self.personality {
+                    self.personality = personality;
+                    commands.push(self.apply_personality());
+                }
+                if resources != self.resources || self.saving_power() != was_saving {
+                    self.resources = resources;
+                    commands.push(self.apply_resources());
+                }
+                Command::batch(commands)
+            }
+
+            // ── Online play messages ──────────────────────────────────────
+            Message::OpenOnline => {
+                let token = settings::load_settings().lichess_token.unwrap_or_default();
+                self.screen = AppScreen::Online(OnlineState::new(token));
+                Command::none()
+            }
+
+            Message::CloseOnline => {
+                self.screen = AppScreen::Game;
+                Command::none()
+            }
+
+            Message::OnlineTokenChanged(token) => {
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.token = token;
+                }
+                Command::none()
+            }
+
+            Message::OnlineControlChanged(control) => {
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.control = control;
+                }
+                Command::none()
+            }
+
+            Message::OnlineOpponentChanged(opponent) => {
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.opponent = opponent;
+                }
+                Command::none()
+            }
+
+            Message::OnlineRatedToggle => {
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.rated = !state.rated;
+                }
+                Command::none()
+            }
+
+            Message::OnlineAiLevel(level) => {
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.ai_level = level;
+                }
+                Command::none()
+            }
+
+            Message::LichessConnect => {
+                let AppScreen::Online(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                if state.connecting || state.token.trim().is_empty() {
+                    return Command::none();
+                }
+                state.connecting = true;
+                state.error = None;
+                let client = LichessClient::new(&state.token);
+                Command::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || client.username().map_err(|e| e.to_string()))
+                            .await
+                            .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    Message::LichessConnected,
+                )
+            }
+
+            Message::LichessConnected(result) => {
+                let t = self.language.strings();
+                let AppScreen::Online(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                state.connecting = false;
+                let client = LichessClient::new(&state.token);
+                let events = result.and_then(|username| {
+                    client
+                        .stream_events()
+                        .map(|events| (username, events))
+                        .map_err(|e| e.to_string())
+                });
+                match events {
+                    Ok((username, events)) => {
+                        state.status = Some(fill(t.logged_in_as, &username));
+                        // Remember the token for the next session
+                        let saved = UserSettings {
+                            lichess_token: Some(state.token.trim().to_string()),
+                            ..settings::load_settings()
+                        };
+                        if let Err(e) = settings::save_settings(&saved) {
+                            eprintln!("Failed to save settings: {}", e);
+                        }
+                        self.lichess = Some(LichessSession {
+                            client,
+                            username,
+                            events,
+                            challenges: Vec::new(),
+                            seek: None,
+                            game: None,
+                        });
+                    }
+                    Err(e) => state.error = Some(fill(t.lichess_login_failed, e)),
+                }
+                Command::none()
+            }
+
+            Message::LichessDisconnect => {
+                // Forget the token too, so the next session starts logged out
+                self.lichess = None;
+                let saved = UserSettings {
+                    lichess_token: None,
+                    ..settings::load_settings()
+                };
+                if let Err(e) = settings::save_settings(&saved) {
+                    eprintln!("Failed to save settings: {}", e);
+                }
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.token.clear();
+                    state.status = None;
+                }
+                self.leave_online_game();
+                Command::none()
+            }
+
+            Message::LichessSeek => {
+                let t = self.language.strings();
+                let (Some(session), AppScreen::Online(state)) = (&mut self.lichess, &mut self.screen) else {
+                    return Command::none();
+                };
+                let Some(control) = lichess::parse_control(&state.control) else {
+                    state.error = Some(fill(t.not_a_time_control, &state.control));
+                    return Command::none();
+                };
+                match session.client.seek(control, state.rated) {
+                    Ok(seek) => {
+                        session.seek = Some(seek);
+                        state.error = None;
+                        state.status = Some(t.looking_for_opponent.to_string());
+                    }
+                    Err(e) => state.error = Some(e.to_string()),
+                }
+                Command::none()
+            }
+
+            Message::LichessCancelSeek => {
+                if let Some(ref mut session) = self.lichess {
+                    session.seek = None;
+                }
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.status = None;
+                }
+                Command::none()
+            }
+
+            Message::LichessChallenge | Message::LichessChallengeAi => {
+                let t = self.language.strings();
+                let AppScreen::Online(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let Some(control) = lichess::parse_control(&state.control) else {
+                    state.error = Some(fill(t.not_a_time_control, &state.control));
+                    return Command::none();
+                };
+                let opponent = state.opponent.trim().to_string();
+                let (rated, level) = (state.rated, state.ai_level);
+                state.error = None;
+                if matches!(message, Message::LichessChallengeAi) {
+                    state.status = Some(fill(t.starting_ai_game, level));
+                    return self.lichess_request(move |client| client.challenge_ai(level, control));
+                }
+                if opponent.is_empty() {
+                    state.error = Some(t.enter_challenge_user.to_string());
+                    return Command::none();
+                }
+                state.status = Some(fill(t.challenge_sent, &opponent));
+                self.lichess_request(move |client| client.challenge(&opponent, control, rated))
+            }
+
+            Message::LichessAccept(id) => {
+                self.forget_challenge(&id);
+                self.lichess_request(move |client| client.accept(&id))
+            }
+
+            Message::LichessDecline(id) => {
+                self.forget_challenge(&id);
+                self.lichess_request(move |client| client.decline(&id))
+            }
+
+            Message::LichessResign => match self.online_game() {
+                Some(online) if !online.is_over() => {
+                    let id = online.id.clone();
+                    self.lichess_request(move |client| client.resign(&id))
+                }
+                _ => Command::none(),
+            },
+
+            Message::LichessLeaveGame => {
+                if self.online_game().is_some_and(OnlineGame::is_over) {
+                    self.leave_online_game();
+                    return self.start_new_game(GameKind::Standard, self.snapshot.player_color);
+                }
+                Command::none()
+            }
+
+            Message::LichessReplied(Ok(())) => Command::none(),
+
+            Message::LichessReplied(Err(error)) => {
+                if let AppScreen::Online(ref mut state) = self.screen {
+                    state.status = None;
+                    state.error = Some(error.clone());
+                }
+                // A refused move comes off the board again
+                let command = self.sync_online_game(true);
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_message(format!("Lichess: {}", error));
+                }
+                command
+            }
+
+            Message::ToggleKibitz => {
+                let Some(online) = self.online_game_mut() else {
+                    return Command::none();
+                };
+                if !online.against_ai {
+                    return Command::none();
+                }
+                online.kibitz = !online.kibitz;
+                online.kibitz_eval = None;
+                if online.kibitz {
+                    return self.start_kibitz();
+                }
+                Command::none()
+            }
+
+            Message::KibitzEvaluated(fen, result) => {
+                let t = self.language.strings();
+                let current = self.game.lock().ok().map(|game| game.current_position().to_string());
+                if let (Some(online), Some(current)) = (self.online_game_mut(), current) {
+                    if online.kibitz && current == fen {
+                        online.kibitz_eval = Some(result.unwrap_or_else(|e| fill(t.engine_error, e)));
+                    }
+                }
+                Command::none()
+            }
+
+            Message::ToggleKibitzer => {
+                if !self.kibitzers.is_empty() || !self.assists_allowed() {
+                    self.kibitzers.clear();
+                    return Command::none();
+                }
+                self.mark_assisted();
+                self.kibitzers = self.kibitzer_paths.iter().cloned().map(Kibitzer::new).collect();
+                self.follow_with_kibitzers()
+            }
+
+            Message::KibitzerStarted(index, result) => {
+                if let (Some(kibitzer), Err(e)) = (self.kibitzers.get_mut(index), result) {
+                    kibitzer.error = Some(e);
+                }
+                Command::none()
+            }
+
+            Message::ToggleThreats => {
+                if self.shown_threats().is_some() || !self.assists_allowed() {
+                    self.threats = None;
+                    return Command::none();
+                }
+                self.mark_assisted();
+                self.find_threats()
+            }
+
+            Message::ThreatsFound(fen, result) => {
+                let Some(threats) = self.threats.as_mut() else {
+                    return Command::none();
+                };
+                // Null moves clear the en passant square, so compare with the
+                // same position the search was given
+                let Some(passed) = threats.board.null_move().filter(|b| b.to_string() == fen) else {
+                    return Command::none();
+                };
+                match result {
+                    Ok(moves) => {
+                        threats.moves = Some(
+                            moves
+                                .iter()
+                                .filter_map(|uci_move| game::parse_uci_move(&passed, uci_move))
+                                .collect(),
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to find threats: {}", e);
+                        self.threats = None;
+                    }
+                }
+                Command::none()
+            }
+
+            Message::ToggleCandidates => {
+                if self.shown_candidates().is_some() || !self.assists_allowed() {
+                    self.candidates = None;
+                    return Command::none();
+                }
+                self.mark_assisted();
+                self.find_candidates()
+            }
+
+            Message::CandidatesFound(fen, result) => {
+                let Some(candidates) = self.candidates.as_mut() else {
+                    return Command::none();
+                };
+                if candidates.board.to_string() != fen {
+                    return Command::none();
+                }
+                let board = candidates.board;
+                let white_to_move = board.side_to_move() == chess::Color::White;
+                match result {
+                    Ok(moves) => {
+                        candidates.moves = Some(
+                            moves
+                                .iter()
+                                .filter_map(|(uci_move, side_cp)| {
+                                    let white_cp = if white_to_move { *side_cp } else { -side_cp };
+                                    game::parse_uci_move(&board, uci_move).map(|m| (m, white_cp))
+                                })
+                                .collect(),
+                        );
+                    }
+                    Err(e) => {
+                        eprintln!("Failed to find candidate moves: {}", e);
+                        self.candidates = None;
+                    }
+                }
+                Command::none()
+            }
+
+            Message::NewGameColor(color) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.player_color = color;
+                }
+                Command::none()
+            }
+
+            Message::NewGameClock(clock) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.clock = clock;
+                    state.clock_error = None;
+                }
+                Command::none()
+            }
+
+            Message::NewGameEngineClock(clock) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.engine_clock = clock;
+                    state.clock_error = None;
+                }
+                Command::none()
+            }
+
+            Message::NewGameClockMode(mode) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.clock_mode = mode;
+                }
+                Command::none()
+            }
+
+            Message::NewGameRated(rated) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.rated = rated;
+                }
+                Command::none()
+            }
+
+            Message::NewGameOpeningQuery(query) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.opening_query = query;
+                }
+                Command::none()
+            }
+
+            Message::NewGameOpening(line) => {
+                if let AppScreen::NewGame(ref mut state) = self.screen {
+                    state.opening = line;
+                }
+                Command::none()
+            }
+
+            Message::StartNewGame(kind) => {
+                let AppScreen::NewGame(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let player_color = state.player_color;
+                let rated = state.rated;
+                let opening = state.opening.clone().filter(|_| kind == GameKind::Standard);
+                let (control, odds) = match state.time_controls() {
+                    Ok(controls) => controls,
+                    Err(e) => {
+                        state.clock_error = Some(e);
+                        return Command::none();
+                    }
+                };
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_time_control(control);
+                    game.set_time_odds(odds);
+                }
+                self.set_drill(None);
+                self.screen = AppScreen::Game;
+                if rated {
+                    self.threats = None;
+                    self.candidates = None;
+                    self.kibitzers.clear();
+                }
+                let command = match opening {
+                    Some(line) => self.start_opening(&line, player_color),
+                    None => self.start_new_game(kind, player_color),
+                };
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_rated(rated);
+                }
+                command
+            }
+
+            Message::BrowserDeleteGame(id) => {
+                let t = self.language.strings();
+                if let Some(ref database) = self.database {
+                    if let Err(e) = database.delete_game(id) {
+                        eprintln!("Failed to delete game: {}", e);
+                    }
+                }
+                let mut state = BrowserState::load(self.database.as_ref(), t);
+                if let AppScreen::Browser(ref mut old) = self.screen {
+                    state.filter = std::mem::take(&mut old.filter);
+                    state.new_tag = std::mem::take(&mut old.new_tag);
+                }
+                self.screen = AppScreen::Browser(state);
+                Command::none()
+            }
+
+            Message::BrowserFilter(filter) => {
+                if let AppScreen::Browser(ref mut state) = self.screen {
+                    state.filter = filter;
+                }
+                Command::none()
+            }
+
+            Message::BrowserNewTagChanged(tag) => {
+                if let AppScreen::Browser(ref mut state) = self.screen {
+                    state.new_tag = tag;
+                }
+                Command::none()
+            }
+
+            Message::BrowserAddTag(id) => {
+                let AppScreen::Browser(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let Some(ref database) = self.database else {
+                    return Command::none();
+                };
+                let tag = state.new_tag.trim().to_string();
+                let Some(game) = state.games.iter_mut().find(|game| game.id == id) else {
+                    return Command::none();
+                };
+                if tag.is_empty() || game.tags.contains(&tag) {
+                    return Command::none();
+                }
+                match database.add_tag(id, &tag) {
+                    Ok(()) => {
+                        game.tags.push(tag);
+                        game.tags.sort();
+                    }
+                    Err(e) => eprintln!("Failed to tag game: {}", e),
+                }
+                Command::none()
+            }
+
+            Message::BrowserRemoveTag(id, tag) => {
+                let AppScreen::Browser(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let Some(ref database) = self.database else {
+                    return Command::none();
+                };
+                match database.remove_tag(id, &tag) {
+                    Ok(()) => {
+                        if let Some(game) = state.games.iter_mut().find(|game| game.id == id) {
+                            game.tags.retain(|t| *t != tag);
+                        }
+                        // A filter on a tag no game has left would hide everything
+                        let tags = state.all_tags();
+                        if state.filter.tag.as_ref().is_some_and(|t| !tags.contains(&t.as_str())) {
+                            state.filter.tag = None;
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to remove tag: {}", e),
+                }
+                Command::none()
+            }
+
+            Message::BrowserUrlChanged(url) => {
+                if let AppScreen::Browser(ref mut state) = self.screen {
+                    state.import_url = url;
+                }
+                Command::none()
+            }
+
+            Message::BrowserResumePathChanged(path) => {
+                if let AppScreen::Browser(ref mut state) = self.screen {
+                    state.resume_path = path;
+                }
+                Command::none()
+            }
+
+            Message::BrowserResumeFile => {
+                let AppScreen::Browser(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                let path = PathBuf::from(state.resume_path.trim());
+                let restored = persistence::load_game_file(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|saved| self.restore_saved_game(&saved));
+                match restored {
+                    Ok(restart) => {
+                        self.screen = AppScreen::Game;
+                        self.engine_thinking = false;
+                        if restart {
+                            return self.start_engine();
+                        }
+                        let needs_engine_move = self.game.lock().is_ok_and(|game| {
+                            game.game_result().is_none()
+                                && game.current_position().side_to_move() != game.player_color()
+                        });
+                        if needs_engine_move {
+                            return self.request_engine_move();
+                        }
+                    }
+                    Err(e) => {
+                        if let AppScreen::Browser(ref mut state) = self.screen {
+                            state.resume_error = Some(format!("{}: {}", path.display(), e));
+                        }
+                    }
+                }
+                Command::none()
+            }
+
+            Message::BrowserImportUrl => {
+                let AppScreen::Browser(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                if state.importing || state.import_url.trim().is_empty() {
+                    return Command::none();
+                }
+                state.importing = true;
+                state.import_error = None;
+                let url = state.import_url.clone();
+                Command::perform(
+                    async move {
+                        tokio::task::spawn_blocking(move || {
+                            online::fetch_game(&url).map_err(|e| e.to_string())
+                        })
+                        .await
+                        .unwrap_or_else(|e| Err(e.to_string()))
+                    },
+                    Message::GameDownloaded,
+                )
+            }
+
+            Message::GameDownloaded(result) => {
+                let t = self.language.strings();
+                let AppScreen::Browser(ref mut state) = self.screen else {
+                    return Command::none();
+                };
+                state.importing = false;
+                let parsed = result.and_then(|text| {
+                    let games = pgn::parse_pgn(&text).map_err(|e| e.to_string())?;
+                    let first = games.into_iter().next();
+                    first.ok_or_else(|| t.download_no_game.to_string())
+                });
+                match parsed {
+                    Ok(pgn_game) => {
+                        self.set_drill(None);
+                        self.set_game_kind(GameKind::Standard);
+                        self.open_pgn_game(&pgn_game);
+                        self.engine_thinking = false;
+                        self.screen = AppScreen::Game;
+                    }
+                    Err(e) => state.import_error = Some(e),
+                }
+                Command::none()
+            }
+        }
+    }
+
+    /// Takebacks, the coach and engine analysis can be used: not in online
+    /// games, nor in rated ones until they are over.
+    fn assists_allowed(&self) -> bool {
+        !self.playing_online() && self.game.lock().is_ok_and(|game| game.assists_allowed())
+    }
+
+    // Engine analysis shown during a game keeps its result out of the
+    // statistics
+    fn mark_assisted(&mut self) {
+        if let Ok(mut game) = self.game.lock() {
+            if game.game_result().is_none() {
+                game.mark_assisted();
+            }
+        }
+    }
+
+    /// Archive the game once it has finished, returning true the first time
+    /// a finished game is seen.  Runs after every update, so the flag resets
+    /// whenever the board goes back to an unfinished game.
+    fn record_finished_game(&mut self) -> bool {
+        if self.snapshot.game_result.is_none() || self.drill.is_some() || self.playing_online() {
+            self.game_saved = false;
+            self.archived = None;
+            self.duplicate = None;
+            return false;
+        }
+        if self.game_saved {
+            return false;
+        }
+        self.game_saved = true;
+        // Before the engine's Elo limit moves on
+        self.update_rating();
+        self.adapt_strength();
+        self.learn_from_book();
+        self.add_session_game();
+
+        let Some(ref database) = self.database else {
+            return true;
+        };
+
+        let now = chrono::Local::now();
+        let engine = self.engine_name();
+        let (stored, tags) = if let Ok(game) = self.game.lock() {
+            let date = now.format("%Y.%m.%d").to_string();
+            let tags = pgn::game_tags(&game, &engine, &date);
+            let stored = StoredGame {
+                id: 0,
+                played_at: now.format("%Y-%m-%d %H:%M").to_string(),
+                engine: engine.clone(),
+                skill_level: self.skill_level,
+                player_color: SavedGame::color_code(game.player_color()),
+                result: pgn::result_string(game.game_result()).to_string(),
+                start_fen: game.start_fen(),
+                moves: game
+                    .move_history()
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                pgn: pgn::export_game(&game, &engine, &date),
+                assisted: game.assisted(),
+                tags: Vec::new(),
+            };
+            (stored, tags)
+        } else {
+            return true;
+        };
+
+        // The same moves against the same engine were stored before, e.g. a
+        // game imported twice and played out
+        match database.find_duplicate(&stored) {
+            Ok(Some((existing, played_at))) => {
+                self.duplicate = Some(DuplicateGame { existing, played_at, game: stored, tags });
+                self.save_session();
+                return true;
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to look for a duplicate game: {}", e),
+        }
+        match database.insert_game(&stored) {
+            Ok(id) => {
+                self.archived = Some((id, tags));
+                if let Some(game) = self.session.games.last_mut() {
+                    game.archive_id = Some(id);
+                }
+            }
+            Err(e) => eprintln!("Failed to save finished game: {}", e),
+        }
+        self.save_session();
+        true
+    }
+
+    // Count the finished game on the board in this session
+    fn add_session_game(&mut self) {
+        let Some(outcome) = Outcome::for_player(
+            pgn::result_string(self.snapshot.game_result),
+            &SavedGame::color_code(self.snapshot.player_color),
+        ) else {
+            return;
+        };
+        let duration = self.game.lock().map_or(Duration::ZERO, |game| game.time_played());
+        self.session.games.push(SessionGame {
+            archive_id: None,
+            outcome,
+            duration,
+            accuracy: None,
+        });
+    }
+
+    /// Store the session's totals with the games, adding it the first time.
+    fn save_session(&mut self) {
+        let Some(ref database) = self.database else {
+            return;
+        };
+        let stored = self.session.stored();
+        let result = match self.session.id {
+            Some(_) => database.update_session(&stored),
+            None => database.insert_session(&stored).map(|id| self.session.id = Some(id)),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to save the session: {}", e);
+        }
+    }
+
+    /// Change the glyph or comment of the move being viewed, keeping the
+    /// archived copy of the game in step.
+    fn annotate_viewed_move(&mut self, edit: impl FnOnce(&mut MoveAnnotation)) {
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let ply = game.view_move_index();
+        if !game.is_view_mode() {
+            return;
+        }
+        let Some(details) = game::move_details(game.get_move_records(), ply) else {
+            return;
+        };
+        let mut annotation = details.annotation.clone();
+        edit(&mut annotation);
+        game.annotate_move(ply, annotation);
+
+        if let (Some((id, tags)), Some(database)) = (&self.archived, &self.database) {
+            let pgn = pgn::export_pgn(tags, game.get_move_records(), pgn::result_string(game.game_result()));
+            if let Err(e) = database.update_pgn(*id, &pgn) {
+                eprintln!("Failed to update the archived game: {}", e);
+            }
+        }
+    }
+
+    /// Analyse the whole game with a separate full-strength engine instance
+    /// and attach a verdict to every move when it finishes.
+    fn start_review(&mut self) -> Command<Message> {
+        let t = self.language.strings();
+        if self.reviewing {
+            return Command::none();
+        }
+
+        let (positions, moves) = if let Ok(mut game) = self.game.lock() {
+            if game.move_history().is_empty() {
+                return Command::none();
+            }
+            game.set_message(t.reviewing_game.to_string());
+            (game.position_history().to_vec(), game.move_history().to_vec())
+        } else {
+            return Command::none();
+        };
+        self.reviewing = true;
+
+        let known = self
+            .background
+            .as_ref()
+            .map(|background| background.evals.clone())
+            .unwrap_or_default();
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        let resources = self.engine_resources();
+        Command::perform(
+            async move {
+                let reviewed_moves = moves.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    analysis::review_game(
+                        &engine_path,
+                        resources,
+                        &uci_options,
+                        &positions,
+                        &moves,
+                        &known,
+                    )
+                    .map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+                (reviewed_moves, result)
+            },
+            |(moves, result)| Message::ReviewFinished(moves, result),
+        )
+    }
+
+    /// Whether to run in the low-power profile now.
+    fn saving_power(&self) -> bool {
+        match self.power_saver {
+            PowerSaver::Off => false,
+            PowerSaver::OnBattery => self.on_battery,
+            PowerSaver::Always => true,
+        }
+    }
+
+    /// The threads and hash size the engines get: a single thread while
+    /// saving power.
+    fn engine_resources(&self) -> Resources {
+        match self.saving_power() {
+            true => Resources { threads: 1, ..self.resources },
+            false => self.resources,
+        }
+    }
+
+    /// Look every so often whether the computer runs on its battery, and
+    /// give the engines fewer threads (or their own back) when that changes.
+    fn check_power(&mut self) -> Command<Message> {
+        if self.power_saver != PowerSaver::OnBattery
+            || self.power_checked.is_some_and(|checked| checked.elapsed() < POWER_CHECK_INTERVAL)
+        {
+            return Command::none();
+        }
+        self.power_checked = Some(Instant::now());
+        let on_battery = power::on_battery().unwrap_or(false);
+        if on_battery == self.on_battery {
+            return Command::none();
+        }
+        self.on_battery = on_battery;
+        self.apply_resources()
+    }
+
+    /// Whether the analysis engines are stopped because the window is in
+    /// the background.
+    fn analysis_paused(&self) -> bool {
+        self.pause_unfocused && !self.focused
+    }
+
+    /// Start the background engine on the first position of the game it
+    /// hasn't evaluated yet, unless it is busy.  Online games are left
+    /// alone, like the kibitzers, and nothing new is started while the
+    /// analysis is paused.
+    fn continue_background_analysis(&mut self) -> Command<Message> {
+        let held = self.playing_online() || self.analysis_paused();
+        let Some(ref mut background) = self.background else {
+            return Command::none();
+        };
+        if background.searching.is_some() || held {
+            return Command::none();
+        }
+        let Ok(game) = self.game.lock() else {
+            return Command::none();
+        };
+        let positions = game.position_history();
+        // Forget positions from earlier games or taken back moves
+        background
+            .evals
+            .retain(|hash, _| positions.iter().any(|board| board.get_hash() == *hash));
+        let Some(&board) = positions
+            .iter()
+            .find(|board| !background.evals.contains_key(&board.get_hash()))
+        else {
+            return Command::none();
+        };
+        background.searching = Some(board);
+
+        let engine = Arc::clone(&background.engine);
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        Command::perform(
+            async move {
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut engine = engine.lock().map_err(|e| e.to_string())?;
+                    if !engine.is_running() {
+                        engine
+                            .start(&engine_path, 20, 0, BACKGROUND_RESOURCES, &uci_options)
+                            .map_err(|e| e.to_string())?;
+                    }
+                    analysis::evaluate_position(
+                        &mut engine,
+                        &board,
+                        SearchLimit::Depth(analysis::REVIEW_DEPTH),
+                    )
+                    .map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+                (board, result)
+            },
+            |(board, result)| Message::BackgroundEvaluated(board, result),
+        )
+    }
+
+    /// Have the coach engine look at the move held in coach mode; the
+    /// verdict comes back as CoachChecked.
+    fn start_coach_check(&mut self) -> Command<Message> {
+        let t = self.language.strings();
+        let (board, chess_move) = if let Ok(game) = self.game.lock() {
+            match game.pending_confirmation() {
+                Some(pending) => (game.current_position(), pending.chess_move),
+                None => return Command::none(),
+            }
+        } else {
+            return Command::none();
+        };
+
+        let coach_engine = Arc::clone(&self.coach_engine);
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        let resources = self.engine_resources();
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let mut engine = coach_engine.lock().map_err(|e| e.to_string())?;
+                    if !engine.is_running() {
+                        engine
+                            .start(&engine_path, 20, 0, resources, &uci_options)
+                            .map_err(|e| e.to_string())?;
+                    }
+                    analysis::check_move(&mut engine, &board, chess_move, t).map_err(|e| e.to_string())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            move |result| Message::CoachChecked(chess_move, result),
+        )
+    }
+
+    /// Answer a move the player just made: the engine replies in a normal
+    /// game that isn't over, a drill checks the move itself.
+    fn respond_to_player_move(&mut self) -> Command<Message> {
+        // Online, the move goes to Lichess and the opponent answers there
+        if let Some(online) = self.online_game() {
+            let id = online.id.clone();
+            let played = self
+                .game
+                .lock()
+                .ok()
+                .and_then(|game| game.move_history().last().map(|m| m.to_string()));
+            return match played {
+                Some(uci_move) => self.lichess_request(move |client| client.make_move(&id, &uci_move)),
+                None => Command::none(),
+            };
+        }
+        match self.drill {
+            Some(Drill::Repertoire(_)) => {
+                self.continue_training();
+                Command::none()
+            }
+            Some(Drill::Puzzle(_)) => {
+                self.check_puzzle_move();
+                Command::none()
+            }
+            Some(Drill::Endgame(_)) => {
+                if self.assess_endgame() {
+                    self.request_engine_move()
+                } else {
+                    Command::none()
+                }
+            }
+            None if !self.game_over() => self.request_engine_move(),
+            None => Command::none(),
+        }
+    }
+
+    fn game_over(&self) -> bool {
+        self.game
+            .lock()
+            .map(|game| game.game_result().is_some())
+            .unwrap_or(true)
+    }
+
+    /// Set up a fresh drill from the starting position; when training Black
+    /// the trainer opens with one of the repertoire's White moves.
+    fn start_training_game(&mut self) {
+        let t = self.language.strings();
+        let Some(Drill::Repertoire(ref trainer)) = self.drill else {
+            return;
+        };
+        self.engine_thinking = false;
+        if let Ok(mut game) = self.game.lock() {
+            game.reset();
+            game.set_player_color(trainer.color());
+            if let Some(opening) = trainer.opponent_reply(&game.current_position()) {
+                game.make_engine_move(&opening.to_string());
+            }
+            game.set_message(fill(t.training_repertoire, color_name(t, trainer.color())));
+        }
+    }
+
+    /// Check the player's move against the repertoire and grade it for the
+    /// review schedule: take it back with a correction if it deviates,
+    /// otherwise play a prepared reply.
+    fn continue_training(&mut self) {
+        let t = self.language.strings();
+        let Some(Drill::Repertoire(ref mut trainer)) = self.drill else {
+            return;
+        };
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let positions = game.position_history();
+        let (Some(&played), Some(&before)) = (
+            game.move_history().last(),
+            positions.len().checked_sub(2).map(|i| &positions[i]),
+        ) else {
+            return;
+        };
+
+        let correct = trainer.known_moves(&before).contains(&played);
+        trainer.grade(&before, correct);
+        if let Err(e) = repertoire::save_schedule(trainer.schedule()) {
+            eprintln!("Failed to save the review schedule: {}", e);
+        }
+
+        let prepared = trainer.known_moves(&before);
+        if !correct {
+            let expected: Vec<String> =
+                prepared.iter().map(|m| game::move_to_san(&before, *m)).collect();
+            let message = fill(
+                &fill(t.not_in_repertoire, game::move_to_san(&before, played)),
+                expected.join(t.or),
+            );
+            game.take_back_move();
+            game.set_message(message);
+            return;
+        }
+
+        let board = game.current_position();
+        let message = match trainer.opponent_reply(&board) {
+            Some(reply) => {
+                let san = game::move_to_san(&board, reply);
+                game.make_engine_move(&reply.to_string());
+                if trainer.known_moves(&game.current_position()).is_empty() {
+                    fill(t.line_ends, san)
+                } else {
+                    fill(t.opponent_replies, san)
+                }
+            }
+            None => t.line_complete.to_string(),
+        };
+        game.set_message(message);
+    }
+
+    /// Switch drills.  Endgame exercises are played against the engine at
+    /// full strength; everything else restores the configured level.
+    fn set_drill(&mut self, drill: Option<Drill>) {
+        let was_endgame = matches!(self.drill, Some(Drill::Endgame(_)));
+        let is_endgame = matches!(drill, Some(Drill::Endgame(_)));
+        if drill.is_some() {
+            self.set_game_kind(GameKind::Standard);
+        }
+        self.drill = drill;
+
+        if was_endgame != is_endgame {
+            let (skill_level, elo) = if is_endgame { (20, None) } else { (self.skill_level, self.elo) };
+            if let Ok(mut engine) = self.engine.lock() {
+                if let Err(e) = engine.set_skill_level(skill_level) {
+                    eprintln!("Failed to change engine skill level: {}", e);
+                }
+                if let Err(e) = engine.set_elo(elo) {
+                    eprintln!("Failed to change engine Elo: {}", e);
+                }
+            }
+        }
+    }
+
+    fn strength(&self) -> Strength {
+        match self.elo {
+            Some(elo) => Strength::Elo(elo),
+            None => Strength::Skill(self.skill_level),
+        }
+    }
+
+    /// Rate a finished rated game against an engine with an Elo limit.
+    /// Material or time odds would skew the rating, so those games don't
+    /// count.
+    fn update_rating(&mut self) {
+        let time_odds = self
+            .game
+            .lock()
+            .map_or(true, |game| game.time_control().is_some() && game.time_odds().is_some());
+        let Some(opponent) = self.elo else {
+            return;
+        };
+        if !self.snapshot.rated || time_odds || matches!(self.game_kind, GameKind::Odds(_)) {
+            return;
+        }
+        let Some(outcome) = Outcome::for_player(
+            pgn::result_string(self.snapshot.game_result),
+            &SavedGame::color_code(self.snapshot.player_color),
+        ) else {
+            return;
+        };
+
+        let played_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        self.rating.record(played_at, outcome, opponent);
+        if let Err(e) = rating::save_rating(&self.rating) {
+            eprintln!("Failed to save your rating: {}", e);
+        }
+    }
+
+    /// Count the finished game's result against the book moves the engine
+    /// played in it, results kept whether or not they weigh the book's
+    /// choices yet.
+    fn learn_from_book(&mut self) {
+        let Some((ref path, ref mut book)) = self.book else {
+            return;
+        };
+        if self.game_kind == GameKind::Chess960 {
+            return;
+        }
+        let Some(outcome) = Outcome::for_player(
+            pgn::result_string(self.snapshot.game_result),
+            &SavedGame::color_code(self.snapshot.player_color),
+        ) else {
+            return;
+        };
+        let Ok(game) = self.game.lock() else {
+            return;
+        };
+        let engine_moves: Vec<(chess::Board, chess::ChessMove)> = game
+            .position_history()
+            .iter()
+            .copied()
+            .zip(game.move_history().iter().copied())
+            .filter(|(board, _)| board.side_to_move() != game.player_color())
+            .collect();
+        // The player's win is the engine's loss
+        let outcome = match outcome {
+            Outcome::Win => Outcome::Loss,
+            Outcome::Draw => Outcome::Draw,
+            Outcome::Loss => Outcome::Win,
+        };
+        book.learn(&engine_moves, outcome);
+        if let Err(e) = book.save_learning(path) {
+            eprintln!("Failed to save the book's learning: {}", e);
+        }
+    }
+
+    /// After a finished game, step the engine's skill level or Elo limit
+    /// towards the player's level when adaptive strength is on.  Odds games
+    /// don't count.
+    fn adapt_strength(&mut self) {
+        let t = self.language.strings();
+        if !self.adaptive.enabled || matches!(self.game_kind, GameKind::Odds(_)) {
+            return;
+        }
+        let Some(outcome) = Outcome::for_player(
+            pgn::result_string(self.snapshot.game_result),
+            &SavedGame::color_code(self.snapshot.player_color),
+        ) else {
+            return;
+        };
+
+        let played_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let current = self.strength();
+        let next = self.adaptive.record(played_at, outcome, current, self.elo_range);
+        if let Err(e) = adaptive::save_adaptive(&self.adaptive) {
+            eprintln!("Failed to save adaptive strength history: {}", e);
+        }
+        if next == current {
+            return;
+        }
+
+        if let Ok(mut engine) = self.engine.lock() {
+            let result = match next {
+                Strength::Skill(level) => {
+                    self.skill_level = level;
+                    engine.set_skill_level(level)
+                }
+                Strength::Elo(elo) => {
+                    self.elo = Some(elo);
+                    engine.set_elo(Some(elo)).map(|_| ())
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to change engine strength: {}", e);
+            }
+        }
+        if let Ok(mut game) = self.game.lock() {
+            game.set_message(fill(t.adaptive_now, next.label()));
+        }
+    }
+
+    /// Give the running engines the current threads and hash size.
+    fn apply_resources(&mut self) -> Command<Message> {
+        let resources = self.engine_resources();
+        for engine in [&self.engine, &self.coach_engine] {
+            if let Ok(mut engine) = engine.lock() {
+                if engine.is_running() {
+                    if let Err(e) = engine.set_resources(resources) {
+                        eprintln!("Failed to change engine threads and hash: {}", e);
+                    }
+                }
+            }
+        }
+        // Changing options drops a search in progress
+        if self.engine_thinking {
+            return self.request_engine_move();
+        }
+        Command::none()
+    }
+
+    /// The EvalFile option every engine is started with
+    fn eval_file(&self) -> Option<&str> {
+        let mut options = self.uci_options.iter();
+        let (_, path) = options.find(|(name, _)| name.eq_ignore_ascii_case(EVAL_FILE))?;
+        Some(path)
+    }
+
+    /// Point the engines at another NNUE network, or back at their own
+    /// with None
+    fn set_eval_file(&mut self, path: Option<String>) -> Command<Message> {
+        self.uci_options.retain(|(name, _)| !name.eq_ignore_ascii_case(EVAL_FILE));
+        if let Some(path) = &path {
+            self.uci_options.push((EVAL_FILE.to_string(), path.clone()));
+        }
+        for engine in [&self.engine, &self.coach_engine] {
+            let Ok(mut engine) = engine.lock() else {
+                continue;
+            };
+            let value = path.clone().or_else(|| engine.option(EVAL_FILE)?.default.clone());
+            if let (true, Some(value)) = (engine.is_running(), value) {
+                if let Err(e) = engine.set_declared_option(EVAL_FILE, &value) {
+                    eprintln!("Failed to change the engine's eval file: {}", e);
+                }
+            }
+        }
+        // Changing options drops a search in progress
+        if self.engine_thinking {
+            return self.request_engine_move();
+        }
+        Command::none()
+    }
+
+    fn apply_personality(&mut self) -> Command<Message> {
+        if let Ok(mut engine) = self.engine.lock() {
+            if engine.is_running() {
+                let style = self.personality.as_ref();
+                let known = &self.personalities;
+                if let Err(e) = personality::apply(&mut engine, style, known, &self.uci_options) {
+                    eprintln!("Failed to change engine personality: {}", e);
+                }
+            }
+        }
+        if self.engine_thinking {
+            return self.request_engine_move();
+        }
+        Command::none()
+    }
+
+    /// Send the chosen Elo limit (or its removal) to the engine.  Changing
+    /// options interrupts a search, so one in progress is started again.
+    fn apply_strength(&mut self) -> Command<Message> {
+        // Endgame drills keep the engine at full strength until they end
+        if matches!(self.drill, Some(Drill::Endgame(_))) {
+            return Command::none();
+        }
+        if let Ok(mut engine) = self.engine.lock() {
+            if let Err(e) = engine.set_skill_level(self.skill_level) {
+                eprintln!("Failed to change engine skill level: {}", e);
+            }
+            match engine.set_elo(self.elo) {
+                Ok(applied) => self.elo = applied,
+                Err(e) => eprintln!("Failed to change engine Elo: {}", e),
+            }
+        }
+        if self.engine_thinking {
+            return self.request_engine_move();
+        }
+        Command::none()
+    }
+
+    // The engine is told when castling follows Chess960 rules
+    fn set_game_kind(&mut self, kind: GameKind) {
+        let chess960 = kind == GameKind::Chess960;
+        if (self.game_kind == GameKind::Chess960) != chess960 {
+            if let Ok(mut engine) = self.engine.lock() {
+                if let Err(e) = engine.set_chess960(chess960) {
+                    eprintln!("Failed to switch engine to Chess960: {}", e);
+                }
+            }
+        }
+        self.game_kind = kind;
+    }
+
+    /// Start a fresh game of the given kind from the New Game screen or
+    /// Reset: Chess960 deals a new random position, odds games rebuild the
+    /// handicap position for the player's colour.
+    fn start_new_game(&mut self, kind: GameKind, player_color: chess::Color) -> Command<Message> {
+        let t = self.language.strings();
+        let fen = match kind {
+            GameKind::Standard => chess::Board::default().to_string(),
+            GameKind::Chess960 => chess960::start_fen(chess960::random_index()),
+            GameKind::Odds(index) => HANDICAPS[index].fen(player_color),
+        };
+        let command = self.start_from_position(&fen, player_color, kind);
+        if kind == GameKind::Standard {
+            if let Ok(mut game) = self.game.lock() {
+                game.set_message(t.new_game_started.to_string());
+            }
+        }
+        command
+    }
 
-                let needs_engine_move = if let Ok(mut game) = self.game.lock() {
-                    game.reset_from_fen(&fen, player_color);
-                    game.current_position().side_to_move() != player_color
-                } else {
-                    false
-                };
+    /// Start a standard game with an opening's moves already played, so
+    /// the move list shows them and play carries on from there.
+    fn start_opening(&mut self, line: &OpeningLine, player_color: chess::Color) -> Command<Message> {
+        let t = self.language.strings();
+        self.set_game_kind(GameKind::Standard);
+        let moves: Vec<String> = line.moves.iter().map(|m| m.to_string()).collect();
+        let needs_engine_move = if let Ok(mut game) = self.game.lock() {
+            game.load_moves(&chess::Board::default().to_string(), &moves, player_color)
+                .expect("the start position is legal");
+            game.set_message(fill(t.new_game_opening, line.opening.display()));
+            game.current_position().side_to_move() != player_color
+        } else {
+            false
+        };
 
-                self.screen = AppScreen::Game;
+        if needs_engine_move {
+            return self.request_engine_move();
+        }
+        Command::none()
+    }
 
-                if needs_engine_move {
-                    self.engine_thinking = true;
-                    if let Ok(mut game) = self.game.lock() {
-                        game.set_thinking(true);
-                    }
-                    let engine_clone = Arc::clone(&self.engine);
-                    let game_clone = Arc::clone(&self.game);
-                    return Command::perform(
-                        async move {
-                            if let Ok(game) = game_clone.lock() {
-                                if let Ok(mut engine) = engine_clone.lock() {
-                                    let fen = game.current_position().to_string();
-                                    let _ = engine.get_move(&fen);
-                                }
-                            }
-                        },
-                        |_| Message::CheckEngineMove,
-                    );
+    /// Load a game read from PGN for replay from its first move.  The player
+    /// takes the side to move at the end, so leaving replay continues the
+    /// game against the engine.
+    fn open_pgn_game(&mut self, pgn_game: &PgnGame) {
+        let t = self.language.strings();
+        let moves = pgn_game.lines.first().map(Vec::as_slice).unwrap_or_default();
+        let end = moves
+            .iter()
+            .fold(pgn_game.start, |board, &m| board.make_move_new(m));
+        let uci_moves: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+
+        self.archived = None;
+        if let Ok(mut game) = self.game.lock() {
+            if let Err(e) = game.load_moves(&pgn_game.start.to_string(), &uci_moves, end.side_to_move()) {
+                game.set_message(fill(t.game_unloadable, e));
+                return;
+            }
+            game.apply_annotations(&pgn_game.annotations);
+            game.apply_clocks(&pgn_game.clocks);
+            if pgn_game.tree.has_variations() {
+                game.set_variations(pgn_game.tree.clone());
+            }
+            game.view_move_at(0);
+            game.set_message(fill(
+                &fill(t.replaying_pgn, uci_moves.len()),
+                color_name(t, end.side_to_move()),
+            ));
+            // Nothing new to archive if the game is already over
+            self.game_saved = game.game_result().is_some();
+        }
+    }
+
+    /// Start a game from a set-up position; the engine moves first if it
+    /// has the move.  Chess960 positions keep their castling rights unless
+    /// the engine can't play Chess960; then they lose any the move
+    /// generator can't play the standard way, and the player is told so.
+    fn start_from_position(
+        &mut self,
+        fen: &str,
+        player_color: chess::Color,
+        kind: GameKind,
+    ) -> Command<Message> {
+        let t = self.language.strings();
+        self.set_game_kind(kind);
+        let engine_plays_960 = self.engine.lock().is_ok_and(|engine| engine.plays_chess960());
+        let playable = match kind {
+            GameKind::Chess960 if !engine_plays_960 => chess960::playable_fen(fen),
+            _ => fen.to_string(),
+        };
+
+        let needs_engine_move = if let Ok(mut game) = self.game.lock() {
+            if let Err(e) = game.reset_from_fen(&playable, player_color) {
+                game.set_message(fill(t.game_unloadable, e));
+                return Command::none();
+            }
+            match kind {
+                GameKind::Chess960 => {
+                    let castling_dropped = chess960::Castling::from_fen(&playable).field()
+                        != chess960::Castling::from_fen(fen).field();
+                    let message = if castling_dropped {
+                        t.chess960_no_castling
+                    } else {
+                        t.chess960_started
+                    };
+                    game.set_message(message.to_string());
+                }
+                GameKind::Odds(index) => {
+                    let handicap = &HANDICAPS[index];
+                    game.set_message(format!("{}: {}", handicap.name, handicap.description));
                 }
+                GameKind::Standard => {}
+            }
+            game.current_position().side_to_move() != player_color
+        } else {
+            false
+        };
 
-                Command::none()
+        if needs_engine_move {
+            return self.request_engine_move();
+        }
+        Command::none()
+    }
+
+    /// Load the current endgame exercise; the engine moves first if the
+    /// player is defending.
+    fn start_endgame(&mut self) -> Command<Message> {
+        let Some(Drill::Endgame(ref mut session)) = self.drill else {
+            return Command::none();
+        };
+        session.outcome = None;
+        let endgame = session.endgame;
+
+        let needs_engine_move = if let Ok(mut game) = self.game.lock() {
+            game.reset_from_fen(endgame.fen, endgame.player).expect("endgame positions are legal");
+            game.set_message(endgame.description.to_string());
+            game.current_position().side_to_move() != endgame.player
+        } else {
+            false
+        };
+
+        if needs_engine_move {
+            return self.request_engine_move();
+        }
+        self.engine_thinking = false;
+        Command::none()
+    }
+
+    /// Check whether the endgame exercise has been converted or botched.
+    /// Returns true while it is still in progress and the engine should
+    /// keep playing.
+    fn assess_endgame(&mut self) -> bool {
+        let t = self.language.strings();
+        let Some(Drill::Endgame(ref mut session)) = self.drill else {
+            return false;
+        };
+        if session.outcome.is_some() {
+            return false;
+        }
+        let Ok(mut game) = self.game.lock() else {
+            return false;
+        };
+
+        let player = session.endgame.player;
+        let player_moves = game
+            .position_history()
+            .iter()
+            .take(game.move_history().len())
+            .filter(|board| board.side_to_move() == player)
+            .count();
+        let outcome =
+            session
+                .endgame
+                .assess(&game.current_position(), player_moves, game.can_claim_draw());
+
+        match outcome {
+            Some(outcome) => {
+                game.set_message(match outcome {
+                    EndgameOutcome::Converted(reason) => fill(t.exercise_complete, reason),
+                    EndgameOutcome::Botched(reason) => fill(t.exercise_failed, reason),
+                });
+                session.outcome = Some(outcome);
+                false
             }
+            None => game.game_result().is_none(),
         }
     }
 
-    fn view(&self) -> Element<'_, Message> {
-        match &self.screen {
-            AppScreen::Setup(state) => {
-                return self.ui.view_setup(state, self.window_size.width, self.window_size.height);
+    /// Put the current puzzle's position on the board with the player to move.
+    fn start_puzzle(&mut self) {
+        let t = self.language.strings();
+        let Some(Drill::Puzzle(ref mut session)) = self.drill else {
+            return;
+        };
+        session.solved = false;
+        self.engine_thinking = false;
+        if let Ok(mut game) = self.game.lock() {
+            let fen = &session.puzzle.fen;
+            let loaded = safe_parse_board(fen, t)
+                .and_then(|board| game.reset_from_fen(fen, board.side_to_move()).map(|()| board));
+            let Ok(board) = loaded else {
+                game.set_message(t.puzzle_unloadable.to_string());
+                return;
+            };
+            game.set_message(fill(t.puzzle_start, color_name(t, board.side_to_move())));
+        }
+    }
+
+    /// Compare the player's move with the puzzle's solution.  A wrong move is
+    /// taken back so the player can try again.
+    fn check_puzzle_move(&mut self) {
+        let t = self.language.strings();
+        let Some(Drill::Puzzle(ref mut session)) = self.drill else {
+            return;
+        };
+        let Ok(mut game) = self.game.lock() else {
+            return;
+        };
+        let Some(&played) = game.move_history().last() else {
+            return;
+        };
+
+        let solved = played.to_string() == session.puzzle.solution;
+        if let Some(ref database) = self.database {
+            if let Err(e) = database.record_puzzle_attempt(session.puzzle.id, solved) {
+                eprintln!("Failed to record puzzle attempt: {}", e);
             }
-            AppScreen::Game => {}
         }
 
-        // Get a snapshot of the game state
-        let game_state = if let Ok(game) = self.game.lock() {
-            (
-                game.current_position(),
-                game.selected_square(),
-                game.possible_moves().clone(),
-                game.message().to_string(),
-                game.is_thinking(),
-                game.player_color(),
-                game.game_result(),
-                game.get_move_records().clone(),
-                game.is_view_mode(),
-                game.view_move_index(),
-                game.pending_promotion(),
-            )
+        if solved {
+            session.solved = true;
+            game.set_message(t.puzzle_correct.to_string());
         } else {
-            // Default state if lock fails
-            (
-                chess::Board::default(),
-                None,
-                Vec::new(),
-                "Error accessing game state".to_string(),
-                false,
-                chess::Color::White,
-                None,
-                Vec::new(),
-                false,
-                0,
-                None,
-            )
+            let board = game.start_position();
+            let message = if session.puzzle.played == played.to_string() {
+                t.puzzle_same_move.to_string()
+            } else {
+                let hint = game::parse_uci_move(&board, &session.puzzle.solution)
+                    .and_then(|m| board.piece_on(m.get_source()))
+                    .map(|piece| fill(t.puzzle_hint, piece_name(t, piece)))
+                    .unwrap_or_default();
+                fill(t.puzzle_wrong, hint)
+            };
+            game.take_back_move();
+            game.set_message(message);
+        }
+    }
+
+    /// Start the first unsolved puzzle after the current one, wrapping round.
+    fn next_puzzle(&mut self) {
+        let t = self.language.strings();
+        let Some(ref database) = self.database else {
+            return;
         };
+        let puzzles = database.list_puzzles().unwrap_or_default();
+        let current = match self.drill {
+            Some(Drill::Puzzle(ref session)) => session.puzzle.id,
+            _ => 0,
+        };
+        let unsolved: Vec<&StoredPuzzle> = puzzles.iter().filter(|p| !p.solved).collect();
+        let next = unsolved
+            .iter()
+            .find(|p| p.id > current)
+            .or_else(|| unsolved.first())
+            .map(|p| (*p).clone());
 
-        // Render the UI with current window size
-        self.ui.view(
-            game_state.0,
-            game_state.1,
-            &game_state.2,
-            &game_state.3,
-            game_state.4,
-            game_state.5,
-            game_state.6,
-            self.window_size.width,
-            self.window_size.height,
-            &game_state.7,
-            game_state.8,
-            game_state.9,
-            game_state.10,
+        match next {
+            Some(puzzle) => {
+                self.set_drill(Some(Drill::Puzzle(PuzzleSession {
+                    puzzle,
+                    solved: false,
+                })));
+                self.start_puzzle();
+            }
+            None => {
+                self.set_drill(None);
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_message(t.no_puzzles_left.to_string());
+                }
+            }
+        }
+    }
+
+    /// Save the positions before the player's blunders as puzzles.  Returns
+    /// how many new ones were queued.
+    fn save_blunder_puzzles(&self, game: &ChessGame, evaluations: &[MoveEvaluation]) -> usize {
+        let Some(ref database) = self.database else {
+            return 0;
+        };
+        let created_at = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        let positions = game.position_history();
+        let moves = game.move_history();
+
+        evaluations
+            .iter()
+            .enumerate()
+            .filter(|(ply, e)| {
+                e.class == analysis::MoveClass::Blunder
+                    && positions.get(*ply).map(|b| b.side_to_move()) == Some(game.player_color())
+                    && !e.best_move.is_empty()
+            })
+            .filter_map(|(ply, e)| {
+                let puzzle = StoredPuzzle {
+                    id: 0,
+                    created_at: created_at.clone(),
+                    fen: positions[ply].to_string(),
+                    solution: e.best_move.clone(),
+                    played: moves.get(ply)?.to_string(),
+                    attempts: 0,
+                    solved: false,
+                };
+                database
+                    .insert_puzzle(&puzzle)
+                    .map_err(|e| eprintln!("Failed to save puzzle: {}", e))
+                    .ok()
+            })
+            .filter(|&added| added)
+            .count()
+    }
+
+    /// Short display name for the engine, taken from its executable name.
+    fn engine_name(&self) -> String {
+        self.engine_path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Engine".to_string())
+    }
+
+    /// Whether the engine picks among its top lines instead of always
+    /// playing the best one; only below full strength, never in endgame
+    /// drills.
+    fn varies_moves(&self) -> bool {
+        self.variety_window > 0
+            && (self.elo.is_some() || self.skill_level < 20)
+            && !matches!(self.drill, Some(Drill::Endgame(_)))
+    }
+
+    /// The move the engine's search for `best_move` expects in answer: the
+    /// ponder move of its bestmove, or for a varied choice from its other
+    /// lines, the second move of that line.
+    fn expected_reply(&self, best_move: &str) -> Option<String> {
+        let engine = self.engine.lock().ok()?;
+        if let Some(best) = engine.last_best().filter(|best| best.best_move == best_move) {
+            if best.ponder.is_some() {
+                return best.ponder.clone();
+            }
+        }
+        let lines = engine.current_lines();
+        let line = lines.iter().find(|line| line.first_move.as_deref() == Some(best_move))?;
+        line.reply.clone()
+    }
+
+    /// How long the engine has been searching past its move time, once
+    /// that is more than the grace period; a paused game or a reply held
+    /// back for pacing doesn't count.
+    fn engine_overrun(&self) -> Option<Duration> {
+        if !self.engine_thinking || self.held_move.is_some() || self.snapshot.paused {
+            return None;
+        }
+        let overdue = Instant::now().checked_duration_since(self.search_deadline?)?;
+        Some(overdue + ENGINE_GRACE)
+    }
+
+    /// Collect the engine's finished search, swapping in a varied choice
+    /// from its top lines when variety is on.
+    fn receive_engine_move(&mut self) -> Option<String> {
+        let mut engine = self.engine.lock().ok()?;
+        let best_move = engine.try_receive_move()?;
+        if self.engine_multipv > 1 {
+            let window = self.variety_window as i32;
+            if let Some(varied) = engine::pick_varied_move(&engine.current_lines(), window) {
+                return Some(varied);
+            }
+        }
+        Some(best_move)
+    }
+
+    /// End the game when a clock runs out.  Online games are timed by the
+    /// server; here the engine's search is dropped if it was still thinking.
+    fn check_flag(&mut self) {
+        if self.playing_online() {
+            return;
+        }
+        let flagged = self.game.lock().ok().and_then(|mut game| game.check_flag());
+        if flagged.is_some() {
+            self.engine_thinking = false;
+            self.held_move = None;
+            self.reply_due = None;
+            self.search_deadline = None;
+        }
+    }
+
+    /// Start the playing engine in the background with the current
+    /// settings; the outcome arrives as EngineStarted or EngineFailed.
+    fn start_engine(&mut self) -> Command<Message> {
+        self.engine_error = None;
+        let engine = Arc::clone(&self.engine);
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        let skill_level = self.skill_level;
+        let think_time = self.think_time;
+        let resources = self.engine_resources();
+        let elo = self.elo;
+        let personalities = self.personalities.clone();
+        let personality = self.personality.clone();
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let mut engine = engine.lock().map_err(|e| e.to_string())?;
+                    engine
+                        .start(&engine_path, skill_level, think_time, resources, &uci_options)
+                        .map_err(|e| e.to_string())?;
+                    let elo = engine.set_elo(elo).unwrap_or_else(|e| {
+                        eprintln!("Failed to set engine Elo: {}", e);
+                        None
+                    });
+                    let style = personality.as_ref();
+                    let known = &personalities;
+                    if let Err(e) = personality::apply(&mut engine, style, known, &uci_options) {
+                        eprintln!("Failed to set engine personality: {}", e);
+                    }
+                    Ok((engine.elo_range(), elo, engine.id().clone()))
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            |result| match result {
+                Ok((elo_range, elo, id)) => Message::EngineStarted { elo_range, elo, id },
+                Err(e) => Message::EngineFailed(e),
+            },
         )
     }
 
-    fn subscription(&self) -> Subscription<Message> {
-        // Subscribe to time ticks for regular updates and window resize events
-        Subscription::batch(vec![
-            iced::time::every(std::time::Duration::from_millis(100)).map(|_| Message::Tick),
-            iced::subscription::events_with(|event, _| {
-                if let Event::Window(window::Event::Resized { width, height }) = event {
-                    Some(Message::WindowResized(width, height))
+    /// Mark the engine as thinking and ask it for a move in the current
+    /// position; the reply is picked up by the CheckEngineMove poll loop.
+    fn request_engine_move(&mut self) -> Command<Message> {
+        if self.playing_online() {
+            return Command::none();
+        }
+        self.engine_thinking = true;
+        self.held_move = None;
+        self.reply_due = None;
+
+        // In book the engine plays one of its moves without searching
+        if let Some(book_move) = self.book_move() {
+            if let Ok(mut game) = self.game.lock() {
+                game.set_thinking(true);
+            }
+            return Command::perform(async move { book_move.to_string() }, Message::EngineMoved);
+        }
+
+        let multipv = if self.varies_moves() { VARIETY_LINES } else { 1 };
+        if multipv != self.engine_multipv {
+            if let Ok(mut engine) = self.engine.lock() {
+                match engine.set_multipv(multipv) {
+                    Ok(()) => self.engine_multipv = multipv,
+                    Err(e) => eprintln!("Failed to set engine MultiPV: {}", e),
+                }
+            }
+        }
+
+        let mut movetime = match self.saving_power() {
+            true => self.think_time.min(POWER_SAVER_THINK_MS),
+            false => self.think_time,
+        };
+        // With clocks the engine manages its own time from them, and may
+        // use all it has left
+        let mut clock_limit = None;
+        if let Ok(mut game) = self.game.lock() {
+            game.set_thinking(true);
+            let white_control = game.control_for(chess::Color::White);
+            let controls = white_control.zip(game.control_for(chess::Color::Black));
+            if let (Some((white_control, black_control)), Some((white, black))) =
+                (controls, game.clock_remaining())
+            {
+                let left = if game.current_position().side_to_move() == chess::Color::White {
+                    white
                 } else {
-                    None
+                    black
+                };
+                // UCI has no delay; engines budget for it as an increment
+                let limit = SearchLimit::Clock {
+                    wtime: white.as_millis() as u64,
+                    btime: black.as_millis() as u64,
+                    winc: white_control.increment_ms,
+                    binc: black_control.increment_ms,
+                };
+                clock_limit = Some((limit, left));
+            }
+            // The engine never searches longer than configured; a longer
+            // paced reply holds the move back instead
+            if self.human_pacing {
+                let board = game.current_position();
+                let reply = pacing::reply_time(&board, game.move_history().last().copied(), self.think_time);
+                movetime = movetime.min(reply.as_millis() as u64);
+                self.reply_due = Some(Instant::now() + reply);
+            }
+        }
+        let budget = clock_limit.map_or(Duration::from_millis(movetime), |(_, left)| left);
+        self.search_deadline = Some(Instant::now() + budget + ENGINE_GRACE);
+
+        let engine_clone = Arc::clone(&self.engine);
+        let game_clone = Arc::clone(&self.game);
+
+        Command::perform(
+            async move {
+                if let Ok(game) = game_clone.lock() {
+                    if let Ok(mut engine) = engine_clone.lock() {
+                        let fen = game.current_fen();
+                        let _ = match clock_limit {
+                            Some((limit, _)) => engine.get_move_by_limit(&fen, limit),
+                            None => engine.get_move_within(&fen, movetime),
+                        };
+                    }
                 }
-            }),
-        ])
+            },
+            |_| Message::CheckEngineMove,
+        )
     }
 
-    fn theme(&self) -> Theme {
-        Theme::Dark
+    /// A move from the engine's book for the position on the board, while
+    /// the game is in it; drills have replies of their own.
+    fn book_move(&self) -> Option<chess::ChessMove> {
+        let (_, book) = self.book.as_ref()?;
+        if self.drill.is_some() || self.game_kind == GameKind::Chess960 {
+            return None;
+        }
+        let game = self.game.lock().ok()?;
+        book.pick(&game.current_position(), self.book_learning)
+    }
+
+    /// Write the in-progress game to the autosave slot when it has changed
+    /// and the save interval has elapsed.  Finished or empty games clear the
+    /// slot so there is nothing stale to offer on the next launch.
+    fn autosave_if_due(&mut self) {
+        // Don't overwrite the previous session's game before the user
+        // decides, and don't offer training drills for resuming
+        if self.resume_offer.is_some() || self.drill.is_some() || self.playing_online() {
+            return;
+        }
+        if self.snapshot.revision == self.autosaved_revision
+            || self.last_autosave.elapsed() < AUTOSAVE_INTERVAL
+        {
+            return;
+        }
+        self.autosaved_revision = self.snapshot.revision;
+        self.last_autosave = Instant::now();
+
+        let saved = if let Ok(game) = self.game.lock() {
+            if game.move_history().is_empty() || game.game_result().is_some() {
+                None
+            } else {
+                Some(self.saved_game(&game))
+            }
+        } else {
+            return;
+        };
+
+        match saved {
+            Some(saved) => {
+                if let Err(e) = persistence::save_autosave(&saved) {
+                    eprintln!("Failed to autosave game: {}", e);
+                }
+            }
+            None => persistence::clear_autosave(),
+        }
+    }
+
+    fn online_game(&self) -> Option<&OnlineGame> {
+        self.lichess.as_ref()?.game.as_ref()
+    }
+
+    fn online_game_mut(&mut self) -> Option<&mut OnlineGame> {
+        self.lichess.as_mut()?.game.as_mut()
+    }
+
+    /// A Lichess game is on the board, finished or not: the local engine
+    /// stays out of it and the game isn't archived or autosaved.
+    fn playing_online(&self) -> bool {
+        self.online_game().is_some()
+    }
+
+    fn leave_online_game(&mut self) {
+        if let Some(ref mut session) = self.lichess {
+            session.game = None;
+        }
+    }
+
+    fn forget_challenge(&mut self, id: &str) {
+        if let Some(ref mut session) = self.lichess {
+            session.challenges.retain(|challenge| challenge.id != id);
+        }
+    }
+
+    /// Send a request to Lichess in the background; the outcome comes back
+    /// as LichessReplied.
+    fn lichess_request(
+        &self,
+        request: impl FnOnce(&LichessClient) -> Result<(), AppError> + Send + 'static,
+    ) -> Command<Message> {
+        let Some(ref session) = self.lichess else {
+            return Command::none();
+        };
+        let client = session.client.clone();
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || request(&client).map_err(|e| e.to_string()))
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            Message::LichessReplied,
+        )
+    }
+
+    /// Read what Lichess has sent since the last tick: challenges, game
+    /// starts, and the moves and clocks of the game on the board.
+    fn poll_lichess(&mut self) -> Command<Message> {
+        let t = self.language.strings();
+        let Some(ref mut session) = self.lichess else {
+            return Command::none();
+        };
+        let (lines, open) = session.events.take_lines();
+        let mut started = None;
+        for line in lines {
+            match lichess::parse_account_event(&line, &session.username) {
+                Some(AccountEvent::Challenge(challenge))
+                    if !session.challenges.iter().any(|known| known.id == challenge.id) =>
+                {
+                    session.challenges.push(challenge);
+                }
+                Some(AccountEvent::ChallengeGone(id)) => {
+                    session.challenges.retain(|challenge| challenge.id != id);
+                }
+                // Games still running are announced again on connecting
+                Some(AccountEvent::GameStart { id, color })
+                    if session.game.as_ref().map(|game| &game.id) != Some(&id) =>
+                {
+                    started = Some((id, color));
+                }
+                _ => {}
+            }
+        }
+        if !open {
+            self.lichess = None;
+            if let Ok(mut game) = self.game.lock() {
+                game.set_message(t.lichess_disconnected.to_string());
+            }
+            return Command::none();
+        }
+        // The seek ends when it is paired or refused
+        if session.seek.as_ref().is_some_and(|seek| !seek.take_lines().1) {
+            session.seek = None;
+        }
+
+        if let Some((id, color)) = started {
+            let stream = match session.client.stream_game(&id) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    if let Ok(mut game) = self.game.lock() {
+                        game.set_message(fill(t.lichess_follow_failed, e));
+                    }
+                    return Command::none();
+                }
+            };
+            session.seek = None;
+            session.game = Some(OnlineGame {
+                id,
+                color,
+                stream: Some(stream),
+                opponent: "Opponent".to_string(),
+                rated: false,
+                against_ai: false,
+                start_fen: chess::Board::default().to_string(),
+                start: Ok(chess::Board::default()),
+                control: None,
+                state: None,
+                received: Instant::now(),
+                kibitz: false,
+                kibitz_eval: None,
+            });
+            self.set_drill(None);
+            self.set_game_kind(GameKind::Standard);
+            self.engine_thinking = false;
+            self.screen = AppScreen::Game;
+            if let Ok(mut game) = self.game.lock() {
+                game.set_coach_mode(false);
+                game.set_message(t.lichess_game_found.to_string());
+            }
+        }
+
+        let Some(online) = self.online_game_mut() else {
+            return Command::none();
+        };
+        let Some(ref stream) = online.stream else {
+            return Command::none();
+        };
+        let (lines, open) = stream.take_lines();
+        let mut updated = false;
+        for line in lines {
+            let state = match lichess::parse_game_event(&line) {
+                Some(GameEvent::Full {
+                    white,
+                    black,
+                    rated,
+                    against_ai,
+                    initial_fen,
+                    control,
+                    state,
+                }) => {
+                    online.opponent = if online.color == chess::Color::White { black } else { white };
+                    online.rated = rated;
+                    online.against_ai = against_ai;
+                    online.control = control;
+                    if let Some(fen) = initial_fen {
+                        online.start = safe_parse_board(&fen, t);
+                        online.start_fen = fen;
+                    }
+                    state
+                }
+                Some(GameEvent::State(state)) => state,
+                None => continue,
+            };
+            online.state = Some(state);
+            online.received = Instant::now();
+            updated = true;
+        }
+        if !open {
+            online.stream = None;
+            if !online.is_over() {
+                if let Ok(mut game) = self.game.lock() {
+                    game.set_message(t.lichess_stopped.to_string());
+                }
+            }
+        }
+        if updated {
+            return self.sync_online_game(false);
+        }
+        Command::none()
+    }
+
+    /// Bring the board in line with the latest Lichess state.  The
+    /// opponent's reply is played on the board; anything else, or `reload`,
+    /// replays the game from the start.
+    fn sync_online_game(&mut self, reload: bool) -> Command<Message> {
+        let t = self.language.strings();
+        let Some(online) = self.online_game() else {
+            return Command::none();
+        };
+        let Some(ref state) = online.state else {
+            return Command::none();
+        };
+        let outcome = state.is_over().then(|| state.outcome());
+        let kibitz = online.kibitz && outcome.is_none();
+
+        if let Ok(mut game) = self.game.lock() {
+            let played: Vec<String> = game.move_history().iter().map(|m| m.to_string()).collect();
+            let board = game.current_position();
+            let reply = state.moves.get(played.len()).filter(|_| {
+                !reload && state.moves.len() == played.len() + 1 && state.moves.starts_with(&played)
+            });
+            if let Err(e) = &online.start {
+                game.set_message(fill(t.game_unloadable, e));
+            } else if let Some(reply) = reply.filter(|reply| game.make_engine_move(reply)) {
+                let san = game::parse_uci_move(&board, reply)
+                    .map_or_else(|| reply.clone(), |m| game::move_to_san(&board, m));
+                game.set_message(fill(&fill(t.opponent_played, &online.opponent), san));
+            } else if reload || state.moves != played {
+                match game.load_moves(&online.start_fen, &state.moves, online.color) {
+                    Ok(_) => game.set_message(fill(
+                        &fill(t.playing_on_lichess, &online.opponent),
+                        color_name(t, online.color),
+                    )),
+                    Err(e) => game.set_message(fill(t.game_unloadable, e)),
+                }
+            }
+            if let Some(outcome) = outcome {
+                game.set_message(outcome);
+            }
+        }
+
+        if kibitz {
+            return self.start_kibitz();
+        }
+        Command::none()
+    }
+
+    /// Hand the move to the opponent and have the coach engine search the
+    /// position, keeping the moves that score close to its best one.
+    fn find_threats(&mut self) -> Command<Message> {
+        let board = self.snapshot.board;
+        // In check the threat is plain, and passing would be illegal
+        let Some(passed) = board.null_move() else {
+            return Command::none();
+        };
+        self.threats = Some(Threats { board, moves: None });
+        let fen = passed.to_string();
+
+        let coach_engine = Arc::clone(&self.coach_engine);
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        let resources = self.engine_resources();
+        Command::perform(
+            async move {
+                let search_fen = fen.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut engine = coach_engine.lock().map_err(|e| e.to_string())?;
+                    if !engine.is_running() {
+                        engine
+                            .start(&engine_path, 20, 0, resources, &uci_options)
+                            .map_err(|e| e.to_string())?;
+                    }
+                    engine.set_multipv(THREAT_LINES).map_err(|e| e.to_string())?;
+                    let analysis = engine.analyse(&search_fen, SearchLimit::Depth(THREAT_DEPTH));
+                    engine.set_multipv(1).map_err(|e| e.to_string())?;
+                    let analysis = analysis.map_err(|e| e.to_string())?;
+                    let best = analysis.score().map_or(0, |score| score.to_cp());
+                    Ok(analysis
+                        .lines
+                        .iter()
+                        .filter(|line| line.score.to_cp() >= best - THREAT_MARGIN)
+                        .filter_map(|line| line.first_move.clone())
+                        .collect())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+                (fen, result)
+            },
+            |(fen, result)| Message::ThreatsFound(fen, result),
+        )
+    }
+
+    /// Ask the coach engine for its best few moves in the position on the
+    /// board, with their scores.
+    fn find_candidates(&mut self) -> Command<Message> {
+        let board = self.snapshot.board;
+        if board.status() != chess::BoardStatus::Ongoing {
+            return Command::none();
+        }
+        self.candidates = Some(Candidates { board, moves: None });
+        let fen = board.to_string();
+
+        let coach_engine = Arc::clone(&self.coach_engine);
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        let resources = self.engine_resources();
+        Command::perform(
+            async move {
+                let search_fen = fen.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let mut engine = coach_engine.lock().map_err(|e| e.to_string())?;
+                    if !engine.is_running() {
+                        engine
+                            .start(&engine_path, 20, 0, resources, &uci_options)
+                            .map_err(|e| e.to_string())?;
+                    }
+                    engine.set_multipv(CANDIDATE_LINES).map_err(|e| e.to_string())?;
+                    let analysis = engine.analyse(&search_fen, SearchLimit::Depth(CANDIDATE_DEPTH));
+                    engine.set_multipv(1).map_err(|e| e.to_string())?;
+                    let analysis = analysis.map_err(|e| e.to_string())?;
+                    Ok(analysis
+                        .lines
+                        .iter()
+                        .filter_map(|line| Some((line.first_move.clone()?, line.score.to_cp())))
+                        .collect())
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()));
+                (fen, result)
+            },
+            |(fen, result)| Message::CandidatesFound(fen, result),
+        )
+    }
+
+    /// Keep the kibitzers on the position on the board: pick up their
+    /// latest lines, and when the position has changed start them
+    /// searching the new one.  Outside help has no place in an online
+    /// game, so they are turned off there.
+    fn follow_with_kibitzers(&mut self) -> Command<Message> {
+        if self.playing_online() {
+            self.kibitzers.clear();
+        }
+        if self.analysis_paused() {
+            return Command::none();
+        }
+        let board = self.snapshot.board;
+        let resources = self.engine_resources();
+        let mut commands = Vec::new();
+        for (index, kibitzer) in self.kibitzers.iter_mut().enumerate() {
+            // The engine is busy starting up while its lock is held
+            if let Ok(engine) = kibitzer.engine.try_lock() {
+                kibitzer.lines = engine.current_lines();
+            }
+            if kibitzer.board == Some(board) {
+                continue;
+            }
+            kibitzer.board = Some(board);
+            kibitzer.lines.clear();
+            kibitzer.error = None;
+
+            let engine = Arc::clone(&kibitzer.engine);
+            let engine_path = kibitzer.path.clone();
+            let uci_options = self.uci_options.clone();
+            commands.push(Command::perform(
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        let mut engine = engine.lock().map_err(|e| e.to_string())?;
+                        if !engine.is_running() {
+                            engine
+                                .start(&engine_path, 20, 0, resources, &uci_options)
+                                .map_err(|e| e.to_string())?;
+                            engine.set_multipv(KIBITZER_LINES).map_err(|e| e.to_string())?;
+                        }
+                        engine.analyse_infinite(&board.to_string()).map_err(|e| e.to_string())
+                    })
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()))
+                },
+                move |result| Message::KibitzerStarted(index, result),
+            ));
+        }
+        Command::batch(commands)
+    }
+
+    /// Have the coach engine evaluate the position on the board for the
+    /// kibitzer; the verdict comes back as KibitzEvaluated.
+    fn start_kibitz(&mut self) -> Command<Message> {
+        let Ok(board) = self.game.lock().map(|game| game.current_position()) else {
+            return Command::none();
+        };
+        let fen = board.to_string();
+
+        let coach_engine = Arc::clone(&self.coach_engine);
+        let engine_path = self.engine_path.clone();
+        let uci_options = self.uci_options.clone();
+        let resources = self.engine_resources();
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || {
+                    let mut engine = coach_engine.lock().map_err(|e| e.to_string())?;
+                    if !engine.is_running() {
+                        engine
+                            .start(&engine_path, 20, 0, resources, &uci_options)
+                            .map_err(|e| e.to_string())?;
+                    }
+                    let limit = SearchLimit::Depth(KIBITZ_DEPTH);
+                    let eval = analysis::evaluate_position(&mut engine, &board, limit)
+                        .map_err(|e| e.to_string())?;
+                    let best = eval
+                        .best_move
+                        .and_then(|uci_move| game::parse_uci_move(&board, &uci_move))
+                        .map(|m| format!(", best {}", game::move_to_san(&board, m)));
+                    Ok(format!("{}{}", analysis::format_eval(eval.white_cp), best.unwrap_or_default()))
+                })
+                .await
+                .unwrap_or_else(|e| Err(e.to_string()))
+            },
+            move |result| Message::KibitzEvaluated(fen, result),
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup_error(fen: &str) -> Option<String> {
+        let mut state = SetupState::from_board(&chess::Board::default(), chess::Color::White);
+        state.parse_fen_to_state(fen, &i18n::ENGLISH);
+        state.fen_error
+    }
+
+    #[test]
+    fn test_impossible_material() {
+        assert_eq!(
+            setup_error("4k3/8/8/8/8/P7/PPPPPPPP/4K3 w - - 0 1").as_deref(),
+            Some("White has 9 pawns; at most 8 are possible")
+        );
+        // A ninth piece beside eight pawns
+        assert_eq!(
+            setup_error("rnbqkbnr/pppppppp/n7/8/8/8/8/4K3 w - - 0 1").as_deref(),
+            Some("Black has 17 pieces; at most 16 are possible")
+        );
+        // Three queens need two promotions, but only one pawn is missing
+        assert_eq!(
+            setup_error("4k3/8/8/8/8/8/PPPPPPP1/QQQ1K3 w - - 0 1").as_deref(),
+            Some(
+                "White has 1 more queens, rooks, bishops or knights than its missing pawns could \
+                 have become"
+            )
+        );
+        assert_eq!(setup_error(&chess::Board::default().to_string()), None);
+    }
+
+    #[test]
+    fn test_check_on_side_not_to_move() {
+        let fen = "4r2k/8/8/8/8/8/8/4K3 b - - 0 1";
+        assert_eq!(
+            setup_error(fen).as_deref(),
+            Some("White is in check but it is not their move")
+        );
     }
 }
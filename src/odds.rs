@@ -0,0 +1,106 @@
+use chess::{Board, BoardBuilder, CastleRights, Color, File, Rank, Square};
+
+/// A material handicap the engine gives the player.
+pub struct Handicap {
+    pub name: &'static str,
+    pub description: &'static str,
+    // Squares emptied on the engine's side, as seen from White's side
+    removed: &'static [Square],
+    // The player moves first whichever colour they play
+    gives_move: bool,
+}
+
+pub const HANDICAPS: [Handicap; 6] = [
+    Handicap {
+        name: "Pawn and move",
+        description: "The engine plays without its f-pawn and you move first.",
+        removed: &[Square::F2],
+        gives_move: true,
+    },
+    Handicap {
+        name: "Pawn odds",
+        description: "The engine plays without its f-pawn.",
+        removed: &[Square::F2],
+        gives_move: false,
+    },
+    Handicap {
+        name: "Knight odds",
+        description: "The engine plays without its queen's knight.",
+        removed: &[Square::B1],
+        gives_move: false,
+    },
+    Handicap {
+        name: "Rook odds",
+        description: "The engine plays without its queen's rook.",
+        removed: &[Square::A1],
+        gives_move: false,
+    },
+    Handicap {
+        name: "Rook and knight odds",
+        description: "The engine plays without its queen's rook and knight.",
+        removed: &[Square::A1, Square::B1],
+        gives_move: false,
+    },
+    Handicap {
+        name: "Queen odds",
+        description: "The engine plays without its queen.",
+        removed: &[Square::D1],
+        gives_move: false,
+    },
+];
+
+impl Handicap {
+    /// Starting position of the handicap game with the player on `player`.
+    pub fn board(&self, player: Color) -> Board {
+        let engine = !player;
+        let mut builder = BoardBuilder::from(Board::default());
+        let mut rights = builder.get_castle_rights(engine);
+
+        for &square in self.removed {
+            let square = match engine {
+                Color::White => square,
+                Color::Black => Square::make_square(Rank::Eighth, square.get_file()),
+            };
+            builder.clear_square(square);
+            // A missing rook takes its castling right with it
+            match square.get_file() {
+                File::A => rights = rights.remove(CastleRights::QueenSide),
+                File::H => rights = rights.remove(CastleRights::KingSide),
+                _ => {}
+            }
+        }
+        builder.castle_rights(engine, rights);
+        if self.gives_move {
+            builder.side_to_move(player);
+        }
+
+        Board::try_from(builder).expect("handicap positions are legal")
+    }
+
+    pub fn fen(&self, player: Color) -> String {
+        self.board(player).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chess::Piece;
+
+    #[test]
+    fn test_handicap_positions() {
+        let rook_odds = HANDICAPS[3].board(Color::White);
+        assert_eq!(rook_odds.piece_on(Square::A8), None);
+        assert_eq!(rook_odds.castle_rights(Color::Black), CastleRights::KingSide);
+        assert_eq!(rook_odds.castle_rights(Color::White), CastleRights::Both);
+
+        // Pawn and move with the player on Black: Black starts
+        let pawn_and_move = HANDICAPS[0].board(Color::Black);
+        assert_eq!(pawn_and_move.piece_on(Square::F2), None);
+        assert_eq!(pawn_and_move.side_to_move(), Color::Black);
+
+        let queen_odds = HANDICAPS[5].board(Color::White);
+        assert_eq!(queen_odds.pieces(Piece::Queen).popcnt(), 1);
+        assert_eq!(queen_odds.side_to_move(), Color::White);
+    }
+}
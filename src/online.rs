@@ -0,0 +1,265 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, Command, Stdio},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use chess::Board;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::error::AppError;
+
+const LICHESS: &str = "https://lichess.org";
+
+/// Lichess analysis board set up on `board`.
+pub fn analysis_url(board: &Board) -> String {
+    format!("{}/analysis/{}", LICHESS, board.to_string().replace(' ', "_"))
+}
+
+#[derive(Deserialize)]
+struct ImportedGame {
+    url: String,
+}
+
+/// Upload a PGN to the Lichess import endpoint and return the URL of the
+/// imported game, where the whole game can be analysed.
+pub fn import_to_lichess(pgn: &str) -> Result<String, AppError> {
+    let response = curl(
+        &[
+            "-X",
+            "POST",
+            "-H",
+            "Accept: application/json",
+            "--data-urlencode",
+            "pgn@-",
+            &format!("{}/api/import", LICHESS),
+        ],
+        Some(pgn),
+    )?;
+    let imported: ImportedGame = serde_json::from_str(&response)?;
+    Ok(imported.url)
+}
+
+/// A game page on one of the sites games can be imported from.
+#[derive(Debug, PartialEq)]
+enum GameLink<'a> {
+    /// Lichess game ID (the first eight characters of the path)
+    Lichess(&'a str),
+    /// Chess.com game kind ("live" or "daily") and ID
+    ChessCom(&'a str, &'a str),
+}
+
+fn parse_game_link(url: &str) -> Option<GameLink<'_>> {
+    let url = url.trim();
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or("");
+    let (host, path) = rest.split_once('/')?;
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    if host == "lichess.org" || host.ends_with(".lichess.org") {
+        // Game IDs are random, so nearly always mix in capitals or digits,
+        // unlike page names such as "analysis" or "training"
+        let id = segments.first()?;
+        let valid = matches!(id.len(), 8 | 12)
+            && id.chars().all(|c| c.is_ascii_alphanumeric())
+            && id.chars().any(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        return valid.then(|| GameLink::Lichess(&id[..8]));
+    }
+    if host == "chess.com" || host.ends_with(".chess.com") {
+        return match segments.as_slice() {
+            ["game", kind @ ("live" | "daily"), id, ..]
+            | [kind @ ("live" | "daily"), "game", id, ..] => Some(GameLink::ChessCom(kind, id)),
+            // Newer links leave out the kind for live games
+            ["game", id, ..] => Some(GameLink::ChessCom("live", id)),
+            _ => None,
+        };
+    }
+    None
+}
+
+/// Download the PGN of a Lichess or Chess.com game from its page URL.
+pub fn fetch_game(url: &str) -> Result<String, AppError> {
+    match parse_game_link(url) {
+        Some(GameLink::Lichess(id)) => curl(
+            &[
+                "-H",
+                "Accept: application/x-chess-pgn",
+                &format!("{}/game/export/{}", LICHESS, id),
+            ],
+            None,
+        ),
+        Some(GameLink::ChessCom(kind, id)) => fetch_chess_com(kind, id),
+        None => Err(AppError::Network(format!(
+            "'{}' isn't a Lichess or Chess.com game link",
+            url.trim()
+        ))),
+    }
+}
+
+// Chess.com only publishes PGN in players' monthly archives, so look up
+// who played the game and when, then find it in White's archive
+fn fetch_chess_com(kind: &str, id: &str) -> Result<String, AppError> {
+    let not_found = || AppError::Network(format!("Chess.com game {} not found", id));
+    let details: Value = serde_json::from_str(&curl(
+        &[&format!("https://www.chess.com/callback/{}/game/{}", kind, id)],
+        None,
+    )?)?;
+    let headers = &details["game"]["pgnHeaders"];
+    let white = headers["White"].as_str().ok_or_else(not_found)?.to_lowercase();
+    let date = headers["Date"].as_str().ok_or_else(not_found)?;
+    let mut parts = date.split('.').map(|part| part.parse::<u32>().ok());
+    let (Some(Some(year)), Some(Some(month))) = (parts.next(), parts.next()) else {
+        return Err(not_found());
+    };
+
+    // A game that started on the last day of a month may be filed under
+    // the next one
+    let next = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    for (year, month) in [(year, month), next] {
+        let archive: Value = serde_json::from_str(&curl(
+            &[&format!(
+                "https://api.chess.com/pub/player/{}/games/{}/{:02}",
+                white, year, month
+            )],
+            None,
+        )?)?;
+        let game = archive["games"].as_array().into_iter().flatten().find(|game| {
+            game["url"]
+                .as_str()
+                .is_some_and(|url| url.trim_end_matches('/').ends_with(&format!("/{}", id)))
+        });
+        if let Some(pgn) = game.and_then(|game| game["pgn"].as_str()) {
+            return Ok(pgn.to_string());
+        }
+    }
+    Err(not_found())
+}
+
+/// Open `url` in the desktop's web browser.
+pub fn open_in_browser(url: &str) -> Result<(), AppError> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| AppError::Network(format!("Couldn't open a browser: {}", e)))?;
+    Ok(())
+}
+
+/// Run curl, which already handles TLS and proxies on every platform, and
+/// return the response body; `input` is fed to its standard input.
+pub fn curl(args: &[&str], input: Option<&str>) -> Result<String, AppError> {
+    let child = spawn_curl(&[&["--max-time", "30"], args].concat(), input)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::Network(error.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Lines of a long-lived response, such as a Lichess event stream, read
+/// on a background thread.  Dropping it closes the connection.
+pub struct LineStream {
+    child: Child,
+    lines: Receiver<String>,
+}
+
+impl LineStream {
+    /// Lines received since the last call, and whether the connection is
+    /// still open.
+    pub fn take_lines(&self) -> (Vec<String>, bool) {
+        let mut lines = Vec::new();
+        loop {
+            match self.lines.try_recv() {
+                Ok(line) => lines.push(line),
+                Err(TryRecvError::Empty) => return (lines, true),
+                Err(TryRecvError::Disconnected) => return (lines, false),
+            }
+        }
+    }
+}
+
+impl Drop for LineStream {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Start curl on a response that stays open, delivering its non-empty
+/// lines as they arrive.
+pub fn curl_stream(args: &[&str], input: Option<&str>) -> Result<LineStream, AppError> {
+    let mut child = spawn_curl(&[&["--no-buffer"], args].concat(), input)?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AppError::Network("curl has no output".to_string()))?;
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            // Blank lines only keep the connection alive
+            if !line.trim().is_empty() && sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(LineStream { child, lines })
+}
+
+fn spawn_curl(args: &[&str], input: Option<&str>) -> Result<Child, AppError> {
+    let mut child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--location"])
+        .args(args)
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AppError::Network(format!("Couldn't run curl: {}", e)))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    Ok(child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analysis_url() {
+        assert_eq!(
+            analysis_url(&Board::default()),
+            "https://lichess.org/analysis/rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR_w_KQkq_-_0_1"
+        );
+    }
+
+    #[test]
+    fn test_parse_game_link() {
+        assert_eq!(
+            parse_game_link("https://lichess.org/abcdEFGH1234/black#12"),
+            Some(GameLink::Lichess("abcdEFGH"))
+        );
+        assert_eq!(
+            parse_game_link("https://www.chess.com/game/daily/123456?tab=review"),
+            Some(GameLink::ChessCom("daily", "123456"))
+        );
+        assert_eq!(
+            parse_game_link("chess.com/live/game/987"),
+            Some(GameLink::ChessCom("live", "987"))
+        );
+        assert_eq!(parse_game_link("https://lichess.org/training"), None);
+        assert_eq!(parse_game_link("https://example.com/game/1"), None);
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use chess::{Board, BoardStatus, ChessMove, MoveGen};
+use rand::Rng;
+
+/// Shortest reply, so even forced moves don't appear instantly
+const MIN_REPLY_MS: u64 = 300;
+
+/// How long a human-paced engine takes to reply in `board`, scaled from the
+/// configured think time: forced moves and recaptures come quickly, busy
+/// positions take longer, and every reply varies a little.
+pub fn reply_time(board: &Board, last_move: Option<ChessMove>, think_time: u64) -> Duration {
+    let jitter = rand::thread_rng().gen_range(0.8..1.2);
+    let ms = think_time as f64 * pace_factor(board, last_move) * jitter;
+    Duration::from_millis((ms as u64).max(MIN_REPLY_MS))
+}
+
+// Fraction of the think time the position deserves
+fn pace_factor(board: &Board, last_move: Option<ChessMove>) -> f64 {
+    if board.status() != BoardStatus::Ongoing {
+        return 0.0;
+    }
+    let moves: Vec<ChessMove> = MoveGen::new_legal(board).collect();
+    if moves.len() == 1 {
+        return 0.1;
+    }
+
+    // The opponent just captured and the piece can be taken back
+    let recapture = last_move.is_some_and(|last| {
+        board.piece_on(last.get_dest()).is_some()
+            && moves.iter().any(|m| m.get_dest() == last.get_dest())
+    });
+    if recapture {
+        return 0.35;
+    }
+
+    // More options and more tension take longer to weigh up
+    let captures = moves
+        .iter()
+        .filter(|m| board.piece_on(m.get_dest()).is_some())
+        .count();
+    let factor = 0.5 + moves.len() as f64 / 40.0 + captures as f64 / 10.0;
+    let factor = if board.checkers().popcnt() > 0 { factor * 0.7 } else { factor };
+    factor.clamp(0.5, 1.6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_pace_factor() {
+        // Only one legal move
+        let forced = Board::from_str("k7/8/8/8/8/8/8/1R4K1 b - - 0 1").unwrap();
+        assert!(pace_factor(&forced, None) < 0.2);
+
+        // White just took on d5 and Black can take back
+        let board = Board::default();
+        let moves = ["e2e4", "d7d5", "e4d5"];
+        let mut last = None;
+        let mut position = board;
+        for uci in moves {
+            let m = crate::game::parse_uci_move(&position, uci).unwrap();
+            position = position.make_move_new(m);
+            last = Some(m);
+        }
+        assert_eq!(pace_factor(&position, last), 0.35);
+
+        let quiet = pace_factor(&Board::default(), None);
+        let busy = Board::from_str("r1bqk2r/pp2bppp/2n1pn2/2pp4/2PP4/2N1PN2/PP2BPPP/R1BQK2R w KQkq - 0 7")
+            .unwrap();
+        assert!(pace_factor(&busy, None) > quiet);
+    }
+}
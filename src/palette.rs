@@ -0,0 +1,109 @@
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Strings;
+
+/// Colour schemes for the board, saved in the settings file.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Palette {
+    /// Green and cream, the original board
+    #[default]
+    Classic,
+    /// Near-white and slate squares with saturated markers, for low vision
+    HighContrast,
+    /// Blue and orange instead of green, for red-green colour blindness
+    Colorblind,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Classic, Palette::HighContrast, Palette::Colorblind];
+
+    pub fn name(self, t: &Strings) -> &'static str {
+        match self {
+            Palette::Classic => t.palette_classic,
+            Palette::HighContrast => t.palette_high_contrast,
+            Palette::Colorblind => t.palette_colorblind,
+        }
+    }
+
+    pub fn colors(self) -> &'static BoardColors {
+        match self {
+            Palette::Classic => &CLASSIC,
+            Palette::HighContrast => &HIGH_CONTRAST,
+            Palette::Colorblind => &COLORBLIND,
+        }
+    }
+}
+
+/// Every colour the board is drawn with.  Markers come in a light and a
+/// dark shade so they stay visible on both kinds of square.
+pub struct BoardColors {
+    pub light: Color,
+    pub dark: Color,
+    pub selected: Color,
+    pub legal_light: Color,
+    pub legal_dark: Color,
+    pub engine_light: Color,
+    pub engine_dark: Color,
+    pub reply_arrow: Color,
+    pub threat_arrow: Color,
+    /// The engine's best, second and third candidate moves
+    pub candidate_arrows: [Color; 3],
+    /// The kibitzer's lines, weighted by how good they are
+    pub analysis_arrow: Color,
+}
+
+pub static CLASSIC: BoardColors = BoardColors {
+    light: Color::from_rgb(0.93, 0.93, 0.8),
+    dark: Color::from_rgb(0.46, 0.59, 0.34),
+    selected: Color::from_rgb(0.9, 0.8, 0.3),
+    legal_light: Color::from_rgb(0.7, 0.9, 0.7),
+    legal_dark: Color::from_rgb(0.5, 0.75, 0.5),
+    engine_light: Color::from_rgb(0.8, 0.87, 0.95),
+    engine_dark: Color::from_rgb(0.47, 0.62, 0.72),
+    reply_arrow: Color::from_rgb(0.18, 0.44, 0.69),
+    threat_arrow: Color::from_rgb(0.85, 0.2, 0.2),
+    candidate_arrows: [
+        Color::from_rgb(0.15, 0.6, 0.25),
+        Color::from_rgb(0.85, 0.6, 0.1),
+        Color::from_rgb(0.55, 0.3, 0.7),
+    ],
+    analysis_arrow: Color::from_rgb(0.1, 0.55, 0.6),
+};
+
+pub static HIGH_CONTRAST: BoardColors = BoardColors {
+    light: Color::from_rgb(0.97, 0.97, 0.95),
+    dark: Color::from_rgb(0.33, 0.35, 0.42),
+    selected: Color::from_rgb(1.0, 0.85, 0.0),
+    legal_light: Color::from_rgb(1.0, 0.62, 0.25),
+    legal_dark: Color::from_rgb(0.85, 0.4, 0.0),
+    engine_light: Color::from_rgb(0.62, 0.75, 1.0),
+    engine_dark: Color::from_rgb(0.2, 0.3, 0.8),
+    reply_arrow: Color::from_rgb(0.0, 0.25, 0.9),
+    threat_arrow: Color::from_rgb(0.9, 0.0, 0.0),
+    candidate_arrows: [
+        Color::from_rgb(0.0, 0.65, 0.0),
+        Color::from_rgb(1.0, 0.6, 0.0),
+        Color::from_rgb(0.6, 0.0, 0.8),
+    ],
+    analysis_arrow: Color::from_rgb(0.0, 0.5, 0.6),
+};
+
+pub static COLORBLIND: BoardColors = BoardColors {
+    light: Color::from_rgb(0.92, 0.9, 0.84),
+    dark: Color::from_rgb(0.44, 0.54, 0.7),
+    selected: Color::from_rgb(1.0, 0.93, 0.35),
+    legal_light: Color::from_rgb(0.98, 0.78, 0.52),
+    legal_dark: Color::from_rgb(0.85, 0.52, 0.2),
+    engine_light: Color::from_rgb(0.84, 0.76, 0.94),
+    engine_dark: Color::from_rgb(0.52, 0.42, 0.68),
+    reply_arrow: Color::from_rgb(0.1, 0.3, 0.75),
+    threat_arrow: Color::from_rgb(0.85, 0.35, 0.0),
+    candidate_arrows: [
+        Color::from_rgb(0.0, 0.45, 0.7),
+        Color::from_rgb(0.95, 0.75, 0.1),
+        Color::from_rgb(0.8, 0.47, 0.65),
+    ],
+    analysis_arrow: Color::from_rgb(0.34, 0.71, 0.91),
+};
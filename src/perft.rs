@@ -0,0 +1,43 @@
+use chess::{Board, ChessMove, MoveGen};
+
+/// Number of leaf positions `depth` plies below `board`.
+pub fn perft(board: &Board, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+    let moves = MoveGen::new_legal(board);
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+    moves
+        .map(|m| perft(&board.make_move_new(m), depth - 1))
+        .sum()
+}
+
+/// Perft split by the first move, in move generation order, for comparing
+/// against another move generator.
+pub fn divide(board: &Board, depth: u32) -> Vec<(ChessMove, u64)> {
+    MoveGen::new_legal(board)
+        .map(|m| (m, perft(&board.make_move_new(m), depth.saturating_sub(1))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_perft_reference_counts() {
+        assert_eq!(perft(&Board::default(), 3), 8_902);
+
+        // "Kiwipete", full of castling, en passant and promotion tricks
+        let kiwipete =
+            Board::from_str("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        assert_eq!(perft(&kiwipete, 2), 2_039);
+        let split = divide(&kiwipete, 2);
+        assert_eq!(split.len(), 48);
+        assert_eq!(split.iter().map(|(_, n)| n).sum::<u64>(), 2_039);
+    }
+}
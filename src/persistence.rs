@@ -0,0 +1,169 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::ClockMode;
+use crate::error::AppError;
+
+/// An in-progress game in a form that survives a restart.
+///
+/// Moves are stored as UCI strings and replayed from `start_fen`, so the
+/// move list, history navigation and SAN records are rebuilt exactly.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SavedGame {
+    pub start_fen: String,
+    pub moves: Vec<String>,
+    /// "w" or "b", matching the FEN side-to-move field
+    pub player_color: String,
+    #[serde(default)]
+    pub rated: bool,
+    /// Clocks and engine set-up; autosaves from older versions lack them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock: Option<SavedClock>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine: Option<EngineSetup>,
+}
+
+/// Where the clocks stood when the game was saved.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedClock {
+    pub base_ms: u64,
+    pub increment_ms: u64,
+    #[serde(default)]
+    pub mode: ClockMode,
+    /// The engine's own time control in a time-odds game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_base_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub engine_increment_ms: Option<u64>,
+    /// Time left for White and Black
+    pub white_ms: u64,
+    pub black_ms: u64,
+    /// Time left after each move, in game order
+    #[serde(default)]
+    pub move_clocks: Vec<Option<u64>>,
+}
+
+/// The engine the game was played against and how it was configured.
+/// Threads and hash belong to the machine, so they aren't included.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct EngineSetup {
+    pub path: PathBuf,
+    pub skill_level: u8,
+    pub elo: Option<u32>,
+    pub think_time: u64,
+    pub human_pacing: bool,
+    pub variety_window: u32,
+    pub uci_options: Vec<(String, String)>,
+    pub chess960: bool,
+}
+
+impl SavedGame {
+    pub fn player_color(&self) -> chess::Color {
+        if self.player_color == "b" {
+            chess::Color::Black
+        } else {
+            chess::Color::White
+        }
+    }
+
+    pub fn color_code(color: chess::Color) -> String {
+        if color == chess::Color::White { "w" } else { "b" }.to_string()
+    }
+}
+
+/// Directory for the application's own files (autosave, databases, ...).
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("chess_engine_player"))
+}
+
+fn autosave_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("autosave.json"))
+}
+
+pub fn save_autosave(saved: &SavedGame) -> Result<(), AppError> {
+    let path = autosave_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory available"))?;
+    write_json(&path, saved)
+}
+
+pub fn load_autosave() -> Option<SavedGame> {
+    let path = autosave_path()?;
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+pub fn clear_autosave() {
+    if let Some(path) = autosave_path() {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Save a game to resume later, independent of the autosave slot.
+pub fn save_game_file(path: &Path, saved: &SavedGame) -> Result<(), AppError> {
+    write_json(&path.to_path_buf(), saved)
+}
+
+pub fn load_game_file(path: &Path) -> Result<SavedGame, AppError> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+// Write via a temporary file and rename so a crash mid-write never leaves a
+// truncated save behind
+pub(crate) fn write_json<T: Serialize>(path: &PathBuf, value: &T) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_string_pretty(value)?)?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_game_formats() {
+        // Autosaves from before game files existed still load
+        let old: SavedGame =
+            serde_json::from_str(r#"{"start_fen":"8/8/8/8/8/8/8/K6k w - - 0 1","moves":[],"player_color":"b"}"#)
+                .unwrap();
+        assert_eq!(old.player_color(), chess::Color::Black);
+        assert!(old.clock.is_none() && old.engine.is_none() && !old.rated);
+
+        let saved = SavedGame {
+            clock: Some(SavedClock {
+                base_ms: 300_000,
+                increment_ms: 2_000,
+                mode: ClockMode::Delay,
+                engine_base_ms: Some(60_000),
+                engine_increment_ms: None,
+                white_ms: 290_500,
+                black_ms: 301_000,
+                move_clocks: vec![Some(301_000), None],
+            }),
+            engine: Some(EngineSetup {
+                path: PathBuf::from("/usr/games/stockfish"),
+                skill_level: 7,
+                elo: Some(1600),
+                think_time: 1500,
+                human_pacing: true,
+                variety_window: 25,
+                uci_options: vec![("Contempt".to_string(), "10".to_string())],
+                chess960: false,
+            }),
+            ..old
+        };
+        let path = std::env::temp_dir().join(format!("cep-saved-game-{}.json", std::process::id()));
+        save_game_file(&path, &saved).unwrap();
+        let loaded = load_game_file(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(loaded.clock, saved.clock);
+        assert_eq!(loaded.engine, saved.engine);
+    }
+}
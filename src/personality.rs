@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+use crate::engine::ChessEngine;
+use crate::error::AppError;
+
+/// A named playing style: UCI options that shape how an engine plays.
+/// Engines only get the options they declare, so one personality can name
+/// the style options of several engines.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Personality {
+    pub name: String,
+    /// Option names and values, e.g. ("Contempt", "50")
+    pub options: Vec<(String, String)>,
+}
+
+/// Styles available without any configuration.  Contempt is Stockfish's
+/// (up to version 11) and Lc0's draw aversion; Personality is the style
+/// setting of Komodo and Dragon.
+pub fn builtin() -> Vec<Personality> {
+    let personality = |name: &str, options: &[(&str, &str)]| Personality {
+        name: name.to_string(),
+        options: options
+            .iter()
+            .map(|(option, value)| (option.to_string(), value.to_string()))
+            .collect(),
+    };
+    vec![
+        personality("Aggressive", &[("Contempt", "50"), ("Personality", "Aggressive")]),
+        personality("Solid", &[("Contempt", "-20"), ("Personality", "Defensive")]),
+    ]
+}
+
+/// The built-in personalities followed by those from the settings file;
+/// a configured one with a built-in's name replaces it.
+pub fn all(configured: &[Personality]) -> Vec<Personality> {
+    let mut personalities: Vec<Personality> = builtin()
+        .into_iter()
+        .filter(|builtin| !configured.iter().any(|p| p.name.eq_ignore_ascii_case(&builtin.name)))
+        .collect();
+    personalities.extend(configured.iter().cloned());
+    personalities
+}
+
+pub fn find<'a>(personalities: &'a [Personality], name: &str) -> Option<&'a Personality> {
+    personalities.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+/// Give `engine` the style of `chosen`, or its own defaults for None.
+/// Every option any of `known` sets goes back to the engine's default
+/// first, so switching styles leaves nothing behind; options in `keep`
+/// (the user's own --uci-option settings) are never touched.
+pub fn apply(
+    engine: &mut ChessEngine,
+    chosen: Option<&Personality>,
+    known: &[Personality],
+    keep: &[(String, String)],
+) -> Result<(), AppError> {
+    let kept = |name: &str| keep.iter().any(|(option, _)| option.eq_ignore_ascii_case(name));
+    let touched = known.iter().flat_map(|p| &p.options).map(|(name, _)| name.as_str());
+    for name in touched.filter(|name| !kept(name)) {
+        let Some(default) = engine.option(name).and_then(|option| option.default.clone()) else {
+            continue;
+        };
+        engine.set_declared_option(name, &default)?;
+    }
+    for (name, value) in chosen.into_iter().flat_map(|p| &p.options) {
+        if !kept(name) {
+            engine.set_declared_option(name, value)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_personalities() {
+        let configured = vec![
+            Personality {
+                name: "solid".to_string(),
+                options: vec![("Contempt".to_string(), "0".to_string())],
+            },
+            Personality {
+                name: "Coffeehouse".to_string(),
+                options: vec![("Contempt".to_string(), "100".to_string())],
+            },
+        ];
+        let personalities = all(&configured);
+        let names: Vec<&str> = personalities.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Aggressive", "solid", "Coffeehouse"]);
+        assert_eq!(find(&personalities, "SOLID").unwrap().options[0].1, "0");
+        assert!(find(&personalities, "Reckless").is_none());
+    }
+}
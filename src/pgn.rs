@@ -0,0 +1,724 @@
+use std::{fs, path::Path, time::Duration};
+
+use chess::{Board, ChessMove, Color, GameResult, Piece};
+
+use crate::analysis::{format_eval, MoveClass, MoveEvaluation};
+use crate::clock::{format_clock, parse_clock};
+use crate::diagram::figurine;
+use crate::error::AppError;
+use crate::fen;
+use crate::game::{
+    fen_counters, move_to_san, parse_uci_move, ChessGame, MoveAnnotation, MoveDetails, MoveRecord,
+    MOVE_GLYPHS,
+};
+use crate::i18n::Language;
+use crate::variation::{VariationTree, ROOT};
+
+/// Movetext lines are wrapped at this width, as the PGN standard recommends
+const LINE_WIDTH: usize = 80;
+
+/// PGN result token for a finished (or unfinished) game.
+pub fn result_string(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::WhiteCheckmates) | Some(GameResult::BlackResigns) => "1-0",
+        Some(GameResult::BlackCheckmates) | Some(GameResult::WhiteResigns) => "0-1",
+        Some(GameResult::Stalemate)
+        | Some(GameResult::DrawAccepted)
+        | Some(GameResult::DrawDeclared) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+/// Build the tag pairs for a game played in this app between the user and
+/// `engine_name`.  `date` uses the PGN "YYYY.MM.DD" format.
+pub fn game_tags(game: &ChessGame, engine_name: &str, date: &str) -> Vec<(String, String)> {
+    let (white, black) = if game.player_color() == Color::White {
+        ("Player", engine_name)
+    } else {
+        (engine_name, "Player")
+    };
+
+    let mut tags = vec![
+        ("Event".to_string(), "Casual game".to_string()),
+        ("Site".to_string(), "Chess Engine Player".to_string()),
+        ("Date".to_string(), date.to_string()),
+        ("Round".to_string(), "-".to_string()),
+        ("White".to_string(), white.to_string()),
+        ("Black".to_string(), black.to_string()),
+        ("Result".to_string(), result_string(game.game_result()).to_string()),
+    ];
+
+    if let Some(opening) = game.opening() {
+        tags.push(("ECO".to_string(), opening.eco.clone()));
+        tags.push(("Opening".to_string(), opening.name.clone()));
+    }
+
+    // PGN has no form for time odds, so each side gets a tag of its own
+    match (game.time_control(), game.time_odds()) {
+        (Some(time_control), None) => tags.push(("TimeControl".to_string(), time_control.to_string())),
+        (Some(_), Some(_)) => {
+            for (tag, side) in [("WhiteTimeControl", Color::White), ("BlackTimeControl", Color::Black)] {
+                if let Some(control) = game.control_for(side) {
+                    tags.push((tag.to_string(), control.to_string()));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if game.flagged().is_some() {
+        tags.push(("Termination".to_string(), "time forfeit".to_string()));
+    }
+
+    // Games from a custom position need the FEN to be replayable
+    let start = game.start_fen();
+    if start != Board::default().to_string() {
+        tags.push(("SetUp".to_string(), "1".to_string()));
+        tags.push(("FEN".to_string(), start));
+    }
+
+    tags
+}
+
+/// How pieces are written in exported moves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Notation {
+    /// Standard PGN letters: Nf3, exd8=Q
+    Letters,
+    /// Unicode piece symbols in the mover's colour: ♘f3, exd8=♕.  Easier to
+    /// read in any language, but not every PGN reader accepts it.
+    Figurine,
+}
+
+impl Notation {
+    /// A SAN move in this notation; castling and pawn moves are unchanged
+    /// apart from a promotion piece.
+    pub fn write(self, san: &str, mover: Color) -> String {
+        if self == Notation::Letters {
+            return san.to_string();
+        }
+        san.chars()
+            .map(|c| match c {
+                'K' => figurine(Piece::King, mover),
+                'Q' => figurine(Piece::Queen, mover),
+                'R' => figurine(Piece::Rook, mover),
+                'B' => figurine(Piece::Bishop, mover),
+                'N' => figurine(Piece::Knight, mover),
+                other => other,
+            })
+            .collect()
+    }
+}
+
+/// Render a complete PGN game from tag pairs and the recorded moves.
+pub fn export_pgn(tags: &[(String, String)], records: &[MoveRecord], result: &str) -> String {
+    export_pgn_in(tags, records, result, Notation::Letters)
+}
+
+/// `export_pgn` with the moves written in `notation`.
+pub fn export_pgn_in(
+    tags: &[(String, String)],
+    records: &[MoveRecord],
+    result: &str,
+    notation: Notation,
+) -> String {
+    let mut tokens = Vec::new();
+    let push = |tokens: &mut Vec<String>, details: &MoveDetails, mover| {
+        push_move(tokens, details, |san| notation.write(san, mover))
+    };
+    for record in records {
+        match (&record.white_move, &record.black_move) {
+            (Some(white), black) => {
+                tokens.push(format!("{}.", record.move_num));
+                push(&mut tokens, white, Color::White);
+                if let Some(black) = black {
+                    // The move number is repeated after a comment
+                    if has_comment(white) {
+                        tokens.push(format!("{}...", record.move_num));
+                    }
+                    push(&mut tokens, black, Color::Black);
+                }
+            }
+            (None, Some(black)) => {
+                // Game started from a position with Black to move
+                tokens.push(format!("{}...", record.move_num));
+                push(&mut tokens, black, Color::Black);
+            }
+            (None, None) => {}
+        }
+    }
+    tokens.push(result.to_string());
+
+    render_pgn(tags, tokens)
+}
+
+fn has_comment(details: &MoveDetails) -> bool {
+    details.annotation.comment.is_some() || details.clock.is_some() || details.time_spent.is_some()
+}
+
+// SAN with its glyph, then the clock and comment a word at a time so it
+// can wrap
+fn push_move(tokens: &mut Vec<String>, details: &MoveDetails, write: impl Fn(&str) -> String) {
+    let annotation = &details.annotation;
+    tokens.push(format!("{}{}", write(&details.notation), annotation.glyph.unwrap_or("")));
+    if !has_comment(details) {
+        return;
+    }
+    tokens.push("{".to_string());
+    if let Some(clock) = details.clock {
+        tokens.push(format!("[%clk {}]", format_clock(clock)));
+    }
+    // Elapsed move time, as ChessBase and Arena write it
+    if let Some(spent) = details.time_spent {
+        tokens.push(format!("[%emt {}]", format_clock(spent)));
+    }
+    if let Some(comment) = &annotation.comment {
+        // A brace would end the comment early
+        tokens.extend(comment.replace('}', ")").split_whitespace().map(str::to_string));
+    }
+    tokens.push("}".to_string());
+}
+
+/// Render a game with the engine's verdict on each move: an `[%eval]`
+/// comment after every move it assessed, plus ?!, ? or ?? and the move it
+/// preferred after inaccuracies, mistakes and blunders.  `evaluations[i]`
+/// belongs to `moves[i]`.
+pub fn export_annotated(
+    tags: &[(String, String)],
+    start: Board,
+    moves: &[ChessMove],
+    evaluations: &[MoveEvaluation],
+    result: &str,
+) -> String {
+    let mut tokens = Vec::new();
+    let mut board = start;
+    let mut move_num = 1;
+    for (i, &chess_move) in moves.iter().enumerate() {
+        let white_to_move = board.side_to_move() == Color::White;
+        if white_to_move {
+            tokens.push(format!("{}.", move_num));
+        } else if i == 0 {
+            tokens.push(format!("{}...", move_num));
+        }
+
+        let mut san = move_to_san(&board, chess_move);
+        if let Some(evaluation) = evaluations.get(i) {
+            // Good and best moves go unmarked
+            let flagged = evaluation.class >= MoveClass::Inaccuracy;
+            if flagged {
+                san.push_str(evaluation.class.symbol());
+            }
+            tokens.push(san);
+
+            let mut comment = format!("{{ [%eval {}]", format_eval(evaluation.eval_after));
+            if flagged {
+                let best = parse_uci_move(&board, &evaluation.best_move)
+                    .map(|m| move_to_san(&board, m))
+                    .unwrap_or_else(|| evaluation.best_move.clone());
+                let label = evaluation.class.label(Language::default().strings());
+                comment.push_str(&format!(" {}. {} was best.", label, best));
+            }
+            comment.push_str(" }");
+            tokens.push(comment);
+        } else {
+            tokens.push(san);
+        }
+
+        board = board.make_move_new(chess_move);
+        if !white_to_move {
+            move_num += 1;
+        }
+    }
+    tokens.push(result.to_string());
+
+    render_pgn(tags, tokens)
+}
+
+// Tag pairs followed by the movetext tokens, wrapped
+fn render_pgn(tags: &[(String, String)], tokens: Vec<String>) -> String {
+    let mut pgn = String::new();
+    for (name, value) in tags {
+        pgn.push_str(&format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\"")));
+    }
+    pgn.push('\n');
+
+    // Wrap the movetext
+    let mut line_len = 0;
+    for token in tokens {
+        // Counted in characters so figurines don't wrap early
+        let width = token.chars().count();
+        if line_len > 0 && line_len + 1 + width > LINE_WIDTH {
+            pgn.push('\n');
+            line_len = 0;
+        } else if line_len > 0 {
+            pgn.push(' ');
+            line_len += 1;
+        }
+        line_len += width;
+        pgn.push_str(&token);
+    }
+    pgn.push('\n');
+
+    pgn
+}
+
+/// Export a game played in the app as PGN text.
+pub fn export_game(game: &ChessGame, engine_name: &str, date: &str) -> String {
+    let tags = game_tags(game, engine_name, date);
+    export_pgn(&tags, game.get_move_records(), result_string(game.game_result()))
+}
+
+/// A game read from PGN text.
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    /// Tag pairs in file order
+    pub tags: Vec<(String, String)>,
+    /// Starting position, from the FEN tag when present
+    pub start: Board,
+    /// Every line in the game from the start to the end of a variation,
+    /// main line first
+    pub lines: Vec<Vec<ChessMove>>,
+    /// Glyphs and comments of the main line, one per move
+    pub annotations: Vec<MoveAnnotation>,
+    /// Clock times from `[%clk]` comments on the main line, one per move
+    pub clocks: Vec<Option<Duration>>,
+    /// The moves with their variations, comments and glyphs
+    pub tree: VariationTree,
+}
+
+impl PgnGame {
+    /// The value of the first tag called `name`, e.g. "White".
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a SAN move, tolerating the variants found in the wild ("0-0",
+/// "e8=Q", check marks and annotation glyphs).
+pub fn parse_san(board: &Board, san: &str) -> Option<ChessMove> {
+    let san = san.trim_end_matches(['!', '?', '+', '#']).replace('=', "");
+    let san = san.replace("0-0-0", "O-O-O").replace("0-0", "O-O");
+    ChessMove::from_san(board, &san).ok()
+}
+
+/// Read every game in a PGN file, including the moves of variations.
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, AppError> {
+    let mut games = Vec::new();
+    let mut tags = Vec::new();
+    let mut movetext = String::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') && line.ends_with(']') {
+            // A tag after some movetext starts the next game
+            if !movetext.trim().is_empty() {
+                games.push(parse_game(std::mem::take(&mut tags), &movetext)?);
+                movetext.clear();
+            }
+            if let Some(tag) = parse_tag(line) {
+                tags.push(tag);
+            }
+        } else if !line.starts_with('%') {
+            movetext.push_str(line);
+            movetext.push('\n');
+        }
+    }
+    if !movetext.trim().is_empty() || !tags.is_empty() {
+        games.push(parse_game(tags, &movetext)?);
+    }
+
+    Ok(games)
+}
+
+/// The first game in a PGN file.
+pub fn read_first_game(path: &Path) -> Result<PgnGame, AppError> {
+    let text = fs::read_to_string(path)?;
+    parse_pgn(&text)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| AppError::Pgn(format!("No games in {}", path.display())))
+}
+
+// [Name "Value"]
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = &line[1..line.len() - 1];
+    let (name, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+fn parse_game(tags: Vec<(String, String)>, movetext: &str) -> Result<PgnGame, AppError> {
+    let fen = tags.iter().find(|(name, _)| name == "FEN").map(|(_, fen)| fen.as_str());
+    let start = match fen {
+        Some(fen) => fen::safe_parse_board(fen, Language::default().strings())
+            .map_err(|e| AppError::Pgn(format!("Invalid FEN tag {}: {}", fen, e)))?,
+        None => Board::default(),
+    };
+    let mut tree = VariationTree::new(start, fen.map_or(1, |fen| fen_counters(fen).1));
+    // Node of the last move read, and those to return to when variations
+    // close; comments before a variation's first move have no move to go with
+    let mut node = ROOT;
+    let mut branches: Vec<usize> = Vec::new();
+    let mut variation_start = false;
+
+    let mut lines = Vec::new();
+    // Current line as moves, the positions before each of them and the
+    // moves' annotations and clock times
+    let mut moves: Vec<ChessMove> = Vec::new();
+    let mut positions: Vec<Board> = vec![start];
+    let mut annotations: Vec<(MoveAnnotation, Option<Duration>)> = Vec::new();
+    // Lines to return to when a variation closes
+    type Line = (Vec<ChessMove>, Vec<Board>, Vec<(MoveAnnotation, Option<Duration>)>);
+    let mut stack: Vec<Line> = Vec::new();
+
+    for token in tokenize(movetext) {
+        match token {
+            Token::Open => {
+                // A variation replaces the last move played
+                stack.push((moves.clone(), positions.clone(), annotations.clone()));
+                if moves.pop().is_some() {
+                    positions.pop();
+                    annotations.pop();
+                }
+                branches.push(node);
+                node = tree.node(node).parent.unwrap_or(ROOT);
+                variation_start = true;
+            }
+            Token::Close => {
+                let (parent_moves, parent_positions, parent_annotations) = stack
+                    .pop()
+                    .ok_or_else(|| AppError::Pgn("Unbalanced ')' in movetext".to_string()))?;
+                lines.push(std::mem::replace(&mut moves, parent_moves));
+                positions = parent_positions;
+                annotations = parent_annotations;
+                node = branches.pop().unwrap_or(ROOT);
+                variation_start = false;
+            }
+            Token::Result(_) => {}
+            // Comments before the first move are about the game, not a move
+            Token::Glyph(glyph) => {
+                if let Some((annotation, _)) = annotations.last_mut() {
+                    annotation.glyph = Some(glyph);
+                }
+                if node != ROOT && !variation_start {
+                    tree.annotation_mut(node).glyph = Some(glyph);
+                }
+            }
+            Token::Comment(comment) => {
+                if let (Some(text), false) = (comment_text(comment), node == ROOT || variation_start) {
+                    let annotation = tree.annotation_mut(node);
+                    annotation.comment = Some(match annotation.comment.take() {
+                        Some(earlier) => format!("{} {}", earlier, text),
+                        None => text,
+                    });
+                }
+                let Some((annotation, clock)) = annotations.last_mut() else {
+                    continue;
+                };
+                if let Some(time) = comment_clock(comment) {
+                    *clock = Some(time);
+                }
+                if let Some(text) = comment_text(comment) {
+                    annotation.comment = Some(match annotation.comment.take() {
+                        Some(earlier) => format!("{} {}", earlier, text),
+                        None => text,
+                    });
+                }
+            }
+            Token::Move(san) => {
+                let board = positions[positions.len() - 1];
+                let chess_move = parse_san(&board, san).ok_or_else(|| {
+                    AppError::Pgn(format!("Illegal move '{}' in {}", san, board))
+                })?;
+                moves.push(chess_move);
+                positions.push(board.make_move_new(chess_move));
+                annotations.push((MoveAnnotation::default(), None));
+                node = tree.child(node, chess_move);
+                variation_start = false;
+            }
+        }
+    }
+    if !stack.is_empty() {
+        return Err(AppError::Pgn("Unclosed variation in movetext".to_string()));
+    }
+    lines.insert(0, moves);
+    let (annotations, clocks) = annotations.into_iter().unzip();
+
+    Ok(PgnGame {
+        tags,
+        start,
+        lines,
+        annotations,
+        clocks,
+        tree,
+    })
+}
+
+// A piece of movetext
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Move(&'a str),
+    Result(&'a str),
+    /// Assessment of the move before it, from a suffix, glyph or NAG
+    Glyph(&'static str),
+    Comment(&'a str),
+    Open,
+    Close,
+}
+
+// Split movetext into tokens, dropping move numbers and NAGs that aren't
+// move assessments
+fn tokenize(movetext: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = movetext;
+    while let Some(c) = rest.chars().next() {
+        match c {
+            '{' | ';' => {
+                let close = if c == '{' { '}' } else { '\n' };
+                let (comment, after) = rest[1..].split_once(close).unwrap_or((&rest[1..], ""));
+                tokens.push(Token::Comment(comment));
+                rest = after;
+            }
+            '(' => {
+                tokens.push(Token::Open);
+                rest = &rest[1..];
+            }
+            ')' => {
+                tokens.push(Token::Close);
+                rest = &rest[1..];
+            }
+            c if c.is_whitespace() => rest = &rest[c.len_utf8()..],
+            _ => {
+                let end = rest
+                    .find(|c: char| c.is_whitespace() || "{;()".contains(c))
+                    .unwrap_or(rest.len());
+                let word = &rest[..end];
+                rest = &rest[end..];
+
+                if let Some(nag) = word.strip_prefix('$') {
+                    let nag = nag.parse().ok();
+                    if let Some(&(glyph, _)) = MOVE_GLYPHS.iter().find(|(_, n)| Some(*n) == nag) {
+                        tokens.push(Token::Glyph(glyph));
+                    }
+                    continue;
+                }
+                if matches!(word, "1-0" | "0-1" | "1/2-1/2" | "*") {
+                    tokens.push(Token::Result(word));
+                    continue;
+                }
+                // "12." and "12..." are move numbers; "12.e4" carries a move
+                let word = if word.starts_with("0-0") {
+                    word
+                } else {
+                    word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+                };
+                // "e4!?" is a move and its assessment; "!?" alone follows one
+                let san = word.trim_end_matches(['!', '?']);
+                if !san.is_empty() {
+                    tokens.push(Token::Move(san));
+                }
+                if let Some(glyph) = glyph(&word[san.len()..]) {
+                    tokens.push(Token::Glyph(glyph));
+                }
+            }
+        }
+    }
+    tokens
+}
+
+fn glyph(text: &str) -> Option<&'static str> {
+    MOVE_GLYPHS.iter().map(|&(glyph, _)| glyph).find(|&glyph| glyph == text)
+}
+
+// Time from a `[%clk h:mm:ss]` command in a comment
+fn comment_clock(comment: &str) -> Option<Duration> {
+    let (_, rest) = comment.split_once("[%clk")?;
+    let (time, _) = rest.split_once(']')?;
+    parse_clock(time)
+}
+
+// Comment text without embedded commands such as [%eval ...]; None if
+// nothing else is left
+fn comment_text(comment: &str) -> Option<String> {
+    let mut text = String::new();
+    let mut rest = comment;
+    while let Some(start) = rest.find("[%") {
+        text.push_str(&rest[..start]);
+        rest = rest[start..].split_once(']').map_or("", |(_, after)| after);
+    }
+    text.push_str(rest);
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_game_movetext() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["f2f3", "e7e5", "g2g4", "d8h4"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::White).unwrap();
+
+        let pgn = export_game(&game, "Stockfish", "2024.01.01");
+
+        assert!(pgn.contains("[White \"Player\"]"));
+        assert!(pgn.contains("[Black \"Stockfish\"]"));
+        assert!(pgn.contains("[Result \"0-1\"]"));
+        assert!(!pgn.contains("[FEN"));
+        assert!(pgn.ends_with("1. f3 e5 2. g4 Qh4# 0-1\n"), "unexpected movetext: {}", pgn);
+
+        let tags = game_tags(&game, "Stockfish", "2024.01.01");
+        let figurine = export_pgn_in(&tags, game.get_move_records(), "0-1", Notation::Figurine);
+        assert!(figurine.ends_with("1. f3 e5 2. g4 ♛h4# 0-1\n"), "unexpected movetext: {}", figurine);
+        assert_eq!(Notation::Figurine.write("exd8=Q+", Color::White), "exd8=♕+");
+        assert_eq!(Notation::Figurine.write("O-O", Color::Black), "O-O");
+    }
+
+    #[test]
+    fn test_parse_pgn_skips_annotations_and_collects_variations() {
+        let text = "[Event \"Test\"]\n\n1. e4 {King's pawn} e5 $1 2. Nf3 ; attack e5\nNc6 3. Bc4 Nf6 \
+                    4. 0-0 (4. Ng5 d5!? 5. exd5) 4...Bc5 1-0\n\n[Event \"Second\"]\n\n1. d4 *\n";
+
+        let games = parse_pgn(text).unwrap();
+
+        assert_eq!(games.len(), 2);
+        let moves: Vec<String> = games[0].lines[0].iter().map(|m| m.to_string()).collect();
+        assert_eq!(moves, ["e2e4", "e7e5", "g1f3", "b8c6", "f1c4", "g8f6", "e1g1", "f8c5"]);
+        let variation: Vec<String> = games[0].lines[1].iter().map(|m| m.to_string()).collect();
+        assert_eq!(variation[6..], ["f3g5", "d7d5", "e4d5"]);
+        assert_eq!(games[1].lines[0].len(), 1);
+
+        // Comments, glyphs and NAGs stay with the main line's moves
+        let notes = &games[0].annotations;
+        assert_eq!(notes.len(), 8);
+        assert_eq!(notes[0].comment.as_deref(), Some("King's pawn"));
+        assert_eq!(notes[1].glyph, Some("!"));
+        assert_eq!(notes[2].comment.as_deref(), Some("attack e5"));
+        assert_eq!(notes[6], MoveAnnotation::default());
+    }
+
+    #[test]
+    fn test_parse_pgn_builds_variation_tree() {
+        let text = "[FEN \"rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1\"]\n\n\
+                    1... e5 2. Nf3 {main} (2. f4 {gambit} exf4 (2... d5 $1) 3. Nf3) 2... Nc6 \
+                    ({Or} 2... d6 3. d4) *\n";
+
+        let game = &parse_pgn(text).unwrap()[0];
+        let tree = &game.tree;
+
+        let main = tree.line_through(ROOT);
+        let main_moves: Vec<String> = tree.moves(&main).iter().map(|m| m.to_string()).collect();
+        assert_eq!(main_moves, ["e7e5", "g1f3", "b8c6"]);
+        assert_eq!(tree.node(main[1]).annotation.comment.as_deref(), Some("main"));
+
+        let lines: Vec<(usize, String)> =
+            tree.variations().into_iter().map(|line| (line.depth, line.text)).collect();
+        assert_eq!(
+            lines,
+            [
+                (1, "2. f4 exf4 3. Nf3".to_string()),
+                (2, "2... d5!".to_string()),
+                (1, "2... d6 3. d4".to_string()),
+            ]
+        );
+        let gambit = tree.variations()[0].start;
+        assert_eq!(tree.node(gambit).annotation.comment.as_deref(), Some("gambit"));
+        // A comment before a variation's first move has no move to go with
+        let last_variation = tree.variations()[2].start;
+        assert_eq!(tree.node(last_variation).annotation, MoveAnnotation::default());
+        assert_eq!(tree.node(main[2]).annotation, MoveAnnotation::default());
+    }
+
+    #[test]
+    fn test_parse_pgn_rejects_impossible_fen_tag() {
+        // The chess crate would abort on a board without a black king
+        let text = "[FEN \"8/8/8/8/8/8/8/4K3 w - - 0 1\"]\n\n1. Kd2 (1. Ke2) *\n";
+        match parse_pgn(text) {
+            Err(AppError::Pgn(message)) => assert!(message.contains("Missing black king")),
+            other => panic!("expected a PGN error, got {:?}", other.map(|games| games.len())),
+        }
+    }
+
+    #[test]
+    fn test_annotations_round_trip() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["e2e4", "e7e5", "g1f3"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::White).unwrap();
+        game.annotate_move(
+            1,
+            MoveAnnotation {
+                glyph: Some("!"),
+                comment: Some("Best by test [%eval 0.3]".to_string()),
+            },
+        );
+        game.annotate_move(
+            3,
+            MoveAnnotation {
+                glyph: Some("?!"),
+                comment: None,
+            },
+        );
+
+        let pgn = export_pgn(&[], game.get_move_records(), "*");
+        assert_eq!(pgn, "\n1. e4! { Best by test [%eval 0.3] } 1... e5 2. Nf3?! *\n");
+
+        let notes = &parse_pgn(&pgn).unwrap()[0].annotations;
+        assert_eq!(notes[0].glyph, Some("!"));
+        assert_eq!(notes[0].comment.as_deref(), Some("Best by test"));
+        assert_eq!(notes[2].glyph, Some("?!"));
+    }
+
+    #[test]
+    fn test_clock_comments_round_trip() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["e2e4", "e7e5"].iter().map(|m| m.to_string()).collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::White).unwrap();
+        game.apply_clocks(&[Some(Duration::from_secs(298)), Some(Duration::from_secs(3_600))]);
+        game.annotate_move(
+            2,
+            MoveAnnotation {
+                glyph: None,
+                comment: Some("Solid".to_string()),
+            },
+        );
+
+        let pgn = export_pgn(&[], game.get_move_records(), "*");
+        assert_eq!(pgn, "\n1. e4 { [%clk 0:04:58] } 1... e5 { [%clk 1:00:00] Solid } *\n");
+
+        let parsed = &parse_pgn(&pgn).unwrap()[0];
+        assert_eq!(parsed.clocks, [Some(Duration::from_secs(298)), Some(Duration::from_secs(3_600))]);
+        assert_eq!(parsed.annotations[0].comment, None);
+        assert_eq!(parsed.annotations[1].comment.as_deref(), Some("Solid"));
+    }
+
+    #[test]
+    fn test_export_annotated_marks_blunders() {
+        let start = Board::default();
+        let e4 = parse_uci_move(&start, "e2e4").unwrap();
+        let f6 = parse_uci_move(&start.make_move_new(e4), "f7f6").unwrap();
+        let evaluation = |class, best: &str, after| MoveEvaluation {
+            class,
+            cp_loss: 0,
+            best_move: best.to_string(),
+            eval_before: 0,
+            eval_after: after,
+        };
+        let evaluations = [
+            evaluation(MoveClass::Best, "e2e4", 35),
+            evaluation(MoveClass::Blunder, "e7e5", 420),
+        ];
+
+        let pgn = export_annotated(&[], start, &[e4, f6], &evaluations, "*");
+
+        assert_eq!(
+            pgn,
+            "\n1. e4 { [%eval 0.35] } f6?? { [%eval 4.20] Blunder. e5 was best. } *\n"
+        );
+        assert_eq!(parse_pgn(&pgn).unwrap()[0].lines[0], [e4, f6]);
+    }
+}
@@ -0,0 +1,108 @@
+use std::{fs, path::Path, process::Command};
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::Strings;
+
+/// When to run in the low-power profile: one engine thread, shorter
+/// thinking and a slower refresh of the window.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerSaver {
+    #[default]
+    Off,
+    /// Only while the computer runs on its battery
+    OnBattery,
+    Always,
+}
+
+impl PowerSaver {
+    pub const ALL: [PowerSaver; 3] = [PowerSaver::Off, PowerSaver::OnBattery, PowerSaver::Always];
+
+    pub fn name(self, t: &Strings) -> &'static str {
+        match self {
+            PowerSaver::Off => t.power_saver_off,
+            PowerSaver::OnBattery => t.power_saver_on_battery,
+            PowerSaver::Always => t.power_saver_always,
+        }
+    }
+}
+
+/// Whether the computer is running on its battery; None when that can't
+/// be told (no battery, or an unsupported system).
+pub fn on_battery() -> Option<bool> {
+    if cfg!(target_os = "macos") {
+        let output = Command::new("pmset").args(["-g", "batt"]).output().ok()?;
+        let report = String::from_utf8_lossy(&output.stdout);
+        return Some(report.contains("'Battery Power'"));
+    }
+    let entries = fs::read_dir("/sys/class/power_supply").ok()?;
+    let supplies: Vec<PowerSupply> = entries
+        .filter_map(Result::ok)
+        .map(|entry| PowerSupply::read(&entry.path()))
+        .collect();
+    battery_in_use(&supplies)
+}
+
+/// What the kernel reports about one power supply.
+#[derive(Debug, Default)]
+struct PowerSupply {
+    /// "Mains", "Battery", "USB", ...
+    kind: String,
+    /// For mains adapters, "1" when plugged in
+    online: Option<String>,
+    /// For batteries, "Charging", "Discharging", "Full", ...
+    status: Option<String>,
+}
+
+impl PowerSupply {
+    fn read(dir: &Path) -> PowerSupply {
+        let field = |name: &str| {
+            let value = fs::read_to_string(dir.join(name)).ok()?;
+            Some(value.trim().to_string())
+        };
+        PowerSupply {
+            kind: field("type").unwrap_or_default(),
+            online: field("online"),
+            status: field("status"),
+        }
+    }
+}
+
+// A plugged-in adapter wins over a battery that still reports discharging
+fn battery_in_use(supplies: &[PowerSupply]) -> Option<bool> {
+    let plugged_in = supplies
+        .iter()
+        .any(|supply| supply.kind == "Mains" && supply.online.as_deref() == Some("1"));
+    let batteries: Vec<&PowerSupply> =
+        supplies.iter().filter(|supply| supply.kind == "Battery").collect();
+    if batteries.is_empty() {
+        return None;
+    }
+    let discharging = batteries
+        .iter()
+        .any(|battery| battery.status.as_deref() == Some("Discharging"));
+    Some(discharging && !plugged_in)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_in_use() {
+        let supply = |kind: &str, online: Option<&str>, status: Option<&str>| PowerSupply {
+            kind: kind.to_string(),
+            online: online.map(str::to_string),
+            status: status.map(str::to_string),
+        };
+        let unplugged = [supply("Mains", Some("0"), None), supply("Battery", None, Some("Discharging"))];
+        assert_eq!(battery_in_use(&unplugged), Some(true));
+        let charging = [supply("Mains", Some("1"), None), supply("Battery", None, Some("Charging"))];
+        assert_eq!(battery_in_use(&charging), Some(false));
+        let stale = [supply("Mains", Some("1"), None), supply("Battery", None, Some("Discharging"))];
+        assert_eq!(battery_in_use(&stale), Some(false));
+        // A desktop has no battery to run on
+        assert_eq!(battery_in_use(&[supply("Mains", Some("1"), None)]), None);
+    }
+}
@@ -0,0 +1,94 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::persistence::{data_dir, write_json};
+use crate::stats::Outcome;
+
+/// Rating before the first rated game
+pub const START_RATING: f32 = 1500.0;
+
+/// Points at stake in a game: a win over an equal opponent gains half
+const K_FACTOR: f32 = 32.0;
+
+/// One rated game's effect on the player's rating.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RatingChange {
+    /// Local time the game finished, "YYYY-MM-DD HH:MM"
+    pub played_at: String,
+    pub outcome: Outcome,
+    /// The Elo limit the engine played at
+    pub opponent: u32,
+    /// The player's rating after the game
+    pub rating: f32,
+}
+
+/// The player's Elo-style rating, rebuilt from rated games against engines
+/// with an Elo limit, oldest first.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PlayerRating {
+    pub history: Vec<RatingChange>,
+}
+
+impl PlayerRating {
+    pub fn current(&self) -> f32 {
+        self.history.last().map_or(START_RATING, |change| change.rating)
+    }
+
+    /// Update the rating for a game against an engine limited to
+    /// `opponent` Elo, returning the new rating.
+    pub fn record(&mut self, played_at: String, outcome: Outcome, opponent: u32) -> f32 {
+        let current = self.current();
+        let rating = current + K_FACTOR * (outcome.points() - expected_score(current, opponent as f32));
+        self.history.push(RatingChange {
+            played_at,
+            outcome,
+            opponent,
+            rating,
+        });
+        rating
+    }
+}
+
+/// Points a player rated `rating` is expected to score against `opponent`.
+pub fn expected_score(rating: f32, opponent: f32) -> f32 {
+    1.0 / (1.0 + 10f32.powf((opponent - rating) / 400.0))
+}
+
+fn rating_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("rating.json"))
+}
+
+pub fn load_rating() -> PlayerRating {
+    rating_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_rating(rating: &PlayerRating) -> Result<(), AppError> {
+    let path = rating_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory available"))?;
+    write_json(&path, rating)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rating_updates() {
+        let mut rating = PlayerRating::default();
+        let played_at = || "2024-01-01 12:00".to_string();
+        assert_eq!(rating.current(), START_RATING);
+
+        assert_eq!(rating.record(played_at(), Outcome::Win, 1500), 1516.0);
+        assert_eq!(rating.record(played_at(), Outcome::Draw, 1516), 1516.0);
+        // Losing to a much stronger engine costs little
+        let after = rating.record(played_at(), Outcome::Loss, 2316);
+        assert!(after < 1516.0 && after > 1515.0);
+        assert_eq!(rating.history.len(), 3);
+        assert!((expected_score(1700.0, 1500.0) - 0.76).abs() < 0.01);
+    }
+}
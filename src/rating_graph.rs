@@ -0,0 +1,70 @@
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke, Text};
+use iced::{mouse, Color as IcedColor, Point, Rectangle, Renderer, Theme};
+
+use crate::Message;
+
+const LINE_COLOR: IcedColor = IcedColor::from_rgb(0.35, 0.65, 1.0);
+const LABEL_COLOR: IcedColor = IcedColor::from_rgb(0.7, 0.7, 0.7);
+
+/// Room at the left for the rating labels
+const LABEL_WIDTH: f32 = 40.0;
+
+/// The player's rating after each rated game, drawn as a line from the
+/// first game at the left to the latest at the right.
+pub struct RatingGraph {
+    /// Ratings in game order, starting with the one before the first game
+    pub ratings: Vec<f32>,
+}
+
+impl canvas::Program<Message> for RatingGraph {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &(),
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        if self.ratings.len() < 2 {
+            return vec![frame.into_geometry()];
+        }
+
+        // Keep a flat run from filling the height with noise
+        let low = self.ratings.iter().copied().fold(f32::MAX, f32::min);
+        let high = self.ratings.iter().copied().fold(f32::MIN, f32::max);
+        let middle = (low + high) / 2.0;
+        let span = (high - low).max(50.0);
+        let (low, high) = (middle - span / 2.0, middle + span / 2.0);
+
+        let width = bounds.width - LABEL_WIDTH;
+        let height = bounds.height - 10.0;
+        let point = |index: usize, rating: f32| {
+            Point::new(
+                LABEL_WIDTH + width * index as f32 / (self.ratings.len() - 1) as f32,
+                5.0 + height * (high - rating) / (high - low),
+            )
+        };
+
+        let line = Path::new(|builder| {
+            builder.move_to(point(0, self.ratings[0]));
+            for (index, &rating) in self.ratings.iter().enumerate().skip(1) {
+                builder.line_to(point(index, rating));
+            }
+        });
+        frame.stroke(&line, Stroke::default().with_color(LINE_COLOR).with_width(2.0));
+
+        for (rating, y) in [(high, 0.0), (low, height)] {
+            frame.fill_text(Text {
+                content: format!("{:.0}", rating),
+                position: Point::new(0.0, y),
+                color: LABEL_COLOR,
+                size: 11.0,
+                ..Text::default()
+            });
+        }
+        vec![frame.into_geometry()]
+    }
+}
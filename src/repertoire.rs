@@ -0,0 +1,351 @@
+use std::{collections::HashMap, fs, io, path::PathBuf};
+
+use chess::{Board, ChessMove, Color};
+use chrono::Datelike;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::game::parse_uci_move;
+use crate::persistence::{data_dir, write_json};
+use crate::pgn;
+
+/// Prepared opening lines, one list per side, each line a sequence of UCI
+/// moves from the standard starting position.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Repertoire {
+    pub white: Vec<Vec<String>>,
+    pub black: Vec<Vec<String>>,
+}
+
+impl Repertoire {
+    pub fn lines(&self, color: Color) -> &Vec<Vec<String>> {
+        match color {
+            Color::White => &self.white,
+            Color::Black => &self.black,
+        }
+    }
+
+    fn lines_mut(&mut self, color: Color) -> &mut Vec<Vec<String>> {
+        match color {
+            Color::White => &mut self.white,
+            Color::Black => &mut self.black,
+        }
+    }
+
+    /// Add every line (main line and variations) of the games in a PGN to
+    /// the repertoire for `color`.  Games from custom positions are skipped.
+    /// Returns the number of new lines.
+    pub fn import_pgn(&mut self, text: &str, color: Color) -> Result<usize, AppError> {
+        let games = pgn::parse_pgn(text)?;
+        let lines = self.lines_mut(color);
+        let before = lines.len();
+
+        for game in games.iter().filter(|game| game.start == Board::default()) {
+            for line in &game.lines {
+                let line: Vec<String> = line.iter().map(|m| m.to_string()).collect();
+                // Lines already covered by a longer one add nothing
+                let covered = lines.iter().any(|known| known.starts_with(&line));
+                if !line.is_empty() && !covered {
+                    lines.retain(|known| !line.starts_with(known));
+                    lines.push(line);
+                }
+            }
+        }
+
+        Ok(lines.len().saturating_sub(before))
+    }
+
+    pub fn clear(&mut self, color: Color) {
+        self.lines_mut(color).clear();
+    }
+}
+
+fn repertoire_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("repertoire.json"))
+}
+
+pub fn load_repertoire() -> Repertoire {
+    repertoire_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_repertoire(repertoire: &Repertoire) -> Result<(), AppError> {
+    let path = repertoire_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory available"))?;
+    write_json(&path, repertoire)
+}
+
+/// Lowest ease a position can fall to, however often it is missed
+const MIN_EASE: f32 = 1.3;
+
+/// How far ahead the trainer looks for a due position when choosing replies
+const LOOKAHEAD: usize = 40;
+
+/// Today as a day number, the unit review dates are kept in.
+pub fn today() -> i32 {
+    chrono::Local::now().date_naive().num_days_from_ce()
+}
+
+/// SM-2 review state of one position where the player has a prepared move.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ReviewCard {
+    /// How fast the interval grows; every miss lowers it
+    pub ease: f32,
+    /// Days between the last review and the next
+    pub interval: u32,
+    /// Correct answers in a row
+    pub repetitions: u32,
+    /// Day number the position is next due
+    pub due: i32,
+}
+
+impl Default for ReviewCard {
+    fn default() -> Self {
+        ReviewCard {
+            ease: 2.5,
+            interval: 0,
+            repetitions: 0,
+            due: 0,
+        }
+    }
+}
+
+impl ReviewCard {
+    /// Grade an answer given on `today`.  A correct move pushes the next
+    /// review out (1 day, 6 days, then growing by the ease); a miss makes
+    /// the position due again straight away.
+    pub fn review(&mut self, correct: bool, today: i32) {
+        // SM-2 with quality 4 for a correct move and 1 for a miss
+        let quality: f32 = if correct { 4.0 } else { 1.0 };
+        let penalty = 5.0 - quality;
+        self.ease = (self.ease + 0.1 - penalty * (0.08 + penalty * 0.02)).max(MIN_EASE);
+        if correct {
+            self.repetitions += 1;
+            self.interval = match self.repetitions {
+                1 => 1,
+                2 => 6,
+                _ => (self.interval as f32 * self.ease).round() as u32,
+            };
+        } else {
+            self.repetitions = 0;
+            self.interval = 0;
+        }
+        self.due = today + self.interval as i32;
+    }
+}
+
+/// Review cards for repertoire positions, by FEN.  Positions without a
+/// card haven't been trained yet and are due.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReviewSchedule {
+    pub cards: HashMap<String, ReviewCard>,
+}
+
+impl ReviewSchedule {
+    pub fn is_due(&self, board: &Board, today: i32) -> bool {
+        match self.cards.get(&board.to_string()) {
+            Some(card) => card.due <= today,
+            None => true,
+        }
+    }
+
+    pub fn review(&mut self, board: &Board, correct: bool, today: i32) {
+        self.cards.entry(board.to_string()).or_default().review(correct, today);
+    }
+}
+
+fn schedule_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("repertoire_schedule.json"))
+}
+
+pub fn load_schedule() -> ReviewSchedule {
+    schedule_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_schedule(schedule: &ReviewSchedule) -> Result<(), AppError> {
+    let path = schedule_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory available"))?;
+    write_json(&path, schedule)
+}
+
+/// Drills one side of the repertoire: knows the prepared moves in every
+/// position of the lines and picks the opponent's replies from them,
+/// steering towards positions that are due for review.
+pub struct RepertoireTrainer {
+    color: Color,
+    // Moves found in the repertoire, by position hash
+    moves: HashMap<u64, Vec<ChessMove>>,
+    // Positions where the player has a prepared move
+    player_positions: Vec<Board>,
+    schedule: ReviewSchedule,
+    today: i32,
+    // Position of the last miss, so a retry there isn't graded again
+    missed: Option<u64>,
+}
+
+impl RepertoireTrainer {
+    pub fn new(repertoire: &Repertoire, color: Color, schedule: ReviewSchedule) -> RepertoireTrainer {
+        let mut moves: HashMap<u64, Vec<ChessMove>> = HashMap::new();
+        let mut player_positions = Vec::new();
+        for line in repertoire.lines(color) {
+            let mut board = Board::default();
+            for uci in line {
+                let Some(chess_move) = parse_uci_move(&board, uci) else {
+                    break;
+                };
+                let known = moves.entry(board.get_hash()).or_default();
+                if known.is_empty() && board.side_to_move() == color {
+                    player_positions.push(board);
+                }
+                if !known.contains(&chess_move) {
+                    known.push(chess_move);
+                }
+                board = board.make_move_new(chess_move);
+            }
+        }
+        RepertoireTrainer {
+            color,
+            moves,
+            player_positions,
+            schedule,
+            today: today(),
+            missed: None,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Repertoire moves in this position (the player's prepared moves on
+    /// their turn, the expected replies on the opponent's).
+    pub fn known_moves(&self, board: &Board) -> &[ChessMove] {
+        self.moves
+            .get(&board.get_hash())
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Pick one of the repertoire's replies for the opponent at random,
+    /// preferring those that lead to a position due for review.
+    pub fn opponent_reply(&self, board: &Board) -> Option<ChessMove> {
+        let replies = self.known_moves(board);
+        let leading: Vec<ChessMove> = replies
+            .iter()
+            .copied()
+            .filter(|&reply| self.due_ahead(&board.make_move_new(reply), LOOKAHEAD))
+            .collect();
+        let choices = if leading.is_empty() { replies } else { &leading };
+        choices.choose(&mut rand::thread_rng()).copied()
+    }
+
+    /// Whether a due position can be reached from `board` within the
+    /// repertoire in at most `depth` moves.
+    fn due_ahead(&self, board: &Board, depth: usize) -> bool {
+        let known = self.known_moves(board);
+        if known.is_empty() || depth == 0 {
+            return false;
+        }
+        if board.side_to_move() == self.color && self.schedule.is_due(board, self.today) {
+            return true;
+        }
+        known
+            .iter()
+            .any(|&m| self.due_ahead(&board.make_move_new(m), depth - 1))
+    }
+
+    /// Positions with a prepared move that are due for review.
+    pub fn due_count(&self) -> usize {
+        self.player_positions
+            .iter()
+            .filter(|board| self.schedule.is_due(board, self.today))
+            .count()
+    }
+
+    /// Grade the player's move in `board`.  Only the first answer in a
+    /// position counts, so retrying after a miss doesn't undo it.
+    pub fn grade(&mut self, board: &Board, correct: bool) {
+        let hash = board.get_hash();
+        if self.missed == Some(hash) {
+            if correct {
+                self.missed = None;
+            }
+            return;
+        }
+        if !correct {
+            self.missed = Some(hash);
+        }
+        self.schedule.review(board, correct, self.today);
+    }
+
+    pub fn schedule(&self) -> &ReviewSchedule {
+        &self.schedule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_keeps_variations_and_trainer_knows_them() {
+        let pgn = "[Event \"Prep\"]\n\n1. e4 e5 (1... c5 2. Nf3) 2. Nf3 Nc6 3. Bb5 *\n";
+        let mut repertoire = Repertoire::default();
+
+        assert_eq!(repertoire.import_pgn(pgn, Color::White).unwrap(), 2);
+        // Re-importing adds nothing new
+        assert_eq!(repertoire.import_pgn(pgn, Color::White).unwrap(), 0);
+        assert!(repertoire.black.is_empty());
+
+        let trainer = RepertoireTrainer::new(&repertoire, Color::White, ReviewSchedule::default());
+        let after_e4 = Board::default().make_move_new(parse_uci_move(&Board::default(), "e2e4").unwrap());
+        let replies: Vec<String> = trainer.known_moves(&after_e4).iter().map(|m| m.to_string()).collect();
+        assert_eq!(replies, vec!["e7e5", "c7c5"]);
+        assert_eq!(trainer.known_moves(&Board::default()).len(), 1);
+    }
+
+    #[test]
+    fn test_missed_positions_come_back_sooner() {
+        let mut card = ReviewCard::default();
+        card.review(true, 100);
+        card.review(true, 101);
+        assert_eq!((card.interval, card.due), (6, 107));
+        card.review(true, 107);
+        assert_eq!(card.interval, 15);
+
+        card.review(false, 122);
+        assert_eq!((card.repetitions, card.due), (0, 122));
+        assert!(card.ease < 2.5);
+
+        let pgn = "[Event \"Prep\"]\n\n1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *\n";
+        let mut repertoire = Repertoire::default();
+        repertoire.import_pgn(pgn, Color::White).unwrap();
+        let mut trainer = RepertoireTrainer::new(&repertoire, Color::White, ReviewSchedule::default());
+        assert_eq!(trainer.due_count(), 3);
+
+        // Knowing the Sicilian sends the trainer to the other line
+        let e4 = parse_uci_move(&Board::default(), "e2e4").unwrap();
+        let after_e4 = Board::default().make_move_new(e4);
+        trainer.grade(&Board::default(), true);
+        let c5 = parse_uci_move(&after_e4, "c7c5").unwrap();
+        trainer.grade(&after_e4.make_move_new(c5), true);
+        assert_eq!(trainer.due_count(), 1);
+        assert_eq!(trainer.opponent_reply(&after_e4).unwrap().to_string(), "e7e5");
+
+        // A retry after a miss isn't graded
+        let after_e5 = after_e4.make_move_new(parse_uci_move(&after_e4, "e7e5").unwrap());
+        trainer.grade(&after_e5, false);
+        trainer.grade(&after_e5, true);
+        assert_eq!(trainer.due_count(), 1);
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Write as _;
+
+use chess::{Board, Color};
+
+use crate::diagram::{board_svg, DiagramOptions};
+use crate::game::{MoveDetails, MoveRecord};
+use crate::pgn::Notation;
+
+/// Tags printed in the scoresheet header, in this order
+const HEADER_TAGS: [&str; 8] = [
+    "Event", "Site", "Date", "Round", "White", "Black", "Opening", "TimeControl",
+];
+
+/// A printable HTML scoresheet: the game's tags, the moves numbered in two
+/// columns, the result and a diagram of the final position.  Opening the
+/// file in a browser and printing it (or saving as PDF) gives a sheet like
+/// an over-the-board one.
+pub fn scoresheet_html(
+    tags: &[(String, String)],
+    records: &[MoveRecord],
+    result: &str,
+    final_position: &Board,
+    orientation: Color,
+    notation: Notation,
+) -> String {
+    let tag = |name: &str| {
+        tags.iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    };
+    let title = format!(
+        "{} vs {}",
+        escape(tag("White").unwrap_or("?")),
+        escape(tag("Black").unwrap_or("?"))
+    );
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}</style>\n</head>\n<body>\n",
+        title, STYLE
+    );
+    let _ = writeln!(html, "<h1>{}</h1>", title);
+
+    html.push_str("<table class=\"tags\">\n");
+    for name in HEADER_TAGS {
+        if let Some(value) = tag(name).filter(|value| !value.is_empty() && *value != "?") {
+            let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", name, escape(value));
+        }
+    }
+    html.push_str("</table>\n");
+
+    // Fill the left column first, like a paper scoresheet
+    let half = records.len().div_ceil(2);
+    html.push_str("<div class=\"moves\">\n");
+    for column in [&records[..half], &records[half..]] {
+        html.push_str("<table>\n<tr><th></th><th>White</th><th>Black</th></tr>\n");
+        for record in column {
+            let _ = writeln!(
+                html,
+                "<tr><td class=\"num\">{}</td><td>{}</td><td>{}</td></tr>",
+                record.move_num,
+                san_cell(&record.white_move, Color::White, notation),
+                san_cell(&record.black_move, Color::Black, notation)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</div>\n");
+
+    let _ = writeln!(html, "<p class=\"result\">Result: {}</p>", escape(result));
+    let options = DiagramOptions {
+        orientation,
+        last_move: None,
+        coordinates: true,
+    };
+    let _ = write!(
+        html,
+        "<div class=\"diagram\">\n{}</div>\n</body>\n</html>\n",
+        board_svg(final_position, &options)
+    );
+    html
+}
+
+fn san_cell(details: &Option<MoveDetails>, mover: Color, notation: Notation) -> String {
+    match details {
+        Some(details) => format!(
+            "{}{}",
+            notation.write(&details.notation, mover),
+            details.annotation.glyph.unwrap_or("")
+        ),
+        None => String::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 760px; margin: 2em auto; }
+h1 { font-size: 1.4em; }
+.tags th { text-align: left; padding-right: 1.5em; font-weight: normal; color: #555; }
+.moves { display: flex; gap: 2em; margin-top: 1.5em; }
+.moves table { flex: 1; border-collapse: collapse; }
+.moves td, .moves th { border: 1px solid #999; padding: 3px 8px; height: 1.2em; }
+.moves td.num { width: 2.5em; text-align: right; color: #555; }
+.result { font-weight: bold; }
+.diagram svg { width: 320px; height: 320px; }
+@media print { body { margin: 0; } }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::ChessGame;
+
+    #[test]
+    fn test_scoresheet_columns() {
+        let mut game = ChessGame::new();
+        let moves: Vec<String> = ["e2e4", "e7e5", "g1f3", "b8c6", "f1b5"]
+            .iter()
+            .map(|m| m.to_string())
+            .collect();
+        game.load_moves(&Board::default().to_string(), &moves, Color::White).unwrap();
+        let tags = vec![
+            ("White".to_string(), "Player".to_string()),
+            ("Black".to_string(), "<Engine>".to_string()),
+        ];
+
+        let board = game.current_position();
+        let html = scoresheet_html(
+            &tags,
+            game.get_move_records(),
+            "*",
+            &board,
+            Color::White,
+            Notation::Letters,
+        );
+
+        assert!(html.contains("<title>Player vs &lt;Engine&gt;</title>"));
+        // Moves 1-2 on the left, 3 on the right
+        assert_eq!(html.matches("<th></th><th>White</th>").count(), 2);
+        assert!(html.contains("<td class=\"num\">3</td><td>Bb5</td><td></td>"));
+        assert!(html.contains("<svg xmlns"));
+    }
+}
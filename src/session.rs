@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use crate::database::StoredSession;
+use crate::stats::Outcome;
+
+/// A game finished during this session.
+#[derive(Clone, Debug)]
+pub struct SessionGame {
+    /// Id of the game in the database, if it was archived
+    pub archive_id: Option<i64>,
+    pub outcome: Outcome,
+    /// Time both sides spent on their moves
+    pub duration: Duration,
+    /// The player's accuracy, once the game has been reviewed
+    pub accuracy: Option<f64>,
+}
+
+/// The games played since the application started, stored in the database
+/// as they finish.
+#[derive(Clone, Debug)]
+pub struct Session {
+    /// Id in the database, once the first game has been stored
+    pub id: Option<i64>,
+    /// Local time the session started, "YYYY-MM-DD HH:MM"
+    pub started_at: String,
+    pub games: Vec<SessionGame>,
+}
+
+impl Session {
+    pub fn new(started_at: String) -> Self {
+        Session {
+            id: None,
+            started_at,
+            games: Vec::new(),
+        }
+    }
+
+    /// Set the accuracy of the session's game archived as `archive_id`.
+    /// Returns false if the game wasn't played this session.
+    pub fn set_accuracy(&mut self, archive_id: i64, accuracy: f64) -> bool {
+        match self.games.iter_mut().find(|game| game.archive_id == Some(archive_id)) {
+            Some(game) => {
+                game.accuracy = Some(accuracy);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn count(&self, outcome: Outcome) -> u32 {
+        self.games.iter().filter(|game| game.outcome == outcome).count() as u32
+    }
+
+    pub fn time_played(&self) -> Duration {
+        self.games.iter().map(|game| game.duration).sum()
+    }
+
+    /// Mean accuracy of the reviewed games, and how many there were.
+    pub fn average_accuracy(&self) -> Option<(f64, usize)> {
+        let reviewed: Vec<f64> = self.games.iter().filter_map(|game| game.accuracy).collect();
+        if reviewed.is_empty() {
+            return None;
+        }
+        Some((reviewed.iter().sum::<f64>() / reviewed.len() as f64, reviewed.len()))
+    }
+
+    /// The session's totals as the database keeps them.
+    pub fn stored(&self) -> StoredSession {
+        StoredSession {
+            id: self.id.unwrap_or(0),
+            started_at: self.started_at.clone(),
+            wins: self.count(Outcome::Win),
+            draws: self.count(Outcome::Draw),
+            losses: self.count(Outcome::Loss),
+            seconds: self.time_played().as_secs(),
+            accuracy: self.average_accuracy().map(|(accuracy, _)| accuracy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_totals() {
+        let mut session = Session::new("2024-01-01 12:00".to_string());
+        let game = |archive_id, outcome, secs| SessionGame {
+            archive_id,
+            outcome,
+            duration: Duration::from_secs(secs),
+            accuracy: None,
+        };
+        session.games.push(game(Some(3), Outcome::Win, 400));
+        session.games.push(game(None, Outcome::Loss, 200));
+        session.games.push(game(Some(5), Outcome::Win, 100));
+        assert_eq!(session.average_accuracy(), None);
+
+        assert!(session.set_accuracy(3, 90.0));
+        assert!(session.set_accuracy(5, 70.0));
+        assert!(!session.set_accuracy(4, 50.0));
+        let stored = session.stored();
+        assert_eq!((stored.wins, stored.draws, stored.losses), (2, 0, 1));
+        assert_eq!(stored.seconds, 700);
+        assert_eq!(stored.accuracy, Some(80.0));
+        assert_eq!(session.average_accuracy(), Some((80.0, 2)));
+    }
+}
@@ -0,0 +1,150 @@
+use std::{fs, io, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Resources;
+use crate::error::AppError;
+use crate::i18n::Language;
+use crate::palette::Palette;
+use crate::persistence::{data_dir, write_json};
+use crate::personality::Personality;
+use crate::power::PowerSaver;
+
+/// Preferences kept between sessions and edited on the Settings screen.
+/// Fields missing from an older file take their defaults.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct UserSettings {
+    /// Threads and hash for the engine; command line flags override them
+    pub resources: Resources,
+    /// Personal access token for online play on Lichess
+    pub lichess_token: Option<String>,
+    /// How the game board is drawn
+    #[serde(flatten)]
+    pub board: BoardSettings,
+    /// Language of the interface
+    pub language: Language,
+    /// Zoom applied to the whole window, 1.0 at the system's own size
+    pub ui_scale: f64,
+    /// Where the window was when it was last closed
+    pub window: Option<WindowGeometry>,
+    /// Name of the engine's playing style; None leaves its options alone
+    pub personality: Option<String>,
+    /// Styles added to the built-in ones, or replacing those of the same
+    /// name; edited by hand in the settings file
+    pub personalities: Vec<Personality>,
+    /// NNUE network the engine evaluates with, sent as its EvalFile
+    /// option; --eval-file overrides it
+    pub eval_file: Option<PathBuf>,
+    /// Evaluate each position with a second engine while the game is
+    /// played, so the review is ready when it ends
+    pub background_analysis: bool,
+    /// Stop the kibitzers and background analysis while the window is
+    /// in the background
+    pub pause_unfocused: bool,
+    /// When to give the engine one thread and less time to think, and
+    /// refresh the window less often
+    pub power_saver: PowerSaver,
+    /// PGN repertoire the engine plays its opening moves from
+    pub engine_book: Option<PathBuf>,
+    /// Favour the book moves the engine has done well with, and play the
+    /// ones it keeps losing with less
+    pub book_learning: bool,
+}
+
+/// Size and position of the main window, in logical pixels at the
+/// system's scale (the interface zoom left out).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WindowGeometry {
+    pub width: u32,
+    pub height: u32,
+    /// Unknown until the window has been moved
+    pub position: Option<(i32, i32)>,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        WindowGeometry {
+            width: 1000,
+            height: 700,
+            position: None,
+        }
+    }
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            resources: Resources::default(),
+            lichess_token: None,
+            board: BoardSettings::default(),
+            language: Language::default(),
+            ui_scale: 1.0,
+            window: None,
+            personality: None,
+            personalities: Vec::new(),
+            eval_file: None,
+            background_analysis: false,
+            pause_unfocused: false,
+            power_saver: PowerSaver::Off,
+            engine_book: None,
+            book_learning: false,
+        }
+    }
+}
+
+/// Smallest and largest interface zoom, and the step the shortcuts take.
+pub const UI_SCALE_RANGE: (f64, f64) = (0.5, 3.0);
+pub const UI_SCALE_STEP: f64 = 0.1;
+
+/// Keep a zoom within range, rounded to whole steps.
+pub fn clamp_ui_scale(scale: f64) -> f64 {
+    let (min, max) = UI_SCALE_RANGE;
+    ((scale / UI_SCALE_STEP).round() * UI_SCALE_STEP).clamp(min, max)
+}
+
+/// Preferences for drawing the game board.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct BoardSettings {
+    /// How long a moved piece takes to slide to its square; 0 jumps
+    pub animation_ms: u32,
+    /// Draw an arrow for the reply the engine expects after its move
+    pub reply_arrow: bool,
+    /// Label files and ranks around the game board
+    pub coordinates: bool,
+    /// Colour scheme of the board
+    pub palette: Palette,
+    /// Larger buttons, and moves made by tapping: a tap promotes to a
+    /// queen, a long press offers the other pieces
+    pub touch_mode: bool,
+}
+
+impl Default for BoardSettings {
+    fn default() -> Self {
+        BoardSettings {
+            animation_ms: 200,
+            reply_arrow: false,
+            coordinates: false,
+            palette: Palette::Classic,
+            touch_mode: false,
+        }
+    }
+}
+
+fn settings_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("settings.json"))
+}
+
+pub fn load_settings() -> UserSettings {
+    settings_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_settings(settings: &UserSettings) -> Result<(), AppError> {
+    let path = settings_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No data directory available"))?;
+    write_json(&path, settings)
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+use crate::database::StoredGame;
+
+/// How many of the most recent games make up the trend figure
+const RECENT_GAMES: usize = 10;
+
+/// A game result from the user's point of view.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+impl Outcome {
+    /// Interpret a PGN result token for the side the user played ("w"/"b").
+    pub fn for_player(result: &str, player_color: &str) -> Option<Outcome> {
+        let white_outcome = match result {
+            "1-0" => Outcome::Win,
+            "0-1" => Outcome::Loss,
+            "1/2-1/2" => Outcome::Draw,
+            _ => return None,
+        };
+        Some(match (white_outcome, player_color) {
+            (Outcome::Win, "b") => Outcome::Loss,
+            (Outcome::Loss, "b") => Outcome::Win,
+            (outcome, _) => outcome,
+        })
+    }
+
+    pub fn points(self) -> f32 {
+        match self {
+            Outcome::Win => 1.0,
+            Outcome::Draw => 0.5,
+            Outcome::Loss => 0.0,
+        }
+    }
+
+    pub fn letter(self) -> char {
+        match self {
+            Outcome::Win => 'W',
+            Outcome::Draw => 'D',
+            Outcome::Loss => 'L',
+        }
+    }
+}
+
+/// Results against one engine at one skill level.
+#[derive(Clone, Debug)]
+pub struct LevelStats {
+    pub engine: String,
+    pub skill_level: u8,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Outcome and length of the run the latest game belongs to
+    pub current_streak: Option<(Outcome, u32)>,
+    pub best_win_streak: u32,
+    /// The most recent results, oldest first
+    pub recent: Vec<Outcome>,
+}
+
+impl LevelStats {
+    fn new(engine: &str, skill_level: u8) -> Self {
+        LevelStats {
+            engine: engine.to_string(),
+            skill_level,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            current_streak: None,
+            best_win_streak: 0,
+            recent: Vec::new(),
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Overall score as a percentage (draws count half).
+    pub fn score_percent(&self) -> f32 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f32 + self.draws as f32 * 0.5) * 100.0 / self.games() as f32
+    }
+
+    /// Score over the recent games only, to show whether results are
+    /// improving relative to the overall figure.
+    pub fn recent_score_percent(&self) -> f32 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        self.recent.iter().map(|o| o.points()).sum::<f32>() * 100.0 / self.recent.len() as f32
+    }
+
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+
+        self.current_streak = match self.current_streak {
+            Some((last, len)) if last == outcome => Some((outcome, len + 1)),
+            _ => Some((outcome, 1)),
+        };
+        if let Some((Outcome::Win, len)) = self.current_streak {
+            self.best_win_streak = self.best_win_streak.max(len);
+        }
+
+        self.recent.push(outcome);
+        if self.recent.len() > RECENT_GAMES {
+            self.recent.remove(0);
+        }
+    }
+}
+
+/// Aggregate stored games (as listed by the database, newest first) into
+/// one entry per engine and skill level, sorted by engine then level.
+/// Casual games played with takebacks, the coach or engine analysis are
+/// left out.
+pub fn compute(games: &[StoredGame]) -> Vec<LevelStats> {
+    let mut stats: Vec<LevelStats> = Vec::new();
+
+    // Walk oldest to newest so streaks and the recent window are in order
+    for game in games.iter().rev().filter(|game| !game.assisted) {
+        let Some(outcome) = Outcome::for_player(&game.result, &game.player_color) else {
+            continue;
+        };
+        let index = match stats
+            .iter()
+            .position(|s| s.engine == game.engine && s.skill_level == game.skill_level)
+        {
+            Some(index) => index,
+            None => {
+                stats.push(LevelStats::new(&game.engine, game.skill_level));
+                stats.len() - 1
+            }
+        };
+        stats[index].add(outcome);
+    }
+
+    stats.sort_by(|a, b| a.engine.cmp(&b.engine).then(a.skill_level.cmp(&b.skill_level)));
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(skill_level: u8, player_color: &str, result: &str) -> StoredGame {
+        StoredGame {
+            id: 0,
+            played_at: String::new(),
+            engine: "stockfish".to_string(),
+            skill_level,
+            player_color: player_color.to_string(),
+            result: result.to_string(),
+            start_fen: String::new(),
+            moves: String::new(),
+            pgn: String::new(),
+            assisted: false,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compute_groups_and_streaks() {
+        // Newest first, as returned by the database
+        let games = vec![
+            stored(5, "w", "1-0"),
+            stored(5, "b", "0-1"),
+            stored(10, "w", "0-1"),
+            stored(5, "w", "1/2-1/2"),
+            stored(5, "b", "0-1"),
+            StoredGame {
+                assisted: true,
+                ..stored(5, "w", "0-1")
+            },
+        ];
+
+        let stats = compute(&games);
+        assert_eq!(stats.len(), 2);
+
+        let level5 = &stats[0];
+        assert_eq!(level5.skill_level, 5);
+        assert_eq!((level5.wins, level5.draws, level5.losses), (3, 1, 0));
+        assert_eq!(level5.current_streak, Some((Outcome::Win, 2)));
+        assert_eq!(level5.best_win_streak, 2);
+        assert!((level5.score_percent() - 87.5).abs() < 0.01);
+
+        let level10 = &stats[1];
+        assert_eq!(level10.losses, 1);
+        assert_eq!(level10.score_percent(), 0.0);
+    }
+}
@@ -1,20 +1,200 @@
-use chess::{Board, ChessMove, Color, File, Piece, Rank, Square};
+use chess::{ChessMove, Color, File, Piece, Rank, Square};
 use iced::widget::button::Appearance;
 use iced::widget::scrollable::Scrollable;
 use iced::{
-    widget::{button, column, container, row, svg, text, text_input, Space},
-    Alignment, Color as IcedColor, Element, Length,
+    widget::{
+        button, column, container, row, slider, svg, text, text_input, tooltip, Canvas, Space, Text,
+    },
+    Alignment, Color as IcedColor, Element, Length, Padding,
 };
 
-use crate::game::{MoveRecord, PromotionPiece};
-use crate::{Message, SetupState};
+use crate::adaptive::ELO_STEP;
+use crate::board_view::{Arrow, BoardView, Slide};
+use crate::analysis::{self, MoveClass, MoveEvaluation};
+use crate::database::GameFilter;
+use crate::endgames::{EndgameGoal, EndgameOutcome, ENDGAMES};
+use crate::engine::{Direction, UciLine};
+use crate::clock::{display_clock, display_move_time, format_clock, ClockMode};
+use crate::diagram::piece_svg;
+use crate::eco::{EcoTable, OpeningLine};
+use crate::famous;
+use crate::i18n::{fill, Language, Strings};
+use crate::lichess::IncomingChallenge;
+use crate::game::{
+    captured_pieces, material_balance, move_details, ClockTimes, GameSnapshot, MoveDetails,
+    MoveRecord, PromotionPiece, MOVE_GLYPHS,
+};
+use crate::odds::HANDICAPS;
+use crate::palette::{BoardColors, Palette};
+use crate::power::PowerSaver;
+use crate::rating::START_RATING;
+use crate::rating_graph::RatingGraph;
+use crate::settings::UI_SCALE_RANGE;
+use crate::stats::{LevelStats, Outcome};
+use crate::variation;
+use crate::{
+    BrowserState, GameKind, Message, NewGameState, OnlineState, PuzzleState, RepertoireState,
+    SessionState, SettingsState, SetupState, StatsState,
+};
+
+/// Room for the coordinate labels beside and under the game board
+const COORDINATE_SIZE: f32 = 16.0;
+
+// Height of the UCI console below the board
+const CONSOLE_HEIGHT: f32 = 220.0;
+
+/// Centipawns behind the best line at which an analysis arrow is drawn
+/// at its lightest
+const ANALYSIS_ARROW_SPAN: f32 = 200.0;
+
+/// Application-level state shown alongside the game that isn't part of
+/// `ChessGame` itself.
+pub struct AppInfo {
+    /// Move count of an unfinished game offered for resuming
+    pub resume_offer: Option<usize>,
+    /// When the stored copy of a finished game that is already in the
+    /// database was played, while the player decides what to do with it
+    pub duplicate: Option<String>,
+    /// A post-game review is running
+    pub reviewing: bool,
+    /// Training session running on the board, if any
+    pub drill: Option<DrillInfo>,
+    /// Elo limit in force, replacing the skill level
+    pub elo: Option<u32>,
+    /// Ratings the engine's Elo limit accepts; None if it has none
+    pub elo_range: Option<(u32, u32)>,
+    pub skill_level: u8,
+    /// Milliseconds the engine spends on a move
+    pub think_time: u64,
+    /// The engine being played, as it introduced itself
+    pub engine: String,
+    /// The engine couldn't be started
+    pub engine_failure: Option<EngineFailure>,
+    /// Seconds the engine has gone past its move time without replying,
+    /// once it looks stuck
+    pub engine_overrun: Option<u64>,
+    /// The engine's latest complaint on stderr, until dismissed
+    pub engine_stderr: Option<String>,
+    /// The analysis-only engines' views, while they are on
+    pub kibitzers: Vec<KibitzerInfo>,
+    /// The UCI console, when it is open
+    pub console: Option<ConsoleInfo>,
+    /// Chess clocks, for games played or recorded with them
+    pub clocks: Option<ClockTimes>,
+    /// Side shown at the bottom of the board
+    pub orientation: Color,
+    /// The board is turned to show the opponent's side
+    pub board_flipped: bool,
+    /// The last move's piece on its way to its square
+    pub slide: Option<Slide>,
+    /// From and to squares of the engine's move, while it is the last one
+    pub engine_move: Option<(Square, Square)>,
+    /// The reply the engine expects from the player, when shown
+    pub expected_reply: Option<(Square, Square)>,
+    /// Label the files and ranks around the board
+    pub coordinates: bool,
+    /// Threats are asked for; the moves come once the engine has found them
+    pub threats_shown: bool,
+    pub threats: Vec<(Square, Square)>,
+    /// Candidate moves are asked for; they come once the engine has found them
+    pub candidates_shown: bool,
+    pub candidates: Vec<Candidate>,
+    /// Exported images highlight the last move
+    pub export_last_move: bool,
+    /// Exported images have coordinates along the edges
+    pub export_coordinates: bool,
+    /// PGN and scoresheet exports use piece symbols instead of letters
+    pub export_figurine: bool,
+    /// The move history is shown as a scoresheet table
+    pub move_table: bool,
+    /// Square the arrow keys have moved to, outlined on the board
+    pub board_cursor: Option<Square>,
+    /// The last move or the result in words
+    pub announcement: Option<String>,
+    /// Text in the "go to move" box over the move list
+    pub go_to_move: String,
+    /// Text in the position search under it, and whether the last search
+    /// found nothing
+    pub find_position: String,
+    pub position_not_found: bool,
+    /// Position reached by the first move "Delete from here" would remove,
+    /// while it waits to be confirmed
+    pub delete_from: Option<usize>,
+    /// The Lichess game on the board, if any
+    pub online: Option<OnlineInfo>,
+}
+
+/// A kibitzer's top lines in the position on the board.
+pub struct KibitzerInfo {
+    pub engine: String,
+    pub depth: u32,
+    /// White's evaluation of each line, e.g. "0.35" or "#-3", and its
+    /// start in SAN; best first, and empty before the first report
+    pub lines: Vec<(String, String)>,
+    /// First move of the best line (UCI), to spot engines disagreeing
+    pub best_move: Option<String>,
+    /// From and to squares of each line's first move, with its score in
+    /// centipawns for the side to move; best first
+    pub moves: Vec<(Square, Square, i32)>,
+    pub error: Option<String>,
+}
+
+/// A Lichess game on the board, for the side panel.
+pub struct OnlineInfo {
+    pub opponent: String,
+    pub rated: bool,
+    /// Time control as Lichess shows it, e.g. "10+5"
+    pub control: Option<String>,
+    /// How the game ended, once it has
+    pub outcome: Option<String>,
+    /// Whether the kibitzer is on; None where it isn't allowed
+    pub kibitz: Option<bool>,
+    /// The kibitzer's latest evaluation
+    pub kibitz_eval: Option<String>,
+}
+
+/// The logged-in Lichess account, for the Online screen.
+pub struct LobbyInfo<'a> {
+    pub username: &'a str,
+    pub challenges: &'a [IncomingChallenge],
+    pub seeking: bool,
+    /// A game is being played on the board
+    pub playing: bool,
+}
+
+/// Why the engine couldn't start, and the path typed in to try instead.
+pub struct EngineFailure {
+    pub error: String,
+    pub path: String,
+}
+
+/// Engine traffic for the UCI console, already filtered.
+pub struct ConsoleInfo {
+    pub filter: String,
+    /// Command being typed for the engine
+    pub command: String,
+    pub lines: Vec<UciLine>,
+}
+
+/// One of the engine's best moves, shown as an arrow and in the side panel.
+pub struct Candidate {
+    pub from: Square,
+    pub to: Square,
+    pub san: String,
+    /// Evaluation after the move, from White's side
+    pub eval: String,
+}
 
-// Colors for the chess board
-const LIGHT_SQUARE: IcedColor = IcedColor::from_rgb(0.93, 0.93, 0.8);
-const DARK_SQUARE: IcedColor = IcedColor::from_rgb(0.46, 0.59, 0.34);
-const SELECTED_SQUARE: IcedColor = IcedColor::from_rgb(0.9, 0.8, 0.3);
-const LEGAL_MOVE_LIGHT_SQUARE: IcedColor = IcedColor::from_rgb(0.7, 0.9, 0.7);
-const LEGAL_MOVE_DARK_SQUARE: IcedColor = IcedColor::from_rgb(0.5, 0.75, 0.5);
+/// What the side panel shows about an active drill.
+pub enum DrillInfo {
+    /// Repertoire training for this side, with the positions still due
+    Repertoire { color: Color, due: usize },
+    Puzzle { solved: bool },
+    Endgame {
+        name: &'static str,
+        outcome: Option<EndgameOutcome>,
+    },
+}
 
 // Chess UI component
 pub struct ChessUI {
@@ -24,6 +204,12 @@ pub struct ChessUI {
     reset_icon: svg::Handle,
     undo_icon: svg::Handle,
     flip_icon: svg::Handle,
+    /// Interface text in the chosen language
+    strings: &'static Strings,
+    /// Board colours from the chosen palette
+    colors: &'static BoardColors,
+    /// Buttons grow for fingers and nothing is left to hover
+    touch: bool,
 }
 
 // Structure to hold SVG handles for chess pieces
@@ -44,76 +230,39 @@ struct PieceHandles {
 
 impl PieceHandles {
     fn new() -> Self {
-        // Load all piece SVGs
-        let wp = Self::load_svg("assets/wp.svg");
-        let wn = Self::load_svg("assets/wn.svg");
-        let wb = Self::load_svg("assets/wb.svg");
-        let wr = Self::load_svg("assets/wr.svg");
-        let wq = Self::load_svg("assets/wq.svg");
-        let wk = Self::load_svg("assets/wk.svg");
-        let bp = Self::load_svg("assets/bp.svg");
-        let bn = Self::load_svg("assets/bn.svg");
-        let bb = Self::load_svg("assets/bb.svg");
-        let br = Self::load_svg("assets/br.svg");
-        let bq = Self::load_svg("assets/bq.svg");
-        let bk = Self::load_svg("assets/bk.svg");
-
+        let load = |piece, color| svg::Handle::from_memory(piece_svg(piece, color).as_bytes());
         PieceHandles {
-            white_pawn: wp,
-            white_knight: wn,
-            white_bishop: wb,
-            white_rook: wr,
-            white_queen: wq,
-            white_king: wk,
-            black_pawn: bp,
-            black_knight: bn,
-            black_bishop: bb,
-            black_rook: br,
-            black_queen: bq,
-            black_king: bk,
+            white_pawn: load(Piece::Pawn, Color::White),
+            white_knight: load(Piece::Knight, Color::White),
+            white_bishop: load(Piece::Bishop, Color::White),
+            white_rook: load(Piece::Rook, Color::White),
+            white_queen: load(Piece::Queen, Color::White),
+            white_king: load(Piece::King, Color::White),
+            black_pawn: load(Piece::Pawn, Color::Black),
+            black_knight: load(Piece::Knight, Color::Black),
+            black_bishop: load(Piece::Bishop, Color::Black),
+            black_rook: load(Piece::Rook, Color::Black),
+            black_queen: load(Piece::Queen, Color::Black),
+            black_king: load(Piece::King, Color::Black),
         }
     }
 
-    fn load_svg(path: &str) -> svg::Handle {
-        let bytes: Vec<u8> = match path {
-            "assets/wp.svg" => include_bytes!("../assets/wp.svg").to_vec(),
-            "assets/wn.svg" => include_bytes!("../assets/wn.svg").to_vec(),
-            "assets/wb.svg" => include_bytes!("../assets/wb.svg").to_vec(),
-            "assets/wr.svg" => include_bytes!("../assets/wr.svg").to_vec(),
-            "assets/wq.svg" => include_bytes!("../assets/wq.svg").to_vec(),
-            "assets/wk.svg" => include_bytes!("../assets/wk.svg").to_vec(),
-            "assets/bp.svg" => include_bytes!("../assets/bp.svg").to_vec(),
-            "assets/bn.svg" => include_bytes!("../assets/bn.svg").to_vec(),
-            "assets/bb.svg" => include_bytes!("../assets/bb.svg").to_vec(),
-            "assets/br.svg" => include_bytes!("../assets/br.svg").to_vec(),
-            "assets/bq.svg" => include_bytes!("../assets/bq.svg").to_vec(),
-            "assets/bk.svg" => include_bytes!("../assets/bk.svg").to_vec(),
-            _ => {
-                eprintln!("Unknown SVG path: {}", path);
-                return svg::Handle::from_memory(
-                    r#"<svg xmlns="http://www.w3.org/2000/svg" width="45" height="45"></svg>"#
-                        .as_bytes()
-                        .to_vec(),
-                );
-            }
-        };
-        svg::Handle::from_memory(bytes)
-    }
-
-    fn get(&self, piece: Piece, color: Color) -> svg::Handle {
+    // Handles are reference counted, so callers cloning the returned handle
+    // into an `svg` widget only bump a counter rather than copy the SVG data
+    fn get(&self, piece: Piece, color: Color) -> &svg::Handle {
         match (piece, color) {
-            (Piece::Pawn, Color::White) => self.white_pawn.clone(),
-            (Piece::Knight, Color::White) => self.white_knight.clone(),
-            (Piece::Bishop, Color::White) => self.white_bishop.clone(),
-            (Piece::Rook, Color::White) => self.white_rook.clone(),
-            (Piece::Queen, Color::White) => self.white_queen.clone(),
-            (Piece::King, Color::White) => self.white_king.clone(),
-            (Piece::Pawn, Color::Black) => self.black_pawn.clone(),
-            (Piece::Knight, Color::Black) => self.black_knight.clone(),
-            (Piece::Bishop, Color::Black) => self.black_bishop.clone(),
-            (Piece::Rook, Color::Black) => self.black_rook.clone(),
-            (Piece::Queen, Color::Black) => self.black_queen.clone(),
-            (Piece::King, Color::Black) => self.black_king.clone(),
+            (Piece::Pawn, Color::White) => &self.white_pawn,
+            (Piece::Knight, Color::White) => &self.white_knight,
+            (Piece::Bishop, Color::White) => &self.white_bishop,
+            (Piece::Rook, Color::White) => &self.white_rook,
+            (Piece::Queen, Color::White) => &self.white_queen,
+            (Piece::King, Color::White) => &self.white_king,
+            (Piece::Pawn, Color::Black) => &self.black_pawn,
+            (Piece::Knight, Color::Black) => &self.black_knight,
+            (Piece::Bishop, Color::Black) => &self.black_bishop,
+            (Piece::Rook, Color::Black) => &self.black_rook,
+            (Piece::Queen, Color::Black) => &self.black_queen,
+            (Piece::King, Color::Black) => &self.black_king,
         }
     }
 }
@@ -403,6 +552,35 @@ impl ChessUI {
             reset_icon: Self::load_icon("assets/reset.svg"),
             undo_icon: Self::load_icon("assets/undo.svg"),
             flip_icon: Self::load_icon("assets/flip.svg"),
+            strings: Language::default().strings(),
+            colors: Palette::default().colors(),
+            touch: false,
+        }
+    }
+
+    pub fn set_language(&mut self, language: Language) {
+        self.strings = language.strings();
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.colors = palette.colors();
+    }
+
+    pub fn set_touch_mode(&mut self, touch: bool) {
+        self.touch = touch;
+    }
+
+    /// A button's padding, made big enough for a fingertip in touch mode.
+    fn tap_padding(&self, padding: impl Into<Padding>) -> Padding {
+        let padding = padding.into();
+        if !self.touch {
+            return padding;
+        }
+        Padding {
+            top: padding.top.max(12.0),
+            right: padding.right.max(16.0),
+            bottom: padding.bottom.max(12.0),
+            left: padding.left.max(16.0),
         }
     }
 
@@ -423,165 +601,179 @@ impl ChessUI {
         svg::Handle::from_memory(bytes)
     }
 
-    pub fn view(
-        &self,
-        board: Board,
-        selected_square: Option<Square>,
-        possible_moves: &[ChessMove],
-        message: &str,
-        thinking: bool,
-        player_color: Color,
-        game_result: Option<chess::GameResult>,
+    pub fn view<'a>(
+        &'a self,
+        snapshot: &'a GameSnapshot,
+        info: &AppInfo,
         window_width: u32,
         window_height: u32,
-        move_records: &[MoveRecord],
-        is_view_mode: bool,
-        view_move_index: usize,
-        pending_promotion: Option<(Square, Square)>,
-    ) -> Element<'_, Message> {
+    ) -> Element<'a, Message> {
+        let t = self.strings;
+        let board = snapshot.board;
+        let selected_square = snapshot.selected_square;
+        let possible_moves: &[ChessMove] = &snapshot.possible_moves;
+        let thinking = snapshot.thinking;
+        let player_color = snapshot.player_color;
+        let game_result = snapshot.game_result;
+        let move_records: &[MoveRecord] = &snapshot.move_records;
+        let is_view_mode = snapshot.view_mode;
+        let view_move_index = snapshot.view_move_index;
+        let pending_promotion = snapshot.pending_promotion;
+
         // Calculate responsive board size based on window dimensions
-        let available_height = window_height as f32 * 0.9; // Use 90% of window height
+        let mut available_height = window_height as f32 * 0.9; // Use 90% of window height
+        if info.console.is_some() {
+            available_height -= CONSOLE_HEIGHT;
+        }
         let available_width = window_width as f32 * 0.6; // Use 60% of window width
-        let board_size = available_height
+        let mut board_size = available_height
             .min(available_width)
             .max(self.min_board_size)
             .min(self.max_board_size);
+        // Leave room for the labels without growing past the space
+        if info.coordinates {
+            board_size -= COORDINATE_SIZE;
+        }
 
-        let square_size = board_size / 8.0;
-
-        // Create a container for the chess board
-        let mut board_container = column![];
-
-        // Create rows for the chess board
-        for rank in 0..8 {
-            let mut row_container = row![];
-
-            for file in 0..8 {
-                // Calculate board coordinates based on player color
-                let (board_file, board_rank) = if player_color == Color::White {
-                    (file, 7 - rank)
+        // Colour each square by its state
+        let colors = self.colors;
+        let mut square_colors = [colors.light; 64];
+        for square in chess::ALL_SQUARES {
+            let is_dark = (square.get_rank().to_index() + square.get_file().to_index()) % 2 == 1;
+            let is_selected = selected_square == Some(square);
+            let is_legal_move = possible_moves.iter().any(|m| m.get_dest() == square);
+            let is_engine_move = info.engine_move.is_some_and(|(from, to)| square == from || square == to);
+
+            square_colors[square.to_index()] = if is_selected {
+                colors.selected
+            } else if is_legal_move {
+                if is_dark {
+                    colors.legal_dark
                 } else {
-                    (7 - file, rank)
-                };
-
-                let square =
-                    Square::make_square(Rank::from_index(board_rank), File::from_index(board_file));
-
-                // Determine square color and state
-                let is_dark = (board_rank + board_file) % 2 == 1;
-                let is_selected = selected_square == Some(square);
-                let is_legal_move = possible_moves.iter().any(|m| m.get_dest() == square);
-
-                let square_color = if is_selected {
-                    SELECTED_SQUARE
-                } else if is_legal_move {
-                    if is_dark {
-                        LEGAL_MOVE_DARK_SQUARE
-                    } else {
-                        LEGAL_MOVE_LIGHT_SQUARE
-                    }
-                } else if is_dark {
-                    DARK_SQUARE
+                    colors.legal_light
+                }
+            } else if is_engine_move {
+                if is_dark {
+                    colors.engine_dark
                 } else {
-                    LIGHT_SQUARE
-                };
-
-                // Create square content
-                let mut square_content = column![];
-
-                // Add piece if present
-                if let Some(piece) = board.piece_on(square) {
-                    let piece_color = board.color_on(square).unwrap();
-
-                    // Get SVG handle for the piece
-                    let handle = self.piece_handles.get(piece, piece_color);
-
-                    // Add SVG to the square with proper centering
-                    square_content = column![
-                        Space::with_height(Length::Fixed(square_size * 0.1)),
-                        svg(handle)
-                            .width(Length::Fixed(square_size * 0.8))
-                            .height(Length::Fixed(square_size * 0.8))
-                    ]
-                    .width(Length::Fixed(square_size))
-                    .height(Length::Fixed(square_size))
-                    .align_items(Alignment::Center);
+                    colors.engine_light
                 }
-
-                // Create clickable square
-                let square_element = button(
-                    container(square_content)
-                        .width(Length::Fixed(square_size))
-                        .height(Length::Fixed(square_size))
-                        .style(iced::theme::Container::Custom(Box::new(ChessSquareStyle {
-                            color: square_color,
-                        }))),
-                )
-                .on_press(Message::SquareClicked(square))
-                .padding(0);
-
-                row_container = row_container.push(square_element);
-            }
-
-            board_container = board_container.push(row_container);
+            } else if is_dark {
+                colors.dark
+            } else {
+                colors.light
+            };
         }
 
+        let pieces = chess::ALL_SQUARES
+            .iter()
+            .filter_map(|&square| {
+                let piece = board.piece_on(square)?;
+                let color = board.color_on(square)?;
+                Some((square, self.piece_handles.get(piece, color).clone()))
+            })
+            .collect();
+
+        // Only the player's own pieces are previewed, on their turn
+        let my_turn = board.side_to_move() == player_color
+            && !thinking
+            && game_result.is_none()
+            && !is_view_mode
+            && pending_promotion.is_none();
+        let hover_moves = match my_turn && !self.touch {
+            true => snapshot.legal_moves.clone(),
+            false => Vec::new(),
+        };
+
         // Create the chess board
-        let board_view = container(board_container)
-            .width(Length::Fixed(board_size))
-            .height(Length::Fixed(board_size));
+        let board_view = BoardView::new(board_size, info.orientation, square_colors, pieces)
+            .slide(info.slide)
+            .hover_moves(hover_moves)
+            .cursor(info.board_cursor)
+            .touch(self.touch)
+            .arrows(analysis_arrows(&info.kibitzers, colors.analysis_arrow))
+            .arrows(info.expected_reply.map(|(from, to)| Arrow {
+                from,
+                to,
+                color: colors.reply_arrow,
+                weight: 1.0,
+            }))
+            .arrows(info.threats.iter().map(|&(from, to)| Arrow {
+                from,
+                to,
+                color: colors.threat_arrow,
+                weight: 1.0,
+            }))
+            .arrows(info.candidates.iter().zip(colors.candidate_arrows).map(|(candidate, color)| Arrow {
+                from: candidate.from,
+                to: candidate.to,
+                color,
+                weight: 1.0,
+            }));
+        let board_view: Element<'a, Message> = if info.coordinates {
+            with_coordinates(board_view.into(), board_size, info.orientation)
+        } else {
+            board_view.into()
+        };
 
         // Create status message
         let status = if is_view_mode {
-            format!("Viewing position after move {}", view_move_index)
-        } else if let Some(result) = game_result {
-            format!("Game over: {:?}", result)
+            fill(t.viewing_move, view_move_index)
+        } else if let Some(result) = snapshot.result_text(t) {
+            result
+        } else if snapshot.paused {
+            t.game_paused.to_string()
         } else if thinking {
-            "Engine is thinking...".to_string()
+            t.engine_thinking.to_string()
         } else {
-            format!(
-                "{} to move",
+            fill(
+                t.to_move,
                 if board.side_to_move() == Color::White {
-                    "White"
+                    t.white
                 } else {
-                    "Black"
-                }
+                    t.black
+                },
             )
         };
 
         // Create player info
-        let player_info = format!(
-            "You are playing as {}",
+        let player_info = fill(
+            t.playing_as,
             if player_color == Color::White {
-                "White"
+                t.white
             } else {
-                "Black"
-            }
+                t.black
+            },
         );
 
+        // An online game keeps the board until it is over and left
+        let offline = info.online.is_none();
+        // A rated game allows no takebacks, and no help until it is over
+        let assists = offline && (!snapshot.rated || game_result.is_some());
+
         // Create control buttons with icons and rounded style
         let reset_icon = svg(self.reset_icon.clone())
             .width(Length::Fixed(16.0))
             .height(Length::Fixed(16.0));
         let reset_button = button(
-            row![reset_icon, text("Reset")]
+            row![reset_icon, text(t.reset)]
                 .spacing(5)
                 .align_items(Alignment::Center),
         )
-        .on_press(Message::ResetGame)
-        .padding(10)
+        .on_press_maybe(offline.then_some(Message::ResetGame))
+        .padding(self.tap_padding(10))
         .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
 
         let undo_icon = svg(self.undo_icon.clone())
             .width(Length::Fixed(16.0))
             .height(Length::Fixed(16.0));
         let undo_button = button(
-            row![undo_icon, text("Undo")]
+            row![undo_icon, text(t.undo)]
                 .spacing(5)
                 .align_items(Alignment::Center),
         )
-        .on_press(Message::UndoMove)
-        .padding(10)
+        .on_press_maybe((offline && !snapshot.rated).then_some(Message::UndoMove))
+        .padding(self.tap_padding(10))
         .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
 
         let flip_button = button(
@@ -589,42 +781,262 @@ impl ChessUI {
                 svg(self.flip_icon.clone())
                     .width(Length::Fixed(16.0))
                     .height(Length::Fixed(16.0)),
-                text("Flip")
+                text(t.flip)
             ]
             .spacing(5)
             .align_items(Alignment::Center),
         )
-        .on_press(Message::FlipSide)
-        .padding(10)
+        .on_press_maybe(offline.then_some(Message::FlipSide))
+        .padding(self.tap_padding(10))
         .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
 
-        let setup_button = button(text("Setup Position"))
-            .on_press(Message::EnterSetupMode)
-            .padding(10)
+        let pause_button = button(text(if snapshot.paused { t.resume } else { t.pause }))
+            .on_press_maybe((offline && game_result.is_none()).then_some(Message::TogglePause))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                active: snapshot.paused,
+            })));
+
+        let flip_board_button = button(text(t.flip_board))
+            .on_press(Message::FlipBoard)
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                active: info.board_flipped,
+            })));
+
+        let new_game_button = button(text(t.new_game))
+            .on_press_maybe(offline.then_some(Message::OpenNewGame))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let setup_button = button(text(t.setup_position))
+            .on_press_maybe(assists.then_some(Message::EnterSetupMode))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let games_button = button(text(t.saved_games))
+            .on_press_maybe(offline.then_some(Message::OpenGameBrowser))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let session_button = button(text(t.session))
+            .on_press(Message::OpenSession)
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let save_button = button(text(t.save_game))
+            .on_press_maybe(offline.then_some(Message::SaveGameFile))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let repertoire_button = button(text(t.repertoire))
+            .on_press(Message::OpenRepertoire)
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let endgames_button = button(text(t.endgames))
+            .on_press(Message::OpenEndgames)
+            .padding(self.tap_padding(10))
             .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
 
-        // Create the layout: game controls on row 1, setup on row 2
-        let controls = column![
-            row![reset_button, undo_button, flip_button]
+        let coach_button = button(text(t.coach))
+            .on_press_maybe(assists.then_some(Message::ToggleCoachMode))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                active: snapshot.coach_mode,
+            })));
+
+        // The engine's opinion of the opponent's ideas would be outside help
+        // in an online game
+        let threats_button = button(text(t.threats))
+            .on_press_maybe(assists.then_some(Message::ToggleThreats))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                active: info.threats_shown,
+            })));
+
+        let candidates_button = button(text(t.candidates))
+            .on_press_maybe(assists.then_some(Message::ToggleCandidates))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                active: info.candidates_shown,
+            })));
+
+        let kibitzer_button = button(text(t.kibitzer))
+            .on_press_maybe(assists.then_some(Message::ToggleKibitzer))
+            .padding(self.tap_padding(10))
+            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                active: !info.kibitzers.is_empty(),
+            })));
+
+        // Create the layout: game controls on row 1, setup and archive on
+        // row 2, training tools on row 3
+        let mut controls = column![
+            row![reset_button, undo_button, pause_button, flip_button, flip_board_button]
                 .spacing(10)
                 .padding([10, 10, 0, 10])
                 .align_items(Alignment::Center),
-            row![setup_button]
-                .spacing(10)
-                .padding([4, 10, 4, 10])
+            row![new_game_button, setup_button, games_button, save_button, session_button]
+                .spacing(8)
+                .padding([4, 10, 0, 10])
                 .align_items(Alignment::Center),
+            row![
+                repertoire_button,
+                endgames_button,
+                coach_button,
+                candidates_button,
+                threats_button,
+                kibitzer_button
+            ]
+            .spacing(8)
+            .padding([4, 10, 4, 10])
+            .align_items(Alignment::Center),
         ]
         .align_items(Alignment::Center);
 
+        // Strength can change mid-game: the skill level, or a rating on
+        // engines with UCI_Elo
+        let mut strength_row = row![].spacing(10).padding([4, 10, 0, 10]).align_items(Alignment::Center);
+        if info.elo_range.is_some() {
+            strength_row = strength_row.push(
+                button(text(t.limit_elo).size(13))
+                    .on_press(Message::ToggleEloLimit)
+                    .padding(self.tap_padding([6, 10]))
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: info.elo.is_some(),
+                    }))),
+            );
+        }
+        strength_row = match (info.elo, info.elo_range) {
+            (Some(elo), Some((min, max))) => strength_row
+                .push(
+                    slider(min..=max, elo, Message::EloChanged)
+                        .on_release(Message::ApplyStrength)
+                        .step(10u32)
+                        .width(Length::Fill),
+                )
+                .push(text(elo).size(14)),
+            _ => strength_row
+                .push(text(t.skill).size(13))
+                .push(
+                    slider(1..=20, info.skill_level, Message::SkillLevelChanged)
+                        .on_release(Message::ApplyStrength)
+                        .width(Length::Fill),
+                )
+                .push(text(info.skill_level).size(14)),
+        };
+        // Longer think times from the command line stay reachable
+        let think_time = info.think_time.min(u32::MAX as u64) as u32;
+        controls = controls.push(strength_row).push(
+            row![
+                text(t.think).size(13),
+                slider(100..=think_time.max(10_000), think_time, |ms| {
+                    Message::ThinkTimeChanged(ms as u64)
+                })
+                .step(100u32)
+                .width(Length::Fill),
+                text(format!("{:.1} s", info.think_time as f64 / 1000.0)).size(14),
+            ]
+            .spacing(10)
+            .padding([4, 10, 0, 10])
+            .align_items(Alignment::Center),
+        );
+
+        controls = controls.push(
+            row![
+                button(text(t.settings).size(13))
+                    .on_press(Message::OpenSettings)
+                    .padding(self.tap_padding([6, 10]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.uci_console).size(13))
+                    .on_press(Message::ToggleConsole)
+                    .padding(self.tap_padding([6, 10]))
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: info.console.is_some(),
+                    }))),
+
+                button(text("Lichess").size(13))
+                    .on_press(Message::OpenLichessAnalysis)
+                    .padding(self.tap_padding([6, 10]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.online).size(13))
+                    .on_press(Message::OpenOnline)
+                    .padding(self.tap_padding([6, 10]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            ]
+            .spacing(10)
+            .padding([4, 10, 0, 10]),
+        );
+
+        let toggle = |label, active, message| {
+            button(text(label).size(12))
+                .on_press(message)
+                .padding(self.tap_padding([5, 8]))
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active })))
+        };
+        let export = |label, extension| {
+            button(text(label).size(12))
+                .on_press(Message::ExportImage(extension))
+                .padding(self.tap_padding([5, 8]))
+                .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)))
+        };
+        controls = controls.push(
+            row![
+                export(t.export_png, "png"),
+                export("SVG", "svg"),
+                button(text(t.board_text).size(12))
+                    .on_press(Message::CopyBoardText)
+                    .padding(self.tap_padding([5, 8]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                toggle(t.last_move, info.export_last_move, Message::ToggleExportLastMove),
+                toggle(t.coordinates_short, info.export_coordinates, Message::ToggleExportCoordinates),
+            ]
+            .spacing(6)
+            .padding([4, 10, 0, 10]),
+        );
+        controls = controls.push(
+            row![
+                button(text(t.scoresheet).size(12))
+                    .on_press(Message::ExportScoresheet)
+                    .padding(self.tap_padding([5, 8]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.export_pgn).size(12))
+                    .on_press(Message::ExportPgn)
+                    .padding(self.tap_padding([5, 8]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                toggle(t.figurines, info.export_figurine, Message::ToggleExportFigurine),
+            ]
+            .spacing(6)
+            .padding([4, 10, 0, 10]),
+        );
+
+        // A finished game that hasn't been analysed yet can be reviewed
+        if game_result.is_some() && !snapshot.reviewed && !info.reviewing {
+            controls = controls.push(
+                button(text(t.review_game))
+                    .on_press(Message::StartReview)
+                    .padding(self.tap_padding(10))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            );
+        }
+
         // Build move history display with table-like layout
         let mut move_history_column = column![];
         move_history_column = move_history_column.width(Length::Fill);
+        if info.move_table {
+            move_history_column = move_history_column.push(self.scoresheet_table(
+                move_records,
+                player_color,
+                is_view_mode.then_some(view_move_index),
+            ));
+        }
 
         // Fixed widths for columns: move number, white move, black move
         let move_num_width = 35.0;
         let move_btn_width = 100.0;
 
-        for record in move_records {
+        let mut ply = 0;
+        for record in move_records.iter().filter(|_| !info.move_table) {
             let mut move_row = row![];
             move_row = move_row.width(Length::Fill);
 
@@ -639,71 +1051,23 @@ impl ChessUI {
                 .center_y(),
             );
 
-            // White move button column (fixed width, with piece SVG)
-            let white_btn: Element<'_, Message> = if let Some(ref white_move) = record.white_move {
-                let white_index = record.move_num * 2 - 1;
-                let is_white_active = is_view_mode && view_move_index == white_index;
-
-                // Get piece SVG for this move
-                let handle = self.piece_handles.get(white_move.piece, Color::White);
-                let piece_content: Element<'_, Message> = row![
-                    svg(handle)
-                        .width(Length::Fixed(22.0))
-                        .height(Length::Fixed(22.0)),
-                    text(white_move.display_text.clone()).size(14)
-                ]
-                .spacing(6)
-                .align_items(Alignment::Center)
-                .into();
-
-                button(container(piece_content).width(Length::Fill).center_y())
-                    .on_press(Message::ViewMove(white_index))
-                    .padding([4, 8])
-                    .width(Length::Fixed(move_btn_width))
-                    .style(iced::theme::Button::Custom(Box::new(
-                        MoveHistoryButtonStyle {
-                            is_active: is_white_active,
-                        },
-                    )))
-                    .into()
-            } else {
-                // Empty placeholder to maintain alignment
-                Space::with_width(Length::Fixed(move_btn_width)).into()
-            };
-            move_row = move_row.push(white_btn);
-
-            // Black move button column (fixed width, with piece SVG)
-            let black_btn: Element<'_, Message> = if let Some(ref black_move) = record.black_move {
-                let black_index = record.move_num * 2;
-                let is_black_active = is_view_mode && view_move_index == black_index;
-
-                // Get piece SVG for this move
-                let handle = self.piece_handles.get(black_move.piece, Color::Black);
-                let piece_content: Element<'_, Message> = row![
-                    svg(handle)
-                        .width(Length::Fixed(22.0))
-                        .height(Length::Fixed(22.0)),
-                    text(black_move.display_text.clone()).size(14)
-                ]
-                .spacing(6)
-                .align_items(Alignment::Center)
-                .into();
-
-                button(container(piece_content).width(Length::Fill).center_y())
-                    .on_press(Message::ViewMove(black_index))
-                    .padding([4, 8])
-                    .width(Length::Fixed(move_btn_width))
-                    .style(iced::theme::Button::Custom(Box::new(
-                        MoveHistoryButtonStyle {
-                            is_active: is_black_active,
-                        },
-                    )))
-                    .into()
-            } else {
-                // Empty placeholder to maintain alignment
-                Space::with_width(Length::Fixed(move_btn_width)).into()
-            };
-            move_row = move_row.push(black_btn);
+            // White and black move button columns (fixed width, with piece SVG)
+            for (details, color) in [
+                (&record.white_move, Color::White),
+                (&record.black_move, Color::Black),
+            ] {
+                let move_btn: Element<'_, Message> = if let Some(details) = details {
+                    // Position index reached by this move
+                    ply += 1;
+                    let is_active = is_view_mode && view_move_index == ply;
+                    let show_review = color == player_color;
+                    self.history_move_button(details, color, ply, is_active, show_review, move_btn_width)
+                } else {
+                    // Empty placeholder to maintain alignment
+                    Space::with_width(Length::Fixed(move_btn_width)).into()
+                };
+                move_row = move_row.push(move_btn);
+            }
 
             move_history_column = move_history_column
                 .push(move_row.spacing(8).align_items(Alignment::Center))
@@ -717,25 +1081,154 @@ impl ChessUI {
             .width(Length::Fill)
             .style(iced::theme::Scrollable::Default);
 
-        // Create move history section
-        let move_history_section = container(
-            column![
-                text("Move History").size(18),
-                Space::with_height(Length::Fixed(10.0)),
-                move_history_scrollable,
+        let go_to_move = text_input(t.go_to_move, &info.go_to_move)
+            .on_input(Message::GoToMoveChanged)
+            .on_submit(Message::GoToMove)
+            .size(13)
+            .padding(4)
+            .width(Length::Fixed(80.0));
+        let mut move_history_content = column![
+            row![
+                text(t.move_history).size(18),
+                Space::with_width(Length::Fill),
+                button(text(t.move_table).size(12))
+                    .on_press(Message::ToggleMoveTable)
+                    .padding(self.tap_padding([4, 8]))
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: info.move_table,
+                    }))),
+                go_to_move
             ]
-            .spacing(5)
-            .padding(10),
-        )
+            .spacing(8)
+            .align_items(Alignment::Center),
+            text_input(t.find_position, &info.find_position)
+                .on_input(Message::FindPositionChanged)
+                .on_submit(Message::FindPosition)
+                .size(13)
+                .padding(4),
+        ]
+        .spacing(5)
+        .padding(10);
+        if info.position_not_found {
+            move_history_content = move_history_content
+                .push(text(t.position_not_found).size(13).style(IcedColor::from_rgb(1.0, 0.45, 0.45)));
+        }
+        move_history_content = move_history_content
+            .push(Space::with_height(Length::Fixed(10.0)))
+            .push(move_history_scrollable);
+
+        // The move being viewed can be given a glyph and a comment
+        let viewed = move_details(move_records, view_move_index).filter(|_| is_view_mode);
+        if let Some(details) = viewed {
+            let annotation = &details.annotation;
+            let mut glyphs = row![].spacing(4);
+            for (glyph, _) in MOVE_GLYPHS {
+                let active = annotation.glyph == Some(glyph);
+                glyphs = glyphs.push(
+                    button(text(glyph).size(13))
+                        .on_press(Message::SetMoveGlyph((!active).then_some(glyph)))
+                        .padding(self.tap_padding([4, 8]))
+                        .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active }))),
+                );
+            }
+            move_history_content = move_history_content.push(glyphs).push(
+                text_input(t.move_comment_placeholder, annotation.comment.as_deref().unwrap_or(""))
+                    .on_input(Message::MoveCommentChanged)
+                    .size(13)
+                    .padding(6),
+            );
+            // Cutting the game short is a takeback, so not while the engine
+            // thinks or in a rated game
+            if !snapshot.thinking && !snapshot.rated && info.online.is_none() {
+                let confirm = info.delete_from == Some(view_move_index);
+                move_history_content = move_history_content.push(match confirm {
+                    false => row![button(text(t.delete_from_here).size(12))
+                        .on_press(Message::DeleteFrom(view_move_index))
+                        .padding(self.tap_padding([4, 8]))
+                        .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)))],
+                    true => row![
+                        text(t.delete_from_here_confirm).size(13),
+                        button(text(t.delete).size(12))
+                            .on_press(Message::ConfirmDelete)
+                            .padding(self.tap_padding([4, 8]))
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                        button(text(t.cancel).size(12))
+                            .on_press(Message::CancelDelete)
+                            .padding(self.tap_padding([4, 8]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                    ]
+                    .spacing(6)
+                    .align_items(Alignment::Center),
+                });
+            }
+        }
+
+        // Side lines of an imported game; the ones the board is on are lit
+        if let Some(ref tree) = snapshot.variations {
+            let on_main_line = snapshot.line_nodes == tree.line_through(variation::ROOT);
+            let mut lines = column![row![
+                text(t.variations).size(14),
+                Space::with_width(Length::Fill),
+                button(text(t.export_line).size(12))
+                    .on_press(Message::ExportLine)
+                    .padding(self.tap_padding([4, 8]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.main_line).size(12))
+                    .on_press(Message::FollowVariation(variation::ROOT))
+                    .padding(self.tap_padding([4, 8]))
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: on_main_line,
+                    }))),
+            ]
+            .align_items(Alignment::Center)]
+            .spacing(3);
+            // Arrows to reorder the lines, named in a tooltip where there's
+            // a mouse to hover with
+            let reorder = |label, tip, message| -> Element<'_, Message> {
+                let arrow = button(text(label).size(12))
+                    .on_press(message)
+                    .padding(self.tap_padding([3, 6]))
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+                match self.touch {
+                    true => arrow.into(),
+                    false => tooltip(arrow, tip, tooltip::Position::Top)
+                        .size(12)
+                        .padding(6)
+                        .style(iced::theme::Container::Box)
+                        .into(),
+                }
+            };
+            for line in tree.variations() {
+                let active = snapshot.line_nodes.contains(&line.start);
+                lines = lines.push(
+                    row![
+                        Space::with_width(Length::Fixed(12.0 * line.depth as f32)),
+                        button(text(&line.text).size(12))
+                            .on_press(Message::FollowVariation(line.start))
+                            .padding(self.tap_padding([3, 6]))
+                            .width(Length::Fill)
+                            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active }))),
+                        reorder("▲", t.promote_variation, Message::PromoteVariation(line.start)),
+                        reorder("▼", t.demote_variation, Message::DemoteVariation(line.start)),
+                    ]
+                    .spacing(3),
+                );
+            }
+            move_history_content = move_history_content
+                .push(Scrollable::new(lines).height(Length::Fixed(140.0)).width(Length::Fill));
+        }
+
+        // Create move history section
+        let move_history_section = container(move_history_content)
         .width(Length::Fill)
         .style(iced::theme::Container::Box);
 
         // Exit view mode button if in view mode
         let exit_view_button = if is_view_mode {
             Some(
-                button(text("Exit View Mode").size(14))
+                button(text(t.exit_view_mode).size(14))
                     .on_press(Message::ExitViewMode)
-                    .padding([8, 16])
+                    .padding(self.tap_padding([8, 16]))
                     .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
             )
         } else {
@@ -743,60 +1236,376 @@ impl ChessUI {
         };
 
         // Build info panel
-        let mut info_panel_content = column![
-            text(player_info).size(20),
-            text(status).size(16),
-            text(message).size(14),
-            Space::with_height(Length::Fixed(20.0)),
-            controls,
-            Space::with_height(Length::Fixed(20.0)),
-            move_history_section,
-        ]
-        .spacing(10)
-        .padding(20)
-        .align_items(Alignment::Center);
-
-        // Add exit view button if in view mode
-        if let Some(btn) = exit_view_button {
-            info_panel_content = info_panel_content.push(Space::with_height(Length::Fixed(10.0)));
-            info_panel_content = info_panel_content.push(btn);
-        }
-
-        let info_panel = container(info_panel_content)
-            .width(Length::Fixed(320.0))
-            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle)));
-
-        // When a promotion is pending, replace the side panel with the
-        // promotion picker so it is always fully visible.
-        let right_panel: Element<'_, Message> = if pending_promotion.is_some() {
-            let btn = |piece: Piece, msg: PromotionPiece| {
-                button(
-                    svg(self.piece_handles.get(piece, player_color))
-                        .width(Length::Fixed(56.0))
-                        .height(Length::Fixed(56.0)),
-                )
-                .on_press(Message::PromotePawn(msg))
-                .padding(8)
-                .style(iced::theme::Button::Custom(Box::new(PromotionButtonStyle)))
-            };
+        let mut info_panel_content = column![];
 
-            container(
+        // Without a working engine nothing will reply: say why, and offer
+        // to try again or start a different engine
+        if let Some(ref failure) = info.engine_failure {
+            let failure_banner = container(
                 column![
-                    Space::with_height(Length::Fill),
-                    text("Promote to:").size(20),
-                    Space::with_height(Length::Fixed(16.0)),
+                    text(fill(t.engine_failed, &failure.error))
+                        .size(14)
+                        .style(IcedColor::from_rgb(1.0, 0.45, 0.45)),
+                    text_input(t.engine_path_placeholder, &failure.path)
+                        .on_input(Message::EnginePathChanged)
+                        .on_submit(Message::UseEnginePath)
+                        .size(13)
+                        .padding(6),
                     row![
-                        btn(Piece::Queen,  PromotionPiece::Queen),
-                        btn(Piece::Rook,   PromotionPiece::Rook),
-                        btn(Piece::Bishop, PromotionPiece::Bishop),
-                        btn(Piece::Knight, PromotionPiece::Knight),
+                        button(text(t.retry).size(13))
+                            .on_press(Message::RetryEngine)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                        button(text(t.use_this_engine).size(13))
+                            .on_press(Message::UseEnginePath)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
                     ]
-                    .spacing(12),
-                    Space::with_height(Length::Fill),
+                    .spacing(10),
                 ]
-                .align_items(Alignment::Center)
-                .width(Length::Fill),
-            )
+                .spacing(6)
+                .align_items(Alignment::Center),
+            )
+            .padding(8)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(failure_banner);
+        }
+
+        // A hung engine would otherwise leave "Engine is thinking..." up
+        // for good
+        if let Some(seconds) = info.engine_overrun {
+            let stuck_banner = container(
+                column![
+                    text(fill(t.engine_overrun, seconds))
+                        .size(14)
+                        .style(IcedColor::from_rgb(1.0, 0.45, 0.45)),
+                    row![
+                        button(text(t.stop_search).size(13))
+                            .on_press(Message::StopEngineSearch)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                        button(text(t.restart_engine).size(13))
+                            .on_press(Message::RestartEngine)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(6),
+            )
+            .padding(8)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(stuck_banner);
+        }
+
+        // Engines report missing network files or rejected options on
+        // stderr, which would otherwise go unseen
+        if let Some(ref warning) = info.engine_stderr {
+            let stderr_banner = container(
+                column![
+                    text(fill(t.engine_says, warning))
+                        .size(14)
+                        .style(IcedColor::from_rgb(1.0, 0.65, 0.3)),
+                    row![
+                        button(text(t.console).size(13))
+                            .on_press(Message::ToggleConsole)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                        button(text(t.dismiss).size(13))
+                            .on_press(Message::DismissEngineStderr)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(6),
+            )
+            .padding(8)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(stderr_banner);
+        }
+
+        // Offer to pick up where the previous session left off
+        if let Some(move_count) = info.resume_offer {
+            let resume_banner = container(
+                column![
+                    text(fill(t.unfinished_game, move_count)).size(14),
+                    row![
+                        button(text(t.resume).size(13))
+                            .on_press(Message::ResumeSavedGame)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                        button(text(t.discard).size(13))
+                            .on_press(Message::DiscardSavedGame)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(6)
+                .align_items(Alignment::Center),
+            )
+            .padding(8)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(resume_banner);
+        }
+
+        // The finished game is in the database already
+        if let Some(ref played_at) = info.duplicate {
+            let duplicate_banner = container(
+                column![
+                    text(fill(t.duplicate_game, played_at)).size(14),
+                    row![
+                        button(text(t.skip).size(13))
+                            .on_press(Message::SkipDuplicate)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                        button(text(t.replace).size(13))
+                            .on_press(Message::ReplaceDuplicate)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(6)
+                .align_items(Alignment::Center),
+            )
+            .padding(8)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(duplicate_banner);
+        }
+
+        let opponent = match info.online {
+            Some(ref online) => format!("Lichess: {}", online.opponent),
+            None => fill(t.engine_opponent, &info.engine),
+        };
+        info_panel_content = info_panel_content
+            .push(text(player_info).size(20))
+            .push(text(opponent).size(14).style(IcedColor::from_rgb(0.7, 0.7, 0.7)));
+        if let Some(ref clocks) = info.clocks {
+            info_panel_content = info_panel_content.push(clock_row(clocks, t));
+        }
+        // The opponent's winnings above the player's, as around the board
+        let balance = material_balance(&board);
+        info_panel_content = info_panel_content
+            .push(self.captured_row(&board, !player_color, balance))
+            .push(self.captured_row(&board, player_color, balance))
+            .push(text(status).size(16));
+        if let Some(ref announcement) = info.announcement {
+            info_panel_content = info_panel_content.push(text(announcement).size(14));
+        }
+        // Review verdicts are tooltips on the move list, out of reach of a finger
+        let viewed_review = move_details(&snapshot.move_records, view_move_index)
+            .filter(|_| self.touch && is_view_mode && board.side_to_move() != player_color)
+            .and_then(|details| details.review.as_ref());
+        if let Some(review) = viewed_review {
+            info_panel_content = info_panel_content.push(
+                text(review_tip(review, t))
+                    .size(14)
+                    .style(Self::review_color(review.class)),
+            );
+        }
+        if let Some(square) = info.board_cursor {
+            let contents = match (board.piece_on(square), board.color_on(square)) {
+                (Some(piece), Some(color)) => {
+                    let side = if color == Color::White { t.white } else { t.black };
+                    format!("{} ({})", crate::piece_name(t, piece), side)
+                }
+                _ => t.empty_square.to_string(),
+            };
+            info_panel_content = info_panel_content.push(
+                text(format!("{}: {}", square, contents))
+                    .size(14)
+                    .style(IcedColor::from_rgb(0.55, 0.75, 1.0)),
+            );
+        }
+
+        if let Some(ref opening) = snapshot.opening {
+            info_panel_content = info_panel_content.push(
+                text(opening.display())
+                    .size(14)
+                    .style(IcedColor::from_rgb(0.75, 0.75, 0.6)),
+            );
+        }
+
+        if !info.candidates.is_empty() {
+            let mut list = row![].spacing(14);
+            for (candidate, color) in info.candidates.iter().zip(colors.candidate_arrows) {
+                list = list.push(
+                    text(format!("{} {}", candidate.san, candidate.eval))
+                        .size(14)
+                        .style(color)
+                        .font(iced::Font::MONOSPACE),
+                );
+            }
+            info_panel_content = info_panel_content.push(list);
+        }
+
+        if !info.kibitzers.is_empty() {
+            info_panel_content = info_panel_content.push(kibitzer_panels(&info.kibitzers, t));
+        }
+
+        if let Some(ref online) = info.online {
+            info_panel_content = info_panel_content.push(online_banner(online, t));
+        }
+
+        if let Some(ref drill) = info.drill {
+            let stop_button = button(text(t.stop).size(13))
+                .on_press(Message::StopDrill)
+                .padding(self.tap_padding([6, 12]))
+                .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle)));
+            let banner_row = match drill {
+                DrillInfo::Repertoire { color, due } => row![
+                    text(fill(
+                        &fill(
+                            t.repertoire_training,
+                            if *color == Color::White { t.white } else { t.black },
+                        ),
+                        due
+                    ))
+                    .size(14),
+                    Space::with_width(Length::Fill),
+                    stop_button,
+                ],
+                DrillInfo::Puzzle { solved } => row![
+                    text(if *solved { t.puzzle_solved } else { t.mistakes_puzzle }).size(14),
+                    Space::with_width(Length::Fill),
+                    button(text(if *solved { t.next } else { t.skip }).size(13))
+                        .on_press(Message::NextPuzzle)
+                        .padding(self.tap_padding([6, 12]))
+                        .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                    stop_button,
+                ],
+                DrillInfo::Endgame { name, outcome } => {
+                    let (label, color) = match outcome {
+                        Some(EndgameOutcome::Converted(_)) => {
+                            (fill(t.endgame_success, name), IcedColor::from_rgb(0.45, 0.85, 0.45))
+                        }
+                        Some(EndgameOutcome::Botched(_)) => {
+                            (fill(t.endgame_failure, name), IcedColor::from_rgb(1.0, 0.45, 0.45))
+                        }
+                        None => (fill(t.endgame_drill, name), IcedColor::WHITE),
+                    };
+                    row![
+                        text(label).size(14).style(color),
+                        Space::with_width(Length::Fill),
+                        button(text(t.menu).size(13))
+                            .on_press(Message::OpenEndgames)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                        stop_button,
+                    ]
+                }
+            };
+            let drill_banner = container(banner_row.spacing(10).align_items(Alignment::Center))
+                .padding(8)
+                .width(Length::Fill)
+                .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(drill_banner);
+        }
+
+        // The coach objected to the chosen move: play it anyway or take it back
+        if let Some(warning) = snapshot
+            .pending_confirmation
+            .as_ref()
+            .and_then(|pending| pending.warning.as_deref())
+        {
+            let coach_banner = container(
+                column![
+                    text(fill(t.play_anyway_question, warning)).size(15),
+                    row![
+                        button(text(t.play_anyway).size(13))
+                            .on_press(Message::ConfirmMove)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                        button(text(t.take_back).size(13))
+                            .on_press(Message::CancelMove)
+                            .padding(self.tap_padding([6, 12]))
+                            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(6)
+                .align_items(Alignment::Center),
+            )
+            .padding(8)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Box);
+            info_panel_content = info_panel_content.push(coach_banner);
+        }
+
+        // Reviewed games show how accurately each side played
+        if let Some(accuracy) = snapshot.accuracy.filter(|_| game_result.is_some()) {
+            let percent = |value: Option<f64>| match value {
+                Some(value) => format!("{:.1}%", value),
+                None => "-".to_string(),
+            };
+            info_panel_content = info_panel_content.push(
+                text(fill(&fill(t.accuracy, percent(accuracy.white)), percent(accuracy.black)))
+                .size(16),
+            );
+        }
+
+        info_panel_content = info_panel_content
+            .push(Text::new(snapshot.message.as_str()).size(14))
+            .push(Space::with_height(Length::Fixed(20.0)))
+            .push(controls)
+            .push(Space::with_height(Length::Fixed(20.0)))
+            .push(move_history_section)
+            .spacing(10)
+            .padding(20)
+            .align_items(Alignment::Center);
+
+        // Add exit view button if in view mode
+        if let Some(btn) = exit_view_button {
+            info_panel_content = info_panel_content.push(Space::with_height(Length::Fixed(10.0)));
+            info_panel_content = info_panel_content.push(btn);
+        }
+
+        let info_panel = container(info_panel_content)
+            .width(Length::Fixed(320.0))
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle)));
+
+        // When a promotion is pending, replace the side panel with the
+        // promotion picker so it is always fully visible.
+        let right_panel: Element<'_, Message> = if pending_promotion.is_some() {
+            let btn = |piece: Piece, msg: PromotionPiece| {
+                button(
+                    svg(self.piece_handles.get(piece, player_color).clone())
+                        .width(Length::Fixed(56.0))
+                        .height(Length::Fixed(56.0)),
+                )
+                .on_press(Message::PromotePawn(msg))
+                .padding(8)
+                .style(iced::theme::Button::Custom(Box::new(PromotionButtonStyle)))
+            };
+
+            container(
+                column![
+                    Space::with_height(Length::Fill),
+                    text(t.promote_to).size(20),
+                    Space::with_height(Length::Fixed(16.0)),
+                    row![
+                        btn(Piece::Queen,  PromotionPiece::Queen),
+                        btn(Piece::Rook,   PromotionPiece::Rook),
+                        btn(Piece::Bishop, PromotionPiece::Bishop),
+                        btn(Piece::Knight, PromotionPiece::Knight),
+                    ]
+                    .spacing(12),
+                    Space::with_height(Length::Fixed(12.0)),
+                    text(t.promotion_keys)
+                        .size(13)
+                        .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    Space::with_height(Length::Fill),
+                ]
+                .align_items(Alignment::Center)
+                .width(Length::Fill),
+            )
             .width(Length::Fixed(320.0))
             .height(Length::Fill)
             .style(iced::theme::Container::Custom(Box::new(SidePanelStyle)))
@@ -805,22 +1614,266 @@ impl ChessUI {
             info_panel.into()
         };
 
-        // Combine board and side panel
-        let content = {
-            container(
-                row![board_view, right_panel]
-                    .spacing(20)
-                    .padding([10, 20])
-                    .align_items(Alignment::Center),
-            )
+        // Combine board and side panel, with the console underneath
+        let mut content = column![row![board_view, right_panel]
+            .spacing(20)
+            .padding([10, 20])
+            .align_items(Alignment::Center)]
+        .align_items(Alignment::Center);
+        if let Some(ref console) = info.console {
+            content = content.push(self.console_panel(console));
+        }
+
+        container(content)
             .width(Length::Fill)
             .height(Length::Fill)
             .center_x()
             .center_y()
             .into()
+    }
+
+    // Raw engine traffic, newest at the bottom: ">" lines were sent to the
+    // engine, "<" lines came back from it and "!" lines are its stderr
+    fn console_panel<'a>(&self, console: &ConsoleInfo) -> Element<'a, Message> {
+        let t = self.strings;
+        let mut lines = column![].width(Length::Fill);
+        for line in &console.lines {
+            let (marker, color) = match line.direction {
+                Direction::ToEngine => (">", IcedColor::from_rgb(0.55, 0.8, 1.0)),
+                Direction::FromEngine => ("<", IcedColor::from_rgb(0.8, 0.8, 0.8)),
+                Direction::Stderr => ("!", IcedColor::from_rgb(1.0, 0.65, 0.3)),
+            };
+            lines = lines.push(
+                text(format!("{} {}", marker, line.text))
+                    .size(12)
+                    .font(iced::Font::MONOSPACE)
+                    .style(color),
+            );
+        }
+
+        let log = Scrollable::new(lines)
+            .id(iced::widget::scrollable::Id::new("uci_console"))
+            .height(Length::Fill)
+            .width(Length::Fill);
+
+        container(
+            column![
+                row![
+                    text(t.uci_console).size(16),
+                    text_input(t.console_filter, &console.filter)
+                        .on_input(Message::ConsoleFilterChanged)
+                        .size(13)
+                        .padding(6),
+                ]
+                .spacing(12)
+                .align_items(Alignment::Center),
+                log,
+                row![
+                    text_input(t.console_command_placeholder, &console.command)
+                        .on_input(Message::ConsoleCommandChanged)
+                        .on_submit(Message::SendConsoleCommand)
+                        .font(iced::Font::MONOSPACE)
+                        .size(13)
+                        .padding(6),
+                    button(text(t.send).size(13))
+                        .on_press(Message::SendConsoleCommand)
+                        .padding(self.tap_padding([6, 12]))
+                        .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            ]
+            .spacing(6)
+            .padding(10),
+        )
+        .width(Length::Fill)
+        .height(Length::Fixed(CONSOLE_HEIGHT))
+        .style(iced::theme::Container::Box)
+        .into()
+    }
+
+    // One entry in the move history: piece icon, SAN text, the time the
+    // move took and, once the game has been reviewed, a badge with the
+    // verdict in a tooltip
+    fn history_move_button<'a>(
+        &'a self,
+        details: &'a MoveDetails,
+        color: Color,
+        ply: usize,
+        is_active: bool,
+        show_review: bool,
+        width: f32,
+    ) -> Element<'a, Message> {
+        let handle = self.piece_handles.get(details.piece, color).clone();
+        let glyph = details.annotation.glyph.unwrap_or("");
+        let mut piece_content = row![
+            svg(handle)
+                .width(Length::Fixed(22.0))
+                .height(Length::Fixed(22.0)),
+            Text::new(format!("{}{}", details.display_text, glyph)).size(14)
+        ]
+        .spacing(6)
+        .align_items(Alignment::Center);
+
+        let review = details.review.as_ref().filter(|_| show_review);
+        if let Some(review) = review {
+            piece_content = piece_content.push(
+                text(review.class.symbol())
+                    .size(14)
+                    .style(Self::review_color(review.class)),
+            );
+        }
+        // How long the move took, lined up at the right edge
+        if let Some(spent) = details.time_spent {
+            piece_content = piece_content.push(Space::with_width(Length::Fill)).push(
+                text(display_move_time(spent))
+                    .size(11)
+                    .style(IcedColor::from_rgb(0.6, 0.6, 0.6)),
+            );
+        }
+
+        let move_button = button(container(piece_content).width(Length::Fill).center_y())
+            .on_press(Message::ViewMove(ply))
+            .padding(self.tap_padding([4, 8]))
+            .width(Length::Fixed(width))
+            .style(iced::theme::Button::Custom(Box::new(MoveHistoryButtonStyle {
+                is_active,
+            })));
+
+        match review {
+            // With nothing to hover in touch mode, the side panel shows the
+            // verdict of the move being viewed instead
+            Some(review) if !self.touch => {
+                tooltip(move_button, review_tip(review, self.strings), tooltip::Position::Top)
+                    .size(12)
+                    .padding(6)
+                    .style(iced::theme::Container::Box)
+                    .into()
+            }
+            _ => move_button.into(),
+        }
+    }
+
+    /// The moves as a scoresheet: number, White's and Black's moves in
+    /// SAN, and once the game is reviewed the evaluation after each row
+    /// and the accuracy of the player's move in it.
+    fn scoresheet_table<'a>(
+        &'a self,
+        records: &'a [MoveRecord],
+        player_color: Color,
+        viewed: Option<usize>,
+    ) -> Element<'a, Message> {
+        let t = self.strings;
+        let grey = IcedColor::from_rgb(0.6, 0.6, 0.6);
+        let reviewed = records
+            .iter()
+            .flat_map(|r| [&r.white_move, &r.black_move])
+            .flatten()
+            .any(|details| details.review.is_some());
+        let cell = |content: Element<'a, Message>, width: f32| {
+            container(content).width(Length::Fixed(width)).center_y()
         };
 
-        content
+        let mut header = row![
+            cell(text("#").size(13).style(grey).into(), 35.0),
+            cell(text(t.white).size(13).style(grey).into(), 90.0),
+            cell(text(t.black).size(13).style(grey).into(), 90.0),
+        ]
+        .spacing(6);
+        if reviewed {
+            header = header
+                .push(cell(text(t.eval).size(13).style(grey).into(), 55.0))
+                .push(cell(text(t.accuracy_short).size(13).style(grey).into(), 45.0));
+        }
+        let mut table = column![header].spacing(2);
+
+        let mut ply = 0;
+        for record in records {
+            let mut table_row = row![cell(text(format!("{}.", record.move_num)).size(14).into(), 35.0)]
+                .spacing(6)
+                .align_items(Alignment::Center);
+            for (details, color) in [
+                (&record.white_move, Color::White),
+                (&record.black_move, Color::Black),
+            ] {
+                let Some(details) = details else {
+                    table_row = table_row.push(Space::with_width(Length::Fixed(90.0)));
+                    continue;
+                };
+                ply += 1;
+                let mut san = row![Text::new(format!(
+                    "{}{}",
+                    details.notation,
+                    details.annotation.glyph.unwrap_or("")
+                ))
+                .size(14)];
+                if let Some(review) = details.review.as_ref().filter(|_| color == player_color) {
+                    let symbol = text(review.class.symbol()).size(14);
+                    san = san.push(symbol.style(Self::review_color(review.class)));
+                }
+                table_row = table_row.push(
+                    button(san)
+                        .on_press(Message::ViewMove(ply))
+                        .padding(self.tap_padding([2, 6]))
+                        .width(Length::Fixed(90.0))
+                        .style(iced::theme::Button::Custom(Box::new(MoveHistoryButtonStyle {
+                            is_active: viewed == Some(ply),
+                        }))),
+                );
+            }
+
+            if reviewed {
+                let last = record.black_move.as_ref().or(record.white_move.as_ref());
+                let eval = last
+                    .and_then(|details| details.review.as_ref())
+                    .map(|review| analysis::format_eval(review.eval_after))
+                    .unwrap_or_default();
+                let own = match player_color {
+                    Color::White => record.white_move.as_ref(),
+                    Color::Black => record.black_move.as_ref(),
+                };
+                let accuracy = own
+                    .and_then(|details| details.review.as_ref())
+                    .map(|review| format!("{:.0}", review.accuracy(player_color)))
+                    .unwrap_or_default();
+                table_row = table_row
+                    .push(cell(text(eval).size(13).font(iced::Font::MONOSPACE).into(), 55.0))
+                    .push(cell(text(accuracy).size(13).style(grey).into(), 45.0));
+            }
+            table = table.push(table_row);
+        }
+        table.into()
+    }
+
+    /// The pieces `side` has taken, and its material lead if it has one.
+    fn captured_row(&self, board: &chess::Board, side: Color, balance: i32) -> Element<'_, Message> {
+        let lead = if side == Color::White { balance } else { -balance };
+        let mut tray = row![].spacing(1).align_items(Alignment::Center);
+        for piece in captured_pieces(board, !side) {
+            tray = tray.push(
+                svg(self.piece_handles.get(piece, !side).clone())
+                    .width(Length::Fixed(18.0))
+                    .height(Length::Fixed(18.0)),
+            );
+        }
+        if lead > 0 {
+            tray = tray.push(
+                text(format!(" +{}", lead))
+                    .size(14)
+                    .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+            );
+        }
+        container(tray).height(Length::Fixed(20.0)).into()
+    }
+
+    fn review_color(class: MoveClass) -> IcedColor {
+        match class {
+            MoveClass::Best => IcedColor::from_rgb(0.45, 0.85, 0.45),
+            MoveClass::Good => IcedColor::from_rgb(0.8, 0.8, 0.8),
+            MoveClass::Inaccuracy => IcedColor::from_rgb(0.95, 0.85, 0.3),
+            MoveClass::Mistake => IcedColor::from_rgb(1.0, 0.6, 0.2),
+            MoveClass::Blunder => IcedColor::from_rgb(1.0, 0.3, 0.3),
+        }
     }
 
     pub fn view_setup<'a>(
@@ -829,6 +1882,7 @@ impl ChessUI {
         window_width: u32,
         window_height: u32,
     ) -> Element<'a, Message> {
+        let t = self.strings;
         // Calculate board size
         let available_height = window_height as f32 * 0.82;
         let available_width = window_width as f32 * 0.55;
@@ -864,11 +1918,11 @@ impl ChessUI {
                     File::from_index(board_file),
                 );
                 let is_dark = (board_rank + board_file) % 2 == 1;
-                let square_color = if is_dark { DARK_SQUARE } else { LIGHT_SQUARE };
+                let square_color = if is_dark { self.colors.dark } else { self.colors.light };
 
                 let mut square_content = column![];
                 if let Some((piece, color)) = state.pieces.get(&sq) {
-                    let handle = self.piece_handles.get(*piece, *color);
+                    let handle = self.piece_handles.get(*piece, *color).clone();
                     square_content = column![
                         Space::with_height(Length::Fixed(square_size * 0.1)),
                         svg(handle)
@@ -925,10 +1979,10 @@ impl ChessUI {
 
         let mut palette_col = column![
             row![
-                container(text("White").size(11))
+                container(text(t.white).size(11))
                     .width(Length::Fixed(palette_size + 10.0))
                     .center_x(),
-                container(text("Black").size(11))
+                container(text(t.black).size(11))
                     .width(Length::Fixed(palette_size + 10.0))
                     .center_x(),
             ]
@@ -941,7 +1995,7 @@ impl ChessUI {
             let is_b = state.selected_palette == Some((*piece, Color::Black));
 
             let w_btn = button(
-                svg(self.piece_handles.get(*piece, Color::White))
+                svg(self.piece_handles.get(*piece, Color::White).clone())
                     .width(Length::Fixed(palette_size))
                     .height(Length::Fixed(palette_size)),
             )
@@ -950,7 +2004,7 @@ impl ChessUI {
             .style(iced::theme::Button::Custom(Box::new(PaletteButtonStyle { selected: is_w })));
 
             let b_btn = button(
-                svg(self.piece_handles.get(*piece, Color::Black))
+                svg(self.piece_handles.get(*piece, Color::Black).clone())
                     .width(Length::Fixed(palette_size))
                     .height(Length::Fixed(palette_size)),
             )
@@ -962,7 +2016,7 @@ impl ChessUI {
         }
 
         let is_eraser = state.selected_palette.is_none();
-        let eraser_btn = button(text("Eraser").size(12))
+        let eraser_btn = button(text(t.eraser).size(12))
             .on_press(Message::SetupPaletteSelected(None))
             .padding([4, 12])
             .style(iced::theme::Button::Custom(Box::new(PaletteButtonStyle { selected: is_eraser })));
@@ -972,15 +2026,15 @@ impl ChessUI {
 
         // ── Side to Move ──────────────────────────────────────────────────
         let stm_section = column![
-            text("Side to move:").size(13),
+            text(t.side_to_move).size(13),
             row![
-                button(text("White").size(12))
+                button(text(t.white).size(12))
                     .on_press(Message::SetupSideToMove(Color::White))
                     .padding([4, 8])
                     .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
                         active: state.side_to_move == Color::White,
                     }))),
-                button(text("Black").size(12))
+                button(text(t.black).size(12))
                     .on_press(Message::SetupSideToMove(Color::Black))
                     .padding([4, 8])
                     .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
@@ -993,9 +2047,9 @@ impl ChessUI {
 
         // ── Castling ──────────────────────────────────────────────────────
         let castling_section = column![
-            text("Castling:").size(13),
+            text(t.castling).size(13),
             row![
-                text("W:").size(11),
+                text(t.castling_white).size(11),
                 button(text("K").size(12))
                     .on_press(Message::SetupCastlingToggle(1))
                     .padding([4, 7])
@@ -1005,7 +2059,7 @@ impl ChessUI {
                     .padding([4, 7])
                     .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active: state.castle_wq }))),
                 Space::with_width(Length::Fixed(4.0)),
-                text("B:").size(11),
+                text(t.castling_black).size(11),
                 button(text("k").size(12))
                     .on_press(Message::SetupCastlingToggle(4))
                     .padding([4, 7])
@@ -1017,6 +2071,10 @@ impl ChessUI {
             ]
             .spacing(4)
             .align_items(Alignment::Center),
+            button(text(t.chess960_rules).size(11))
+                .on_press(Message::SetupChess960Toggle)
+                .padding([3, 6])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active: state.chess960 }))),
         ]
         .spacing(4);
 
@@ -1040,22 +2098,48 @@ impl ChessUI {
             ep_row = ep_row.push(ep_btn);
         }
         let ep_section = column![
-            text("En passant:").size(13),
+            text(t.en_passant).size(13),
             ep_row,
         ]
         .spacing(4);
 
+        // ── Move counters ─────────────────────────────────────────────────
+        let counter = |value: String, on_input: fn(String) -> Message| {
+            text_input("", &value)
+                .on_input(on_input)
+                .size(12)
+                .padding([3, 5])
+                .width(Length::Fixed(48.0))
+        };
+        let fullmove = match state.fullmove_number {
+            0 => String::new(),
+            n => n.to_string(),
+        };
+        let counters_section = column![
+            row![
+                text(t.halfmove_clock).size(12).width(Length::Fill),
+                counter(state.halfmove_clock.to_string(), Message::SetupHalfmoveClock),
+            ]
+            .align_items(Alignment::Center),
+            row![
+                text(t.move_number).size(12).width(Length::Fill),
+                counter(fullmove, Message::SetupFullmoveNumber),
+            ]
+            .align_items(Alignment::Center),
+        ]
+        .spacing(4);
+
         // ── You play as ───────────────────────────────────────────────────
         let you_play_section = column![
-            text("You play as:").size(13),
+            text(t.you_play_as).size(13),
             row![
-                button(text("White").size(12))
+                button(text(t.white).size(12))
                     .on_press(Message::SetupPlayerColor(Color::White))
                     .padding([4, 8])
                     .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
                         active: state.player_color == Color::White,
                     }))),
-                button(text("Black").size(12))
+                button(text(t.black).size(12))
                     .on_press(Message::SetupPlayerColor(Color::Black))
                     .padding([4, 8])
                     .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
@@ -1069,9 +2153,10 @@ impl ChessUI {
         // ── Side Panel Assembly ───────────────────────────────────────────
         let side_panel = container(
             column![
-                text("Piece Palette").size(15),
+                text(t.piece_palette).size(15),
                 Space::with_height(Length::Fixed(6.0)),
                 palette_col,
+                text(t.setup_keys).size(11).style(IcedColor::from_rgb(0.6, 0.6, 0.6)),
                 Space::with_height(Length::Fixed(10.0)),
                 stm_section,
                 Space::with_height(Length::Fixed(8.0)),
@@ -1079,6 +2164,8 @@ impl ChessUI {
                 Space::with_height(Length::Fixed(8.0)),
                 ep_section,
                 Space::with_height(Length::Fixed(8.0)),
+                counters_section,
+                Space::with_height(Length::Fixed(8.0)),
                 you_play_section,
             ]
             .spacing(2)
@@ -1088,14 +2175,14 @@ impl ChessUI {
         .style(iced::theme::Container::Custom(Box::new(SidePanelStyle)));
 
         // ── FEN Input ─────────────────────────────────────────────────────
-        let fen_input = text_input("FEN string...", &state.fen_string)
-            .on_input(|s| Message::SetupFenChanged(s))
+        let fen_input = text_input(t.fen_placeholder, &state.fen_string)
+            .on_input(Message::SetupFenChanged)
             .padding(7)
             .size(13)
             .width(Length::Fill);
 
         let fen_error_el: Element<'_, Message> = if let Some(ref err) = state.fen_error {
-            text(format!("Error: {}", err))
+            text(fill(t.fen_error, err))
                 .size(12)
                 .style(IcedColor::from_rgb(1.0, 0.35, 0.35))
                 .into()
@@ -1103,29 +2190,63 @@ impl ChessUI {
             Space::with_height(Length::Fixed(0.0)).into()
         };
 
-        let clear_btn = button(text("Clear").size(13))
+        let clear_btn = button(text(t.clear).size(13))
             .on_press(Message::SetupClearBoard)
             .padding([6, 12])
             .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
 
-        let start_pos_btn = button(text("Starting Pos").size(13))
+        let start_pos_btn = button(text(t.starting_position).size(13))
             .on_press(Message::SetupLoadStart)
             .padding([6, 12])
             .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
 
-        let cancel_btn = button(text("Cancel").size(14))
+        let paste_btn = button(text(t.paste_fen).size(13))
+            .on_press(Message::PasteFen)
+            .padding([6, 12])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let pgn_btn = button(text(t.paste_pgn).size(13))
+            .on_press(Message::SetupPastePgn)
+            .padding([6, 12])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+        let pgn_move_input = text_input(t.pgn_move_placeholder, &state.pgn_move)
+            .on_input(Message::SetupPgnMove)
+            .padding(6)
+            .size(13)
+            .width(Length::Fixed(190.0));
+        let pgn_status = text(state.pgn_status.as_deref().unwrap_or(t.pgn_hint))
+            .size(12)
+            .style(IcedColor::from_rgb(0.7, 0.7, 0.7));
+
+        let chess960_btn = button(text(t.random_960).size(13))
+            .on_press(Message::SetupRandomChess960)
+            .padding([6, 12])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let endgame_btn = button(text(t.random_endgame).size(13))
+            .on_press(Message::SetupRandomEndgame)
+            .padding([6, 12])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+        let endgame_input = text_input(t.endgame_material_placeholder, &state.endgame_material)
+            .on_input(Message::SetupEndgameMaterial)
+            .on_submit(Message::SetupRandomEndgame)
+            .padding(6)
+            .size(13)
+            .width(Length::Fixed(120.0));
+
+        let cancel_btn = button(text(t.cancel).size(14))
             .on_press(Message::ExitSetupMode)
             .padding([6, 16])
             .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle)));
 
         let start_game_btn: Element<'_, Message> = if state.fen_error.is_none() {
-            button(text("Start Game").size(14))
+            button(text(t.start_game).size(14))
                 .on_press(Message::SetupStartGame)
                 .padding([6, 16])
                 .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)))
                 .into()
         } else {
-            button(text("Start Game").size(14))
+            button(text(t.start_game).size(14))
                 .padding([6, 16])
                 .style(iced::theme::Button::Custom(Box::new(DisabledButtonStyle)))
                 .into()
@@ -1134,15 +2255,27 @@ impl ChessUI {
         let bottom_bar = container(
             column![
                 row![
-                    text("FEN:").size(13),
+                    text(t.fen_label).size(13),
                     fen_input,
                 ]
                 .spacing(8)
                 .align_items(Alignment::Center),
                 fen_error_el,
+                row![
+                    pgn_btn,
+                    pgn_move_input,
+                    pgn_status,
+                    Space::with_width(Length::Fill),
+                    endgame_input,
+                    endgame_btn,
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
                 row![
                     clear_btn,
                     start_pos_btn,
+                    paste_btn,
+                    chess960_btn,
                     Space::with_width(Length::Fill),
                     cancel_btn,
                     start_game_btn,
@@ -1172,4 +2305,1600 @@ impl ChessUI {
         .center_x()
         .into()
     }
+
+    pub fn view_browser<'a>(&'a self, state: &'a BrowserState) -> Element<'a, Message> {
+        let t = self.strings;
+        let header_color = IcedColor::from_rgb(0.7, 0.7, 0.7);
+        let cell = |content: Element<'a, Message>, width: f32| {
+            container(content).width(Length::Fixed(width)).center_y()
+        };
+
+        let header = row![
+            cell(text(t.date).size(13).style(header_color).into(), 140.0),
+            cell(text(t.engine).size(13).style(header_color).into(), 160.0),
+            cell(text(t.level).size(13).style(header_color).into(), 50.0),
+            cell(text(t.side).size(13).style(header_color).into(), 60.0),
+            cell(text(t.result).size(13).style(header_color).into(), 70.0),
+            cell(text(t.moves).size(13).style(header_color).into(), 60.0),
+            cell(text(t.tags).size(13).style(header_color).into(), 220.0),
+        ]
+        .spacing(8);
+
+        let new_tag = state.new_tag.trim();
+        let mut game_list = column![].spacing(4).width(Length::Fill);
+        for stored in state.shown_games() {
+            let mut tags = row![].spacing(4).align_items(Alignment::Center);
+            for tag in &stored.tags {
+                tags = tags.push(
+                    button(text(format!("{} \u{2715}", tag)).size(11))
+                        .on_press(Message::BrowserRemoveTag(stored.id, tag.clone()))
+                        .padding([2, 6])
+                        .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                            active: true,
+                        }))),
+                );
+            }
+            let side = if stored.player_color == "b" { t.black } else { t.white };
+            let move_count = stored.move_list().len().div_ceil(2);
+
+            let game_row = row![
+                cell(Text::new(stored.played_at.as_str()).size(13).into(), 140.0),
+                cell(Text::new(stored.engine.as_str()).size(13).into(), 160.0),
+                cell(text(stored.skill_level).size(13).into(), 50.0),
+                cell(text(side).size(13).into(), 60.0),
+                cell(Text::new(stored.result.as_str()).size(13).into(), 70.0),
+                cell(text(move_count).size(13).into(), 60.0),
+                cell(tags.into(), 220.0),
+                button(text(t.tag).size(12))
+                    .on_press_maybe(
+                        (!new_tag.is_empty() && !stored.tags.iter().any(|tag| tag == new_tag))
+                            .then_some(Message::BrowserAddTag(stored.id)),
+                    )
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.open).size(12))
+                    .on_press(Message::BrowserOpenGame(stored.id))
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.delete).size(12))
+                    .on_press(Message::BrowserDeleteGame(stored.id))
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center);
+
+            game_list = game_list.push(game_row);
+        }
+
+        let body: Element<'a, Message> = if let Some(ref err) = state.error {
+            text(fill(t.error, err))
+                .size(14)
+                .style(IcedColor::from_rgb(1.0, 0.35, 0.35))
+                .into()
+        } else if state.games.is_empty() {
+            text(t.no_games_yet).size(14).into()
+        } else if state.shown_games().next().is_none() {
+            text(t.no_games_match).size(14).into()
+        } else {
+            Scrollable::new(game_list)
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .into()
+        };
+
+        let import_label = if state.importing { t.downloading } else { t.import };
+        let import_btn = button(text(import_label).size(13))
+            .on_press(Message::BrowserImportUrl)
+            .padding([6, 14])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+        let mut import_section = column![row![
+            text_input(t.import_url_placeholder, &state.import_url)
+                .on_input(Message::BrowserUrlChanged)
+                .on_submit(Message::BrowserImportUrl)
+                .padding(7)
+                .size(13)
+                .width(Length::Fill),
+            import_btn,
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)]
+        .spacing(4);
+        if let Some(ref err) = state.import_error {
+            import_section = import_section.push(
+                text(fill(t.import_failed, err))
+                    .size(13)
+                    .style(IcedColor::from_rgb(1.0, 0.35, 0.35)),
+            );
+        }
+        import_section = import_section.push(
+            row![
+                text_input(t.resume_path_placeholder, &state.resume_path)
+                    .on_input(Message::BrowserResumePathChanged)
+                    .on_submit(Message::BrowserResumeFile)
+                    .padding(7)
+                    .size(13)
+                    .width(Length::Fill),
+                button(text(t.resume).size(13))
+                    .on_press(Message::BrowserResumeFile)
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+        if let Some(ref err) = state.resume_error {
+            import_section = import_section.push(
+                text(fill(t.resume_failed, err))
+                    .size(13)
+                    .style(IcedColor::from_rgb(1.0, 0.35, 0.35)),
+            );
+        }
+
+        // Each control sends the whole filter with its own part changed
+        let filter = &state.filter;
+        type SetFilter = fn(&mut GameFilter, String);
+        let filter_input = |placeholder: &str, value: &str, width: f32, set: SetFilter| {
+            let filter = filter.clone();
+            text_input(placeholder, value)
+                .on_input(move |input| {
+                    let mut filter = filter.clone();
+                    set(&mut filter, input);
+                    Message::BrowserFilter(filter)
+                })
+                .padding(5)
+                .size(13)
+                .width(Length::Fixed(width))
+        };
+        let mut outcomes = row![text(t.result).size(13).style(header_color)]
+            .spacing(6)
+            .align_items(Alignment::Center);
+        let choices = [
+            (t.filter_all, None),
+            (t.filter_won, Some(Outcome::Win)),
+            (t.filter_drawn, Some(Outcome::Draw)),
+            (t.filter_lost, Some(Outcome::Loss)),
+        ];
+        for (label, outcome) in choices {
+            outcomes = outcomes.push(
+                button(text(label).size(12))
+                    .on_press(Message::BrowserFilter(GameFilter { outcome, ..filter.clone() }))
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: filter.outcome == outcome,
+                    }))),
+            );
+        }
+        let filter_row = row![
+            outcomes,
+            filter_input(t.engine, &filter.engine, 120.0, |f, input| f.engine = input),
+            filter_input(t.filter_opening, &filter.opening, 140.0, |f, input| f.opening = input),
+            filter_input(t.filter_from, &filter.from, 130.0, |f, input| f.from = input),
+            filter_input(t.filter_to, &filter.to, 130.0, |f, input| f.to = input),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        // Clicking a tag lists only its games, and clicking it again all
+        let mut tag_row = row![text(t.tags).size(13).style(header_color)]
+            .spacing(6)
+            .align_items(Alignment::Center);
+        for tag in state.all_tags() {
+            let active = filter.tag.as_deref() == Some(tag);
+            let chosen = (!active).then(|| tag.to_string());
+            tag_row = tag_row.push(
+                button(text(tag).size(12))
+                    .on_press(Message::BrowserFilter(GameFilter { tag: chosen, ..filter.clone() }))
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active }))),
+            );
+        }
+        tag_row = tag_row.push(Space::with_width(Length::Fill)).push(
+            text_input(t.new_tag_placeholder, &state.new_tag)
+                .on_input(Message::BrowserNewTagChanged)
+                .padding(5)
+                .size(13)
+                .width(Length::Fixed(220.0)),
+        );
+        let filter_section = column![filter_row, tag_row].spacing(6);
+
+        let stats_btn = button(text(t.statistics).size(14))
+            .on_press(Message::OpenStatistics)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let famous_btn = button(text(t.famous_games).size(14))
+            .on_press(Message::OpenFamousGames)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let puzzles_btn = button(text(t.my_mistakes).size(14))
+            .on_press(Message::OpenPuzzles)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseGameBrowser)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![
+                        text(t.saved_games).size(22),
+                        Space::with_width(Length::Fill),
+                        famous_btn,
+                        puzzles_btn,
+                        stats_btn,
+                        back_btn
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                    import_section,
+                    filter_section,
+                    header,
+                    body,
+                ]
+                .spacing(10)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_statistics<'a>(&'a self, state: &'a StatsState) -> Element<'a, Message> {
+        let t = self.strings;
+        let header_color = IcedColor::from_rgb(0.7, 0.7, 0.7);
+        let cell = |content: Element<'a, Message>, width: f32| {
+            container(content).width(Length::Fixed(width)).center_y()
+        };
+
+        let header = row![
+            cell(text(t.engine).size(13).style(header_color).into(), 160.0),
+            cell(text(t.level).size(13).style(header_color).into(), 50.0),
+            cell(text(t.games).size(13).style(header_color).into(), 55.0),
+            cell(text("+ / = / -").size(13).style(header_color).into(), 90.0),
+            cell(text(t.score).size(13).style(header_color).into(), 60.0),
+            cell(text(t.streak).size(13).style(header_color).into(), 60.0),
+            cell(text(t.best).size(13).style(header_color).into(), 45.0),
+            cell(text(t.recent_form).size(13).style(header_color).into(), 190.0),
+        ]
+        .spacing(8);
+
+        let mut level_list = column![].spacing(6).width(Length::Fill);
+        for level in &state.levels {
+            level_list = level_list.push(
+                row![
+                    cell(Text::new(level.engine.as_str()).size(13).into(), 160.0),
+                    cell(text(level.skill_level).size(13).into(), 50.0),
+                    cell(text(level.games()).size(13).into(), 55.0),
+                    cell(
+                        text(format!("{} / {} / {}", level.wins, level.draws, level.losses))
+                            .size(13)
+                            .into(),
+                        90.0
+                    ),
+                    cell(text(format!("{:.0}%", level.score_percent())).size(13).into(), 60.0),
+                    cell(text(Self::streak_label(level)).size(13).into(), 60.0),
+                    cell(text(level.best_win_streak).size(13).into(), 45.0),
+                    cell(text(Self::trend_label(level)).size(13).into(), 190.0),
+                ]
+                .spacing(8)
+                .align_items(Alignment::Center),
+            );
+        }
+
+        let body: Element<'a, Message> = if let Some(ref err) = state.error {
+            text(fill(t.error, err))
+                .size(14)
+                .style(IcedColor::from_rgb(1.0, 0.35, 0.35))
+                .into()
+        } else if state.levels.is_empty() {
+            text(t.no_statistics).size(14).into()
+        } else {
+            Scrollable::new(level_list)
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .into()
+        };
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::OpenGameBrowser)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        // The player's rating and how it has moved
+        let rating = &state.rating;
+        let mut rating_section = column![
+            text(fill(
+                &fill(t.your_rating, format!("{:.0}", rating.current())),
+                rating.history.len()
+            ))
+            .size(16),
+            text(t.rating_note).size(12)
+            .style(header_color),
+        ]
+        .spacing(6);
+        if !rating.history.is_empty() {
+            let ratings = std::iter::once(START_RATING)
+                .chain(rating.history.iter().map(|change| change.rating))
+                .collect();
+            rating_section = rating_section.push(
+                Canvas::new(RatingGraph { ratings })
+                    .width(Length::Fill)
+                    .height(Length::Fixed(100.0)),
+            );
+        }
+
+        // Adaptive strength: the switch, how it works and what it has done
+        let adaptive = &state.adaptive;
+        let mut adjustments = column![].spacing(2);
+        for adjustment in adaptive.history.iter().rev().take(8) {
+            adjustments = adjustments.push(
+                text(format!(
+                    "{}  {}  {} → {}",
+                    adjustment.played_at,
+                    adjustment.outcome.letter(),
+                    adjustment.from.label(),
+                    adjustment.to.label()
+                ))
+                .size(12)
+                .style(header_color),
+            );
+        }
+        let adaptive_section = column![
+            row![
+                text(fill(t.adaptive_strength, state.strength.label())).size(16),
+                Space::with_width(Length::Fill),
+                button(text(if adaptive.enabled { t.on } else { t.off }).size(13))
+                    .on_press(Message::ToggleAdaptiveStrength)
+                    .padding([4, 14])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: adaptive.enabled,
+                    }))),
+            ]
+            .align_items(Alignment::Center),
+            text(fill(t.adaptive_note, ELO_STEP)).size(12)
+            .style(header_color),
+            adjustments,
+        ]
+        .spacing(6);
+
+        container(
+            container(
+                column![
+                    row![text(t.statistics).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    header,
+                    body,
+                    rating_section,
+                    adaptive_section,
+                ]
+                .spacing(10)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_session<'a>(&'a self, state: &'a SessionState) -> Element<'a, Message> {
+        let t = self.strings;
+        let header_color = IcedColor::from_rgb(0.7, 0.7, 0.7);
+        let session = &state.session;
+        let (wins, draws, losses) = (
+            session.count(Outcome::Win),
+            session.count(Outcome::Draw),
+            session.count(Outcome::Loss),
+        );
+        let accuracy = match session.average_accuracy() {
+            Some((accuracy, reviewed)) => {
+                fill(&fill(t.average_accuracy, format!("{:.1}", accuracy)), reviewed)
+            }
+            None => t.no_accuracy.to_string(),
+        };
+
+        let mut games = column![].spacing(4);
+        for (index, game) in session.games.iter().enumerate() {
+            games = games.push(
+                text(format!(
+                    "{}  {}  {}{}",
+                    fill(t.session_game, index + 1),
+                    game.outcome.letter(),
+                    format_clock(game.duration),
+                    game.accuracy.map(|a| format!("  {:.1}%", a)).unwrap_or_default()
+                ))
+                .size(13),
+            );
+        }
+        let summary = column![
+            text(fill(t.session_since, &session.started_at)).size(16),
+            text(format!(
+                "{}: +{} ={} -{}, {}",
+                fill(t.game_count, session.games.len()),
+                wins,
+                draws,
+                losses,
+                fill(t.time_played, format_clock(session.time_played()))
+            ))
+            .size(14),
+            text(accuracy).size(14),
+            games,
+        ]
+        .spacing(6);
+
+        let mut past = column![text(t.earlier_sessions).size(16)].spacing(4);
+        if let Some(ref err) = state.error {
+            past = past.push(
+                text(fill(t.error, err))
+                    .size(13)
+                    .style(IcedColor::from_rgb(1.0, 0.35, 0.35)),
+            );
+        }
+        for stored in state.past.iter().filter(|stored| stored.games() > 0) {
+            past = past.push(
+                text(format!(
+                    "{}  {}  +{} ={} -{}  {}{}",
+                    stored.started_at,
+                    fill(t.game_count, stored.games()),
+                    stored.wins,
+                    stored.draws,
+                    stored.losses,
+                    format_clock(std::time::Duration::from_secs(stored.seconds)),
+                    stored.accuracy.map(|a| format!("  {:.1}%", a)).unwrap_or_default()
+                ))
+                .size(12)
+                .style(header_color),
+            );
+        }
+
+        // Closing the window shows the summary first; a second close, or
+        // Quit, ends the program
+        let mut buttons = row![Space::with_width(Length::Fill)].spacing(10);
+        if state.quitting {
+            buttons = buttons.push(
+                button(text(t.quit).size(14))
+                    .on_press(Message::WindowCloseRequested)
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            );
+        }
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseSession)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.session).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    Scrollable::new(column![summary, past].spacing(16))
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                    buttons,
+                ]
+                .spacing(10)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_settings<'a>(&'a self, state: &'a SettingsState) -> Element<'a, Message> {
+        let t = self.strings;
+        let resources = state.resources;
+        let caption = |label: String| text(label).size(14).width(Length::Fixed(150.0));
+        let note = |note: &'static str| text(note).size(13).style(IcedColor::from_rgb(0.7, 0.7, 0.7));
+
+        // The hash slider moves in powers of two
+        let log2 = |mb: u32| 31 - mb.max(1).leading_zeros();
+        let hash_slider = slider(4..=log2(state.max_hash_mb), log2(resources.hash_mb), |exponent| {
+            Message::SettingsHash(1 << exponent)
+        });
+
+        let style_button = |label: String, name: Option<&String>| {
+            button(text(label).size(14))
+                .on_press(Message::SettingsPersonality(name.cloned()))
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.personality.as_ref() == name,
+                })))
+        };
+        let mut styles = row![
+            caption(t.opponent_style.to_string()),
+            style_button(t.style_default.to_string(), None),
+        ]
+        .spacing(8)
+        .align_items(Alignment::Center);
+        for name in &state.personalities {
+            styles = styles.push(style_button(name.clone(), Some(name)));
+        }
+
+        let mut power_savers = row![caption(t.power_saver.to_string())]
+            .spacing(8)
+            .align_items(Alignment::Center);
+        for power_saver in PowerSaver::ALL {
+            power_savers = power_savers.push(
+                button(text(power_saver.name(t)).size(14))
+                    .on_press(Message::SettingsPowerSaver(power_saver))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: state.power_saver == power_saver,
+                    }))),
+            );
+        }
+
+        let mut engine_section = column![
+            text(t.engine).size(18),
+            row![
+                caption(fill(t.threads, resources.threads)),
+                slider(1..=state.max_threads, resources.threads, Message::SettingsThreads),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+            row![caption(fill(t.hash, resources.hash_mb)), hash_slider]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            note(t.engine_note),
+            styles,
+            note(t.style_note),
+            button(text(t.background_analysis).size(14))
+                .on_press(Message::SettingsBackgroundAnalysis(!state.background_analysis))
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.background_analysis,
+                }))),
+            note(t.background_note),
+            button(text(t.pause_unfocused).size(14))
+                .on_press(Message::SettingsPauseUnfocused(!state.pause_unfocused))
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.pause_unfocused,
+                }))),
+            note(t.pause_unfocused_note),
+            power_savers,
+            note(t.power_saver_note),
+            row![
+                caption(t.eval_file.to_string()),
+                text_input(t.eval_file_placeholder, &state.eval_file)
+                    .on_input(Message::SettingsEvalFile)
+                    .on_submit(Message::SaveSettings)
+                    .padding(6)
+                    .size(14),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        ]
+        .spacing(10);
+        if let Some(ref err) = state.eval_file_error {
+            engine_section = engine_section.push(
+                text(err)
+                    .size(13)
+                    .style(IcedColor::from_rgb(1.0, 0.35, 0.35)),
+            );
+        }
+        engine_section = engine_section.push(
+            row![
+                caption(t.engine_book.to_string()),
+                text_input(t.engine_book_placeholder, &state.engine_book)
+                    .on_input(Message::SettingsEngineBook)
+                    .on_submit(Message::SaveSettings)
+                    .padding(6)
+                    .size(14),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        );
+        if let Some(ref err) = state.engine_book_error {
+            engine_section = engine_section.push(
+                text(err)
+                    .size(13)
+                    .style(IcedColor::from_rgb(1.0, 0.35, 0.35)),
+            );
+        }
+        engine_section = engine_section
+            .push(
+                button(text(t.book_learning).size(14))
+                    .on_press(Message::SettingsBookLearning(!state.book_learning))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: state.book_learning,
+                    }))),
+            )
+            .push(note(t.book_learning_note));
+
+        let animation = match state.board.animation_ms {
+            0 => t.moves_instant.to_string(),
+            ms => fill(t.moves_ms, ms),
+        };
+        let mut palettes = row![caption(t.palette.to_string())]
+            .spacing(8)
+            .align_items(Alignment::Center);
+        for palette in Palette::ALL {
+            palettes = palettes.push(
+                button(text(palette.name(t)).size(14))
+                    .on_press(Message::SettingsPalette(palette))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: state.board.palette == palette,
+                    }))),
+            );
+        }
+        let board_section = column![
+            text(t.board).size(18),
+            row![
+                caption(animation),
+                slider(0..=1000, state.board.animation_ms, Message::SettingsAnimation).step(50u32),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+            note(t.animation_note),
+            button(text(t.show_reply).size(14))
+                .on_press(Message::SettingsReplyArrow(!state.board.reply_arrow))
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.board.reply_arrow,
+                }))),
+            note(t.reply_note),
+            button(text(t.show_coordinates).size(14))
+                .on_press(Message::SettingsCoordinates(!state.board.coordinates))
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.board.coordinates,
+                }))),
+            palettes,
+            button(text(t.touch_mode).size(14))
+                .on_press(Message::SettingsTouchMode(!state.board.touch_mode))
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.board.touch_mode,
+                }))),
+            note(t.touch_note),
+        ]
+        .spacing(10);
+
+        let mut languages = row![text(t.language).size(18), Space::with_width(Length::Fixed(10.0))]
+            .spacing(8)
+            .align_items(Alignment::Center);
+        for language in Language::ALL {
+            languages = languages.push(
+                button(text(language.name()).size(14))
+                    .on_press(Message::SettingsLanguage(language))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                        active: state.language == language,
+                    }))),
+            );
+        }
+
+        let percent = (state.ui_scale * 100.0).round() as u32;
+        let (min_scale, max_scale) = UI_SCALE_RANGE;
+        let scale_range = (min_scale * 100.0) as u32..=(max_scale * 100.0) as u32;
+        let interface_size = column![
+            row![
+                caption(fill(t.interface_size, percent)),
+                slider(scale_range, percent, |percent| {
+                    Message::SettingsUiScale(f64::from(percent) / 100.0)
+                })
+                .step(10u32),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+            note(t.interface_size_note),
+        ]
+        .spacing(10);
+
+        let buttons = row![
+            Space::with_width(Length::Fill),
+            button(text(t.machine_defaults).size(14))
+                .on_press(Message::SettingsDefaults)
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            button(text(t.save).size(14))
+                .on_press(Message::SaveSettings)
+                .padding([6, 16])
+                .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+        ]
+        .spacing(10);
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseSettings)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.settings).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    engine_section,
+                    board_section,
+                    languages,
+                    interface_size,
+                    buttons,
+                ]
+                .spacing(16)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_new_game<'a>(&'a self, state: &'a NewGameState) -> Element<'a, Message> {
+        let t = self.strings;
+        let color_button = |label, color| {
+            button(text(label).size(13))
+                .on_press(Message::NewGameColor(color))
+                .padding([4, 12])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.player_color == color,
+                })))
+        };
+        let mode_button = |label, mode| {
+            button(text(label).size(13))
+                .on_press(Message::NewGameClockMode(mode))
+                .padding([4, 12])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.clock_mode == mode,
+                })))
+        };
+        let rated_button = |label, rated| {
+            button(text(label).size(13))
+                .on_press(Message::NewGameRated(rated))
+                .padding([4, 12])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.rated == rated,
+                })))
+        };
+        let mut clocks = column![
+            row![
+                text(t.clocks).size(14),
+                text_input(t.clock_placeholder, &state.clock)
+                    .on_input(Message::NewGameClock)
+                    .padding(4)
+                    .size(13)
+                    .width(Length::Fixed(280.0)),
+                mode_button(t.increment, ClockMode::Increment),
+                mode_button(t.delay, ClockMode::Delay),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+            row![
+                text(t.engine_clock).size(14),
+                text_input(t.engine_clock_placeholder, &state.engine_clock)
+                    .on_input(Message::NewGameEngineClock)
+                    .padding(4)
+                    .size(13)
+                    .width(Length::Fixed(280.0)),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+            text(t.clock_note)
+                .size(13)
+                .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+        ]
+        .spacing(4);
+        if let Some(ref err) = state.clock_error {
+            clocks = clocks.push(
+                text(err)
+                    .size(13)
+                    .style(IcedColor::from_rgb(1.0, 0.35, 0.35)),
+            );
+        }
+        // Openings matching the search, to start a standard game from
+        let opening_button = |line: &OpeningLine| {
+            let chosen = state.opening.as_ref().is_some_and(|o| o.opening == line.opening);
+            button(text(line.opening.display()).size(13))
+                .on_press(Message::NewGameOpening((!chosen).then(|| line.clone())))
+                .padding([4, 12])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active: chosen })))
+        };
+        let mut openings = column![
+            row![
+                text(t.opening).size(14),
+                text_input(t.opening_placeholder, &state.opening_query)
+                    .on_input(Message::NewGameOpeningQuery)
+                    .padding(4)
+                    .size(13)
+                    .width(Length::Fixed(280.0)),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center),
+        ]
+        .spacing(4);
+        if let Some(ref line) = state.opening {
+            openings = openings.push(opening_button(line));
+        }
+        let chosen = state.opening.as_ref().map(|line| &line.opening);
+        let matches = EcoTable::bundled().search(&state.opening_query);
+        for line in matches.into_iter().filter(|line| chosen != Some(&line.opening)).take(6) {
+            openings = openings.push(opening_button(line));
+        }
+        openings = openings.push(
+            text(t.opening_note)
+                .size(13)
+                .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+        );
+        let game_row = |name: &'static str, description: &'static str, kind| {
+            row![
+                column![
+                    text(name).size(16),
+                    text(description)
+                        .size(13)
+                        .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                ]
+                .spacing(2)
+                .width(Length::Fill),
+                button(text(t.play).size(13))
+                    .on_press(Message::StartNewGame(kind))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center)
+        };
+
+        let mut game_list = column![
+            game_row(t.standard, t.standard_description, GameKind::Standard),
+            game_row("Chess960", t.chess960_description, GameKind::Chess960),
+            text(t.material_odds).size(18),
+        ]
+        .spacing(8)
+        .width(Length::Fill);
+        for (index, handicap) in HANDICAPS.iter().enumerate() {
+            game_list = game_list.push(game_row(
+                handicap.name,
+                handicap.description,
+                GameKind::Odds(index),
+            ));
+        }
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseNewGame)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.new_game).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    row![
+                        text(t.you_play_as).size(14),
+                        color_button(t.white, Color::White),
+                        color_button(t.black, Color::Black),
+                    ]
+                    .spacing(8)
+                    .align_items(Alignment::Center),
+                    column![
+                        row![
+                            text(t.game_mode).size(14),
+                            rated_button(t.casual, false),
+                            rated_button(t.rated, true),
+                        ]
+                        .spacing(8)
+                        .align_items(Alignment::Center),
+                        text(t.rated_note)
+                            .size(13)
+                            .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    ]
+                    .spacing(4),
+                    clocks,
+                    openings,
+                    Scrollable::new(game_list)
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                ]
+                .spacing(12)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_famous_games(&self) -> Element<'_, Message> {
+        let t = self.strings;
+        let mut game_list = column![].spacing(8).width(Length::Fill);
+        for (index, game) in famous::bundled().iter().enumerate() {
+            let tag = |name| game.tag(name).unwrap_or("?");
+            let players = fill(&fill(t.versus, tag("White")), tag("Black"));
+            let game_row = row![
+                column![
+                    text(game.tag("Event").unwrap_or("?")).size(16),
+                    text(format!(
+                        "{}, {} {}",
+                        players,
+                        game.tag("Site").unwrap_or("?"),
+                        game.tag("Date").and_then(|date| date.get(..4)).unwrap_or("?")
+                    ))
+                    .size(12),
+                ]
+                .spacing(2)
+                .width(Length::Fill),
+                button(text(t.replay).size(13))
+                    .on_press(Message::OpenFamousGame(index))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+            game_list = game_list.push(game_row);
+        }
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseFamousGames)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.famous_games).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    text(t.famous_games_note)
+                        .size(13)
+                    .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    Scrollable::new(game_list)
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                ]
+                .spacing(12)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_endgames(&self) -> Element<'_, Message> {
+        let t = self.strings;
+        let mut endgame_list = column![].spacing(8).width(Length::Fill);
+        for (index, endgame) in ENDGAMES.iter().enumerate() {
+            let goal = match endgame.goal {
+                EndgameGoal::Checkmate => t.goal_checkmate.to_string(),
+                EndgameGoal::Promote => t.goal_promote.to_string(),
+                EndgameGoal::Draw { moves } => fill(t.goal_draw, moves),
+            };
+            let side = if endgame.player == Color::White { t.white } else { t.black };
+
+            let endgame_row = row![
+                column![
+                    text(endgame.name).size(16),
+                    text(endgame.description)
+                        .size(13)
+                        .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    text(format!("{} {}", fill(t.you_play, side), goal)).size(12),
+                ]
+                .spacing(2)
+                .width(Length::Fill),
+                button(text(t.play).size(13))
+                    .on_press(Message::StartEndgame(index))
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+            endgame_list = endgame_list.push(endgame_row);
+        }
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseEndgames)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.endgame_training).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    text(t.endgame_training_note)
+                        .size(13)
+                        .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    Scrollable::new(endgame_list)
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                ]
+                .spacing(12)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_puzzles<'a>(&'a self, state: &'a PuzzleState) -> Element<'a, Message> {
+        let t = self.strings;
+        let header_color = IcedColor::from_rgb(0.7, 0.7, 0.7);
+        let cell = |content: Element<'a, Message>, width: f32| {
+            container(content).width(Length::Fixed(width)).center_y()
+        };
+
+        let header = row![
+            cell(text(t.saved).size(13).style(header_color).into(), 140.0),
+            cell(text(t.to_move_header).size(13).style(header_color).into(), 70.0),
+            cell(text(t.you_played).size(13).style(header_color).into(), 90.0),
+            cell(text(t.attempts).size(13).style(header_color).into(), 70.0),
+            cell(text(t.status).size(13).style(header_color).into(), 70.0),
+        ]
+        .spacing(8);
+
+        let mut puzzle_list = column![].spacing(4).width(Length::Fill);
+        for puzzle in &state.puzzles {
+            let board = crate::fen::safe_parse_board(&puzzle.fen, t).ok();
+            let to_move = match board.map(|b| b.side_to_move()) {
+                Some(Color::White) => t.white,
+                Some(Color::Black) => t.black,
+                None => "-",
+            };
+            let played = board
+                .and_then(|b| Some(crate::game::move_to_san(&b, crate::game::parse_uci_move(&b, &puzzle.played)?)))
+                .unwrap_or_else(|| puzzle.played.clone());
+
+            let puzzle_row = row![
+                cell(Text::new(puzzle.created_at.as_str()).size(13).into(), 140.0),
+                cell(text(to_move).size(13).into(), 70.0),
+                cell(text(played).size(13).into(), 90.0),
+                cell(text(puzzle.attempts).size(13).into(), 70.0),
+                cell(text(if puzzle.solved { t.solved } else { t.unsolved }).size(13).into(), 70.0),
+                button(text(t.solve).size(12))
+                    .on_press(Message::SolvePuzzle(puzzle.id))
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.delete).size(12))
+                    .on_press(Message::DeletePuzzle(puzzle.id))
+                    .padding([4, 10])
+                    .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center);
+
+            puzzle_list = puzzle_list.push(puzzle_row);
+        }
+
+        let body: Element<'a, Message> = if let Some(ref err) = state.error {
+            text(fill(t.error, err))
+                .size(14)
+                .style(IcedColor::from_rgb(1.0, 0.35, 0.35))
+                .into()
+        } else if state.puzzles.is_empty() {
+            text(t.no_puzzles)
+                .size(14)
+                .into()
+        } else {
+            Scrollable::new(puzzle_list)
+                .height(Length::Fill)
+                .width(Length::Fill)
+                .into()
+        };
+
+        let mut start_btn = button(text(t.start_queue).size(14))
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+        if state.puzzles.iter().any(|p| !p.solved) {
+            start_btn = start_btn.on_press(Message::NextPuzzle);
+        }
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::ClosePuzzles)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![
+                        text(t.my_mistakes).size(22),
+                        Space::with_width(Length::Fill),
+                        start_btn,
+                        back_btn
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                    header,
+                    body,
+                ]
+                .spacing(10)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_repertoire<'a>(&'a self, state: &'a RepertoireState) -> Element<'a, Message> {
+        let t = self.strings;
+        let side_row = |color: Color| {
+            let name = if color == Color::White { t.white } else { t.black };
+            let count = state.repertoire.lines(color).len();
+            let lines = if count == 1 { t.repertoire_line } else { t.repertoire_lines };
+            let due = state.due[color.to_index()];
+            row![
+                container(text(fill(&fill(lines, name), count)).size(15))
+                    .width(Length::Fixed(160.0)),
+                container(text(fill(t.due, due)).size(13).style(if due > 0 {
+                    IcedColor::from_rgb(1.0, 0.75, 0.3)
+                } else {
+                    IcedColor::from_rgb(0.7, 0.7, 0.7)
+                }))
+                .width(Length::Fixed(70.0)),
+                button(text(t.train).size(13))
+                    .on_press(Message::StartTraining(color))
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+                button(text(t.clear).size(13))
+                    .on_press(Message::RepertoireClear(color))
+                    .padding([6, 14])
+                    .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center)
+        };
+
+        let color_toggle = |color: Color, label: &'static str| {
+            button(text(label).size(13))
+                .on_press(Message::RepertoireColor(color))
+                .padding([6, 14])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                    active: state.import_color == color,
+                })))
+        };
+
+        let import_section = column![
+            text(t.repertoire_import).size(14),
+            text_input(t.pgn_path_placeholder, &state.import_path)
+                .on_input(Message::RepertoirePathChanged)
+                .on_submit(Message::RepertoireImport)
+                .padding(7)
+                .size(13)
+                .width(Length::Fill),
+            row![
+                text(t.lines_are_for).size(13),
+                color_toggle(Color::White, t.white),
+                color_toggle(Color::Black, t.black),
+                Space::with_width(Length::Fill),
+                button(text(t.import).size(13))
+                    .on_press(Message::RepertoireImport)
+                    .padding([6, 16])
+                    .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle))),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        ]
+        .spacing(8);
+
+        let feedback: Element<'a, Message> = if let Some(ref err) = state.error {
+            text(fill(t.error, err))
+                .size(14)
+                .style(IcedColor::from_rgb(1.0, 0.35, 0.35))
+                .into()
+        } else if let Some(ref status) = state.status {
+            Text::new(status.as_str()).size(14).into()
+        } else {
+            Space::with_height(Length::Fixed(0.0)).into()
+        };
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseRepertoire)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.repertoire).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    text(t.repertoire_note)
+                        .size(13)
+                        .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    text(t.repertoire_spacing_note)
+                        .size(13)
+                        .style(IcedColor::from_rgb(0.7, 0.7, 0.7)),
+                    side_row(Color::White),
+                    side_row(Color::Black),
+                    Space::with_height(Length::Fixed(10.0)),
+                    import_section,
+                    feedback,
+                ]
+                .spacing(12)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    pub fn view_online<'a>(
+        &'a self,
+        state: &'a OnlineState,
+        lobby: Option<LobbyInfo<'a>>,
+    ) -> Element<'a, Message> {
+        let t = self.strings;
+        let note = |note: &'static str| text(note).size(13).style(IcedColor::from_rgb(0.7, 0.7, 0.7));
+        let action = |label, message: Option<Message>| {
+            button(text(label).size(13))
+                .on_press_maybe(message)
+                .padding([6, 14])
+                .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)))
+        };
+
+        let body: Element<'a, Message> = match lobby {
+            None => column![
+                text(t.lichess_login).size(15),
+                note(t.lichess_token_note),
+                row![
+                    text_input(t.token, &state.token)
+                        .on_input(Message::OnlineTokenChanged)
+                        .on_submit(Message::LichessConnect)
+                        .password()
+                        .padding(7)
+                        .size(13)
+                        .width(Length::Fill),
+                    action(
+                        if state.connecting { t.connecting } else { t.log_in },
+                        (!state.connecting).then_some(Message::LichessConnect),
+                    ),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            ]
+            .spacing(10)
+            .into(),
+            Some(lobby) => {
+                let idle = !lobby.playing;
+                let mut challenges = column![text(t.challenges).size(16)].spacing(6);
+                if lobby.challenges.is_empty() {
+                    challenges = challenges.push(note(t.no_challenges));
+                }
+                for challenge in lobby.challenges {
+                    challenges = challenges.push(
+                        row![
+                            Text::new(format!("{} ({})", challenge.challenger, challenge.description))
+                                .size(14)
+                                .width(Length::Fill),
+                            action(t.accept, idle.then(|| Message::LichessAccept(challenge.id.clone()))),
+                            button(text(t.decline).size(13))
+                                .on_press(Message::LichessDecline(challenge.id.clone()))
+                                .padding([6, 14])
+                                .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                        ]
+                        .spacing(10)
+                        .align_items(Alignment::Center),
+                    );
+                }
+
+                let seek_button = if lobby.seeking {
+                    action(t.cancel_seek, Some(Message::LichessCancelSeek))
+                } else {
+                    action(t.seek, idle.then_some(Message::LichessSeek))
+                };
+                column![
+                    row![
+                        Text::new(fill(t.logged_in_as, lobby.username)).size(15),
+                        Space::with_width(Length::Fill),
+                        button(text(t.log_out).size(13))
+                            .on_press(Message::LichessDisconnect)
+                            .padding([6, 14])
+                            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+                    ]
+                    .align_items(Alignment::Center),
+                    row![
+                        text(t.time_control).size(14),
+                        text_input("10+5", &state.control)
+                            .on_input(Message::OnlineControlChanged)
+                            .padding(6)
+                            .size(13)
+                            .width(Length::Fixed(80.0)),
+                        button(text(t.rated).size(13))
+                            .on_press(Message::OnlineRatedToggle)
+                            .padding([6, 14])
+                            .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle {
+                                active: state.rated,
+                            }))),
+                        seek_button,
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                    note(t.seek_note),
+                    row![
+                        text_input(t.lichess_user, &state.opponent)
+                            .on_input(Message::OnlineOpponentChanged)
+                            .on_submit(Message::LichessChallenge)
+                            .padding(6)
+                            .size(13)
+                            .width(Length::Fill),
+                        action(t.challenge, idle.then_some(Message::LichessChallenge)),
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                    row![
+                        text(fill(t.lichess_ai_level, state.ai_level)).size(14),
+                        slider(1..=8, state.ai_level, Message::OnlineAiLevel).width(Length::Fixed(160.0)),
+                        action(t.play_ai, idle.then_some(Message::LichessChallengeAi)),
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                    challenges,
+                ]
+                .spacing(12)
+                .into()
+            }
+        };
+
+        let feedback: Element<'a, Message> = if let Some(ref err) = state.error {
+            text(fill(t.error, err))
+                .size(14)
+                .style(IcedColor::from_rgb(1.0, 0.35, 0.35))
+                .into()
+        } else if let Some(ref status) = state.status {
+            Text::new(status.as_str()).size(14).into()
+        } else {
+            Space::with_height(Length::Fixed(0.0)).into()
+        };
+
+        let back_btn = button(text(t.back).size(14))
+            .on_press(Message::CloseOnline)
+            .padding([6, 16])
+            .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)));
+
+        container(
+            container(
+                column![
+                    row![text(t.play_online).size(22), Space::with_width(Length::Fill), back_btn]
+                        .align_items(Alignment::Center),
+                    note(t.online_note),
+                    body,
+                    feedback,
+                ]
+                .spacing(16)
+                .padding(20),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(SidePanelStyle))),
+        )
+        .padding(20)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+    }
+
+    // e.g. "W3" for three wins in a row
+    fn streak_label(level: &LevelStats) -> String {
+        match level.current_streak {
+            Some((outcome, len)) => format!("{}{}", outcome.letter(), len),
+            None => "-".to_string(),
+        }
+    }
+
+    // Recent results plus how the recent score compares with the overall one,
+    // e.g. "WWDLW  70% (+12)"
+    fn trend_label(level: &LevelStats) -> String {
+        let form: String = level.recent.iter().map(|o| o.letter()).collect();
+        let delta = level.recent_score_percent() - level.score_percent();
+        format!("{}  {:.0}% ({:+.0})", form, level.recent_score_percent(), delta)
+    }
+}
+
+// A move's review verdict in words, with the engine's choice if it differed.
+fn review_tip(review: &MoveEvaluation, t: &Strings) -> String {
+    let mut tip = format!("{} (-{} cp)", review.class.label(t), review.cp_loss);
+    if review.class != MoveClass::Best && !review.best_move.is_empty() {
+        tip.push_str(&fill(t.best_was, &review.best_move));
+    }
+    tip
+}
+
+// Rank numbers down the left of the game board and file letters under it,
+// in the order the board is seen from.
+fn with_coordinates(
+    board: Element<'_, Message>,
+    board_size: f32,
+    orientation: Color,
+) -> Element<'_, Message> {
+    let square_size = board_size / 8.0;
+    let label = |index: usize, file: bool| {
+        let name = if file { (b'a' + index as u8) as char } else { (b'1' + index as u8) as char };
+        text(name.to_string()).size(11).style(IcedColor::from_rgb(0.7, 0.7, 0.7))
+    };
+    let ordered = |index: usize, flip: bool| if flip { 7 - index } else { index };
+
+    let mut ranks = column![];
+    for row in 0..8 {
+        let rank = ordered(row, orientation == Color::White);
+        ranks = ranks.push(
+            container(label(rank, false))
+                .width(Length::Fixed(COORDINATE_SIZE))
+                .height(Length::Fixed(square_size))
+                .center_y(),
+        );
+    }
+    let mut files = row![Space::with_width(Length::Fixed(COORDINATE_SIZE))];
+    for column in 0..8 {
+        let file = ordered(column, orientation == Color::Black);
+        files = files.push(
+            container(label(file, true))
+                .width(Length::Fixed(square_size))
+                .height(Length::Fixed(COORDINATE_SIZE))
+                .center_x(),
+        );
+    }
+    column![row![ranks, board], files].into()
+}
+
+// Opponent and terms of the Lichess game, with Resign while it's on and
+// Leave once it's over.  The kibitzer is offered only against the AI.
+fn online_banner(online: &OnlineInfo, t: &Strings) -> Element<'static, Message> {
+    let terms = format!(
+        "{}{}",
+        online.control.as_deref().map_or(String::new(), |control| format!("{} ", control)),
+        if online.rated { t.rated_lower } else { t.casual_lower }
+    );
+    let banner_text = fill(&fill(t.online_vs, &online.opponent), terms);
+    let mut banner = column![text(banner_text).size(14)].spacing(6);
+    let end_button = match online.outcome {
+        Some(ref outcome) => {
+            banner = banner.push(text(outcome).size(14).style(IcedColor::from_rgb(0.95, 0.85, 0.4)));
+            button(text(t.leave).size(13))
+                .on_press(Message::LichessLeaveGame)
+                .padding([6, 12])
+                .style(iced::theme::Button::Custom(Box::new(RoundedButtonStyle)))
+        }
+        None => button(text(t.resign).size(13))
+            .on_press(Message::LichessResign)
+            .padding([6, 12])
+            .style(iced::theme::Button::Custom(Box::new(ExitViewButtonStyle))),
+    };
+    let mut buttons = row![end_button].spacing(10).align_items(Alignment::Center);
+    if let Some(active) = online.kibitz {
+        buttons = buttons.push(
+            button(text(t.kibitz).size(13))
+                .on_press(Message::ToggleKibitz)
+                .padding([6, 12])
+                .style(iced::theme::Button::Custom(Box::new(ToggleButtonStyle { active }))),
+        );
+        if let Some(ref eval) = online.kibitz_eval.as_ref().filter(|_| active) {
+            buttons = buttons.push(text(eval).size(14));
+        }
+    }
+    container(banner.push(buttons))
+        .padding(8)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+// The first kibitzer's lines as arrows, the best one full and the others
+// thinner and fainter the more they give away
+fn analysis_arrows(kibitzers: &[KibitzerInfo], color: IcedColor) -> Vec<Arrow> {
+    let Some(moves) = kibitzers.iter().map(|k| &k.moves).find(|moves| !moves.is_empty()) else {
+        return Vec::new();
+    };
+    let best = moves[0].2;
+    moves
+        .iter()
+        .map(|&(from, to, cp)| Arrow {
+            from,
+            to,
+            color,
+            weight: (1.0 - best.saturating_sub(cp) as f32 / ANALYSIS_ARROW_SPAN).max(0.3),
+        })
+        .collect()
+}
+
+// Each kibitzer's lines in a column of its own, with how deep it has
+// looked, and a note when their best moves differ
+fn kibitzer_panels(kibitzers: &[KibitzerInfo], t: &Strings) -> Element<'static, Message> {
+    let grey = IcedColor::from_rgb(0.7, 0.7, 0.7);
+    let mut columns = row![].spacing(8);
+    for kibitzer in kibitzers {
+        let heading = match kibitzer.depth {
+            0 => kibitzer.engine.clone(),
+            depth => fill(&fill(t.kibitzer_depth, &kibitzer.engine), depth),
+        };
+        let mut column = column![text(heading).size(13).style(grey)].spacing(4);
+        if let Some(ref error) = kibitzer.error {
+            column = column.push(
+                text(fill(t.engine_error, error))
+                    .size(14)
+                    .style(IcedColor::from_rgb(1.0, 0.45, 0.45)),
+            );
+        } else if kibitzer.lines.is_empty() {
+            column = column.push(text(t.thinking).size(14).style(grey));
+        }
+        for (eval, line) in &kibitzer.lines {
+            let line = text(format!("{}  {}", eval, line)).size(14);
+            column = column.push(line.font(iced::Font::MONOSPACE));
+        }
+        columns = columns.push(
+            container(column)
+                .padding(8)
+                .width(Length::Fill)
+                .style(iced::theme::Container::Box),
+        );
+    }
+
+    let mut best_moves: Vec<&str> = kibitzers.iter().filter_map(|k| k.best_move.as_deref()).collect();
+    best_moves.sort_unstable();
+    best_moves.dedup();
+    let mut panel = column![].spacing(4);
+    if best_moves.len() > 1 {
+        panel = panel.push(
+            text(t.engines_disagree)
+                .size(13)
+                .style(IcedColor::from_rgb(1.0, 0.65, 0.3)),
+        );
+    }
+    panel.push(columns).into()
+}
+
+// Both clocks side by side, the running one highlighted
+fn clock_row(clocks: &ClockTimes, t: &Strings) -> Element<'static, Message> {
+    let clock = |label: &str, side: Color, time: Option<std::time::Duration>| {
+        let reading = time.map_or_else(|| "-".to_string(), display_clock);
+        let color = if clocks.running == Some(side) {
+            IcedColor::WHITE
+        } else {
+            IcedColor::from_rgb(0.6, 0.6, 0.6)
+        };
+        text(format!("{} {}", label, reading)).size(18).style(color)
+    };
+    row![
+        clock(t.white, Color::White, clocks.white),
+        clock(t.black, Color::Black, clocks.black),
+    ]
+    .spacing(20)
+    .into()
 }
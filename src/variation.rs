@@ -0,0 +1,312 @@
+use chess::{Board, ChessMove, Color};
+
+use crate::game::{move_to_san, MoveAnnotation};
+
+/// The node standing for the start position.
+pub const ROOT: usize = 0;
+
+/// A game's moves and the variations branching off them.  Each node is
+/// the move that reached a position; its first child carries the line
+/// on and any others start variations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariationTree {
+    start: Board,
+    /// Number of the first move, from the start position's FEN
+    first_move_number: u32,
+    /// Indexed by node id; nodes cut from the tree stay, unreachable
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// None at the root
+    pub chess_move: Option<ChessMove>,
+    pub parent: Option<usize>,
+    /// Continuations, the one on the same line first
+    pub children: Vec<usize>,
+    pub annotation: MoveAnnotation,
+}
+
+/// A variation as listed beside the move list.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariationLine {
+    /// Node of its first move
+    pub start: usize,
+    /// 1 for a variation on the main line, 2 for one inside that, ...
+    pub depth: usize,
+    /// Its moves with numbers and glyphs, e.g. "10... Ke8 11. Rd8#"
+    pub text: String,
+}
+
+impl VariationTree {
+    pub fn new(start: Board, first_move_number: u32) -> Self {
+        let root = Node {
+            chess_move: None,
+            parent: None,
+            children: Vec::new(),
+            annotation: MoveAnnotation::default(),
+        };
+        VariationTree {
+            start,
+            first_move_number: first_move_number.max(1),
+            nodes: vec![root],
+        }
+    }
+
+    pub fn node(&self, id: usize) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn annotation_mut(&mut self, id: usize) -> &mut MoveAnnotation {
+        &mut self.nodes[id].annotation
+    }
+
+    /// The child of `parent` that `chess_move` leads to, added as a new
+    /// variation if there is none.
+    pub fn child(&mut self, parent: usize, chess_move: ChessMove) -> usize {
+        if let Some(id) = self.find_child(parent, chess_move) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            chess_move: Some(chess_move),
+            parent: Some(parent),
+            children: Vec::new(),
+            annotation: MoveAnnotation::default(),
+        });
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    fn find_child(&self, parent: usize, chess_move: ChessMove) -> Option<usize> {
+        let children = &self.nodes[parent].children;
+        children.iter().copied().find(|&id| self.nodes[id].chess_move == Some(chess_move))
+    }
+
+    /// The nodes of `moves` played from the start, adding those the tree
+    /// doesn't have yet.
+    pub fn add_line(&mut self, moves: &[ChessMove]) -> Vec<usize> {
+        let mut node = ROOT;
+        moves
+            .iter()
+            .map(|&chess_move| {
+                node = self.child(node, chess_move);
+                node
+            })
+            .collect()
+    }
+
+    /// The nodes of `moves` played from the start, if the tree has them all.
+    pub fn find_line(&self, moves: &[ChessMove]) -> Option<Vec<usize>> {
+        let mut node = ROOT;
+        moves
+            .iter()
+            .map(|&chess_move| {
+                node = self.find_child(node, chess_move)?;
+                Some(node)
+            })
+            .collect()
+    }
+
+    /// The nodes from the first move up to `id`; empty for the root.
+    pub fn path(&self, id: usize) -> Vec<usize> {
+        let mut path = Vec::new();
+        let mut node = id;
+        while let Some(parent) = self.nodes[node].parent {
+            path.push(node);
+            node = parent;
+        }
+        path.reverse();
+        path
+    }
+
+    /// The whole line `id` is on: its path, carried on through first
+    /// children to where the line ends.
+    pub fn line_through(&self, id: usize) -> Vec<usize> {
+        let mut line = self.path(id);
+        let mut node = id;
+        while let Some(&next) = self.nodes[node].children.first() {
+            line.push(next);
+            node = next;
+        }
+        line
+    }
+
+    /// Make the line through `id` the main line; the lines it takes over
+    /// from become its variations.
+    pub fn promote(&mut self, id: usize) {
+        for node in self.path(id) {
+            let parent = self.nodes[node].parent.unwrap_or(ROOT);
+            let children = &mut self.nodes[parent].children;
+            if let Some(index) = children.iter().position(|&child| child == node) {
+                children.remove(index);
+                children.insert(0, node);
+            }
+        }
+    }
+
+    /// Move the line through `id` one place down where it branches off:
+    /// a main line gives way to its first variation, a variation swaps
+    /// with the one after it.
+    pub fn demote(&mut self, id: usize) {
+        for node in self.path(id).into_iter().rev() {
+            let parent = self.nodes[node].parent.unwrap_or(ROOT);
+            let children = &mut self.nodes[parent].children;
+            let Some(index) = children.iter().position(|&child| child == node) else {
+                return;
+            };
+            if index + 1 < children.len() {
+                children.swap(index, index + 1);
+                return;
+            }
+            // The start of a variation; the line it branches from stays put
+            if index > 0 {
+                return;
+            }
+        }
+    }
+
+    /// Cut `id` and the moves after it out of the tree.
+    pub fn remove(&mut self, id: usize) {
+        if let Some(parent) = self.nodes[id].parent {
+            self.nodes[parent].children.retain(|&child| child != id);
+        }
+    }
+
+    pub fn moves(&self, nodes: &[usize]) -> Vec<ChessMove> {
+        nodes.iter().filter_map(|&id| self.nodes[id].chess_move).collect()
+    }
+
+    pub fn has_variations(&self) -> bool {
+        let mut stack = vec![ROOT];
+        while let Some(id) = stack.pop() {
+            let children = &self.nodes[id].children;
+            if children.len() > 1 {
+                return true;
+            }
+            stack.extend(children);
+        }
+        false
+    }
+
+    /// Every variation, in the order a PGN file has them: each one after
+    /// the move it replaces, with those inside it before the line goes on.
+    pub fn variations(&self) -> Vec<VariationLine> {
+        let mut lines = Vec::new();
+        self.collect_variations(ROOT, 0, &mut lines);
+        lines
+    }
+
+    fn collect_variations(&self, from: usize, depth: usize, lines: &mut Vec<VariationLine>) {
+        let mut node = from;
+        while let Some((&next, alternatives)) = self.nodes[node].children.split_first() {
+            for &start in alternatives {
+                lines.push(VariationLine {
+                    start,
+                    depth: depth + 1,
+                    text: self.line_text(start),
+                });
+                self.collect_variations(start, depth + 1, lines);
+            }
+            node = next;
+        }
+    }
+
+    // The moves from `start` to the end of its line, numbered as in PGN
+    fn line_text(&self, start: usize) -> String {
+        let path = self.path(start);
+        let mut board = self.moves(&path[..path.len() - 1])
+            .into_iter()
+            .fold(self.start, |board, chess_move| board.make_move_new(chess_move));
+        // Plies before the first one White plays
+        let offset = usize::from(self.start.side_to_move() == Color::Black);
+        let mut words = Vec::new();
+        let line = self.line_through(start);
+        for (ply, &id) in line.iter().enumerate().skip(path.len() - 1) {
+            let node = &self.nodes[id];
+            let Some(chess_move) = node.chess_move else {
+                continue;
+            };
+            let number = self.first_move_number as usize + (ply + offset) / 2;
+            let white = (ply + offset) % 2 == 0;
+            let san = move_to_san(&board, chess_move) + node.annotation.glyph.unwrap_or_default();
+            words.push(match (white, words.is_empty()) {
+                (true, _) => format!("{}. {}", number, san),
+                (false, true) => format!("{}... {}", number, san),
+                (false, false) => san,
+            });
+            board = board.make_move_new(chess_move);
+        }
+        words.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::parse_uci_move;
+
+    fn play(tree: &mut VariationTree, moves: &[&str]) -> Vec<usize> {
+        let mut board = tree.start;
+        let moves: Vec<ChessMove> = moves
+            .iter()
+            .map(|uci| {
+                let chess_move = parse_uci_move(&board, uci).unwrap();
+                board = board.make_move_new(chess_move);
+                chess_move
+            })
+            .collect();
+        tree.add_line(&moves)
+    }
+
+    #[test]
+    fn test_lines_and_variations() {
+        let mut tree = VariationTree::new(Board::default(), 1);
+        let main = play(&mut tree, &["e2e4", "e7e5", "g1f3", "b8c6"]);
+        let sicilian = play(&mut tree, &["e2e4", "c7c5", "g1f3"]);
+        let inner = play(&mut tree, &["e2e4", "c7c5", "b1c3"]);
+        let queen_pawn = play(&mut tree, &["d2d4"]);
+
+        // Shared moves aren't added twice
+        assert_eq!(sicilian[0], main[0]);
+        assert_eq!(tree.find_line(&tree.moves(&inner)), Some(inner.clone()));
+        assert_eq!(tree.path(inner[2]), inner);
+        assert_eq!(tree.line_through(sicilian[1]), sicilian);
+        assert_eq!(tree.line_through(ROOT), main);
+
+        tree.annotation_mut(sicilian[1]).glyph = Some("!?");
+        let lines = tree.variations();
+        let listed: Vec<(usize, usize, &str)> =
+            lines.iter().map(|line| (line.start, line.depth, line.text.as_str())).collect();
+        assert_eq!(
+            listed,
+            [
+                (queen_pawn[0], 1, "1. d4"),
+                (sicilian[1], 1, "1... c5!? 2. Nf3"),
+                (inner[2], 2, "2. Nc3"),
+            ]
+        );
+
+        // The Sicilian takes over, and 1... e5 becomes its variation
+        tree.promote(inner[2]);
+        assert_eq!(tree.line_through(ROOT), inner);
+        assert_eq!(tree.line_through(main[1]), main);
+        // Demoting 2. Nc3 brings back 2. Nf3 under 1... c5
+        tree.demote(inner[2]);
+        assert_eq!(tree.line_through(ROOT), sicilian);
+        // 1. d4 is the last variation on the first move and stays there,
+        tree.demote(queen_pawn[0]);
+        assert_eq!(tree.node(ROOT).children, [main[0], queen_pawn[0]]);
+        // as does 1... e5, the last on Black's first move
+        tree.demote(main[3]);
+        assert_eq!(tree.line_through(main[1]), main);
+        assert_eq!(tree.line_through(ROOT), sicilian);
+
+        // Without 1... c5 its variation, 1... e5, carries the line on
+        tree.remove(sicilian[1]);
+        assert_eq!(tree.line_through(ROOT), main);
+        assert_eq!(tree.find_line(&tree.moves(&inner)), None);
+        tree.remove(queen_pawn[0]);
+        assert!(!tree.has_variations());
+    }
+}